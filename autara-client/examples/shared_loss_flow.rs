@@ -178,6 +178,8 @@ async fn main() -> Result<()> {
                 collateral_oracle_config: env.collateral_oracle_config(),
                 interest_rate: InterestRateCurveKind::new_adaptive(),
                 lending_market_fee_in_bps: 100,
+                metadata_uri: Default::default(),
+                borrow_origination_grace_seconds: 0,
             },
             supply_mint,
             collateral_mint,
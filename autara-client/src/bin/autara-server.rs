@@ -151,6 +151,8 @@ fn default_market_config(
         collateral_oracle_config: OracleConfig::new_pyth(collateral_feed_id, oracle_program_id),
         interest_rate: InterestRateCurveKind::new_adaptive(),
         lending_market_fee_in_bps: 100,
+        metadata_uri: Default::default(),
+        borrow_origination_grace_seconds: 0,
     }
 }
 
@@ -1167,6 +1167,7 @@ async fn handle_oracle_command(
                 client: rpc.clone(),
                 autara_oracle_program_id: oracle_program_id,
                 network,
+                max_price_move_bps: None,
             };
             pusher
                 .push_pyth_price(&signer_keypair, feed_id, &pyth_price)
@@ -1224,12 +1225,12 @@ async fn handle_oracle_command(
                 let (supply_oracle_key, collateral_oracle_key) = m.get_oracle_keys();
                 println!("=== Oracle Feeds for Market {:?} ===", market_key);
                 println!("\nSupply Oracle:");
-                println!("  Feed Account: {:?}", supply_oracle_key);
+                print_feed_account(supply_oracle_key);
                 print_oracle_provider_info(
                     m.supply_vault().oracle_provider().oracle_provider_ref(),
                 );
                 println!("\nCollateral Oracle:");
-                println!("  Feed Account: {:?}", collateral_oracle_key);
+                print_feed_account(collateral_oracle_key);
                 print_oracle_provider_info(
                     m.collateral_vault().oracle_provider().oracle_provider_ref(),
                 );
@@ -1241,6 +1242,13 @@ async fn handle_oracle_command(
     Ok(())
 }
 
+fn print_feed_account(feed_account: Option<Pubkey>) {
+    match feed_account {
+        Some(feed_account) => println!("  Feed Account: {:?}", feed_account),
+        None => println!("  Feed Account: none, constant price"),
+    }
+}
+
 fn print_oracle_provider_info(provider: autara_lib::oracle::oracle_provider::OracleProviderRef) {
     use autara_lib::oracle::oracle_provider::OracleProviderRef;
     match provider {
@@ -1255,5 +1263,9 @@ fn print_oracle_provider_info(provider: autara_lib::oracle::oracle_provider::Ora
             println!("  Program ID: {:?}", chaos.program_id);
             println!("  Required Signatures: {}", chaos.required_signatures);
         }
+        OracleProviderRef::Constant(constant) => {
+            println!("  Type: Constant");
+            println!("  Price: {}", constant.price);
+        }
     }
 }
@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use arch_sdk::{arch_program::pubkey::Pubkey, AccountInfoWithPubkey, AsyncArchRpcClient};
+
+use crate::{
+    client::single_thread_client::get_unix_timestamp, filter::AutaraAccountData,
+    rpc_ext::ArchAsyncRpcExt,
+};
+
+/// Cache of autara accounts and oracles which can be refreshed account by account
+pub trait AccountCache {
+    fn arch_client(&self) -> &AsyncArchRpcClient;
+    fn autara_program_id(&self) -> &Pubkey;
+    /// Drops `key` from the cache as the account no longer exists
+    fn evict(&mut self, key: &Pubkey);
+    /// Stores `account` if it is an oracle already tracked by the cache
+    fn update_oracle(&mut self, account: AccountInfoWithPubkey);
+    fn update_autara_account(&mut self, key: Pubkey, data: AutaraAccountData, unix_timestamp: i64);
+}
+
+/// Refetches only `pubkeys`, e.g. the accounts written by a transaction which was just sent,
+/// leaving the rest of `cache` untouched. Accounts which no longer exist are evicted
+pub async fn reload_accounts(
+    cache: &mut impl AccountCache,
+    pubkeys: &[Pubkey],
+) -> anyhow::Result<()> {
+    let mut accounts: HashMap<Pubkey, AccountInfoWithPubkey> = cache
+        .arch_client()
+        .get_multiple_accounts_batch(pubkeys)
+        .await
+        .context("failed to fetch accounts")?
+        .into_iter()
+        .map(|acc| (acc.key, acc))
+        .collect();
+    let mut autara_accounts = Vec::new();
+    for key in pubkeys {
+        let Some(account) = accounts.remove(key) else {
+            cache.evict(key);
+            continue;
+        };
+        match AutaraAccountData::try_from_account(cache.autara_program_id(), &account) {
+            Some(data) => autara_accounts.push((*key, data)),
+            None => cache.update_oracle(account),
+        }
+    }
+    // markets are synced after the oracles they may depend on were refreshed
+    let ts = get_unix_timestamp();
+    for (key, data) in autara_accounts {
+        cache.update_autara_account(key, data, ts);
+    }
+    Ok(())
+}
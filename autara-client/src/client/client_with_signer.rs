@@ -1,18 +1,21 @@
+use anyhow::Context;
 use arch_sdk::{
     arch_program::{
         bitcoin::{key::Keypair, Network},
         instruction::Instruction,
         pubkey::Pubkey,
     },
-    AsyncArchRpcClient,
+    AsyncArchRpcClient, RuntimeTransaction,
 };
 use autara_lib::{
-    event::AutaraEvents,
+    error::LendingResult,
+    event::{AutaraEvent, AutaraEvents},
     ixs::{
         BorrowDepositAplInstruction, CreateMarketInstruction, UpdateConfigInstruction,
         UpdateGlobalConfigInstruction, WithdrawRepayAplInstruction,
     },
-    state::borrow_position::BorrowPositionHealth,
+    math::ifixed_point::IFixedPoint,
+    state::{borrow_position::BorrowPositionHealth, market_wrapper::UserMarketSummary},
 };
 
 use crate::client::{
@@ -28,6 +31,7 @@ pub struct AutaraFullClientWithSigner<T: AutaraReadClient> {
     network: Network,
     signer: Keypair,
     signer_pubkey: Pubkey,
+    balance_precheck: bool,
 }
 
 impl AutaraFullClientWithSigner<AutaraReadClientImpl> {
@@ -44,6 +48,7 @@ impl AutaraFullClientWithSigner<AutaraReadClientImpl> {
             arch_client,
             signer_pubkey: Pubkey::from_slice(&signer.x_only_public_key().0.serialize()),
             signer,
+            balance_precheck: false,
         }
     }
 
@@ -60,6 +65,10 @@ impl AutaraFullClientWithSigner<AutaraReadClientImpl> {
             .reload_authority_accounts_for_market(market, &self.signer_pubkey)
             .await
     }
+
+    pub async fn reload_accounts(&mut self, pubkeys: &[Pubkey]) -> anyhow::Result<()> {
+        self.read_client.reload_accounts(pubkeys).await
+    }
 }
 
 impl<T: AutaraReadClient> AutaraFullClientWithSigner<T> {
@@ -76,9 +85,18 @@ impl<T: AutaraReadClient> AutaraFullClientWithSigner<T> {
             network,
             signer,
             signer_pubkey,
+            balance_precheck: false,
         }
     }
 
+    /// Check the wallet balance of the signer before building `supply`, `deposit_collateral`
+    /// and `repay` transactions, failing with [AutaraClientError::InsufficientBalance] instead
+    /// of paying for a transaction bound to fail. Costs an extra RPC call per transaction
+    pub fn with_balance_precheck(mut self, balance_precheck: bool) -> Self {
+        self.balance_precheck = balance_precheck;
+        self
+    }
+
     pub fn with_signer<'a>(&'a self, signer: Keypair) -> AutaraFullClientWithSigner<&'a T> {
         AutaraFullClientWithSigner {
             read_client: &self.read_client,
@@ -86,6 +104,7 @@ impl<T: AutaraReadClient> AutaraFullClientWithSigner<T> {
             network: self.network,
             signer_pubkey: Pubkey::from_slice(&signer.x_only_public_key().0.serialize()),
             signer,
+            balance_precheck: self.balance_precheck,
         }
     }
 
@@ -101,6 +120,59 @@ impl<T: AutaraReadClient> AutaraFullClientWithSigner<T> {
         &self.signer_pubkey
     }
 
+    /// Broadcast a transaction signed outside of this client,
+    /// see [TransactionToSign::attach_signatures](crate::client::tx_builder::TransactionToSign::attach_signatures)
+    pub async fn broadcast_transaction(
+        &self,
+        transaction: RuntimeTransaction,
+    ) -> Result<AutaraEvents, AutaraClientError> {
+        self.tx_broadcast().broadcast_transaction(transaction).await
+    }
+
+    /// Wallet balance of the signer for `mint`, 0 if its token account does not exist
+    pub async fn token_balance(&self, mint: &Pubkey) -> anyhow::Result<u64> {
+        self.read_client
+            .token_balance(&self.signer_pubkey, mint)
+            .await
+    }
+
+    async fn ensure_balance(&self, mint: &Pubkey, required: u64) -> Result<(), AutaraClientError> {
+        let available = self.token_balance(mint).await?;
+        if available < required {
+            return Err(AutaraClientError::InsufficientBalance {
+                mint: *mint,
+                required,
+                available,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks the configured program id matches the one compiled in `autara_program` and
+    /// that it is deployed as an executable account, as PDAs derived from a wrong program id
+    /// silently point to accounts which do not exist
+    pub async fn verify_program_id(&self) -> anyhow::Result<()> {
+        let program_id = *self.read_client.autara_program_id();
+        let expected_program_id = autara_program::id();
+        anyhow::ensure!(
+            program_id == expected_program_id,
+            "configured program id {} does not match expected program id {}",
+            program_id,
+            expected_program_id
+        );
+        let program_account = self
+            .arch_client
+            .read_account_info(program_id)
+            .await
+            .with_context(|| format!("program account {} not found", program_id))?;
+        anyhow::ensure!(
+            program_account.is_executable,
+            "program account {} is not executable",
+            program_id
+        );
+        Ok(())
+    }
+
     pub fn get_supply_position(
         &self,
         market: &Pubkey,
@@ -120,6 +192,11 @@ impl<T: AutaraReadClient> AutaraFullClientWithSigner<T> {
             .get_borrow_position_health(market, &self.signer_pubkey)
     }
 
+    pub fn user_market_summary(&self, market: &Pubkey) -> LendingResult<UserMarketSummary> {
+        self.read_client
+            .user_market_summary(market, &self.signer_pubkey)
+    }
+
     pub async fn create_market(
         &self,
         create_market: CreateMarketInstruction,
@@ -177,6 +254,16 @@ impl<T: AutaraReadClient> AutaraFullClientWithSigner<T> {
         market: &Pubkey,
         amount: u64,
     ) -> Result<AutaraEvents, AutaraClientError> {
+        if self.balance_precheck {
+            let supply_mint = self
+                .read_client
+                .get_market(market)
+                .context("market not found")?
+                .market()
+                .supply_token_info()
+                .mint;
+            self.ensure_balance(&supply_mint, amount).await?;
+        }
         let tx = self.tx_builder().supply(market, amount).await?;
         let events = self
             .tx_broadcast()
@@ -185,11 +272,38 @@ impl<T: AutaraReadClient> AutaraFullClientWithSigner<T> {
         Ok(events)
     }
 
+    pub async fn supply_and_deposit_collateral(
+        &self,
+        market: &Pubkey,
+        supply_amount: u64,
+        collateral_amount: u64,
+    ) -> Result<AutaraEvents, AutaraClientError> {
+        let tx = self
+            .tx_builder()
+            .supply_and_deposit_collateral(market, supply_amount, collateral_amount)
+            .await?;
+        let events = self
+            .tx_broadcast()
+            .broadcast_transaction(tx.sign(&[self.signer], self.network))
+            .await?;
+        Ok(events)
+    }
+
     pub async fn deposit_collateral(
         &self,
         market: &Pubkey,
         amount: u64,
     ) -> Result<AutaraEvents, AutaraClientError> {
+        if self.balance_precheck {
+            let collateral_mint = self
+                .read_client
+                .get_market(market)
+                .context("market not found")?
+                .market()
+                .collateral_token_info()
+                .mint;
+            self.ensure_balance(&collateral_mint, amount).await?;
+        }
         let tx = self.tx_builder().deposit_collateral(market, amount).await?;
         let events = self
             .tx_broadcast()
@@ -232,6 +346,30 @@ impl<T: AutaraReadClient> AutaraFullClientWithSigner<T> {
         market: &Pubkey,
         amount: Option<u64>,
     ) -> Result<AutaraEvents, AutaraClientError> {
+        if self.balance_precheck {
+            let (supply_mint, required) = {
+                let market_w = self
+                    .read_client
+                    .get_market(market)
+                    .context("market not found")?;
+                let supply_vault = market_w.market().supply_vault();
+                let required = match amount {
+                    Some(amount) => amount,
+                    None => {
+                        let (_, borrow_position) = self
+                            .read_client
+                            .get_borrow_position(market, &self.signer_pubkey);
+                        let borrow_position =
+                            borrow_position.context("borrow position not found")?;
+                        supply_vault
+                            .borrow_shares_to_atoms(borrow_position.borrowed_shares())
+                            .map_err(anyhow::Error::from)?
+                    }
+                };
+                (*supply_vault.mint(), required)
+            };
+            self.ensure_balance(&supply_mint, required).await?;
+        }
         let tx = self.tx_builder().repay(market, amount).await?;
         let events = self
             .tx_broadcast()
@@ -315,6 +453,27 @@ impl<T: AutaraReadClient> AutaraFullClientWithSigner<T> {
         Ok(events)
     }
 
+    pub async fn sync_markets(
+        &self,
+        markets: &[Pubkey],
+    ) -> Result<AutaraEvents, AutaraClientError> {
+        let tx = self.tx_builder().sync_markets(markets).await?;
+        let events = self
+            .tx_broadcast()
+            .broadcast_transaction(tx.sign(&[self.signer], self.network))
+            .await?;
+        Ok(events)
+    }
+
+    pub async fn sweep_fee_dust(&self, market: &Pubkey) -> Result<AutaraEvents, AutaraClientError> {
+        let tx = self.tx_builder().sweep_fee_dust(market).await?;
+        let events = self
+            .tx_broadcast()
+            .broadcast_transaction(tx.sign(&[self.signer], self.network))
+            .await?;
+        Ok(events)
+    }
+
     pub async fn update_global_config(
         &self,
         update: UpdateGlobalConfigInstruction,
@@ -327,6 +486,15 @@ impl<T: AutaraReadClient> AutaraFullClientWithSigner<T> {
         Ok(events)
     }
 
+    /// Clear a pending admin nomination, e.g. one made to a wrong key
+    pub async fn cancel_pending_admin(&self) -> Result<AutaraEvents, AutaraClientError> {
+        self.update_global_config(UpdateGlobalConfigInstruction {
+            cancel_nomination: true,
+            ..Default::default()
+        })
+        .await
+    }
+
     pub async fn borrow_deposit(
         &self,
         market_key: &Pubkey,
@@ -353,6 +521,23 @@ impl<T: AutaraReadClient> AutaraFullClientWithSigner<T> {
         Ok(events)
     }
 
+    pub async fn deleverage_to_ltv(
+        &self,
+        market_key: &Pubkey,
+        target_ltv: IFixedPoint,
+        swap_ix: Instruction,
+    ) -> Result<AutaraEvents, AutaraClientError> {
+        let tx = self
+            .tx_builder()
+            .deleverage_to_ltv(market_key, target_ltv, swap_ix)
+            .await?;
+        let events = self
+            .tx_broadcast()
+            .broadcast_transaction(tx.sign(&[self.signer], self.network))
+            .await?;
+        Ok(events)
+    }
+
     pub async fn donate_supply(
         &self,
         market: &Pubkey,
@@ -379,6 +564,37 @@ impl<T: AutaraReadClient> AutaraFullClientWithSigner<T> {
         Ok(events)
     }
 
+    /// Socialize the loss of every borrow position of `market` whose ltv reached 100%,
+    /// returns the total debt socialized
+    pub async fn socialize_all_eligible(&self, market: &Pubkey) -> Result<u64, AutaraClientError> {
+        let eligible = {
+            let Some(market_w) = self.read_client.get_market(market) else {
+                return Ok(0);
+            };
+            self.read_client
+                .all_borrow_position()
+                .filter(|(_, position)| position.market() == market)
+                .filter(|(_, position)| !position.borrowed_shares().is_zero())
+                .filter(|(_, position)| {
+                    market_w
+                        .borrow_position_health(position)
+                        .is_ok_and(|health| health.ltv >= IFixedPoint::one())
+                })
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>()
+        };
+        let mut debt_socialized = 0;
+        for position in eligible {
+            let events = self.socialize_loss(market, &position).await?;
+            for event in events.events {
+                if let AutaraEvent::SocializeLoss(event) = event {
+                    debt_socialized += event.debt_socialized;
+                }
+            }
+        }
+        Ok(debt_socialized)
+    }
+
     pub fn tx_builder(&self) -> AutaraTransactionBuilder<'_, T> {
         AutaraTransactionBuilder {
             arch_client: &self.arch_client,
@@ -1,8 +1,10 @@
+pub mod account_cache;
 pub mod blockhash_cache;
 pub mod client_with_signer;
 pub mod client_without_signer;
 pub mod read;
 pub mod shared_autara_state;
+pub mod simulation;
 pub mod single_thread_client;
 pub mod tx_broadcast;
 pub mod tx_builder;
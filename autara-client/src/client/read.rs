@@ -1,16 +1,27 @@
-use std::ops::Deref;
+use std::{collections::BTreeMap, future::Future, ops::Deref};
 
 use anyhow::Context;
 use arch_sdk::arch_program::pubkey::Pubkey;
-use autara_lib::state::{
-    borrow_position::{BorrowPosition, BorrowPositionHealth},
-    global_config::GlobalConfig,
-    market::Market,
-    market_wrapper::MarketWrapper,
-    supply_position::SupplyPosition,
+use autara_lib::{
+    error::{LendingError, LendingResult, LendingResultExt},
+    interest_rate::interest_rate_per_second::InterestRatePerSecond,
+    math::{ifixed_point::IFixedPoint, safe_math::SafeMath, ufixed_point::UFixedPoint},
+    operation::liquidation::LiquidationResultWithBonus,
+    oracle::oracle_price::OracleRate,
+    state::{
+        borrow_position::{BorrowPosition, BorrowPositionHealth},
+        global_config::GlobalConfig,
+        market::{Market, MarketProtocolRevenue},
+        market_wrapper::{MarketSide, MarketWrapper, UserMarketSummary},
+        supply_position::SupplyPosition,
+    },
 };
 use serde::{Deserialize, Serialize};
 
+use crate::client::{
+    simulation::OracleOverrideSimulation, single_thread_client::get_unix_timestamp,
+};
+
 #[auto_impl::auto_impl(&, Arc, Box)]
 pub trait AutaraReadClient: Send + Sync {
     fn autara_program_id(&self) -> &Pubkey;
@@ -41,6 +52,12 @@ pub trait AutaraReadClient: Send + Sync {
         authority: &Pubkey,
     ) -> (Pubkey, Option<impl Deref<Target = SupplyPosition>>);
     fn get_global_config(&self) -> Option<impl Deref<Target = GlobalConfig>>;
+    /// Balance of the associated token account of `owner` for `mint`, 0 if it does not exist
+    fn token_balance(
+        &self,
+        owner: &Pubkey,
+        mint: &Pubkey,
+    ) -> impl Future<Output = anyhow::Result<u64>> + Send;
     fn get_borrow_position_health(
         &self,
         market: &Pubkey,
@@ -53,6 +70,171 @@ pub trait AutaraReadClient: Send + Sync {
         let market_w = self.get_market(&market).context("market not found")?;
         Ok(market_w.borrow_position_health(&borrow_position)?)
     }
+    fn user_market_summary(
+        &self,
+        market: &Pubkey,
+        authority: &Pubkey,
+    ) -> LendingResult<UserMarketSummary> {
+        let Some(market_w) = self.get_market(market) else {
+            return Err(LendingError::FailedToLoadAccount.into()).with_msg("market not found");
+        };
+        let (_, supply_position) = self.get_supply_position(market, authority);
+        let (_, borrow_position) = self.get_borrow_position(market, authority);
+        market_w.user_market_summary(supply_position.as_deref(), borrow_position.as_deref())
+    }
+    /// Protocol fees which can be redeemed across all markets previewed to now, per supply mint
+    fn total_pending_protocol_fees(&self) -> LendingResult<BTreeMap<Pubkey, u64>> {
+        let markets = self
+            .all_markets_maybe_stale()
+            .map(|(_, market, _)| *market.market())
+            .collect::<Vec<_>>();
+        Market::pending_protocol_fees_by_mint(&markets, get_unix_timestamp())
+    }
+    /// Protocol fees accrued by each market since its creation previewed to now, redeemed or
+    /// not, in atoms of the supply mint of the market
+    fn protocol_revenue_by_market(&self) -> LendingResult<Vec<MarketProtocolRevenue>> {
+        let markets = self
+            .all_markets_maybe_stale()
+            .map(|(key, market, _)| (key, *market.market()))
+            .collect::<Vec<_>>();
+        Market::protocol_revenue_by_market(
+            markets.iter().map(|(key, market)| (*key, market)),
+            get_unix_timestamp(),
+        )
+    }
+    /// Markets which did not accrue interest for more than `staleness_seconds` and should
+    /// be synced to refresh their metrics
+    fn markets_needing_sync(&self, staleness_seconds: u64) -> Vec<Pubkey> {
+        let markets = self
+            .all_markets_maybe_stale()
+            .map(|(key, market, _)| (key, *market.market()))
+            .collect::<Vec<_>>();
+        Market::markets_needing_sync(
+            markets.iter().map(|(key, market)| (*key, market)),
+            get_unix_timestamp(),
+            staleness_seconds,
+        )
+    }
+    /// Markets whose curator is `curator`
+    fn markets_for_curator(&self, curator: &Pubkey) -> Vec<(Pubkey, Market)> {
+        self.all_markets_maybe_stale()
+            .filter(|(_, market, _)| market.market().config().curator() == curator)
+            .map(|(key, market, _)| (key, *market.market()))
+            .collect()
+    }
+    /// Last borrow rate of the markets of `curator` weighted by the value borrowed in each
+    /// market, zero when nothing is borrowed
+    fn curator_weighted_borrow_rate(
+        &self,
+        curator: &Pubkey,
+    ) -> LendingResult<InterestRatePerSecond> {
+        let mut weighted_rate = IFixedPoint::zero();
+        let mut total_borrow_value = IFixedPoint::zero();
+        for (_, market_w, _) in self
+            .all_markets_maybe_stale()
+            .filter(|(_, market, _)| market.market().config().curator() == curator)
+        {
+            let supply_vault = market_w.market().supply_vault();
+            let borrow_value = market_w
+                .supply_oracle()
+                .borrow_value(supply_vault.total_borrow()?, supply_vault.mint_decimals())?;
+            weighted_rate = weighted_rate.safe_add(
+                supply_vault
+                    .last_borrow_interest_rate()
+                    .0
+                    .safe_mul(borrow_value)?,
+            )?;
+            total_borrow_value = total_borrow_value.safe_add(borrow_value)?;
+        }
+        if total_borrow_value.is_zero() {
+            return Ok(InterestRatePerSecond::new(IFixedPoint::zero()));
+        }
+        Ok(InterestRatePerSecond::new(
+            weighted_rate.safe_div(total_borrow_value)?,
+        ))
+    }
+    /// Borrow positions of `market` sorted by estimated seconds until liquidation at flat prices,
+    /// already liquidatable positions first and positions which never become liquidatable last
+    fn positions_by_time_to_liquidation(&self, market: &Pubkey) -> Vec<(Pubkey, Option<u64>)> {
+        let Some(market_w) = self.get_market(market) else {
+            return Vec::new();
+        };
+        let mut positions = self
+            .all_borrow_position()
+            .filter(|(_, position)| position.market() == market)
+            .filter_map(|(key, position)| {
+                let seconds = market_w.seconds_until_liquidation(&position).ok()?;
+                Some((key, seconds))
+            })
+            .collect::<Vec<_>>();
+        positions.sort_by_key(|(_, seconds)| (seconds.is_none(), *seconds));
+        positions
+    }
+    /// Liquidatable borrow positions of `market` whose liquidation bonus is worth at
+    /// least `min_bonus_value`, with the liquidation a keeper can send for each of them
+    fn liquidatable_profitable(
+        &self,
+        market: &Pubkey,
+        min_bonus_value: IFixedPoint,
+    ) -> Vec<(Pubkey, LiquidationResultWithBonus)> {
+        let Some(market_w) = self.get_market(market) else {
+            return Vec::new();
+        };
+        let positions = self
+            .all_borrow_position()
+            .filter(|(_, position)| position.market() == market)
+            .collect::<Vec<_>>();
+        market_w.liquidatable_profitable(
+            positions.iter().map(|(key, position)| (*key, &**position)),
+            min_bonus_value,
+        )
+    }
+    /// Shares of the supply or borrow `position` of `market` with the atoms per share of the
+    /// matching side of the market, both read from the same snapshot so that their product
+    /// is the atom value of the position
+    fn position_shares_and_rate(
+        &self,
+        market: &Pubkey,
+        position: &Pubkey,
+    ) -> LendingResult<(UFixedPoint, UFixedPoint)> {
+        let Some(market_w) = self.get_market(market) else {
+            return Err(LendingError::FailedToLoadAccount.into()).with_msg("market not found");
+        };
+        let supply_vault = market_w.market().supply_vault();
+        if let Some((_, supply_position)) = self
+            .all_supply_position()
+            .find(|(key, supply_position)| key == position && supply_position.market() == market)
+        {
+            return Ok((
+                supply_position.shares(),
+                supply_vault.supply_exchange_rate(),
+            ));
+        }
+        if let Some((_, borrow_position)) = self
+            .all_borrow_position()
+            .find(|(key, borrow_position)| key == position && borrow_position.market() == market)
+        {
+            return Ok((
+                borrow_position.borrowed_shares(),
+                supply_vault.borrow_exchange_rate(),
+            ));
+        }
+        Err(LendingError::FailedToLoadAccount.into()).with_msg("position not found")
+    }
+    /// Simulation view of this client where the oracle rate of one side of `market` is
+    /// replaced by `rate`, to evaluate price moves without any on-chain push
+    #[auto_impl(keep_default_for(&, Arc, Box))]
+    fn override_oracle_rate(
+        &self,
+        market: Pubkey,
+        side: MarketSide,
+        rate: OracleRate,
+    ) -> OracleOverrideSimulation<&Self>
+    where
+        Self: Sized,
+    {
+        OracleOverrideSimulation::new(self).override_oracle_rate(market, side, rate)
+    }
     fn user_positions(&self, authority: &Pubkey) -> UserPositions {
         let mut supply_positions = Vec::new();
         let mut borrow_positions = Vec::new();
@@ -6,17 +6,26 @@ use std::{
 use anyhow::Context;
 use arch_sdk::{arch_program::pubkey::Pubkey, AccountInfoWithPubkey, AsyncArchRpcClient};
 use autara_lib::{
+    event::{AutaraEvent, AutaraEvents},
+    metrics::client::{CuratorFeePoint, CuratorFeeTimeline},
     pda::{find_borrow_position_pda, find_global_config_pda, find_supply_position_pda},
     state::{
-        borrow_position::BorrowPosition, global_config::GlobalConfig, market::Market,
-        market_wrapper::MarketWrapper, supply_position::SupplyPosition,
+        borrow_position::BorrowPosition,
+        global_config::GlobalConfig,
+        market::Market,
+        market_wrapper::{MarketWrapper, OracleAges},
+        supply_position::SupplyPosition,
     },
 };
 use dashmap::DashMap;
 
 use crate::{
-    client::{read::AutaraReadClient, single_thread_client::get_unix_timestamp},
-    filter::{borrow_position_filter, market_filter, supply_position_filter},
+    client::{
+        account_cache::{reload_accounts, AccountCache},
+        read::AutaraReadClient,
+        single_thread_client::get_unix_timestamp,
+    },
+    filter::{borrow_position_filter, market_filter, supply_position_filter, AutaraAccountData},
     rpc_ext::ArchAsyncRpcExt,
 };
 
@@ -29,8 +38,14 @@ pub struct AutaraSharedState {
     oracle_map: DashMap<Pubkey, AccountInfoWithPubkey>,
     mint_decimals: DashMap<Pubkey, u8>,
     global_config: RwLock<GlobalConfig>,
+    curator_fee_timelines: DashMap<Pubkey, CuratorFeeTimeline>,
 }
 
+type OracleAccountRef<'a> = dashmap::mapref::one::Ref<'a, Pubkey, AccountInfoWithPubkey>;
+
+/// Number of reloads kept in the curator fee timeline of each market
+const CURATOR_FEE_TIMELINE_CAPACITY: usize = 720;
+
 impl AutaraSharedState {
     pub fn new(arch_client: AsyncArchRpcClient, autara_program_id: Pubkey) -> Self {
         Self {
@@ -42,6 +57,7 @@ impl AutaraSharedState {
             oracle_map: DashMap::new(),
             mint_decimals: DashMap::new(),
             global_config: RwLock::new(GlobalConfig::default()),
+            curator_fee_timelines: DashMap::new(),
         }
     }
 
@@ -49,6 +65,39 @@ impl AutaraSharedState {
         self.mint_decimals.get(mint).map(|r| *r.value())
     }
 
+    /// Pending and redeemed curator fees of the market observed over the last reloads
+    pub fn curator_fee_timeline(&self, market: &Pubkey) -> Vec<CuratorFeePoint> {
+        self.curator_fee_timelines
+            .get(market)
+            .map(|timeline| timeline.points().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Records the curator fee redemptions of `events` in the fee timelines, with the exact
+    /// redeemed amounts instead of the ones inferred from the pending fees on reload
+    pub fn observe_events(&self, events: &AutaraEvents) {
+        for event in &events.events {
+            if let AutaraEvent::ReedeemCuratorFees(redeem) = event {
+                self.curator_fee_timelines
+                    .entry(redeem.market)
+                    .or_insert_with(|| CuratorFeeTimeline::new(CURATOR_FEE_TIMELINE_CAPACITY))
+                    .observe_redeem(event);
+            }
+        }
+    }
+
+    /// Age of the last loaded price of both oracles of the market
+    pub fn oracle_ages(&self, market: &Market) -> Option<OracleAges> {
+        let (supply_oracle, collateral_oracle) = self.market_oracle_accounts(market)?;
+        OracleAges::load(
+            market,
+            supply_oracle.as_deref().map(Into::into),
+            collateral_oracle.as_deref().map(Into::into),
+            get_unix_timestamp(),
+        )
+        .ok()
+    }
+
     pub fn spawn(self) -> (Arc<Self>, tokio::task::JoinHandle<()>) {
         let state = Arc::new(self);
         let handle = tokio::spawn({
@@ -98,7 +147,7 @@ impl AutaraSharedState {
             .iter()
             .flat_map(|(_, m)| {
                 let keys = m.get_oracle_keys();
-                [keys.0, keys.1]
+                [keys.0, keys.1].into_iter().flatten()
             })
             .collect();
         let accs = self
@@ -119,6 +168,13 @@ impl AutaraSharedState {
         Ok(())
     }
 
+    /// Refetches only `pubkeys` instead of waiting for the next full reload, e.g. the accounts
+    /// written by a transaction which was just sent. Accounts which no longer exist are evicted
+    pub async fn invalidate(&self, pubkeys: &[Pubkey]) -> anyhow::Result<()> {
+        let mut cache = self;
+        reload_accounts(&mut cache, pubkeys).await
+    }
+
     fn process_single_market(
         &self,
         key: Pubkey,
@@ -130,37 +186,49 @@ impl AutaraSharedState {
                 .entry(token_info.mint)
                 .or_insert(token_info.decimals);
         }
-        let (supply_oracle_id, collateral_oracle_id) = market.get_oracle_keys();
-        let supply_oracle = self
-            .oracle_map
-            .get(&supply_oracle_id)
-            .context("supply oracle not found")?;
-        let collateral_oracle = self
-            .oracle_map
-            .get(&collateral_oracle_id)
-            .context("collateral oracle not found")?;
+        let (supply_oracle, collateral_oracle) = self
+            .market_oracle_accounts(&market)
+            .context("oracle not found")?;
+        // the local clock can lag behind the chain clock, which would be rejected as going backward
+        let ts = ts.max(market.supply_vault().last_update_unix_timestamp());
         market
             .wrapper_mut(
-                supply_oracle.value().into(),
-                collateral_oracle.value().into(),
+                supply_oracle.as_deref().map(Into::into),
+                collateral_oracle.as_deref().map(Into::into),
                 ts,
             )?
             .sync_clock(ts)?;
+        self.curator_fee_timelines
+            .entry(key)
+            .or_insert_with(|| CuratorFeeTimeline::new(CURATOR_FEE_TIMELINE_CAPACITY))
+            .observe_summary(&market.supply_vault().get_summary()?);
         self.market_map.insert(key, market);
         Ok(())
     }
 
+    /// Loaded feed accounts of the oracles of `market`, none for a constant oracle which has
+    /// no feed. `None` while a feed account is not loaded
+    fn market_oracle_accounts(
+        &self,
+        market: &Market,
+    ) -> Option<(Option<OracleAccountRef<'_>>, Option<OracleAccountRef<'_>>)> {
+        let (supply_oracle_id, collateral_oracle_id) = market.get_oracle_keys();
+        let load = |oracle_id: Option<Pubkey>| match oracle_id {
+            Some(oracle_id) => self.oracle_map.get(&oracle_id).map(Some),
+            None => Some(None),
+        };
+        Some((load(supply_oracle_id)?, load(collateral_oracle_id)?))
+    }
+
     fn load_market_wrapper<T: std::ops::Deref<Target = Market>>(
         &self,
         market: T,
     ) -> Option<MarketWrapper<T>> {
-        let (supply_oracle_id, collateral_oracle_id) = market.get_oracle_keys();
-        let supply_oracle = self.oracle_map.get(&supply_oracle_id)?;
-        let collateral_oracle = self.oracle_map.get(&collateral_oracle_id)?;
+        let (supply_oracle, collateral_oracle) = self.market_oracle_accounts(&market)?;
         MarketWrapper::try_new(
             market,
-            supply_oracle.value().into(),
-            collateral_oracle.value().into(),
+            supply_oracle.as_deref().map(Into::into),
+            collateral_oracle.as_deref().map(Into::into),
             get_unix_timestamp(),
         )
         .ok()
@@ -170,24 +238,68 @@ impl AutaraSharedState {
         &self,
         market: T,
     ) -> Option<(MarketWrapper<T>, bool)> {
-        let (supply_oracle_id, collateral_oracle_id) = market.get_oracle_keys();
-        let supply_oracle = self.oracle_map.get(&supply_oracle_id)?;
-        let collateral_oracle = self.oracle_map.get(&collateral_oracle_id)?;
+        let (supply_oracle, collateral_oracle) = self.market_oracle_accounts(&market)?;
         MarketWrapper::try_new_or_unchecked(
             market,
-            supply_oracle.value().into(),
-            collateral_oracle.value().into(),
+            supply_oracle.as_deref().map(Into::into),
+            collateral_oracle.as_deref().map(Into::into),
             get_unix_timestamp(),
         )
         .ok()
     }
 }
 
+impl AccountCache for &AutaraSharedState {
+    fn arch_client(&self) -> &AsyncArchRpcClient {
+        &self.arch_client
+    }
+
+    fn autara_program_id(&self) -> &Pubkey {
+        &self.autara_program_id
+    }
+
+    fn evict(&mut self, key: &Pubkey) {
+        self.market_map.remove(key);
+        self.supply_position_map.remove(key);
+        self.borrow_position_map.remove(key);
+        self.oracle_map.remove(key);
+    }
+
+    fn update_oracle(&mut self, account: AccountInfoWithPubkey) {
+        if self.oracle_map.contains_key(&account.key) {
+            self.oracle_map.insert(account.key, account);
+        }
+    }
+
+    fn update_autara_account(&mut self, key: Pubkey, data: AutaraAccountData, unix_timestamp: i64) {
+        match data {
+            AutaraAccountData::Market(market) => {
+                if let Err(e) = self.process_single_market(key, *market, unix_timestamp) {
+                    tracing::error!("Failed to process market {}: {:?}", key, e);
+                }
+            }
+            AutaraAccountData::SupplyPosition(position) => {
+                self.supply_position_map.insert(key, position);
+            }
+            AutaraAccountData::BorrowPosition(position) => {
+                self.borrow_position_map.insert(key, position);
+            }
+            AutaraAccountData::GlobalConfig(global_config) => {
+                *self.global_config.write().unwrap() = global_config;
+            }
+        }
+    }
+}
+
 impl AutaraReadClient for AutaraSharedState {
     fn autara_program_id(&self) -> &Pubkey {
         &self.autara_program_id
     }
 
+    async fn token_balance(&self, owner: &Pubkey, mint: &Pubkey) -> anyhow::Result<u64> {
+        self.arch_client.token_balance(owner, mint).await
+    }
+
     fn all_markets(
         &self,
     ) -> impl Iterator<Item = (Pubkey, MarketWrapper<impl std::ops::Deref<Target = Market>>)> {
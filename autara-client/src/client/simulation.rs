@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+use anyhow::Context;
+use arch_sdk::arch_program::pubkey::Pubkey;
+use autara_lib::{
+    error::{LendingError, LendingResult, LendingResultExt},
+    oracle::oracle_price::OracleRate,
+    state::{
+        borrow_position::BorrowPositionHealth,
+        market_wrapper::{MarketSide, MarketWrapper, OwnedMarket, UserMarketSummary},
+    },
+};
+
+use crate::client::read::AutaraReadClient;
+
+/// Read only view over an [`AutaraReadClient`] where oracle rates of some markets are
+/// substituted, to simulate price moves without pushing prices on chain.
+/// It does not implement [`AutaraReadClient`] so it can never be used to build transactions.
+pub struct OracleOverrideSimulation<T> {
+    read_client: T,
+    oracle_overrides: HashMap<(Pubkey, MarketSide), OracleRate>,
+}
+
+impl<T: AutaraReadClient> OracleOverrideSimulation<T> {
+    pub fn new(read_client: T) -> Self {
+        Self {
+            read_client,
+            oracle_overrides: HashMap::new(),
+        }
+    }
+
+    pub fn override_oracle_rate(
+        mut self,
+        market: Pubkey,
+        side: MarketSide,
+        rate: OracleRate,
+    ) -> Self {
+        self.oracle_overrides.insert((market, side), rate);
+        self
+    }
+
+    pub fn get_market(&self, market: &Pubkey) -> Option<MarketWrapper<OwnedMarket>> {
+        let mut market_w = self.read_client.get_market(market)?.owned();
+        for side in [MarketSide::Supply, MarketSide::Collateral] {
+            if let Some(rate) = self.oracle_overrides.get(&(*market, side)) {
+                market_w = market_w.with_oracle_rate(side, *rate);
+            }
+        }
+        Some(market_w)
+    }
+
+    pub fn get_borrow_position_health(
+        &self,
+        market: &Pubkey,
+        authority: &Pubkey,
+    ) -> anyhow::Result<BorrowPositionHealth> {
+        let borrow_position = self
+            .read_client
+            .get_borrow_position(market, authority)
+            .1
+            .context("borrow position not found")?;
+        let market_w = self.get_market(market).context("market not found")?;
+        Ok(market_w.borrow_position_health(&borrow_position)?)
+    }
+
+    pub fn user_market_summary(
+        &self,
+        market: &Pubkey,
+        authority: &Pubkey,
+    ) -> LendingResult<UserMarketSummary> {
+        let Some(market_w) = self.get_market(market) else {
+            return Err(LendingError::FailedToLoadAccount.into()).with_msg("market not found");
+        };
+        let (_, supply_position) = self.read_client.get_supply_position(market, authority);
+        let (_, borrow_position) = self.read_client.get_borrow_position(market, authority);
+        market_w.user_market_summary(supply_position.as_deref(), borrow_position.as_deref())
+    }
+}
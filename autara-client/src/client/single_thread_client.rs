@@ -20,8 +20,11 @@ use autara_lib::{
 use bytemuck::Pod;
 
 use crate::{
-    client::read::AutaraReadClient,
-    filter::{borrow_position_filter, market_filter, supply_position_filter},
+    client::{
+        account_cache::{self, AccountCache},
+        read::AutaraReadClient,
+    },
+    filter::{borrow_position_filter, market_filter, supply_position_filter, AutaraAccountData},
     rpc_ext::ArchAsyncRpcExt,
 };
 
@@ -72,7 +75,7 @@ impl AutaraReadClientImpl {
             .values()
             .flat_map(|m| {
                 let keys = m.get_oracle_keys();
-                [keys.0, keys.1]
+                [keys.0, keys.1].into_iter().flatten()
             })
             .collect::<Vec<_>>();
         let accs = self
@@ -108,9 +111,13 @@ impl AutaraReadClientImpl {
             .await
             .context("failed to deserialize lending market account")?;
         let (supply_oracle_id, collateral_oracle_id) = market.get_oracle_keys();
+        let oracles = [supply_oracle_id, collateral_oracle_id]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
         let accs = self
             .arch_client
-            .get_multiple_accounts_batch(&[supply_oracle_id, collateral_oracle_id])
+            .get_multiple_accounts_batch(&oracles)
             .await?;
         accs.into_iter().for_each(|acc| {
             self.oracle_map.insert(acc.key, acc);
@@ -147,6 +154,12 @@ impl AutaraReadClientImpl {
         Ok(())
     }
 
+    /// Refetches only `pubkeys`, e.g. the accounts written by a transaction which was just sent,
+    /// leaving the rest of the cache untouched. Accounts which no longer exist are evicted
+    pub async fn reload_accounts(&mut self, pubkeys: &[Pubkey]) -> anyhow::Result<()> {
+        account_cache::reload_accounts(self, pubkeys).await
+    }
+
     pub async fn reload_global_config(&mut self) -> anyhow::Result<()> {
         self.global_config_map = self
             .get_pod_account(&find_global_config_pda(&self.autara_program_id).0)
@@ -221,17 +234,14 @@ impl AutaraReadClientImpl {
         market: &mut Market,
         unix_timestamp: i64,
     ) -> anyhow::Result<()> {
-        let (supply_oracle_id, collateral_oracle_id) = market.get_oracle_keys();
-        let supply_oracle = oracle_map
-            .get(&supply_oracle_id)
-            .context("supply oracle not found")?;
-        let collateral_oracle = oracle_map
-            .get(&collateral_oracle_id)
-            .context("collateral oracle not found")?;
+        let (supply_oracle, collateral_oracle) =
+            market_oracle_accounts(oracle_map, market).context("oracle not found")?;
+        // the local clock can lag behind the chain clock, which would be rejected as going backward
+        let unix_timestamp = unix_timestamp.max(market.supply_vault().last_update_unix_timestamp());
         market
             .wrapper_mut(
-                supply_oracle.into(),
-                collateral_oracle.into(),
+                supply_oracle.map(Into::into),
+                collateral_oracle.map(Into::into),
                 unix_timestamp,
             )?
             .sync_clock(unix_timestamp)?;
@@ -242,13 +252,11 @@ impl AutaraReadClientImpl {
         &self,
         market: T,
     ) -> Option<MarketWrapper<T>> {
-        let (supply_oracle_id, collateral_oracle_id) = market.get_oracle_keys();
-        let supply_oracle = self.oracle_map.get(&supply_oracle_id)?;
-        let collateral_oracle = self.oracle_map.get(&collateral_oracle_id)?;
+        let (supply_oracle, collateral_oracle) = market_oracle_accounts(&self.oracle_map, &market)?;
         MarketWrapper::try_new(
             market,
-            supply_oracle.into(),
-            collateral_oracle.into(),
+            supply_oracle.map(Into::into),
+            collateral_oracle.map(Into::into),
             get_unix_timestamp(),
         )
         .ok()
@@ -258,24 +266,84 @@ impl AutaraReadClientImpl {
         &self,
         market: T,
     ) -> Option<(MarketWrapper<T>, bool)> {
-        let (supply_oracle_id, collateral_oracle_id) = market.get_oracle_keys();
-        let supply_oracle = self.oracle_map.get(&supply_oracle_id)?;
-        let collateral_oracle = self.oracle_map.get(&collateral_oracle_id)?;
+        let (supply_oracle, collateral_oracle) = market_oracle_accounts(&self.oracle_map, &market)?;
         MarketWrapper::try_new_or_unchecked(
             market,
-            supply_oracle.into(),
-            collateral_oracle.into(),
+            supply_oracle.map(Into::into),
+            collateral_oracle.map(Into::into),
             get_unix_timestamp(),
         )
         .ok()
     }
 }
 
+/// Loaded feed accounts of the oracles of `market`, none for a constant oracle which has no
+/// feed. `None` while a feed account is not loaded
+fn market_oracle_accounts<'a>(
+    oracle_map: &'a HashMap<Pubkey, AccountInfoWithPubkey>,
+    market: &Market,
+) -> Option<(
+    Option<&'a AccountInfoWithPubkey>,
+    Option<&'a AccountInfoWithPubkey>,
+)> {
+    let (supply_oracle_id, collateral_oracle_id) = market.get_oracle_keys();
+    let load = |oracle_id: Option<Pubkey>| match oracle_id {
+        Some(oracle_id) => oracle_map.get(&oracle_id).map(Some),
+        None => Some(None),
+    };
+    Some((load(supply_oracle_id)?, load(collateral_oracle_id)?))
+}
+
+impl AccountCache for AutaraReadClientImpl {
+    fn arch_client(&self) -> &AsyncArchRpcClient {
+        &self.arch_client
+    }
+
+    fn autara_program_id(&self) -> &Pubkey {
+        &self.autara_program_id
+    }
+
+    fn evict(&mut self, key: &Pubkey) {
+        self.market_map.remove(key);
+        self.supply_position_map.remove(key);
+        self.borrow_position_map.remove(key);
+        self.oracle_map.remove(key);
+    }
+
+    fn update_oracle(&mut self, account: AccountInfoWithPubkey) {
+        if let Some(oracle) = self.oracle_map.get_mut(&account.key) {
+            *oracle = account;
+        }
+    }
+
+    fn update_autara_account(&mut self, key: Pubkey, data: AutaraAccountData, unix_timestamp: i64) {
+        match data {
+            AutaraAccountData::Market(mut market) => {
+                let _ = Self::inner_reload_market(&self.oracle_map, &mut market, unix_timestamp);
+                self.market_map.insert(key, *market);
+            }
+            AutaraAccountData::SupplyPosition(position) => {
+                self.supply_position_map.insert(key, position);
+            }
+            AutaraAccountData::BorrowPosition(position) => {
+                self.borrow_position_map.insert(key, position);
+            }
+            AutaraAccountData::GlobalConfig(global_config) => {
+                self.global_config_map = global_config;
+            }
+        }
+    }
+}
+
 impl AutaraReadClient for AutaraReadClientImpl {
     fn autara_program_id(&self) -> &Pubkey {
         &self.autara_program_id
     }
 
+    async fn token_balance(&self, owner: &Pubkey, mint: &Pubkey) -> anyhow::Result<u64> {
+        self.arch_client.token_balance(owner, mint).await
+    }
+
     fn get_market(&self, market: &Pubkey) -> Option<MarketWrapper<impl Deref<Target = Market>>> {
         self.market_map
             .get(market)
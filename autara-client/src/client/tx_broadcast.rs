@@ -76,6 +76,12 @@ pub enum AutaraClientError {
     },
     #[error("An error occurred: {0}")]
     Arch(#[from] arch_sdk::ArchError),
+    #[error("Insufficient balance of {mint}: {required} atoms required, {available} available")]
+    InsufficientBalance {
+        mint: Pubkey,
+        required: u64,
+        available: u64,
+    },
     #[error("An error occurred: {0}")]
     Other(#[from] anyhow::Error),
 }
@@ -89,6 +95,7 @@ where
             AutaraClientError::AutaraTxError { kind, .. } => kind == other,
             AutaraClientError::Other(_) => false,
             AutaraClientError::Arch(_) => false,
+            AutaraClientError::InsufficientBalance { .. } => false,
         }
     }
 }
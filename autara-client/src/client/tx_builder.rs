@@ -4,15 +4,18 @@ use arch_sdk::{
         bitcoin::{key::Keypair, Network},
         instruction::Instruction,
         pubkey::Pubkey,
-        sanitized::ArchMessage,
+        sanitized::{ArchMessage, MAX_INSTRUCTION_COUNT_PER_TRANSACTION, MAX_PUBKEYS_ALLOWED},
     },
-    sign_message_bip322, AsyncArchRpcClient, RuntimeTransaction, Signature,
+    sign_message_bip322, verify_message_bip322, AsyncArchRpcClient, RuntimeTransaction, Signature,
+    RUNTIME_TX_SIZE_LIMIT,
 };
 use autara_lib::{
     ixs::{
-        reedeem_curator_fees_ix, reedeem_protocol_fees_ix, BorrowDepositAplInstruction,
-        CreateMarketInstruction, WithdrawRepayAplInstruction,
+        reedeem_curator_fees_ix, reedeem_protocol_fees_ix, AurataInstructionTag,
+        BorrowDepositAplInstruction, CreateMarketInstruction, WithdrawRepayAplInstruction,
     },
+    math::ifixed_point::IFixedPoint,
+    state::market::Market,
     token::create_ata_ix,
 };
 use autara_lib::{
@@ -39,6 +42,11 @@ impl<'a, T: AutaraReadClient> AutaraTransactionBuilder<'a, T> {
         supply_mint: Pubkey,
         collateral_mint: Pubkey,
     ) -> anyhow::Result<(Pubkey, TransactionToSign)> {
+        anyhow::ensure!(
+            supply_mint != collateral_mint,
+            "supply and collateral mints must differ"
+        );
+        create_market.interest_rate.validate()?;
         let (market, ix) = autara_lib::ixs::create_market_ix(
             create_market,
             supply_mint,
@@ -82,7 +90,44 @@ impl<'a, T: AutaraReadClient> AutaraTransactionBuilder<'a, T> {
             .autara_read_client
             .get_market(market_key)
             .context("market not found")?;
+        let ixs = self.supply_ixs(market_key, market.market(), atoms);
+        self.build_transaction_digest_hash_to_sign(ixs).await
+    }
 
+    pub async fn deposit_collateral(
+        &self,
+        market_key: &Pubkey,
+        atoms: u64,
+    ) -> anyhow::Result<TransactionToSign> {
+        let market = self
+            .autara_read_client
+            .get_market(market_key)
+            .context("market not found")?;
+        let ixs = self.deposit_collateral_ixs(market_key, market.market(), atoms);
+        self.build_transaction_digest_hash_to_sign(ixs).await
+    }
+
+    /// Onboard a user on a market in a single transaction: create the missing token accounts
+    /// and positions, supply `supply_atoms` and deposit `collateral_atoms` as collateral
+    pub async fn supply_and_deposit_collateral(
+        &self,
+        market_key: &Pubkey,
+        supply_atoms: u64,
+        collateral_atoms: u64,
+    ) -> anyhow::Result<TransactionToSign> {
+        let market = self
+            .autara_read_client
+            .get_market(market_key)
+            .context("market not found")?;
+        let mut ixs = self.maybe_create_market_atas(market.market()).await?;
+        ixs.extend(self.supply_ixs(market_key, market.market(), supply_atoms));
+        ixs.extend(self.deposit_collateral_ixs(market_key, market.market(), collateral_atoms));
+        let tx = self.build_transaction_digest_hash_to_sign(ixs).await?;
+        tx.check_limits()?;
+        Ok(tx)
+    }
+
+    fn supply_ixs(&self, market_key: &Pubkey, market: &Market, atoms: u64) -> Vec<Instruction> {
         let (supply_pda, supply_position) = self
             .autara_read_client
             .get_supply_position(market_key, &self.authority_key);
@@ -97,36 +142,30 @@ impl<'a, T: AutaraReadClient> AutaraTransactionBuilder<'a, T> {
             );
             ixs.push(ix);
         }
-        let (supply_oracle_id, collateral_oracle_id) = market.market().get_oracle_keys();
+        let (supply_oracle_id, collateral_oracle_id) = market.get_oracle_keys();
         let supply_ix = autara_lib::ixs::supply_apl_ix(
             self.autara_program_id,
             *market_key,
             supply_pda,
             self.authority_key,
             market
-                .market()
                 .supply_token_info()
                 .get_associated_token_address(&self.authority_key),
-            *market.market().supply_vault().vault(),
+            *market.supply_vault().vault(),
             supply_oracle_id,
             collateral_oracle_id,
             atoms,
         );
         ixs.push(supply_ix);
-
-        self.build_transaction_digest_hash_to_sign(ixs).await
+        ixs
     }
 
-    pub async fn deposit_collateral(
+    fn deposit_collateral_ixs(
         &self,
         market_key: &Pubkey,
+        market: &Market,
         atoms: u64,
-    ) -> anyhow::Result<TransactionToSign> {
-        let market = self
-            .autara_read_client
-            .get_market(market_key)
-            .context("market not found")?;
-
+    ) -> Vec<Instruction> {
         let (borrow_pda, borrow_position) = self
             .autara_read_client
             .get_borrow_position(market_key, &self.authority_key);
@@ -142,24 +181,22 @@ impl<'a, T: AutaraReadClient> AutaraTransactionBuilder<'a, T> {
             ixs.push(ix);
         }
 
-        let (supply_oracle_id, collateral_oracle_id) = market.market().get_oracle_keys();
+        let (supply_oracle_id, collateral_oracle_id) = market.get_oracle_keys();
         let collateral_ix = autara_lib::ixs::deposit_apl_collateral_ix(
             self.autara_program_id,
             *market_key,
             self.authority_key,
             borrow_pda,
             market
-                .market()
                 .collateral_token_info()
                 .get_associated_token_address(&self.authority_key),
-            *market.market().collateral_vault().vault(),
+            *market.collateral_vault().vault(),
             supply_oracle_id,
             collateral_oracle_id,
             atoms,
         );
         ixs.push(collateral_ix);
-
-        self.build_transaction_digest_hash_to_sign(ixs).await
+        ixs
     }
 
     pub async fn borrow(
@@ -316,6 +353,10 @@ impl<'a, T: AutaraReadClient> AutaraTransactionBuilder<'a, T> {
         market_key: &Pubkey,
         config: UpdateConfigInstruction,
     ) -> anyhow::Result<TransactionToSign> {
+        anyhow::ensure!(
+            config.max_supply_atoms != Some(0),
+            "max supply of zero would reject every deposit, use u64::MAX for no cap"
+        );
         let market = self
             .autara_read_client
             .get_market(market_key)
@@ -345,46 +386,88 @@ impl<'a, T: AutaraReadClient> AutaraTransactionBuilder<'a, T> {
             .autara_read_client
             .get_market(market_key)
             .context("market not found")?;
-        let mut ixs = vec![];
-        if let Some(ix) = self
-            .maybe_create_ata(&self.authority_key, market.market().supply_vault().mint())
-            .await?
-        {
-            ixs.push(ix);
+        let mut ixs = self.maybe_create_market_atas(market.market()).await?;
+        ixs.push(self.liquidate_ix(
+            market_key,
+            market.market(),
+            borrow_position_key,
+            max_borrowed_atoms_to_repay,
+            min_collateral_atoms_to_receive,
+            ix_callback,
+        ));
+        self.build_transaction_digest_hash_to_sign(ixs).await
+    }
+
+    /// Liquidate several positions of the same market in a single transaction, the
+    /// liquidator token accounts being created at most once
+    pub async fn liquidate_batch(
+        &self,
+        market_key: &Pubkey,
+        positions: &[(Pubkey, Option<u64>)],
+    ) -> anyhow::Result<TransactionToSign> {
+        anyhow::ensure!(!positions.is_empty(), "no position to liquidate");
+        let market = self
+            .autara_read_client
+            .get_market(market_key)
+            .context("market not found")?;
+        let mut ixs = self.maybe_create_market_atas(market.market()).await?;
+        for (borrow_position_key, max_borrowed_atoms_to_repay) in positions {
+            ixs.push(self.liquidate_ix(
+                market_key,
+                market.market(),
+                borrow_position_key,
+                *max_borrowed_atoms_to_repay,
+                None,
+                None,
+            ));
         }
-        if let Some(ix) = self
-            .maybe_create_ata(
-                &self.authority_key,
-                market.market().collateral_vault().mint(),
-            )
-            .await?
-        {
-            ixs.push(ix);
+        let tx = self.build_transaction_digest_hash_to_sign(ixs).await?;
+        tx.check_limits()?;
+        Ok(tx)
+    }
+
+    async fn maybe_create_market_atas(&self, market: &Market) -> anyhow::Result<Vec<Instruction>> {
+        let mut ixs = vec![];
+        for mint in [
+            market.supply_vault().mint(),
+            market.collateral_vault().mint(),
+        ] {
+            if let Some(ix) = self.maybe_create_ata(&self.authority_key, mint).await? {
+                ixs.push(ix);
+            }
         }
-        let (supply_oracle_id, collateral_oracle_id) = market.market().get_oracle_keys();
-        let liquidate_ix = autara_lib::ixs::liquidate_ix(
+        Ok(ixs)
+    }
+
+    fn liquidate_ix(
+        &self,
+        market_key: &Pubkey,
+        market: &Market,
+        borrow_position_key: &Pubkey,
+        max_borrowed_atoms_to_repay: Option<u64>,
+        min_collateral_atoms_to_receive: Option<u64>,
+        ix_callback: Option<Instruction>,
+    ) -> Instruction {
+        let (supply_oracle_id, collateral_oracle_id) = market.get_oracle_keys();
+        autara_lib::ixs::liquidate_ix(
             self.autara_program_id,
             *market_key,
             *borrow_position_key,
             self.authority_key,
             market
-                .market()
                 .supply_token_info()
                 .get_associated_token_address(&self.authority_key),
             market
-                .market()
                 .collateral_token_info()
                 .get_associated_token_address(&self.authority_key),
-            *market.market().supply_vault().vault(),
-            *market.market().collateral_vault().vault(),
+            *market.supply_vault().vault(),
+            *market.collateral_vault().vault(),
             supply_oracle_id,
             collateral_oracle_id,
             max_borrowed_atoms_to_repay.unwrap_or(u64::MAX),
             min_collateral_atoms_to_receive.unwrap_or(0),
             ix_callback,
-        );
-        ixs.push(liquidate_ix);
-        self.build_transaction_digest_hash_to_sign(ixs).await
+        )
     }
 
     pub async fn redeem_curator_fees(
@@ -446,6 +529,15 @@ impl<'a, T: AutaraReadClient> AutaraTransactionBuilder<'a, T> {
         self.build_transaction_digest_hash_to_sign(ixs).await
     }
 
+    pub async fn sweep_fee_dust(&self, market_key: &Pubkey) -> anyhow::Result<TransactionToSign> {
+        let ix = autara_lib::ixs::sweep_fee_dust_ix(
+            self.autara_program_id,
+            *market_key,
+            self.authority_key,
+        );
+        self.build_transaction_digest_hash_to_sign(vec![ix]).await
+    }
+
     pub async fn update_global_config(
         &self,
         update: UpdateGlobalConfigInstruction,
@@ -563,6 +655,42 @@ impl<'a, T: AutaraReadClient> AutaraTransactionBuilder<'a, T> {
         self.build_transaction_digest_hash_to_sign(ixs).await
     }
 
+    /// Withdraw collateral, swap it through `swap_ix` and repay debt so that
+    /// the position ends up at `target_ltv`
+    pub async fn deleverage_to_ltv(
+        &self,
+        market_key: &Pubkey,
+        target_ltv: IFixedPoint,
+        swap_ix: Instruction,
+    ) -> anyhow::Result<TransactionToSign> {
+        let amounts = {
+            let market = self
+                .autara_read_client
+                .get_market(market_key)
+                .context("market not found")?;
+            let (_, borrow_position) = self
+                .autara_read_client
+                .get_borrow_position(market_key, &self.authority_key);
+            let borrow_position = borrow_position.context("borrow position not found")?;
+            market.deleverage_amounts(&borrow_position, target_ltv)?
+        };
+        self.withdraw_repay(
+            market_key,
+            WithdrawRepayAplInstruction {
+                repay_amount: if amounts.repay_all {
+                    0
+                } else {
+                    amounts.repay_atoms
+                },
+                withdraw_amount: amounts.withdraw_collateral_atoms,
+                repay_all: amounts.repay_all,
+                withdraw_all: false,
+                ix_callback: Some(swap_ix),
+            },
+        )
+        .await
+    }
+
     pub async fn socialize_loss(
         &self,
         market_key: &Pubkey,
@@ -626,6 +754,22 @@ impl<'a, T: AutaraReadClient> AutaraTransactionBuilder<'a, T> {
         self.build_transaction_digest_hash_to_sign(ixs).await
     }
 
+    pub async fn sync_markets(&self, market_keys: &[Pubkey]) -> anyhow::Result<TransactionToSign> {
+        let ixs = market_keys
+            .iter()
+            .map(|market_key| autara_lib::ixs::sync_market_ix(self.autara_program_id, *market_key))
+            .collect();
+        self.build_transaction_digest_hash_to_sign(ixs).await
+    }
+
+    pub async fn migrate_accounts(&self, keys: &[Pubkey]) -> anyhow::Result<TransactionToSign> {
+        let ixs = keys
+            .iter()
+            .map(|key| autara_lib::ixs::migrate_account_ix(self.autara_program_id, *key))
+            .collect();
+        self.build_transaction_digest_hash_to_sign(ixs).await
+    }
+
     async fn build_transaction_digest_hash_to_sign(
         &self,
         ixs: Vec<Instruction>,
@@ -640,6 +784,7 @@ impl<'a, T: AutaraReadClient> AutaraTransactionBuilder<'a, T> {
             },
         );
         Ok(TransactionToSign {
+            autara_program_id: self.autara_program_id,
             message_hash: message.hash(),
             message,
             instructions: ixs,
@@ -670,18 +815,71 @@ impl<'a, T: AutaraReadClient> AutaraTransactionBuilder<'a, T> {
 
 #[derive(Debug, Clone)]
 pub struct TransactionToSign {
+    pub autara_program_id: Pubkey,
     pub instructions: Vec<Instruction>,
     pub message: ArchMessage,
     pub message_hash: Vec<u8>,
 }
 
 impl TransactionToSign {
+    /// Size of the runtime transaction once signed by all required signers
+    pub fn signed_size(&self) -> usize {
+        // version, signature count and 64 bytes per signature
+        4 + 1
+            + 64 * self.message.header.num_required_signatures as usize
+            + self.message.serialize().len()
+    }
+
+    /// Check the transaction fits in the runtime account, instruction and size limits
+    pub fn check_limits(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.message.account_keys.len() <= MAX_PUBKEYS_ALLOWED as usize,
+            "transaction uses {} accounts, max is {}",
+            self.message.account_keys.len(),
+            MAX_PUBKEYS_ALLOWED
+        );
+        anyhow::ensure!(
+            self.instructions.len() <= MAX_INSTRUCTION_COUNT_PER_TRANSACTION,
+            "transaction has {} instructions, max is {}",
+            self.instructions.len(),
+            MAX_INSTRUCTION_COUNT_PER_TRANSACTION
+        );
+        anyhow::ensure!(
+            self.signed_size() <= RUNTIME_TX_SIZE_LIMIT,
+            "transaction size {} exceeds {}",
+            self.signed_size(),
+            RUNTIME_TX_SIZE_LIMIT
+        );
+        Ok(())
+    }
+
+    /// Check every Autara instruction provides the accounts its program handler reads,
+    /// with the expected writable and signer flags
+    pub fn validate_accounts(&self) -> anyhow::Result<()> {
+        for (index, ix) in self.instructions.iter().enumerate() {
+            if ix.program_id != self.autara_program_id {
+                continue;
+            }
+            let tag = ix
+                .data
+                .first()
+                .and_then(|tag| AurataInstructionTag::try_from(*tag).ok())
+                .with_context(|| format!("instruction #{index} is not an Autara instruction"))?;
+            tag.check_accounts(&ix.accounts)
+                .with_context(|| format!("instruction #{index} {tag:?}"))?;
+        }
+        Ok(())
+    }
+
+    /// Keys which must sign `message_hash`, in the order expected by [Self::attach_signatures]
+    pub fn required_signers(&self) -> &[Pubkey] {
+        &self.message.account_keys[..self.message.header.num_required_signatures as usize]
+    }
+
     pub fn sign(&self, signers: &[Keypair], network: Network) -> RuntimeTransaction {
         let signatures = self
-            .message
-            .account_keys
+            .required_signers()
             .iter()
-            .take(self.message.header.num_required_signatures as usize)
             .map(|key| {
                 let sign = sign_message_bip322(
                     signers.iter().find(|signer| {
@@ -700,4 +898,36 @@ impl TransactionToSign {
             message: self.message.clone(),
         }
     }
+
+    /// Build the transaction from bip322 signatures of `message_hash` produced elsewhere,
+    /// e.g. by a hardware or air-gapped signer. Signatures must follow [Self::required_signers]
+    /// and are verified before being attached
+    pub fn attach_signatures(
+        &self,
+        signatures: Vec<Signature>,
+        network: Network,
+    ) -> anyhow::Result<RuntimeTransaction> {
+        let signers = self.required_signers();
+        anyhow::ensure!(
+            signatures.len() == signers.len(),
+            "transaction requires {} signatures, got {}",
+            signers.len(),
+            signatures.len()
+        );
+        for (key, signature) in signers.iter().zip(&signatures) {
+            verify_message_bip322(
+                &self.message_hash,
+                key.serialize(),
+                signature.0,
+                false,
+                network,
+            )
+            .with_context(|| format!("invalid signature for {key}"))?;
+        }
+        Ok(RuntimeTransaction {
+            version: 0,
+            signatures,
+            message: self.message.clone(),
+        })
+    }
 }
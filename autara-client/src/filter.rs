@@ -23,3 +23,44 @@ pub fn global_config_filter() -> Vec<AccountFilter> {
         autara_lib::state::global_config::GlobalConfig,
     >())]
 }
+
+/// Autara account decoded from its data size, consistently with the filters above
+pub enum AutaraAccountData {
+    Market(Box<autara_lib::state::market::Market>),
+    SupplyPosition(autara_lib::state::supply_position::SupplyPosition),
+    BorrowPosition(autara_lib::state::borrow_position::BorrowPosition),
+    GlobalConfig(autara_lib::state::global_config::GlobalConfig),
+}
+
+impl AutaraAccountData {
+    pub fn try_from_account(
+        autara_program_id: &arch_sdk::arch_program::pubkey::Pubkey,
+        account: &arch_sdk::AccountInfoWithPubkey,
+    ) -> Option<Self> {
+        if &account.owner != autara_program_id {
+            return None;
+        }
+        let data = account.data.as_slice();
+        bytemuck::try_from_bytes(data)
+            .ok()
+            .map(|market| Self::Market(Box::new(*market)))
+            .or_else(|| {
+                bytemuck::try_from_bytes(data)
+                    .ok()
+                    .copied()
+                    .map(Self::SupplyPosition)
+            })
+            .or_else(|| {
+                bytemuck::try_from_bytes(data)
+                    .ok()
+                    .copied()
+                    .map(Self::BorrowPosition)
+            })
+            .or_else(|| {
+                bytemuck::try_from_bytes(data)
+                    .ok()
+                    .copied()
+                    .map(Self::GlobalConfig)
+            })
+    }
+}
@@ -67,6 +67,8 @@ async fn main() -> Result<(), AutaraClientError> {
                 ),
                 interest_rate: InterestRateCurveKind::new_adaptive(),
                 lending_market_fee_in_bps: 100,
+                metadata_uri: Default::default(),
+                borrow_origination_grace_seconds: 0,
             },
             test_env.supply_mint,
             test_env.collateral_mint,
@@ -92,7 +94,7 @@ async fn main() -> Result<(), AutaraClientError> {
         .reload_authority_accounts_for_market(&market)
         .await?;
 
-    println!("{:#?}", autara_client.get_supply_position(&market).unwrap());
+    print_user_market_summary(&autara_client, &market)?;
 
     autara_client
         .deposit_collateral(&market, 1_000_000_000) // 1 BTC
@@ -106,7 +108,7 @@ async fn main() -> Result<(), AutaraClientError> {
         .reload_authority_accounts_for_market(&market)
         .await?;
 
-    println!("{:#?}", autara_client.get_borrow_position_health(&market)?);
+    print_user_market_summary(&autara_client, &market)?;
 
     autara_client
         .repay(&market, Some(5_000_000_000_000)) // 5k USDC
@@ -116,7 +118,7 @@ async fn main() -> Result<(), AutaraClientError> {
         .reload_authority_accounts_for_market(&market)
         .await?;
 
-    println!("{:#?}", autara_client.get_borrow_position_health(&market)?);
+    print_user_market_summary(&autara_client, &market)?;
 
     autara_client
         .withdraw_supply(&market, Some(50_000_000_000_000)) // 50k USDC
@@ -126,7 +128,7 @@ async fn main() -> Result<(), AutaraClientError> {
         .reload_authority_accounts_for_market(&market)
         .await?;
 
-    println!("{:#?}", autara_client.get_supply_position(&market).unwrap());
+    print_user_market_summary(&autara_client, &market)?;
 
     test_env.push_collateral_price(39_000.).await?;
 
@@ -134,7 +136,7 @@ async fn main() -> Result<(), AutaraClientError> {
         .reload_authority_accounts_for_market(&market)
         .await?;
 
-    println!("{:#?}", autara_client.get_borrow_position_health(&market)?);
+    print_user_market_summary(&autara_client, &market)?;
 
     let position = autara_client
         .read_client()
@@ -148,7 +150,15 @@ async fn main() -> Result<(), AutaraClientError> {
         .reload_authority_accounts_for_market(&market)
         .await?;
 
-    println!("{:#?}", autara_client.get_borrow_position_health(&market)?);
+    print_user_market_summary(&autara_client, &market)?;
 
     Ok(())
 }
+
+fn print_user_market_summary<T: AutaraReadClient>(
+    autara_client: &AutaraFullClientWithSigner<T>,
+    market: &Pubkey,
+) -> anyhow::Result<()> {
+    println!("{:#?}", autara_client.user_market_summary(market)?);
+    Ok(())
+}
@@ -37,12 +37,26 @@ impl PrometheusAutaraIndexer {
     }
 
     fn refresh(&self) {
-        for (market_address, market) in self.state.all_markets() {
-            let market_address = market_address.to_string();
+        for (market_key, market) in self.state.all_markets() {
+            let market_address = market_key.to_string();
             if let Ok(liquidity_summary) = market.liquidity_summary() {
                 self.market_metrics
                     .set_market_liquidity(&market_address, &liquidity_summary);
             }
+            if let Some(oracle_ages) = self.state.oracle_ages(market.market()) {
+                let positions = self
+                    .state
+                    .all_borrow_position()
+                    .filter(|(_, position)| position.market() == &market_key)
+                    .map(|(_, position)| position)
+                    .collect::<Vec<_>>();
+                if let Ok(risk_summary) =
+                    market.risk_summary(positions.iter().map(|p| &**p), oracle_ages)
+                {
+                    self.market_metrics
+                        .set_market_risk(&market_address, &risk_summary);
+                }
+            }
             if let Ok(utilisation_rate) = market.market().supply_vault().utilisation_rate() {
                 self.market_metrics
                     .set_market_utilization_rate(&market_address, utilisation_rate.to_float());
@@ -1,4 +1,4 @@
-use autara_lib::metrics::client::LiquiditySummary;
+use autara_lib::{metrics::client::LiquiditySummary, state::market_wrapper::RiskSummary};
 use prometheus::GaugeVec;
 
 use crate::prometheus::LiquidityType;
@@ -9,6 +9,10 @@ pub struct MarketMetrics {
     market_liquidity_usd: GaugeVec,
     market_borrow_rate: GaugeVec,
     market_lending_rate: GaugeVec,
+    market_solvency: GaugeVec,
+    market_oracle_age: GaugeVec,
+    market_liquidatable_positions: GaugeVec,
+    market_socialized_loss: GaugeVec,
 }
 
 impl MarketMetrics {
@@ -44,6 +48,30 @@ impl MarketMetrics {
                 &["market_address"]
             )
             .unwrap(),
+            market_solvency: prometheus::register_gauge_vec!(
+                "autara_market_solvency",
+                "Market collateral value over debt value",
+                &["market_address"]
+            )
+            .unwrap(),
+            market_oracle_age: prometheus::register_gauge_vec!(
+                "autara_market_oracle_age_seconds",
+                "Seconds since the market oracle was published",
+                &["market_address", "liquidity_type"]
+            )
+            .unwrap(),
+            market_liquidatable_positions: prometheus::register_gauge_vec!(
+                "autara_market_liquidatable_positions",
+                "Number of liquidatable borrow positions",
+                &["market_address"]
+            )
+            .unwrap(),
+            market_socialized_loss: prometheus::register_gauge_vec!(
+                "autara_market_socialized_loss_atoms",
+                "Cumulative debt socialized across the suppliers",
+                &["market_address"]
+            )
+            .unwrap(),
         }
     }
 
@@ -111,4 +139,22 @@ impl MarketMetrics {
             .with_label_values(&[market])
             .set(lending_rate);
     }
+
+    pub fn set_market_risk(&self, market: &str, risk_summary: &RiskSummary) {
+        self.market_solvency
+            .with_label_values(&[market])
+            .set(risk_summary.aggregate_solvency.to_float());
+        self.market_oracle_age
+            .with_label_values(&[market, LiquidityType::Supply.as_str()])
+            .set(risk_summary.oracle_ages.supply_seconds as f64);
+        self.market_oracle_age
+            .with_label_values(&[market, LiquidityType::Collateral.as_str()])
+            .set(risk_summary.oracle_ages.collateral_seconds as f64);
+        self.market_liquidatable_positions
+            .with_label_values(&[market])
+            .set(risk_summary.liquidatable_positions as f64);
+        self.market_socialized_loss
+            .with_label_values(&[market])
+            .set(risk_summary.socialized_loss_atoms as f64);
+    }
 }
@@ -3,9 +3,10 @@ use std::collections::HashMap;
 use anyhow::Context;
 use apl_token::state::GenericTokenAccount;
 use arch_sdk::{
-    arch_program::pubkey::Pubkey, AccountFilter, AccountInfo, AccountInfoWithPubkey,
-    AsyncArchRpcClient,
+    arch_program::{hash::Hash, pubkey::Pubkey},
+    AccountFilter, AccountInfo, AccountInfoWithPubkey, AsyncArchRpcClient,
 };
+use autara_lib::event::{position_history, AutaraEvents, PositionEvent};
 use bytemuck::Pod;
 
 use crate::token_mint::TokenMint;
@@ -41,6 +42,14 @@ pub trait ArchAsyncRpcExt {
         map: impl FnMut(AccountInfo) -> Option<T> + Send + Sync,
     ) -> anyhow::Result<impl Iterator<Item = (Pubkey, T)>>;
     async fn get_pod_account<T: Pod>(&self, key: &Pubkey) -> anyhow::Result<T>;
+    /// Timeline of the events of `position` emitted by `program_id` in the given transactions.
+    /// The RPC cannot list the transactions of an account, so `txids` must come from an indexer
+    async fn position_history(
+        &self,
+        program_id: &Pubkey,
+        position: &Pubkey,
+        txids: &[Hash],
+    ) -> anyhow::Result<Vec<PositionEvent>>;
     async fn get_mints(&self, pubkeys: &[Pubkey]) -> anyhow::Result<HashMap<Pubkey, TokenMint>> {
         self.get_multiple_accounts_batch(pubkeys)
             .await?
@@ -75,6 +84,17 @@ pub trait ArchAsyncRpcExt {
             .filter_map(|acc| unpack_mint_balance(&acc.data))
             .collect())
     }
+    /// Balance of the associated token account of `owner` for `mint`, 0 if it does not exist
+    async fn token_balance(&self, owner: &Pubkey, mint: &Pubkey) -> anyhow::Result<u64> {
+        let ata = autara_lib::token::get_associated_token_address(owner, mint);
+        Ok(self
+            .get_multiple_accounts_batch(&[ata])
+            .await?
+            .iter()
+            .filter_map(|acc| unpack_mint_balance(&acc.data))
+            .find(|(account_mint, _)| account_mint == mint)
+            .map_or(0, |(_, balance)| balance))
+    }
     async fn get_all_balances(&self, owner: &Pubkey) -> anyhow::Result<HashMap<Pubkey, u64>> {
         self.get_program_accounts_with(
             &apl_token::id(),
@@ -124,6 +144,23 @@ impl ArchAsyncRpcExt for AsyncArchRpcClient {
             .copied()
             .context("failed to deserialize account data")
     }
+    async fn position_history(
+        &self,
+        program_id: &Pubkey,
+        position: &Pubkey,
+        txids: &[Hash],
+    ) -> anyhow::Result<Vec<PositionEvent>> {
+        let mut transactions = Vec::with_capacity(txids.len());
+        for txid in txids {
+            let tx = self
+                .get_processed_transaction(txid)
+                .await?
+                .with_context(|| format!("transaction {} not found", txid))?;
+            let events = AutaraEvents::from_processed_tx(&tx, |id| id == program_id);
+            transactions.push((*txid, events));
+        }
+        Ok(position_history(transactions, position))
+    }
 }
 
 fn unpack_mint_balance(data: &[u8]) -> Option<(Pubkey, u64)> {
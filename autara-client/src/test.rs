@@ -13,7 +13,7 @@ use autara_lib::{
     oracle::{oracle_config::OracleConfig, pyth::PythPrice},
     token::{create_ata_ix, get_associated_token_address},
 };
-use autara_pyth::{fetch_and_push_feeds, AutaraPythPusherClient};
+use autara_pyth::{fetch_and_push_feeds, AutaraPythPusherClient, NO_PRICE_MOVE_BOUND};
 
 use crate::config::path_from_workspace;
 
@@ -142,6 +142,8 @@ impl AutaraTestEnv {
             client: self.arch_client.clone(),
             autara_oracle_program_id: self.autara_oracle_program_pubkey,
             network: BITCOIN_NETWORK,
+            // tests crash prices on purpose
+            max_price_move_bps: Some(NO_PRICE_MOVE_BOUND),
         }
         .push_pyth_price(&self.authority_keypair, self.supply_feed_id, &pyth)
         .await
@@ -153,6 +155,8 @@ impl AutaraTestEnv {
             client: self.arch_client.clone(),
             autara_oracle_program_id: self.autara_oracle_program_pubkey,
             network: BITCOIN_NETWORK,
+            // tests crash prices on purpose
+            max_price_move_bps: Some(NO_PRICE_MOVE_BOUND),
         }
         .push_pyth_price(&self.authority_keypair, self.collateral_feed_id, &pyth)
         .await
@@ -164,6 +168,8 @@ impl AutaraTestEnv {
             client: self.arch_client.clone(),
             autara_oracle_program_id: self.autara_oracle_program_pubkey,
             network: BITCOIN_NETWORK,
+            // tests crash prices on purpose
+            max_price_move_bps: Some(NO_PRICE_MOVE_BOUND),
         }
         .push_pyth_price(&self.authority_keypair, feed_id, &pyth)
         .await
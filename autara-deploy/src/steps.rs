@@ -211,6 +211,8 @@ fn build_create_market_instruction(
         collateral_oracle_config: collateral_oracle,
         interest_rate: InterestRateCurveKind::new_adaptive(),
         lending_market_fee_in_bps: lending_market_fee_bps,
+        metadata_uri: Default::default(),
+        borrow_origination_grace_seconds: 0,
     }
 }
 
@@ -28,7 +28,7 @@ use arch_program::pubkey::Pubkey;
 use arch_sdk::AsyncArchRpcClient;
 
 use autara_lib::math::ifixed_point::IFixedPoint;
-use autara_lib::oracle::pyth::PythPriceAccount;
+use autara_lib::oracle::pyth::{PythPrice, PythPriceAccount, PYTH_PRICE_ACCOUNT_WITHOUT_BOUND_LEN};
 use autara_lib::pda::{find_global_config_pda, find_market_pda};
 use autara_lib::state::global_config::GlobalConfig;
 use autara_lib::state::market::Market;
@@ -308,9 +308,17 @@ async fn verify_oracle(
                 );
                 return;
             }
-            match decode_pod::<PythPriceAccount>(&info.data) {
-                Some(acc) => {
-                    let publish_time = acc.pyth_price.price.publish_time;
+            let pyth_price = decode_pod::<PythPriceAccount>(&info.data)
+                .map(|acc| acc.pyth_price)
+                // feeds created before the price move bound was stored, until their next push
+                .or_else(|| {
+                    (info.data.len() == PYTH_PRICE_ACCOUNT_WITHOUT_BOUND_LEN)
+                        .then(|| decode_pod::<PythPrice>(&info.data))
+                        .flatten()
+                });
+            match pyth_price {
+                Some(pyth_price) => {
+                    let publish_time = pyth_price.price.publish_time;
                     let age = now_unix() - publish_time;
                     let fresh = age <= ORACLE_MAX_AGE_SECS;
                     report.record(
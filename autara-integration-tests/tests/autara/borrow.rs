@@ -1,4 +1,5 @@
-use autara_lib::error::LendingError;
+use autara_client::client::read::AutaraReadClient;
+use autara_lib::{error::LendingError, math::safe_math::SafeMath};
 
 use crate::fixture::autara_fixture::{AutaraFixture, BTC, USDC};
 
@@ -35,6 +36,56 @@ async fn can_borrow() {
     assert_eq!(position.collateral_atoms, BTC(0.1));
 }
 
+#[tokio::test]
+async fn position_shares_and_rate_match_the_position_atoms() {
+    let mut fixture = AutaraFixture::new().await;
+    let market = fixture.create_market().await;
+    fixture
+        .user_client()
+        .supply(&market, USDC(1_000_000.))
+        .await
+        .unwrap();
+    fixture
+        .user_client()
+        .deposit_collateral(&market, BTC(10.))
+        .await
+        .unwrap();
+    fixture
+        .user_client()
+        .borrow(&market, USDC(500_000.))
+        .await
+        .unwrap();
+    fixture.reload_market(&market).await;
+    let client = fixture.user_client();
+    let read_client = client.read_client();
+    let summary = client.user_market_summary(&market).unwrap();
+
+    let (supply_position, _) = read_client.get_supply_position(&market, client.signer_pubkey());
+    let (shares, atoms_per_share) = read_client
+        .position_shares_and_rate(&market, &supply_position)
+        .unwrap();
+    let supplied_atoms = shares
+        .safe_mul(atoms_per_share)
+        .unwrap()
+        .as_u64_rounded_down()
+        .unwrap();
+    assert_eq!(supplied_atoms, summary.supplied_atoms);
+
+    let (borrow_position, _) = read_client.get_borrow_position(&market, client.signer_pubkey());
+    let (shares, atoms_per_share) = read_client
+        .position_shares_and_rate(&market, &borrow_position)
+        .unwrap();
+    let borrowed_atoms = shares
+        .safe_mul(atoms_per_share)
+        .unwrap()
+        .as_u64_rounded_up()
+        .unwrap();
+    assert_eq!(
+        borrowed_atoms,
+        summary.borrow_position_health.unwrap().borrowed_atoms
+    );
+}
+
 #[tokio::test]
 async fn can_repay() {
     let mut fixture = AutaraFixture::new().await;
@@ -154,3 +205,24 @@ async fn cant_borrow_more_than_max_utilisation_rate() {
         .unwrap_err();
     assert_eq!(err, LendingError::MaxUtilisationRateReached)
 }
+
+#[tokio::test]
+async fn validate_accounts_catches_tampered_borrow() {
+    let mut fixture = AutaraFixture::new().await;
+    let market = fixture.create_market().await;
+    let mut tx = fixture
+        .user_client()
+        .tx_builder()
+        .borrow(&market, USDC(0.1))
+        .await
+        .unwrap();
+    tx.validate_accounts().unwrap();
+    tx.instructions.last_mut().unwrap().accounts.remove(1);
+    let err = tx.validate_accounts().unwrap_err();
+    let msg = format!("{err:#}");
+    assert!(msg.contains("BorrowApl"), "{msg}");
+    assert!(
+        msg.contains("account #1 borrow_position must be writable"),
+        "{msg}"
+    );
+}
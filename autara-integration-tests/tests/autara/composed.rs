@@ -5,7 +5,7 @@ use autara_lib::{
     ixs::{BorrowDepositAplInstruction, WithdrawRepayAplInstruction},
 };
 
-use crate::fixture::autara_fixture::AutaraFixture;
+use crate::fixture::autara_fixture::{AutaraFixture, BTC, USDC};
 
 #[tokio::test]
 async fn can_deposit_and_borrow() {
@@ -118,3 +118,47 @@ async fn can_deposit_and_borrow_then_withdraw_repay() {
         .unwrap();
     assert!(position.ltv.is_zero());
 }
+
+#[tokio::test]
+async fn can_onboard_user_in_one_transaction() {
+    let mut fixture = AutaraFixture::new().await;
+    let market = fixture.create_market().await;
+    let supply_amount = USDC(1_000.);
+    let collateral_amount = BTC(0.1);
+    let tx = fixture
+        .user_client()
+        .tx_builder()
+        .supply_and_deposit_collateral(&market, supply_amount, collateral_amount)
+        .await
+        .unwrap();
+    // create supply position, supply, create borrow position and deposit
+    assert_eq!(
+        tx.instructions
+            .iter()
+            .filter(|ix| ix.program_id == fixture.env().autara_program_pubkey)
+            .count(),
+        4
+    );
+    let events = fixture
+        .user_client()
+        .supply_and_deposit_collateral(&market, supply_amount, collateral_amount)
+        .await
+        .unwrap();
+    assert!(events
+        .events
+        .iter()
+        .any(|event| matches!(event, AutaraEvent::Supply(_))));
+    assert!(events
+        .events
+        .iter()
+        .any(|event| matches!(event, AutaraEvent::DepositCollateral(_))));
+    fixture.reload_market(&market).await;
+    let supply_position = fixture.user_client().get_supply_position(&market).unwrap();
+    assert!(supply_position.deposited_atoms() >= supply_amount - 1);
+    let position = fixture
+        .user_client()
+        .get_borrow_position_health(&market)
+        .unwrap();
+    assert_eq!(position.collateral_atoms, collateral_amount);
+    assert_eq!(position.borrowed_atoms, 0);
+}
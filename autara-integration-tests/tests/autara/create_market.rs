@@ -1,5 +1,5 @@
 use crate::fixture::autara_fixture::{
-    AutaraFixture, LIQUIDATION_BONUS, LTV, MAX_UTILISATION_RATE, UNHEALTHY_LTV,
+    AutaraFixture, BTC, LIQUIDATION_BONUS, LTV, MAX_UTILISATION_RATE, UNHEALTHY_LTV, USDC,
 };
 use autara_client::client::read::AutaraReadClient;
 use autara_lib::{
@@ -26,6 +26,8 @@ async fn can_create_a_market() {
                 collateral_oracle_config: fixture.env().collateral_oracle_config(),
                 interest_rate: InterestRateCurveKind::new_adaptive(),
                 lending_market_fee_in_bps: 0,
+                metadata_uri: Default::default(),
+                borrow_origination_grace_seconds: 0,
             },
             fixture.env().supply_mint,
             fixture.env().collateral_mint,
@@ -73,6 +75,8 @@ async fn cant_create_a_market_with_unhealthy_ltv_less_than_max_ltv() {
                 collateral_oracle_config: fixture.env().collateral_oracle_config(),
                 interest_rate: InterestRateCurveKind::new_adaptive(),
                 lending_market_fee_in_bps: 0,
+                metadata_uri: Default::default(),
+                borrow_origination_grace_seconds: 0,
             },
             fixture.env().supply_mint,
             fixture.env().collateral_mint,
@@ -101,6 +105,8 @@ async fn cant_create_a_market_when_unhealthy_ltv_times_liquidation_bonus_is_grea
                 collateral_oracle_config: fixture.env().collateral_oracle_config(),
                 interest_rate: InterestRateCurveKind::new_adaptive(),
                 lending_market_fee_in_bps: 0,
+                metadata_uri: Default::default(),
+                borrow_origination_grace_seconds: 0,
             },
             fixture.env().supply_mint,
             fixture.env().collateral_mint,
@@ -108,3 +114,110 @@ async fn cant_create_a_market_when_unhealthy_ltv_times_liquidation_bonus_is_grea
         .await;
     assert_eq!(err.unwrap_err(), LendingError::InvalidLtvConfig);
 }
+
+#[tokio::test]
+async fn markets_for_curator_only_lists_its_markets() {
+    let mut fixture = AutaraFixture::new().await;
+    let curator_market = fixture.create_market().await;
+    let other_curator = fixture.user_two_client();
+    let other_market = other_curator
+        .create_market(
+            CreateMarketInstruction {
+                market_bump: 0,
+                index: 0,
+                ltv_config: LtvConfig {
+                    max_ltv: LTV,
+                    unhealthy_ltv: UNHEALTHY_LTV,
+                    liquidation_bonus: LIQUIDATION_BONUS,
+                },
+                max_utilisation_rate: MAX_UTILISATION_RATE,
+                supply_oracle_config: fixture.env().supply_oracle_config(),
+                collateral_oracle_config: fixture.env().collateral_oracle_config(),
+                interest_rate: InterestRateCurveKind::new_adaptive(),
+                lending_market_fee_in_bps: 0,
+                metadata_uri: Default::default(),
+                borrow_origination_grace_seconds: 0,
+            },
+            fixture.env().supply_mint,
+            fixture.env().collateral_mint,
+        )
+        .await
+        .unwrap();
+    let other_curator_key = *other_curator.signer_pubkey();
+    fixture.reload().await;
+    let read_client = fixture.user_client().read_client();
+    let markets = read_client.markets_for_curator(fixture.curator_client().signer_pubkey());
+    assert!(markets.iter().any(|(key, _)| *key == curator_market));
+    assert!(!markets.iter().any(|(key, _)| *key == other_market));
+    assert!(markets
+        .iter()
+        .all(|(_, market)| market.config().curator() == fixture.curator_client().signer_pubkey()));
+    let other_markets = read_client.markets_for_curator(&other_curator_key);
+    assert!(other_markets.iter().any(|(key, _)| *key == other_market));
+    assert!(!other_markets.iter().any(|(key, _)| *key == curator_market));
+}
+
+#[tokio::test]
+async fn curator_weighted_borrow_rate_weights_markets_by_borrow_value() {
+    let mut fixture = AutaraFixture::new().await;
+    let curator = fixture.curator_client();
+    let mut markets = Vec::new();
+    for (index, apy) in [(0, 0.1), (1, 0.3)] {
+        let market = curator
+            .create_market(
+                CreateMarketInstruction {
+                    market_bump: 0,
+                    index,
+                    ltv_config: LtvConfig {
+                        max_ltv: LTV,
+                        unhealthy_ltv: UNHEALTHY_LTV,
+                        liquidation_bonus: LIQUIDATION_BONUS,
+                    },
+                    max_utilisation_rate: MAX_UTILISATION_RATE,
+                    supply_oracle_config: fixture.env().supply_oracle_config(),
+                    collateral_oracle_config: fixture.env().collateral_oracle_config(),
+                    interest_rate: InterestRateCurveKind::new_approximate_fixed_apy(apy),
+                    lending_market_fee_in_bps: 0,
+                    metadata_uri: Default::default(),
+                    borrow_origination_grace_seconds: 0,
+                },
+                fixture.env().supply_mint,
+                fixture.env().collateral_mint,
+            )
+            .await
+            .unwrap();
+        markets.push(market);
+    }
+    fixture.reload().await;
+    for (market, borrowed) in markets.iter().zip([USDC(100.), USDC(300.)]) {
+        let user = fixture.user_client();
+        user.supply(market, USDC(1_000.)).await.unwrap();
+        user.deposit_collateral(market, BTC(0.1)).await.unwrap();
+        user.borrow(market, borrowed).await.unwrap();
+    }
+    fixture.reload().await;
+    let read_client = fixture.user_client().read_client();
+    let (mut weighted_rate, mut total_value) = (0., 0.);
+    for market in markets.iter() {
+        let market_w = read_client.get_market(market).unwrap();
+        let supply_vault = market_w.market().supply_vault();
+        let value = market_w
+            .supply_oracle()
+            .borrow_value(
+                supply_vault.total_borrow().unwrap(),
+                supply_vault.mint_decimals(),
+            )
+            .unwrap()
+            .to_float();
+        weighted_rate += supply_vault.last_borrow_interest_rate().0.to_float() * value;
+        total_value += value;
+    }
+    let rate = read_client
+        .curator_weighted_borrow_rate(fixture.curator_client().signer_pubkey())
+        .unwrap();
+    let expected = weighted_rate / total_value;
+    assert!((rate.0.to_float() - expected).abs() <= expected * 1e-6);
+    // three quarters of the borrowed value pays the higher rate
+    let apy = rate.approximate_apy().unwrap();
+    assert!(apy > 0.2 && apy < 0.3);
+}
@@ -27,6 +27,8 @@ async fn can_collect_curator_fees() {
                 collateral_oracle_config: fixture.env().collateral_oracle_config(),
                 interest_rate: InterestRateCurveKind::new_adaptive(),
                 lending_market_fee_in_bps: 1000,
+                metadata_uri: Default::default(),
+                borrow_origination_grace_seconds: 0,
             },
             fixture.env().supply_mint,
             fixture.env().collateral_mint,
@@ -93,6 +95,8 @@ async fn can_collect_admin_fees() {
                 collateral_oracle_config: fixture.env().collateral_oracle_config(),
                 interest_rate: InterestRateCurveKind::new_approximate_fixed_apy(1000000.),
                 lending_market_fee_in_bps: 2000,
+                metadata_uri: Default::default(),
+                borrow_origination_grace_seconds: 0,
             },
             fixture.env().supply_mint,
             fixture.env().collateral_mint,
@@ -1,3 +1,8 @@
+use arch_sdk::{
+    arch_program::{bitcoin::Network, pubkey::Pubkey},
+    sign_message_bip322, Signature,
+};
+use autara_client::client::{read::AutaraReadClient, tx_broadcast::AutaraClientError};
 use autara_lib::{error::LendingError, event::AutaraEvent, ixs::UpdateConfigInstruction};
 
 use crate::fixture::autara_fixture::AutaraFixture;
@@ -49,6 +54,33 @@ async fn can_withdraw() {
     assert_eq!(position.shares(), 0);
 }
 
+#[tokio::test]
+async fn underfunded_supply_is_rejected_client_side() {
+    let mut fixture = AutaraFixture::new().await;
+    let market = fixture.create_market().await;
+    let supply_mint = fixture.env().supply_mint;
+    let client = fixture.user_two_client().with_balance_precheck(true);
+    let available = client.token_balance(&supply_mint).await.unwrap();
+    let err = client.supply(&market, available + 1).await.unwrap_err();
+    match err {
+        AutaraClientError::InsufficientBalance {
+            mint,
+            required,
+            available: reported,
+        } => {
+            assert_eq!(mint, supply_mint);
+            assert_eq!(required, available + 1);
+            assert_eq!(reported, available);
+        }
+        err => panic!("expected insufficient balance, got {:?}", err),
+    }
+    assert_eq!(client.token_balance(&supply_mint).await.unwrap(), available);
+    let (_, position) = client
+        .read_client()
+        .get_supply_position(&market, client.signer_pubkey());
+    assert!(position.is_none());
+}
+
 #[tokio::test]
 async fn cant_withdraw_more_than_deposited() {
     let mut fixture = AutaraFixture::new().await;
@@ -119,3 +151,117 @@ async fn can_donate() {
     };
     assert_eq!(event.amount, 15_000);
 }
+
+#[tokio::test]
+async fn reload_accounts_only_updates_named_accounts() {
+    let mut fixture = AutaraFixture::new().await;
+    let market = fixture.create_market().await;
+    fixture.user_client().supply(&market, 10000).await.unwrap();
+    fixture
+        .user_two_client()
+        .supply(&market, 20000)
+        .await
+        .unwrap();
+    let read_client = fixture.user_client().read_client();
+    let user_two = *fixture.user_two_client().signer_pubkey();
+    let user_position = read_client
+        .get_supply_position(&market, fixture.user_client().signer_pubkey())
+        .0;
+    let user_two_position = read_client.get_supply_position(&market, &user_two).0;
+    let total_supply_before = read_client
+        .get_market(&market)
+        .unwrap()
+        .market()
+        .supply_vault()
+        .total_supply()
+        .unwrap();
+
+    fixture.reload_accounts(&[user_position]).await;
+    let read_client = fixture.user_client().read_client();
+    let position = fixture.user_client().get_supply_position(&market).unwrap();
+    assert_eq!(position.deposited_atoms(), 10000);
+    assert!(read_client
+        .get_supply_position(&market, &user_two)
+        .1
+        .is_none());
+    assert_eq!(
+        read_client
+            .get_market(&market)
+            .unwrap()
+            .market()
+            .supply_vault()
+            .total_supply()
+            .unwrap(),
+        total_supply_before
+    );
+
+    fixture.reload_accounts(&[user_two_position, market]).await;
+    let read_client = fixture.user_client().read_client();
+    let position = read_client
+        .get_supply_position(&market, &user_two)
+        .1
+        .unwrap();
+    assert_eq!(position.deposited_atoms(), 20000);
+    assert!(
+        read_client
+            .get_market(&market)
+            .unwrap()
+            .market()
+            .supply_vault()
+            .total_supply()
+            .unwrap()
+            >= total_supply_before + 30000
+    );
+}
+
+#[tokio::test]
+async fn can_read_wallet_token_balance() {
+    let fixture = AutaraFixture::new().await;
+    let supply_mint = fixture.env().supply_mint;
+    let balance = fixture.fetch_user_balance().await;
+    assert!(balance.supply > 0);
+    assert_eq!(
+        fixture
+            .user_client()
+            .token_balance(&supply_mint)
+            .await
+            .unwrap(),
+        balance.supply
+    );
+    // owner without a token account
+    assert_eq!(
+        fixture
+            .user_client()
+            .read_client()
+            .token_balance(&Pubkey::new_unique(), &supply_mint)
+            .await
+            .unwrap(),
+        0
+    );
+}
+
+#[tokio::test]
+async fn can_attach_signature_produced_offline() {
+    let mut fixture = AutaraFixture::new().await;
+    let market = fixture.create_market().await;
+    let deposit = 10000;
+    let network = Network::Regtest;
+    let client = fixture.user_client();
+    let tx = client.tx_builder().supply(&market, deposit).await.unwrap();
+    assert_eq!(tx.required_signers(), &[*client.signer_pubkey()]);
+    // only the message hash is handed to the external signer
+    let signature = Signature(sign_message_bip322(
+        &fixture.env().user_keypair,
+        &tx.message_hash,
+        network,
+    ));
+    assert!(tx
+        .attach_signatures(vec![Signature([0; 64])], network)
+        .is_err());
+    assert!(tx.attach_signatures(vec![], network).is_err());
+    let signed = tx.attach_signatures(vec![signature], network).unwrap();
+    client.broadcast_transaction(signed).await.unwrap();
+    fixture.reload_market(&market).await;
+    let position = fixture.user_client().get_supply_position(&market).unwrap();
+    assert_eq!(position.deposited_atoms(), deposit);
+}
@@ -1,7 +1,9 @@
+use arch_sdk::arch_program::pubkey::Pubkey;
 use autara_client::client::{read::AutaraReadClient, tx_broadcast::AutaraClientError};
 use autara_lib::{
-    error::LendingError, event::AutaraEvent, math::ifixed_point::IFixedPoint,
-    pda::find_borrow_position_pda, token::get_associated_token_address,
+    error::LendingError, event::AutaraEvent, ixs::AurataInstructionTag,
+    math::ifixed_point::IFixedPoint, pda::find_borrow_position_pda,
+    token::get_associated_token_address,
 };
 
 use crate::fixture::autara_fixture::{AutaraFixture, BTC, UNHEALTHY_LTV, USDC};
@@ -301,3 +303,99 @@ async fn cant_liquidate_twice_with_callback_unhealthy_position() {
     assert!(matches!(first_event, AutaraEvent::Liquidate(_)));
     assert_eq!(err, LendingError::PositionIsHealthy);
 }
+
+#[tokio::test]
+async fn liquidate_batch_bundles_positions_and_creates_atas_once() {
+    let mut fixture = AutaraFixture::new().await;
+    let market = fixture.create_market().await;
+    fixture.reload_market(&market).await;
+    let program_id = fixture.env().autara_program_pubkey;
+    let positions = [
+        *fixture.user_client().signer_pubkey(),
+        *fixture.user_two_client().signer_pubkey(),
+    ]
+    .map(|authority| {
+        (
+            find_borrow_position_pda(&program_id, &market, &authority).0,
+            None,
+        )
+    });
+    let mut tx_builder = fixture.user_client().tx_builder();
+    // a fresh liquidator has no token account yet
+    tx_builder.authority_key = Pubkey::new_unique();
+    let tx = tx_builder
+        .liquidate_batch(&market, &positions)
+        .await
+        .unwrap();
+    let liquidate_ixs = tx
+        .instructions
+        .iter()
+        .filter(|ix| {
+            ix.program_id == program_id
+                && AurataInstructionTag::try_from(ix.data[0]) == Ok(AurataInstructionTag::Liquidate)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(liquidate_ixs.len(), 2);
+    for (ix, (position, _)) in liquidate_ixs.iter().zip(positions.iter()) {
+        assert!(ix.accounts.iter().any(|meta| meta.pubkey == *position));
+    }
+    let create_ata_ixs = tx
+        .instructions
+        .iter()
+        .filter(|ix| ix.program_id != program_id)
+        .count();
+    assert_eq!(create_ata_ixs, 2);
+    tx.check_limits().unwrap();
+}
+
+#[tokio::test]
+async fn positions_are_sorted_by_time_to_liquidation() {
+    let mut fixture = AutaraFixture::new().await;
+    let market = fixture.create_market().await;
+    fixture
+        .user_client()
+        .supply(&market, USDC(100_000.))
+        .await
+        .unwrap();
+    fixture
+        .user_client()
+        .deposit_collateral(&market, BTC(0.1))
+        .await
+        .unwrap();
+    fixture
+        .user_client()
+        .borrow(&market, USDC(1_000.))
+        .await
+        .unwrap();
+    fixture
+        .user_two_client()
+        .deposit_collateral(&market, BTC(0.1))
+        .await
+        .unwrap();
+    fixture
+        .user_two_client()
+        .borrow(&market, USDC(5_000.))
+        .await
+        .unwrap();
+    fixture.reload_market(&market).await;
+    let read_client = fixture.user_client().read_client();
+    let user_position = read_client
+        .get_borrow_position(&market, fixture.user_client().signer_pubkey())
+        .0;
+    let user_two_position = read_client
+        .get_borrow_position(&market, fixture.user_two_client().signer_pubkey())
+        .0;
+    let positions = read_client.positions_by_time_to_liquidation(&market);
+    assert_eq!(positions.len(), 2);
+    assert_eq!(positions[0].0, user_two_position);
+    assert_eq!(positions[1].0, user_position);
+    assert!(positions[0].1.unwrap() < positions[1].1.unwrap());
+
+    fixture.env().push_collateral_price(55000.).await.unwrap();
+    fixture.reload_market(&market).await;
+    let positions = fixture
+        .user_client()
+        .read_client()
+        .positions_by_time_to_liquidation(&market);
+    assert_eq!(positions[0], (user_two_position, Some(0)));
+}
@@ -5,5 +5,7 @@ pub mod create_market;
 pub mod fees;
 pub mod lend;
 pub mod liquidate;
+pub mod program_id;
+pub mod simulation;
 pub mod socialize_loss;
 pub mod update_config;
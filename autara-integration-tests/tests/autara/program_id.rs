@@ -0,0 +1,22 @@
+use arch_sdk::arch_program::pubkey::Pubkey;
+use autara_client::client::client_with_signer::AutaraFullClientWithSigner;
+
+use crate::fixture::autara_fixture::AutaraFixture;
+
+#[tokio::test]
+async fn verify_program_id_passes_for_deployed_program() {
+    let fixture = AutaraFixture::new().await;
+    fixture.user_client().verify_program_id().await.unwrap();
+}
+
+#[tokio::test]
+async fn verify_program_id_fails_for_wrong_program_id() {
+    let fixture = AutaraFixture::new().await;
+    let client = AutaraFullClientWithSigner::new_simple(
+        fixture.user_client().rpc_client().clone(),
+        arch_sdk::arch_program::bitcoin::Network::Regtest,
+        Pubkey::new_unique(),
+        fixture.env().user_keypair,
+    );
+    client.verify_program_id().await.unwrap_err();
+}
@@ -0,0 +1,58 @@
+use autara_client::client::read::AutaraReadClient;
+use autara_lib::{
+    math::{ifixed_point::IFixedPoint, safe_math::SafeMath},
+    oracle::oracle_price::OracleRate,
+    state::market_wrapper::MarketSide,
+};
+
+use crate::fixture::autara_fixture::{AutaraFixture, BTC, USDC};
+
+#[tokio::test]
+async fn overridden_collateral_rate_is_reflected_in_simulated_health() {
+    let mut fixture = AutaraFixture::new().await;
+    let market = fixture.create_market().await;
+    fixture
+        .user_client()
+        .supply(&market, USDC(1_000_000.))
+        .await
+        .unwrap();
+    fixture
+        .user_client()
+        .deposit_collateral(&market, BTC(1.))
+        .await
+        .unwrap();
+    fixture
+        .user_client()
+        .borrow(&market, USDC(50_000.))
+        .await
+        .unwrap();
+    fixture.reload_market(&market).await;
+    let user = *fixture.user_client().signer_pubkey();
+    let read_client = fixture.user_client().read_client();
+    let health = read_client
+        .get_borrow_position_health(&market, &user)
+        .unwrap();
+    let collateral_rate = *read_client.get_market(&market).unwrap().collateral_oracle();
+    let crashed_rate = OracleRate::new(
+        collateral_rate
+            .rate()
+            .safe_mul(IFixedPoint::from_i64_u64_ratio(8, 10))
+            .unwrap(),
+        collateral_rate.confidence(),
+    );
+    let simulated_health = read_client
+        .override_oracle_rate(market, MarketSide::Collateral, crashed_rate)
+        .get_borrow_position_health(&market, &user)
+        .unwrap();
+    assert_eq!(simulated_health.borrowed_atoms, health.borrowed_atoms);
+    assert!(simulated_health.ltv > health.ltv);
+    assert!(simulated_health.collateral_value < health.collateral_value);
+    // the loaded market is left untouched
+    assert_eq!(
+        read_client
+            .get_borrow_position_health(&market, &user)
+            .unwrap()
+            .ltv,
+        health.ltv
+    );
+}
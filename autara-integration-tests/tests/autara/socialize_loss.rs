@@ -124,3 +124,70 @@ async fn can_donate() {
     };
     assert!(*amount >= 100000000 + 1000000 - 1); // rounding down error
 }
+
+#[tokio::test]
+async fn curator_can_socialize_all_eligible_positions() {
+    let mut fixture = AutaraFixture::new().await;
+    let market = fixture.create_market().await;
+    fixture
+        .user_two_client()
+        .supply(&market, 100000000)
+        .await
+        .unwrap();
+    fixture
+        .user_client()
+        .deposit_collateral(&market, 1000000)
+        .await
+        .unwrap();
+    fixture.user_client().borrow(&market, 10000).await.unwrap();
+    fixture
+        .user_two_client()
+        .deposit_collateral(&market, 1000000)
+        .await
+        .unwrap();
+    fixture
+        .user_two_client()
+        .borrow(&market, 20000)
+        .await
+        .unwrap();
+    fixture.env().push_supply_price(10000000000.).await.unwrap();
+    fixture.env().push_collateral_price(0.1).await.unwrap();
+    fixture.reload().await;
+    assert!(
+        fixture
+            .user_client()
+            .get_borrow_position_health(&market)
+            .unwrap()
+            .ltv
+            > 1.into()
+    );
+    assert!(
+        fixture
+            .user_two_client()
+            .get_borrow_position_health(&market)
+            .unwrap()
+            .ltv
+            > 1.into()
+    );
+    let debt_socialized = fixture
+        .curator_client()
+        .socialize_all_eligible(&market)
+        .await
+        .unwrap();
+    assert!(debt_socialized >= 10000 + 20000);
+    fixture.reload().await;
+    let market_w = fixture
+        .user_client()
+        .read_client()
+        .get_market(&market)
+        .unwrap();
+    assert_eq!(market_w.market().supply_vault().total_borrow().unwrap(), 0);
+    assert_eq!(
+        fixture
+            .curator_client()
+            .socialize_all_eligible(&market)
+            .await
+            .unwrap(),
+        0
+    );
+}
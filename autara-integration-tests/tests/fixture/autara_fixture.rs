@@ -30,6 +30,8 @@ pub const LTV: IFixedPoint = IFixedPoint::from_i64_u64_ratio(8, 10);
 pub const UNHEALTHY_LTV: IFixedPoint = IFixedPoint::from_i64_u64_ratio(9, 10);
 pub const LIQUIDATION_BONUS: IFixedPoint = IFixedPoint::from_i64_u64_ratio(5, 100);
 pub const MAX_UTILISATION_RATE: IFixedPoint = IFixedPoint::from_i64_u64_ratio(9, 10);
+pub const COLLATERAL_PRICE: f64 = 100_000.;
+pub const SUPPLY_PRICE: f64 = 1.;
 
 impl AutaraFixture {
     pub async fn new() -> Self {
@@ -44,11 +46,7 @@ impl AutaraFixture {
         .await
         .unwrap();
         let this = Self::from_env(arch_client, env, admin);
-        tokio::try_join!(
-            this.env().push_collateral_price(100_000.),
-            this.env().push_supply_price(1.)
-        )
-        .unwrap();
+        this.push_default_prices().await;
         return this;
     }
 
@@ -88,6 +86,14 @@ impl AutaraFixture {
         self.user_client.with_signer(self.admin.clone())
     }
 
+    async fn push_default_prices(&self) {
+        tokio::try_join!(
+            self.env().push_collateral_price(COLLATERAL_PRICE),
+            self.env().push_supply_price(SUPPLY_PRICE)
+        )
+        .unwrap();
+    }
+
     pub async fn reload(&mut self) {
         self.user_client
             .full_reload()
@@ -102,6 +108,13 @@ impl AutaraFixture {
             .expect("Failed to reload user client");
     }
 
+    pub async fn reload_accounts(&mut self, pubkeys: &[Pubkey]) {
+        self.user_client
+            .reload_accounts(pubkeys)
+            .await
+            .expect("Failed to reload accounts");
+    }
+
     pub async fn fetch_balance(&self, user: &Pubkey) -> Balance {
         let balance = self
             .user_client
@@ -148,6 +161,8 @@ impl AutaraFixture {
                     collateral_oracle_config: self.env().collateral_oracle_config(),
                     interest_rate: InterestRateCurveKind::new_adaptive(),
                     lending_market_fee_in_bps: 100,
+                    metadata_uri: Default::default(),
+                    borrow_origination_grace_seconds: 0,
                 },
                 self.env().supply_mint,
                 self.env().collateral_mint,
@@ -97,6 +97,28 @@ pub enum LendingError {
     CannotSocializeDebtForHealthyPosition,
     UnsupportedMintDecimals,
     InvalidOracleConfig,
+    LiquidationInvariantViolated,
+    MinLiquidityBufferReached,
+    InvalidGlobalConfig,
+    InvalidInstructionArguments,
+    OracleNotInitialized,
+    BorrowCapLtvReached,
+    ClockWentBackward,
+    UnsupportedAccountVersion,
+    CollateralVaultOverflow,
+    BpsOutOfRange,
+    InvalidMaxSupply,
+    DonationsDisabled,
+    InsufficientMarketDepth,
+    PolylineTooManyPoints,
+    PolylineFirstPointInvalid,
+    PolylinePointsNotInOrder,
+    SameSupplyAndCollateralMint,
+    ReentrancyDetected,
+    CallbackProgramNotAllowed,
+    CuratorMarketLimitReached,
+    MissingOracleAccount,
+    MinCollateralAfterLiquidationTooHigh,
 }
 
 impl LendingError {
@@ -1,9 +1,10 @@
-use arch_program::pubkey::Pubkey;
+use arch_program::{hash::Hash, pubkey::Pubkey};
 use borsh::{BorshDeserialize, BorshSerialize};
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
 use crate::{
     ixs::AurataInstructionTag,
+    math::ufixed_point::UFixedPoint,
     oracle::oracle_price::OracleRate,
     state::{borrow_position::BorrowPositionHealth, supply_vault::SupplyVaultSummary},
 };
@@ -26,6 +27,7 @@ pub enum AurataEventTag {
     WithdrawAndRepay,
     SocializeLoss,
     Donation,
+    SweepFeeDust,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
@@ -125,6 +127,21 @@ pub struct DonateSupplyEvent {
     pub amount: u64,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(
+    feature = "client",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct SweepFeeDustEvent {
+    pub market: Pubkey,
+    pub admin: Pubkey,
+    pub mint: Pubkey,
+    /// Pending fee shares worth less than an atom given to the suppliers
+    pub swept_shares: UFixedPoint,
+    pub supply_vault_summary: SupplyVaultSummary,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(
     feature = "client",
@@ -145,19 +162,79 @@ pub enum AutaraEvent {
     ReedeemCuratorFees(ReedeemFeeEvent),
     SocializeLoss(SocializeLossEvent),
     DonateSupply(DonateSupplyEvent),
+    SweepFeeDust(SweepFeeDustEvent),
+}
+
+/// Decode the event carried by the data of a `Log` instruction. `None` for the data of any
+/// other instruction, or if trailing bytes follow the event
+pub fn decode_log_event(data: &[u8]) -> Option<AutaraEvent> {
+    AutaraEvent::from_bytes(data)
 }
 
 impl AutaraEvent {
+    /// See [decode_log_event]
     pub fn from_bytes(bytes: &[u8]) -> Option<AutaraEvent> {
-        let mut cursor = &mut &bytes[..];
+        let mut cursor = bytes;
         let tag = AurataInstructionTag::deserialize(&mut cursor).ok()?;
         if tag != AurataInstructionTag::Log {
             return None;
         }
-        AutaraEvent::deserialize(cursor).ok()
+        let event = AutaraEvent::deserialize(&mut cursor).ok()?;
+        cursor.is_empty().then_some(event)
+    }
+
+    /// Supply or borrow position the event applies to, `None` for market wide events
+    pub fn position(&self) -> Option<&Pubkey> {
+        match self {
+            AutaraEvent::Liquidate(event) => Some(&event.liquidatee_position),
+            AutaraEvent::Supply(event)
+            | AutaraEvent::Withdraw(event)
+            | AutaraEvent::DepositCollateral(event)
+            | AutaraEvent::WithdrawCollateral(event)
+            | AutaraEvent::Borrow(event)
+            | AutaraEvent::Repay(event) => Some(&event.position),
+            AutaraEvent::BorrowAndDeposit(event) | AutaraEvent::WithdrawAndRepay(event) => {
+                Some(&event.position)
+            }
+            AutaraEvent::SocializeLoss(event) => Some(&event.position),
+            AutaraEvent::ReedeemProtocolFees(_)
+            | AutaraEvent::ReedeemCuratorFees(_)
+            | AutaraEvent::DonateSupply(_)
+            | AutaraEvent::SweepFeeDust(_) => None,
+        }
     }
 }
 
+/// Event of a position along with the transaction which emitted it
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "client",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct PositionEvent {
+    pub txid: Hash,
+    pub event: AutaraEvent,
+}
+
+/// Timeline of the events of `position`, keeping the order of the given transactions
+/// and of the events within each transaction
+pub fn position_history(
+    transactions: impl IntoIterator<Item = (Hash, AutaraEvents)>,
+    position: &Pubkey,
+) -> Vec<PositionEvent> {
+    transactions
+        .into_iter()
+        .flat_map(|(txid, events)| {
+            events
+                .events
+                .into_iter()
+                .filter(|event| event.position() == Some(position))
+                .map(move |event| PositionEvent { txid, event })
+        })
+        .collect()
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
 #[cfg_attr(
     feature = "client",
@@ -182,7 +259,7 @@ pub mod client {
                 events: get_ix_data_with_program_ids(tx)
                     .filter_map(|ix| {
                         if validate_program_id(ix.0) {
-                            AutaraEvent::from_bytes(ix.1)
+                            decode_log_event(ix.1)
                         } else {
                             None
                         }
@@ -264,6 +341,10 @@ impl BorshSerialize for AutaraEvent {
                 AurataEventTag::Donation.serialize(writer)?;
                 event.serialize(writer)
             }
+            AutaraEvent::SweepFeeDust(event) => {
+                AurataEventTag::SweepFeeDust.serialize(writer)?;
+                event.serialize(writer)
+            }
         }
     }
 }
@@ -303,6 +384,180 @@ impl BorshDeserialize for AutaraEvent {
             AurataEventTag::Donation => {
                 Ok(AutaraEvent::DonateSupply(<_>::deserialize_reader(reader)?))
             }
+            AurataEventTag::SweepFeeDust => {
+                Ok(AutaraEvent::SweepFeeDust(<_>::deserialize_reader(reader)?))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::{
+        ixs::log_ix,
+        oracle::oracle_config::tests::{default_btc_oracle_rate, default_usd_oracle_rate},
+        state::supply_vault::SupplyVaultSummary,
+    };
+
+    use super::*;
+
+    fn single_market_event(position: Pubkey, amount: u64) -> SingleMarketTransactionEvent {
+        SingleMarketTransactionEvent {
+            market: Pubkey::system_program(),
+            user: Pubkey::system_program(),
+            position,
+            mint: Pubkey::system_program(),
+            amount,
+            supply_vault_summary: SupplyVaultSummary::default(),
+            collateral_vault_atoms: 0,
+            supply_oracle_rate: default_usd_oracle_rate(),
+            collateral_oracle_rate: default_btc_oracle_rate(),
+        }
+    }
+
+    fn logged_events(events: Vec<AutaraEvent>) -> AutaraEvents {
+        AutaraEvents {
+            events: events
+                .into_iter()
+                .filter_map(|event| {
+                    let ix = log_ix(&Pubkey::system_program(), &Pubkey::system_program(), event);
+                    AutaraEvent::from_bytes(&ix.data)
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn decode_log_event_roundtrips_every_event() {
+        let position = Pubkey::new_unique();
+        let double_market_event = DoubleMarketTransactionEvent {
+            market: Pubkey::system_program(),
+            user: Pubkey::system_program(),
+            position,
+            mint_in: Pubkey::new_unique(),
+            amount_in: 7,
+            mint_out: Pubkey::new_unique(),
+            amount_out: 8,
+            supply_vault_summary: SupplyVaultSummary::default(),
+            collateral_vault_atoms: 9,
+            supply_oracle_rate: default_usd_oracle_rate(),
+            collateral_oracle_rate: default_btc_oracle_rate(),
+        };
+        let fee_event = ReedeemFeeEvent {
+            market: Pubkey::system_program(),
+            fee_receiver: Pubkey::new_unique(),
+            fee_amount: 10,
+            mint: Pubkey::system_program(),
+            supply_vault_snapshot: SupplyVaultSummary::default(),
+        };
+        let events = vec![
+            AutaraEvent::Liquidate(LiquidateEvent {
+                market: Pubkey::system_program(),
+                liquidator: Pubkey::new_unique(),
+                liquidatee_position: position,
+                supply_mint: Pubkey::new_unique(),
+                collateral_mint: Pubkey::new_unique(),
+                health_before_liquidation: BorrowPositionHealth::default(),
+                health_after_liquidation: BorrowPositionHealth::default(),
+                supply_repaid: 1,
+                collateral_liquidated: 2,
+                liquidator_fee: 3,
+            }),
+            AutaraEvent::Supply(single_market_event(position, 1)),
+            AutaraEvent::Withdraw(single_market_event(position, 2)),
+            AutaraEvent::DepositCollateral(single_market_event(position, 3)),
+            AutaraEvent::WithdrawCollateral(single_market_event(position, 4)),
+            AutaraEvent::BorrowAndDeposit(double_market_event),
+            AutaraEvent::WithdrawAndRepay(double_market_event),
+            AutaraEvent::Borrow(single_market_event(position, 5)),
+            AutaraEvent::Repay(single_market_event(position, 6)),
+            AutaraEvent::ReedeemProtocolFees(fee_event),
+            AutaraEvent::ReedeemCuratorFees(fee_event),
+            AutaraEvent::SocializeLoss(SocializeLossEvent {
+                market: Pubkey::system_program(),
+                position,
+                debt_socialized: 11,
+                collateral_liquidated: 12,
+            }),
+            AutaraEvent::DonateSupply(DonateSupplyEvent {
+                market: Pubkey::system_program(),
+                donor: position,
+                mint: Pubkey::system_program(),
+                amount: 13,
+            }),
+            AutaraEvent::SweepFeeDust(SweepFeeDustEvent {
+                market: Pubkey::system_program(),
+                admin: Pubkey::new_unique(),
+                mint: Pubkey::system_program(),
+                swept_shares: UFixedPoint::from_u64_u64_ratio(1, 3),
+                supply_vault_summary: SupplyVaultSummary::default(),
+            }),
+        ];
+        for event in events {
+            let mut data = log_ix(
+                &Pubkey::system_program(),
+                &Pubkey::system_program(),
+                event.clone(),
+            )
+            .data;
+            assert_eq!(decode_log_event(&data), Some(event));
+            data.push(0);
+            assert_eq!(decode_log_event(&data), None);
         }
     }
+
+    #[test]
+    fn decode_log_event_ignores_other_instructions() {
+        let ix = crate::ixs::sync_market_ix(Pubkey::new_unique(), Pubkey::new_unique());
+        assert_eq!(decode_log_event(&ix.data), None);
+        assert_eq!(decode_log_event(&[]), None);
+    }
+
+    #[test]
+    fn position_history_decodes_logged_events_in_order() {
+        let position = Pubkey::new_unique();
+        let other_position = Pubkey::new_unique();
+        let first_tx = Hash::from([1; 32]);
+        let second_tx = Hash::from([2; 32]);
+        let transactions = vec![
+            (
+                first_tx,
+                logged_events(vec![
+                    AutaraEvent::DepositCollateral(single_market_event(position, 1)),
+                    AutaraEvent::Supply(single_market_event(other_position, 2)),
+                    AutaraEvent::Borrow(single_market_event(position, 3)),
+                ]),
+            ),
+            (
+                second_tx,
+                logged_events(vec![
+                    AutaraEvent::DonateSupply(DonateSupplyEvent {
+                        market: Pubkey::system_program(),
+                        donor: position,
+                        mint: Pubkey::system_program(),
+                        amount: 4,
+                    }),
+                    AutaraEvent::Repay(single_market_event(position, 5)),
+                ]),
+            ),
+        ];
+        let history = position_history(transactions, &position);
+        assert_eq!(
+            history,
+            vec![
+                PositionEvent {
+                    txid: first_tx,
+                    event: AutaraEvent::DepositCollateral(single_market_event(position, 1)),
+                },
+                PositionEvent {
+                    txid: first_tx,
+                    event: AutaraEvent::Borrow(single_market_event(position, 3)),
+                },
+                PositionEvent {
+                    txid: second_tx,
+                    event: AutaraEvent::Repay(single_market_event(position, 5)),
+                },
+            ]
+        );
+    }
 }
@@ -29,27 +29,105 @@ use crate::{
 )]
 pub struct AdaptiveInterestRateCurve {
     rate_at_target: InterestRatePerSecond,
+    /// Utilisation the curve steers the market to. The parameters are all zero for the
+    /// defaults, which curves created before they were configurable use
+    #[cfg_attr(feature = "client", serde(default))]
+    target_utilisation: IFixedPoint,
+    /// Speed per second at which the rate at target adapts
+    #[cfg_attr(feature = "client", serde(default))]
+    adjustment_speed: IFixedPoint,
+    /// Ratio between the rate at 100% utilisation and the rate at target
+    #[cfg_attr(feature = "client", serde(default))]
+    curve_steepness: IFixedPoint,
 }
 
-const TARGET_UTILISATION_RATE: IFixedPoint = IFixedPoint::from_i64_u64_ratio(9, 10);
-const ONE_MINUS_TARGET_UTILISATION_RATE: IFixedPoint = IFixedPoint::from_i64_u64_ratio(1, 10);
-const ADJUSTMENT_SPEED: IFixedPoint = IFixedPoint::from_i64_u64_ratio(50, SECONDS_PER_YEAR);
-const CURVE_STEEPNESS: u64 = 4;
+pub const DEFAULT_TARGET_UTILISATION_RATE: IFixedPoint = IFixedPoint::from_i64_u64_ratio(9, 10);
+pub const DEFAULT_ADJUSTMENT_SPEED: IFixedPoint =
+    IFixedPoint::from_i64_u64_ratio(50, SECONDS_PER_YEAR);
+pub const DEFAULT_CURVE_STEEPNESS: IFixedPoint = IFixedPoint::from_i64_u64_ratio(4, 1);
+/// Twice the default speed: an idle market at full utilisation keeps its adaptation within
+/// the domain of `checked_exp` for about 6 months, see the note on [AdaptiveInterestRateCurve]
+pub const MAX_ADJUSTMENT_SPEED: IFixedPoint =
+    IFixedPoint::from_i64_u64_ratio(100, SECONDS_PER_YEAR);
+/// Caps the rate at 100% utilisation to 10 times the rate at target
+pub const MAX_CURVE_STEEPNESS: IFixedPoint = IFixedPoint::from_i64_u64_ratio(10, 1);
 
 const INITIAL_RATE_AT_TARGET: InterestRatePerSecond =
     InterestRatePerSecond::const_from_apr(IFixedPoint::from_i64_u64_ratio(4, 100));
 const MIN_RATE_AT_TARGET: InterestRatePerSecond =
     InterestRatePerSecond::const_from_apr(IFixedPoint::from_i64_u64_ratio(1, 100));
-const MAX_RATE_AT_TARGET: InterestRatePerSecond =
+pub(crate) const MAX_RATE_AT_TARGET: InterestRatePerSecond =
     InterestRatePerSecond::const_from_apr(IFixedPoint::from_i64_u64_ratio(200, 100));
 
 impl AdaptiveInterestRateCurve {
     pub fn new() -> Self {
+        Self::new_with_params(
+            DEFAULT_TARGET_UTILISATION_RATE,
+            DEFAULT_ADJUSTMENT_SPEED,
+            DEFAULT_CURVE_STEEPNESS,
+        )
+    }
+
+    /// Curve steering the market to `target_utilisation`, see [Self::is_valid] for the
+    /// accepted parameters
+    pub fn new_with_params(
+        target_utilisation: IFixedPoint,
+        adjustment_speed: IFixedPoint,
+        curve_steepness: IFixedPoint,
+    ) -> Self {
         AdaptiveInterestRateCurve {
             rate_at_target: InterestRatePerSecond::const_from_apr(IFixedPoint::zero()),
+            target_utilisation,
+            adjustment_speed,
+            curve_steepness,
         }
     }
 
+    /// The target utilisation must be within (0, 1), the adjustment speed within
+    /// [0, [MAX_ADJUSTMENT_SPEED]] and the steepness within [1, [MAX_CURVE_STEEPNESS]]
+    pub fn is_valid(&self) -> bool {
+        let target_utilisation = self.target_utilisation();
+        let adjustment_speed = self.adjustment_speed();
+        let curve_steepness = self.curve_steepness();
+        target_utilisation > IFixedPoint::zero()
+            && target_utilisation < IFixedPoint::one()
+            && !adjustment_speed.is_negative()
+            && adjustment_speed <= MAX_ADJUSTMENT_SPEED
+            && curve_steepness >= IFixedPoint::one()
+            && curve_steepness <= MAX_CURVE_STEEPNESS
+    }
+
+    pub fn target_utilisation(&self) -> IFixedPoint {
+        if self.has_default_params() {
+            DEFAULT_TARGET_UTILISATION_RATE
+        } else {
+            self.target_utilisation
+        }
+    }
+
+    pub fn adjustment_speed(&self) -> IFixedPoint {
+        if self.has_default_params() {
+            DEFAULT_ADJUSTMENT_SPEED
+        } else {
+            self.adjustment_speed
+        }
+    }
+
+    pub fn curve_steepness(&self) -> IFixedPoint {
+        if self.has_default_params() {
+            DEFAULT_CURVE_STEEPNESS
+        } else {
+            self.curve_steepness
+        }
+    }
+
+    /// Curves created before the parameters were configurable have them all zeroed
+    fn has_default_params(&self) -> bool {
+        self.target_utilisation.is_zero()
+            && self.adjustment_speed.is_zero()
+            && self.curve_steepness.is_zero()
+    }
+
     pub fn borrow_rate(
         &mut self,
         params: MarketBorrowRateParameters,
@@ -63,14 +141,15 @@ impl AdaptiveInterestRateCurve {
         &self,
         params: MarketBorrowRateParameters,
     ) -> LendingResult<(InterestRatePerSecond, InterestRatePerSecond)> {
-        let err_norm_factor = if params.utilisation_rate > &TARGET_UTILISATION_RATE {
-            ONE_MINUS_TARGET_UTILISATION_RATE
+        let target_utilisation = self.target_utilisation();
+        let err_norm_factor = if params.utilisation_rate > &target_utilisation {
+            IFixedPoint::one().safe_sub(target_utilisation)?
         } else {
-            TARGET_UTILISATION_RATE
+            target_utilisation
         };
         let err = params
             .utilisation_rate
-            .safe_sub(TARGET_UTILISATION_RATE)?
+            .safe_sub(target_utilisation)?
             .safe_div(err_norm_factor)?;
         let start_rate_at_target = self.rate_at_target;
         let avg_rate_at_target;
@@ -79,7 +158,7 @@ impl AdaptiveInterestRateCurve {
             avg_rate_at_target = INITIAL_RATE_AT_TARGET;
             end_rate_at_target = INITIAL_RATE_AT_TARGET;
         } else {
-            let speed = ADJUSTMENT_SPEED.safe_mul(err)?;
+            let speed = self.adjustment_speed().safe_mul(err)?;
             let linear_adaptation = speed.safe_mul(params.elapsed_seconds_since_last_update)?;
             if linear_adaptation.is_zero() {
                 avg_rate_at_target = start_rate_at_target;
@@ -97,18 +176,20 @@ impl AdaptiveInterestRateCurve {
                 );
             }
         }
-        let rate_at_target = Self::curve(avg_rate_at_target, err)?;
+        let rate_at_target = self.curve(avg_rate_at_target, err)?;
         Ok((rate_at_target, end_rate_at_target))
     }
 
     fn curve(
+        &self,
         rate_at_target: InterestRatePerSecond,
         err: IFixedPoint,
     ) -> LendingResult<InterestRatePerSecond> {
+        let curve_steepness = self.curve_steepness();
         let coeff = if err.is_negative() {
-            IFixedPoint::one().safe_sub(IFixedPoint::one().safe_div(CURVE_STEEPNESS)?)?
+            IFixedPoint::one().safe_sub(IFixedPoint::one().safe_div(curve_steepness)?)?
         } else {
-            CURVE_STEEPNESS.safe_sub(IFixedPoint::one())?
+            curve_steepness.safe_sub(IFixedPoint::one())?
         };
         coeff
             .safe_mul(err)?
@@ -131,7 +212,10 @@ impl AdaptiveInterestRateCurve {
 /// Morpho tests : https://github.com/morpho-org/morpho-blue-irm/blob/main/test/forge/AdaptiveCurveIrmTest.sol
 pub mod tests {
     use super::*;
-    use crate::{assert_eq_float, constant::SECONDS_PER_DAY};
+    use crate::{
+        assert_eq_float, constant::SECONDS_PER_DAY,
+        interest_rate::interest_rate_kind::InterestRateCurveKind,
+    };
 
     fn days_to_seconds(days: u64) -> u64 {
         days * SECONDS_PER_DAY
@@ -190,7 +274,8 @@ pub mod tests {
         assert_eq_float!(
             rate,
             (INITIAL_RATE_AT_TARGET.0.to_float() * 4.)
-                * ((1.9836 - 1.) / (ADJUSTMENT_SPEED.to_float() * days_to_seconds(5) as f64)),
+                * ((1.9836 - 1.)
+                    / (DEFAULT_ADJUSTMENT_SPEED.to_float() * days_to_seconds(5) as f64)),
             0.05
         );
         assert_eq_float!(
@@ -237,7 +322,7 @@ pub mod tests {
 
         // First establish the curve at target utilization
         let params = MarketBorrowRateParameters {
-            utilisation_rate: &TARGET_UTILISATION_RATE,
+            utilisation_rate: &DEFAULT_TARGET_UTILISATION_RATE,
             elapsed_seconds_since_last_update: days_to_seconds(1),
         };
         let rate = curve.borrow_rate(params).unwrap();
@@ -269,7 +354,7 @@ pub mod tests {
 
         // First establish the curve at target utilization
         let params = MarketBorrowRateParameters {
-            utilisation_rate: &TARGET_UTILISATION_RATE,
+            utilisation_rate: &DEFAULT_TARGET_UTILISATION_RATE,
             elapsed_seconds_since_last_update: days_to_seconds(1),
         };
         let rate = curve.borrow_rate(params).unwrap();
@@ -342,7 +427,7 @@ pub mod tests {
 
             // First establish the curve at target utilization
             let params = MarketBorrowRateParameters {
-                utilisation_rate: &TARGET_UTILISATION_RATE,
+                utilisation_rate: &DEFAULT_TARGET_UTILISATION_RATE,
                 elapsed_seconds_since_last_update: days_to_seconds(1),
             };
             let rate = curve.borrow_rate(params).unwrap();
@@ -354,7 +439,7 @@ pub mod tests {
 
             // Test after elapsed time at target utilization
             let params = MarketBorrowRateParameters {
-                utilisation_rate: &TARGET_UTILISATION_RATE,
+                utilisation_rate: &DEFAULT_TARGET_UTILISATION_RATE,
                 elapsed_seconds_since_last_update: elapsed,
             };
             curve.borrow_rate(params).unwrap();
@@ -373,7 +458,7 @@ pub mod tests {
 
         // First establish the curve at target utilization
         let params = MarketBorrowRateParameters {
-            utilisation_rate: &TARGET_UTILISATION_RATE,
+            utilisation_rate: &DEFAULT_TARGET_UTILISATION_RATE,
             elapsed_seconds_since_last_update: days_to_seconds(1),
         };
         let rate = curve.borrow_rate(params).unwrap();
@@ -384,7 +469,7 @@ pub mod tests {
         );
 
         let mut total_supply_assets = IFixedPoint::one();
-        let mut total_borrow_assets = TARGET_UTILISATION_RATE;
+        let mut total_borrow_assets = DEFAULT_TARGET_UTILISATION_RATE;
 
         // Simulate 3 weeks of minute-by-minute updates
         let minutes_in_3_weeks = 3 * 7 * 24 * 60;
@@ -412,7 +497,7 @@ pub mod tests {
         let final_utilization = total_borrow_assets.safe_div(total_supply_assets).unwrap();
         assert_eq_float!(
             final_utilization.to_float(),
-            TARGET_UTILISATION_RATE.to_float(),
+            DEFAULT_TARGET_UTILISATION_RATE.to_float(),
             0.01
         );
 
@@ -426,4 +511,138 @@ pub mod tests {
             0.1 // 10% tolerance
         );
     }
+
+    #[test]
+    fn custom_target_utilisation() {
+        let mut curve = AdaptiveInterestRateCurve::new_with_params(
+            IFixedPoint::from_i64_u64_ratio(7, 10),
+            DEFAULT_ADJUSTMENT_SPEED,
+            IFixedPoint::from_i64_u64_ratio(2, 1),
+        );
+        assert!(curve.is_valid());
+        let rate_at = |curve: &AdaptiveInterestRateCurve, utilisation_rate| {
+            curve
+                .compute_next_rates(MarketBorrowRateParameters {
+                    utilisation_rate: &utilisation_rate,
+                    elapsed_seconds_since_last_update: days_to_seconds(1),
+                })
+                .unwrap()
+                .0
+                .to_float()
+        };
+        let initial = INITIAL_RATE_AT_TARGET.0.to_float();
+        assert_eq_float!(
+            rate_at(&curve, IFixedPoint::from_i64_u64_ratio(7, 10)),
+            initial
+        );
+        assert_eq_float!(rate_at(&curve, IFixedPoint::zero()), initial / 2.);
+        assert_eq_float!(rate_at(&curve, IFixedPoint::one()), initial * 2.);
+        // the rate at target adapts around the custom target, where the default curve
+        // would lower it at 80% utilisation
+        curve
+            .borrow_rate(MarketBorrowRateParameters {
+                utilisation_rate: &IFixedPoint::from_i64_u64_ratio(7, 10),
+                elapsed_seconds_since_last_update: days_to_seconds(1),
+            })
+            .unwrap();
+        let end_rate_at_target = |utilisation_rate| {
+            curve
+                .compute_next_rates(MarketBorrowRateParameters {
+                    utilisation_rate: &utilisation_rate,
+                    elapsed_seconds_since_last_update: days_to_seconds(1),
+                })
+                .unwrap()
+                .1
+        };
+        let eighty_percent = IFixedPoint::from_i64_u64_ratio(8, 10);
+        assert!(end_rate_at_target(eighty_percent) > INITIAL_RATE_AT_TARGET);
+        assert_eq!(
+            end_rate_at_target(IFixedPoint::from_i64_u64_ratio(7, 10)),
+            INITIAL_RATE_AT_TARGET
+        );
+        assert!(
+            end_rate_at_target(IFixedPoint::from_i64_u64_ratio(6, 10)) < INITIAL_RATE_AT_TARGET
+        );
+        let mut default_curve = AdaptiveInterestRateCurve::new();
+        default_curve
+            .borrow_rate(MarketBorrowRateParameters {
+                utilisation_rate: &eighty_percent,
+                elapsed_seconds_since_last_update: days_to_seconds(1),
+            })
+            .unwrap();
+        assert!(
+            default_curve
+                .compute_next_rates(MarketBorrowRateParameters {
+                    utilisation_rate: &eighty_percent,
+                    elapsed_seconds_since_last_update: days_to_seconds(1),
+                })
+                .unwrap()
+                .1
+                < INITIAL_RATE_AT_TARGET
+        );
+    }
+
+    #[test]
+    fn zeroed_params_use_the_defaults() {
+        let legacy = AdaptiveInterestRateCurve::zeroed();
+        assert!(legacy.is_valid());
+        assert_eq!(legacy.target_utilisation(), DEFAULT_TARGET_UTILISATION_RATE);
+        assert_eq!(legacy.adjustment_speed(), DEFAULT_ADJUSTMENT_SPEED);
+        assert_eq!(legacy.curve_steepness(), DEFAULT_CURVE_STEEPNESS);
+        let params = MarketBorrowRateParameters {
+            utilisation_rate: &IFixedPoint::one(),
+            elapsed_seconds_since_last_update: days_to_seconds(1),
+        };
+        assert_eq!(
+            legacy.compute_next_rates(params).unwrap(),
+            AdaptiveInterestRateCurve::new()
+                .compute_next_rates(params)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_params_are_rejected() {
+        let curve = |target_utilisation, curve_steepness| {
+            AdaptiveInterestRateCurve::new_with_params(
+                target_utilisation,
+                DEFAULT_ADJUSTMENT_SPEED,
+                curve_steepness,
+            )
+        };
+        assert!(!curve(IFixedPoint::zero(), DEFAULT_CURVE_STEEPNESS).is_valid());
+        assert!(!curve(IFixedPoint::one(), DEFAULT_CURVE_STEEPNESS).is_valid());
+        assert!(!curve(IFixedPoint::lit("-0.5"), DEFAULT_CURVE_STEEPNESS).is_valid());
+        assert!(!curve(
+            DEFAULT_TARGET_UTILISATION_RATE,
+            IFixedPoint::from_i64_u64_ratio(1, 2)
+        )
+        .is_valid());
+        assert!(curve(DEFAULT_TARGET_UTILISATION_RATE, IFixedPoint::one()).is_valid());
+        assert!(!InterestRateCurveKind::Adaptive(curve(
+            IFixedPoint::one(),
+            DEFAULT_CURVE_STEEPNESS
+        ))
+        .is_valid());
+    }
+
+    #[test]
+    fn params_above_their_max_are_rejected() {
+        let curve = |adjustment_speed, curve_steepness| {
+            AdaptiveInterestRateCurve::new_with_params(
+                DEFAULT_TARGET_UTILISATION_RATE,
+                adjustment_speed,
+                curve_steepness,
+            )
+        };
+        let above = |max: IFixedPoint| max.safe_add(IFixedPoint::from_bits(1)).unwrap();
+        assert!(curve(MAX_ADJUSTMENT_SPEED, MAX_CURVE_STEEPNESS).is_valid());
+        assert!(!curve(above(MAX_ADJUSTMENT_SPEED), DEFAULT_CURVE_STEEPNESS).is_valid());
+        assert!(!curve(DEFAULT_ADJUSTMENT_SPEED, above(MAX_CURVE_STEEPNESS)).is_valid());
+        assert!(!InterestRateCurveKind::Adaptive(curve(
+            DEFAULT_ADJUSTMENT_SPEED,
+            above(MAX_CURVE_STEEPNESS)
+        ))
+        .is_valid());
+    }
 }
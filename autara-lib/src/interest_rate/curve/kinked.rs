@@ -0,0 +1,162 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use bytemuck::{Pod, Zeroable};
+
+use crate::{
+    error::{LendingError, LendingResult, LendingResultExt},
+    interest_rate::{
+        curve::adaptative_curve::MAX_RATE_AT_TARGET,
+        interest_rate_per_second::InterestRatePerSecond,
+    },
+    math::{bps::ONE_IN_BPS, ifixed_point::IFixedPoint, safe_math::SafeMath},
+};
+
+/// Two slopes curve: the borrow rate grows from `base_rate_bps` by `slope1_bps` up to
+/// the optimal utilisation, then by `slope2_bps` more up to 100% utilisation
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Pod, Zeroable, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(
+    feature = "client",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct KinkedInterestRateCurve {
+    base_rate_bps: u32,
+    slope1_bps: u32,
+    slope2_bps: u32,
+    optimal_utilisation_bps: u32,
+}
+
+impl KinkedInterestRateCurve {
+    pub fn try_new(
+        base_rate_bps: u32,
+        slope1_bps: u32,
+        slope2_bps: u32,
+        optimal_utilisation_bps: u32,
+    ) -> LendingResult<Self> {
+        let curve = Self {
+            base_rate_bps,
+            slope1_bps,
+            slope2_bps,
+            optimal_utilisation_bps,
+        };
+        curve.validate()?;
+        Ok(curve)
+    }
+
+    pub fn validate(&self) -> LendingResult {
+        if self.optimal_utilisation_bps > ONE_IN_BPS {
+            return Err(LendingError::InvalidCurve.into())
+                .with_msg("kinked curve optimal utilisation should be at most 100%");
+        }
+        // the rate at 100% utilisation must stay low enough for the compounding in
+        // sync_clock not to overflow
+        let max_rate_bps =
+            self.base_rate_bps as u64 + self.slope1_bps as u64 + self.slope2_bps as u64;
+        if Self::bps_to_rate_per_second(max_rate_bps) > MAX_RATE_AT_TARGET {
+            return Err(LendingError::InvalidCurve.into()).with_msg(
+                "kinked curve rate at 100% utilisation should be at most the max rate at target",
+            );
+        }
+        Ok(())
+    }
+
+    pub fn base_rate_bps(&self) -> u32 {
+        self.base_rate_bps
+    }
+
+    pub fn slope1_bps(&self) -> u32 {
+        self.slope1_bps
+    }
+
+    pub fn slope2_bps(&self) -> u32 {
+        self.slope2_bps
+    }
+
+    pub fn optimal_utilisation_bps(&self) -> u32 {
+        self.optimal_utilisation_bps
+    }
+
+    pub fn interest_rate_per_second(&self, utilisation_rate_bps: u32) -> InterestRatePerSecond {
+        Self::bps_to_rate_per_second(self.apr_borrow_rate_bps(utilisation_rate_bps) as u64)
+    }
+
+    fn bps_to_rate_per_second(apr_bps: u64) -> InterestRatePerSecond {
+        InterestRatePerSecond::from_apr(
+            IFixedPoint::from_num(apr_bps)
+                .safe_div(ONE_IN_BPS as u64)
+                .expect("should be non zero"),
+        )
+    }
+
+    /// Borrow rate at the given utilisation rate, which is clamped to 100%.
+    /// The result saturates at u32::MAX
+    pub fn apr_borrow_rate_bps(&self, utilisation_rate_bps: u32) -> u32 {
+        let utilisation = utilisation_rate_bps.min(ONE_IN_BPS) as u64;
+        let optimal = self.optimal_utilisation_bps.min(ONE_IN_BPS) as u64;
+        let rate = if utilisation < optimal {
+            let below = self.slope1_bps as u64 * utilisation / optimal;
+            self.base_rate_bps as u64 + below
+        } else {
+            // a kink at 100% leaves no room above it
+            let above = (self.slope2_bps as u64 * (utilisation - optimal))
+                .checked_div(ONE_IN_BPS as u64 - optimal)
+                .unwrap_or(0);
+            self.base_rate_bps as u64 + self.slope1_bps as u64 + above
+        };
+        rate.min(u32::MAX as u64) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn curve() -> KinkedInterestRateCurve {
+        // 2% base, +4% up to 80% utilisation, +60% more up to 100%
+        KinkedInterestRateCurve::try_new(2_00, 4_00, 60_00, 80_00).unwrap()
+    }
+
+    #[test]
+    fn interpolates_below_the_kink() {
+        let curve = curve();
+        assert_eq!(curve.apr_borrow_rate_bps(0), 2_00);
+        assert_eq!(curve.apr_borrow_rate_bps(40_00), 4_00);
+        assert_eq!(curve.apr_borrow_rate_bps(80_00), 6_00);
+    }
+
+    #[test]
+    fn jumps_with_the_second_slope_above_the_kink() {
+        let curve = curve();
+        assert_eq!(curve.apr_borrow_rate_bps(90_00), 36_00);
+        assert_eq!(curve.apr_borrow_rate_bps(10_000), 66_00);
+        // utilisation is clamped to 100%
+        assert_eq!(curve.apr_borrow_rate_bps(2 * ONE_IN_BPS), 66_00);
+    }
+
+    #[test]
+    fn kink_at_the_bounds() {
+        let at_zero = KinkedInterestRateCurve::try_new(1_00, 4_00, 10_00, 0).unwrap();
+        assert_eq!(at_zero.apr_borrow_rate_bps(0), 5_00);
+        assert_eq!(at_zero.apr_borrow_rate_bps(50_00), 10_00);
+        let at_full = KinkedInterestRateCurve::try_new(1_00, 4_00, 10_00, 10_000).unwrap();
+        assert_eq!(at_full.apr_borrow_rate_bps(50_00), 3_00);
+        assert_eq!(at_full.apr_borrow_rate_bps(10_000), 5_00);
+    }
+
+    #[test]
+    fn rate_above_max_rate_at_target_is_rejected() {
+        let err =
+            KinkedInterestRateCurve::try_new(u32::MAX, u32::MAX, u32::MAX, 50_00).unwrap_err();
+        assert_eq!(err, LendingError::InvalidCurve);
+        // 200% at 100% utilisation is the highest accepted rate
+        KinkedInterestRateCurve::try_new(10_00, 40_00, 15_000, 80_00).unwrap();
+        let err = KinkedInterestRateCurve::try_new(10_00, 40_00, 15_001, 80_00).unwrap_err();
+        assert_eq!(err, LendingError::InvalidCurve);
+    }
+
+    #[test]
+    fn optimal_utilisation_above_100_percent_is_rejected() {
+        let err = KinkedInterestRateCurve::try_new(2_00, 4_00, 60_00, ONE_IN_BPS + 1).unwrap_err();
+        assert_eq!(err, LendingError::InvalidCurve);
+    }
+}
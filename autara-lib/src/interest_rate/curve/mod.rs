@@ -1,2 +1,3 @@
 pub mod adaptative_curve;
+pub mod kinked;
 pub mod polyline;
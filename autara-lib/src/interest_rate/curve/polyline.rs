@@ -2,6 +2,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use bytemuck::{Pod, Zeroable};
 
 use crate::{
+    error::{DisplayCow, ErrorWithContext, LendingError},
     interest_rate::interest_rate_per_second::InterestRatePerSecond,
     math::{bps::ONE_IN_BPS, ifixed_point::IFixedPoint, safe_math::SafeMath},
 };
@@ -26,6 +27,41 @@ pub enum PolylineInterestRateCurveError {
     PointsNotInOrder,
 }
 
+impl From<PolylineInterestRateCurveError> for LendingError {
+    fn from(error: PolylineInterestRateCurveError) -> Self {
+        match error {
+            PolylineInterestRateCurveError::TooManyPoints => LendingError::PolylineTooManyPoints,
+            PolylineInterestRateCurveError::FirstPointInvalid => {
+                LendingError::PolylineFirstPointInvalid
+            }
+            PolylineInterestRateCurveError::PointsNotInOrder => {
+                LendingError::PolylinePointsNotInOrder
+            }
+        }
+    }
+}
+
+impl From<PolylineInterestRateCurveError> for ErrorWithContext<LendingError> {
+    #[track_caller]
+    fn from(error: PolylineInterestRateCurveError) -> Self {
+        let msg = match error {
+            PolylineInterestRateCurveError::TooManyPoints => {
+                format!("polyline curve has more than {POLYLINE_MAX_POINTS} points")
+            }
+            PolylineInterestRateCurveError::FirstPointInvalid => {
+                "polyline curve should start at 0% utilization with a non zero rate".to_string()
+            }
+            PolylineInterestRateCurveError::PointsNotInOrder => {
+                "polyline curve points should be strictly increasing".to_string()
+            }
+        };
+        let mut err =
+            ErrorWithContext::new(LendingError::from(error), std::panic::Location::caller());
+        err.msg.push(DisplayCow(msg.into()));
+        err
+    }
+}
+
 impl PolylineInterestRateCurve {
     pub fn try_new(
         points: &[PolylineInterestRatePoint],
@@ -59,7 +95,10 @@ impl PolylineInterestRateCurve {
         )
     }
 
+    /// Borrow rate at the given utilization rate, which is clamped to 100%.
+    /// The last segment is extrapolated up to 100% utilization
     pub fn apr_borrow_rate_bps(&self, utilization_rate_bps: u32) -> u32 {
+        let utilization_rate_bps = utilization_rate_bps.min(ONE_IN_BPS);
         let mut points = self.points();
         let mut start = points.next().expect("At least one point must exist");
         let mut end = match points.next() {
@@ -78,6 +117,49 @@ impl PolylineInterestRateCurve {
         Line { start, end }.value_at(utilization_rate_bps)
     }
 
+    /// Same as [`Self::apr_borrow_rate_bps`] with the borrow rate capped at `max_borrow_rate_bps`
+    pub fn apr_borrow_rate_bps_capped(
+        &self,
+        utilization_rate_bps: u32,
+        max_borrow_rate_bps: u32,
+    ) -> u32 {
+        self.apr_borrow_rate_bps(utilization_rate_bps)
+            .min(max_borrow_rate_bps)
+    }
+
+    /// Lowest utilization rate at which the borrow rate reaches `borrow_rate_bps`, the inverse
+    /// of [`Self::apr_borrow_rate_bps`]. `None` if the rate is below the first point, clamped
+    /// to 100% if the curve does not reach the rate before full utilization
+    pub fn utilisation_at_borrow_rate_bps(&self, borrow_rate_bps: u32) -> Option<u32> {
+        let mut points = self.points();
+        let mut start = points.next().expect("At least one point must exist");
+        if borrow_rate_bps < start.borrow_rate_bps {
+            return None;
+        }
+        let mut end = match points.next() {
+            Some(next) => next,
+            None => {
+                return Some(if borrow_rate_bps == start.borrow_rate_bps {
+                    0
+                } else {
+                    ONE_IN_BPS
+                });
+            }
+        };
+        for point in points {
+            if borrow_rate_bps <= end.borrow_rate_bps {
+                break;
+            }
+            start = end;
+            end = point;
+        }
+        Some(
+            Line { start, end }
+                .utilization_at(borrow_rate_bps)
+                .min(ONE_IN_BPS),
+        )
+    }
+
     fn validate_points(
         points: &[PolylineInterestRatePoint],
     ) -> Result<(), PolylineInterestRateCurveError> {
@@ -172,15 +254,59 @@ struct Line<'a> {
 }
 
 impl<'a> Line<'a> {
-    /// caller should assure that start and end are not the same point
-    /// and that utilization_rate_bps should be greater than or equal to start.utilization_rate_bps
+    /// Linear interpolation between start and end, extrapolated past end.
+    /// Utilization rates below start are clamped to start and a vertical line
+    /// (start and end at the same utilization rate) steps from start to end.
+    /// The result saturates at u32::MAX
     fn value_at(&self, utilization_rate_bps: u32) -> u32 {
-        self.start.borrow_rate_bps.saturating_add(
-            ((self.end.borrow_rate_bps as u64 - self.start.borrow_rate_bps as u64)
-                * (utilization_rate_bps as u64 - self.start.utilization_rate_bps as u64)
-                / (self.end.utilization_rate_bps as u64 - self.start.utilization_rate_bps as u64))
-                as u32,
-        )
+        let Some(utilization_delta) = self
+            .end
+            .utilization_rate_bps
+            .checked_sub(self.start.utilization_rate_bps)
+            .filter(|delta| *delta > 0)
+        else {
+            return if utilization_rate_bps < self.end.utilization_rate_bps {
+                self.start.borrow_rate_bps
+            } else {
+                self.end.borrow_rate_bps
+            };
+        };
+        let borrow_rate_delta = self
+            .end
+            .borrow_rate_bps
+            .saturating_sub(self.start.borrow_rate_bps) as u64;
+        let increase = borrow_rate_delta
+            .checked_mul(
+                utilization_rate_bps.saturating_sub(self.start.utilization_rate_bps) as u64,
+            )
+            .and_then(|increase| increase.checked_div(utilization_delta as u64))
+            .map_or(u32::MAX, |increase| {
+                u32::try_from(increase).unwrap_or(u32::MAX)
+            });
+        self.start.borrow_rate_bps.saturating_add(increase)
+    }
+
+    /// Inverse of [`Self::value_at`] for an increasing line: the lowest utilization rate at
+    /// which the value reaches `borrow_rate_bps`, extrapolated past end.
+    /// Borrow rates below start are clamped to start
+    fn utilization_at(&self, borrow_rate_bps: u32) -> u32 {
+        let borrow_rate_delta = self
+            .end
+            .borrow_rate_bps
+            .saturating_sub(self.start.borrow_rate_bps) as u64;
+        if borrow_rate_delta == 0 {
+            return self.start.utilization_rate_bps;
+        }
+        let utilization_delta = self
+            .end
+            .utilization_rate_bps
+            .saturating_sub(self.start.utilization_rate_bps) as u64;
+        let increase = (borrow_rate_bps.saturating_sub(self.start.borrow_rate_bps) as u64
+            * utilization_delta)
+            .div_ceil(borrow_rate_delta);
+        self.start
+            .utilization_rate_bps
+            .saturating_add(u32::try_from(increase).unwrap_or(u32::MAX))
     }
 }
 
@@ -217,6 +343,66 @@ mod tests {
         assert_eq!(line.value_at(6000), 1200);
     }
 
+    #[test]
+    fn test_line_value_at_extreme_values_saturates() {
+        let start = PolylineInterestRatePoint {
+            utilization_rate_bps: 0,
+            borrow_rate_bps: 1,
+        };
+        let end = PolylineInterestRatePoint {
+            utilization_rate_bps: 1,
+            borrow_rate_bps: u32::MAX,
+        };
+        let line = Line {
+            start: &start,
+            end: &end,
+        };
+        assert_eq!(line.value_at(0), 1);
+        assert_eq!(line.value_at(1), u32::MAX);
+        assert_eq!(line.value_at(u32::MAX), u32::MAX);
+
+        let curve =
+            PolylineInterestRateCurve::try_new(&[(0, 1).into(), (ONE_IN_BPS, u32::MAX).into()])
+                .unwrap();
+        assert_eq!(
+            curve.apr_borrow_rate_bps(ONE_IN_BPS / 2),
+            1 + (u32::MAX - 1) / 2
+        );
+        assert_eq!(curve.apr_borrow_rate_bps(ONE_IN_BPS), u32::MAX);
+        assert_eq!(curve.apr_borrow_rate_bps(u32::MAX), u32::MAX);
+    }
+
+    #[test]
+    fn test_line_value_at_degenerate_cases() {
+        let start = PolylineInterestRatePoint {
+            utilization_rate_bps: 5000,
+            borrow_rate_bps: 200,
+        };
+        let end = PolylineInterestRatePoint {
+            utilization_rate_bps: 5000,
+            borrow_rate_bps: 1000,
+        };
+        let vertical = Line {
+            start: &start,
+            end: &end,
+        };
+        assert_eq!(vertical.value_at(0), 200);
+        assert_eq!(vertical.value_at(5000), 1000);
+        assert_eq!(vertical.value_at(u32::MAX), 1000);
+
+        let end = PolylineInterestRatePoint {
+            utilization_rate_bps: 8000,
+            borrow_rate_bps: 1000,
+        };
+        let line = Line {
+            start: &start,
+            end: &end,
+        };
+        // below start is clamped to start
+        assert_eq!(line.value_at(0), 200);
+        assert_eq!(line.value_at(4999), 200);
+    }
+
     #[test]
     fn test_polyline_interest_rate_point_maybe_uninit() {
         // Test initialized point
@@ -356,6 +542,29 @@ mod tests {
         assert_eq!(curve, Err(PolylineInterestRateCurveError::PointsNotInOrder));
     }
 
+    #[test]
+    fn polyline_errors_map_to_distinct_lending_errors() {
+        let codes = [
+            PolylineInterestRateCurveError::TooManyPoints,
+            PolylineInterestRateCurveError::FirstPointInvalid,
+            PolylineInterestRateCurveError::PointsNotInOrder,
+        ]
+        .map(|error| LendingError::from(error) as u8);
+        assert_eq!(
+            codes,
+            [
+                LendingError::PolylineTooManyPoints as u8,
+                LendingError::PolylineFirstPointInvalid as u8,
+                LendingError::PolylinePointsNotInOrder as u8,
+            ]
+        );
+        assert!(!codes.contains(&(LendingError::InvalidCurve as u8)));
+        let err: ErrorWithContext<LendingError> =
+            PolylineInterestRateCurveError::PointsNotInOrder.into();
+        assert_eq!(err.error, LendingError::PolylinePointsNotInOrder);
+        assert!(err.msg[0].0.contains("strictly increasing"));
+    }
+
     #[test]
     fn test_polyline_points_iterator() {
         let points = vec![
@@ -421,6 +630,56 @@ mod tests {
         assert_eq!(curve.apr_borrow_rate_bps(10000), 1800);
     }
 
+    #[test]
+    fn test_polyline_utilisation_at_borrow_rate_bps_interpolation() {
+        let curve =
+            PolylineInterestRateCurve::try_new(&[(0, 200).into(), (5000, 1000).into()]).unwrap();
+
+        // Test exact points
+        assert_eq!(curve.utilisation_at_borrow_rate_bps(200), Some(0));
+        assert_eq!(curve.utilisation_at_borrow_rate_bps(1000), Some(5000));
+
+        // Test interpolation
+        assert_eq!(curve.utilisation_at_borrow_rate_bps(600), Some(2500)); // Midpoint
+        assert_eq!(curve.utilisation_at_borrow_rate_bps(400), Some(1250)); // Quarter point
+
+        // Test beyond last point
+        assert_eq!(curve.utilisation_at_borrow_rate_bps(1800), Some(10000));
+        assert_eq!(curve.utilisation_at_borrow_rate_bps(5000), Some(10000));
+
+        // Test below first point
+        assert_eq!(curve.utilisation_at_borrow_rate_bps(199), None);
+    }
+
+    #[test]
+    fn test_polyline_utilisation_at_borrow_rate_bps_multiple_segments() {
+        let curve = PolylineInterestRateCurve::try_new(&[
+            (0, 100).into(),
+            (2000, 300).into(),
+            (8000, 1500).into(),
+        ])
+        .unwrap();
+        assert_eq!(curve.utilisation_at_borrow_rate_bps(200), Some(1000));
+        assert_eq!(curve.utilisation_at_borrow_rate_bps(300), Some(2000));
+        assert_eq!(curve.utilisation_at_borrow_rate_bps(900), Some(5000));
+        assert_eq!(curve.utilisation_at_borrow_rate_bps(1500), Some(8000));
+        assert_eq!(curve.utilisation_at_borrow_rate_bps(1900), Some(10000));
+
+        // rounds up to the first utilization reaching the rate
+        let utilization = curve.utilisation_at_borrow_rate_bps(301).unwrap();
+        assert_eq!(utilization, 2005);
+        assert!(curve.apr_borrow_rate_bps(utilization) >= 301);
+        assert!(curve.apr_borrow_rate_bps(utilization - 1) < 301);
+    }
+
+    #[test]
+    fn test_polyline_utilisation_at_borrow_rate_bps_single_point() {
+        let curve = PolylineInterestRateCurve::try_new(&[(0, 500).into()]).unwrap();
+        assert_eq!(curve.utilisation_at_borrow_rate_bps(499), None);
+        assert_eq!(curve.utilisation_at_borrow_rate_bps(500), Some(0));
+        assert_eq!(curve.utilisation_at_borrow_rate_bps(501), Some(ONE_IN_BPS));
+    }
+
     #[test]
     fn test_polyline_borrow_rate_bps_multiple_segments() {
         let points = vec![
@@ -452,4 +711,30 @@ mod tests {
         // Test beyond last point
         assert_eq!(curve.apr_borrow_rate_bps(10000), 1900);
     }
+
+    #[test]
+    fn test_polyline_utilization_above_100_percent_is_clamped() {
+        let curve = PolylineInterestRateCurve::try_new(&[
+            (0, 100).into(),
+            (2000, 300).into(),
+            (8000, 1500).into(),
+        ])
+        .unwrap();
+        assert_eq!(curve.apr_borrow_rate_bps(ONE_IN_BPS), 1900);
+        assert_eq!(curve.apr_borrow_rate_bps(ONE_IN_BPS + 1), 1900);
+        assert_eq!(curve.apr_borrow_rate_bps(2 * ONE_IN_BPS), 1900);
+        assert_eq!(curve.apr_borrow_rate_bps(u32::MAX), 1900);
+
+        let default_curve = PolylineInterestRateCurve::default();
+        assert_eq!(default_curve.apr_borrow_rate_bps(u32::MAX), 10_000);
+    }
+
+    #[test]
+    fn test_polyline_borrow_rate_cap() {
+        let curve = PolylineInterestRateCurve::default();
+        assert_eq!(curve.apr_borrow_rate_bps_capped(0, 50_00), 2_00);
+        assert_eq!(curve.apr_borrow_rate_bps_capped(92_00, 50_00), 7_00);
+        assert_eq!(curve.apr_borrow_rate_bps_capped(96_00, 50_00), 50_00);
+        assert_eq!(curve.apr_borrow_rate_bps_capped(u32::MAX, 50_00), 50_00);
+    }
 }
@@ -1,12 +1,17 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 
 use crate::{
-    error::LendingResult,
+    error::{LendingError, LendingResult},
     interest_rate::{
-        curve::{adaptative_curve::AdaptiveInterestRateCurve, polyline::PolylineInterestRateCurve},
+        curve::{
+            adaptative_curve::{AdaptiveInterestRateCurve, MAX_RATE_AT_TARGET},
+            kinked::KinkedInterestRateCurve,
+            polyline::{PolylineInterestRateCurve, PolylineInterestRatePoint},
+        },
         interest_rate_per_second::InterestRatePerSecond,
         lending_interest_rate::{LendingInterestRateCurveMut, MarketBorrowRateParameters},
     },
+    math::ifixed_point::IFixedPoint,
 };
 
 #[repr(C)]
@@ -20,6 +25,7 @@ pub enum InterestRateCurveKind {
     Fixed(InterestRatePerSecond),
     Polyline(PolylineInterestRateCurve),
     Adaptive(AdaptiveInterestRateCurve),
+    Kinked(KinkedInterestRateCurve),
 }
 
 impl InterestRateCurveKind {
@@ -27,20 +33,75 @@ impl InterestRateCurveKind {
         InterestRateCurveKind::Fixed(InterestRatePerSecond::approximate_from_apy(apy))
     }
 
+    /// Fixed borrow rate continuously compounding to `apr` over a year, whatever the
+    /// utilisation
+    pub fn new_fixed_apr(apr: f64) -> Self {
+        InterestRateCurveKind::Fixed(InterestRatePerSecond::approximate_from_apr(apr))
+    }
+
     pub fn new_adaptive() -> Self {
         InterestRateCurveKind::Adaptive(AdaptiveInterestRateCurve::new())
     }
 
+    /// See [`AdaptiveInterestRateCurve::new_with_params`]
+    pub fn new_adaptive_with_params(
+        target_utilisation: IFixedPoint,
+        adjustment_speed: IFixedPoint,
+        curve_steepness: IFixedPoint,
+    ) -> Self {
+        InterestRateCurveKind::Adaptive(AdaptiveInterestRateCurve::new_with_params(
+            target_utilisation,
+            adjustment_speed,
+            curve_steepness,
+        ))
+    }
+
+    /// Polyline curve going through `points`, with at most
+    /// [`POLYLINE_MAX_POINTS`](crate::interest_rate::curve::polyline::POLYLINE_MAX_POINTS) points
+    pub fn try_new_polyline(points: &[PolylineInterestRatePoint]) -> LendingResult<Self> {
+        Ok(InterestRateCurveKind::Polyline(
+            PolylineInterestRateCurve::try_new(points)?,
+        ))
+    }
+
+    /// Two slopes curve with a kink at `optimal_utilisation_bps`,
+    /// see [`KinkedInterestRateCurve`]
+    pub fn try_new_kinked(
+        base_rate_bps: u32,
+        slope1_bps: u32,
+        slope2_bps: u32,
+        optimal_utilisation_bps: u32,
+    ) -> LendingResult<Self> {
+        Ok(InterestRateCurveKind::Kinked(
+            KinkedInterestRateCurve::try_new(
+                base_rate_bps,
+                slope1_bps,
+                slope2_bps,
+                optimal_utilisation_bps,
+            )?,
+        ))
+    }
+
+    /// Same as [`Self::is_valid`] but reports why a polyline or kinked curve is rejected
+    pub fn validate(&self) -> LendingResult {
+        match self {
+            InterestRateCurveKind::Polyline(curve) => Ok(curve.validate()?),
+            InterestRateCurveKind::Kinked(curve) => curve.validate(),
+            _ if self.is_valid() => Ok(()),
+            _ => Err(LendingError::InvalidCurve.into()),
+        }
+    }
+
     pub fn is_valid(&self) -> bool {
         match self {
             InterestRateCurveKind::Fixed(rate) => {
                 // Reject negative rates (would cause NegativeInterestRate in sync_clock)
-                // and rates that would overflow checked_exp after 1 second (MAX_EXP_ARG ≈ 55.26)
-                !rate.0.is_negative()
-                    && rate.0 <= crate::math::ifixed_point::IFixedPoint::from_i64_u64_ratio(5526, 100)
+                // and rates above the highest rate the adaptive curve can reach
+                !rate.0.is_negative() && *rate <= MAX_RATE_AT_TARGET
             }
             InterestRateCurveKind::Polyline(curve) => curve.validate().is_ok(),
-            InterestRateCurveKind::Adaptive(_) => true,
+            InterestRateCurveKind::Adaptive(curve) => curve.is_valid(),
+            InterestRateCurveKind::Kinked(curve) => curve.validate().is_ok(),
         }
     }
 }
@@ -48,7 +109,7 @@ impl InterestRateCurveKind {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::math::ifixed_point::IFixedPoint;
+    use crate::interest_rate::curve::polyline::POLYLINE_MAX_POINTS;
 
     #[test]
     fn fixed_negative_rate_is_invalid() {
@@ -66,6 +127,18 @@ mod tests {
         assert!(!curve.is_valid());
     }
 
+    #[test]
+    fn fixed_rate_above_max_rate_at_target_is_invalid() {
+        assert!(InterestRateCurveKind::new_fixed_apr(1.99).is_valid());
+        assert!(!InterestRateCurveKind::new_fixed_apr(2.01).is_valid());
+        assert_eq!(
+            InterestRateCurveKind::new_fixed_apr(2.01)
+                .validate()
+                .unwrap_err(),
+            LendingError::InvalidCurve
+        );
+    }
+
     #[test]
     fn fixed_zero_rate_is_valid() {
         let curve = InterestRateCurveKind::Fixed(InterestRatePerSecond::new(IFixedPoint::zero()));
@@ -77,6 +150,38 @@ mod tests {
         let curve = InterestRateCurveKind::new_approximate_fixed_apy(0.10);
         assert!(curve.is_valid());
     }
+
+    #[test]
+    fn polyline_with_max_points_is_valid() {
+        let points = (0..POLYLINE_MAX_POINTS as u32)
+            .map(|i| PolylineInterestRatePoint::from((i * 10_00, (i + 1) * 5_00)))
+            .collect::<Vec<_>>();
+        let curve = InterestRateCurveKind::try_new_polyline(&points).unwrap();
+        curve.validate().unwrap();
+        assert!(curve.is_valid());
+    }
+
+    #[test]
+    fn kinked_optimal_utilisation_above_100_percent_is_invalid() {
+        let curve = InterestRateCurveKind::try_new_kinked(2_00, 4_00, 60_00, 80_00).unwrap();
+        assert!(curve.is_valid());
+        // an out of range kink cannot be built, but may still come from raw bytes
+        let curve = InterestRateCurveKind::Kinked(bytemuck::cast([2_00u32, 4_00, 60_00, 10_001]));
+        assert!(!curve.is_valid());
+        assert_eq!(curve.validate().unwrap_err(), LendingError::InvalidCurve);
+    }
+
+    #[test]
+    fn polyline_with_too_many_points_is_rejected() {
+        let points = (0..POLYLINE_MAX_POINTS as u32 + 1)
+            .map(|i| PolylineInterestRatePoint::from((i * 10_00, (i + 1) * 5_00)))
+            .collect::<Vec<_>>();
+        let err = InterestRateCurveKind::try_new_polyline(&points).unwrap_err();
+        assert_eq!(err.error, LendingError::PolylineTooManyPoints);
+        assert!(err.msg[0]
+            .0
+            .contains(&format!("more than {POLYLINE_MAX_POINTS} points")));
+    }
 }
 
 impl Default for InterestRateCurveKind {
@@ -96,6 +201,9 @@ impl LendingInterestRateCurveMut for InterestRateCurveKind {
                 Ok(curve.interest_rate_per_second(params.utilisation_rate_bps()?))
             }
             InterestRateCurveKind::Adaptive(curve) => curve.borrow_rate(params),
+            InterestRateCurveKind::Kinked(curve) => {
+                Ok(curve.interest_rate_per_second(params.utilisation_rate_bps()?))
+            }
         }
     }
 }
@@ -106,6 +214,7 @@ pub enum InterestRateKindCurveMut<'a> {
     Fixed(&'a mut InterestRatePerSecond),
     Polyline(&'a mut PolylineInterestRateCurve),
     Adaptive(&'a mut AdaptiveInterestRateCurve),
+    Kinked(&'a mut KinkedInterestRateCurve),
 }
 
 impl LendingInterestRateCurveMut for InterestRateKindCurveMut<'_> {
@@ -119,6 +228,9 @@ impl LendingInterestRateCurveMut for InterestRateKindCurveMut<'_> {
                 Ok(curve.interest_rate_per_second(params.utilisation_rate_bps()?))
             }
             InterestRateKindCurveMut::Adaptive(curve) => curve.borrow_rate(params),
+            InterestRateKindCurveMut::Kinked(curve) => {
+                Ok(curve.interest_rate_per_second(params.utilisation_rate_bps()?))
+            }
         }
     }
 }
@@ -3,7 +3,10 @@ use bytemuck::{Pod, Zeroable};
 
 use crate::{
     interest_rate::{
-        curve::{adaptative_curve::AdaptiveInterestRateCurve, polyline::PolylineInterestRateCurve},
+        curve::{
+            adaptative_curve::AdaptiveInterestRateCurve, kinked::KinkedInterestRateCurve,
+            polyline::PolylineInterestRateCurve,
+        },
         interest_rate_kind::{InterestRateCurveKind, InterestRateKindCurveMut},
         interest_rate_per_second::InterestRatePerSecond,
     },
@@ -17,6 +20,7 @@ pub enum PodInterestRateCurveKind {
     Fixed = 0,
     Polyline = 1,
     Adaptive = 2,
+    Kinked = 3,
 }
 
 impl PodInterestRateCurveKind {
@@ -25,6 +29,7 @@ impl PodInterestRateCurveKind {
             PodInterestRateCurveKind::Fixed => std::mem::size_of::<InterestRatePerSecond>(),
             PodInterestRateCurveKind::Polyline => std::mem::size_of::<PolylineInterestRateCurve>(),
             PodInterestRateCurveKind::Adaptive => std::mem::size_of::<AdaptiveInterestRateCurve>(),
+            PodInterestRateCurveKind::Kinked => std::mem::size_of::<KinkedInterestRateCurve>(),
         }
     }
 }
@@ -35,6 +40,7 @@ const POD_UNION_SIZE: usize = const_max_usizes(&[
     PodInterestRateCurveKind::Fixed.size(),
     PodInterestRateCurveKind::Polyline.size(),
     PodInterestRateCurveKind::Adaptive.size(),
+    PodInterestRateCurveKind::Kinked.size(),
 ]);
 
 crate::validate_struct!(PodInterestRateCurve, 72);
@@ -74,6 +80,14 @@ impl PodInterestRateCurve {
                     union,
                 }
             }
+            InterestRateCurveKind::Kinked(kinked) => {
+                union[..PodInterestRateCurveKind::Kinked.size()]
+                    .copy_from_slice(bytemuck::bytes_of(&kinked));
+                PodInterestRateCurve {
+                    kind: PodInterestRateCurveKind::Kinked,
+                    union,
+                }
+            }
         }
     }
 
@@ -88,6 +102,9 @@ impl PodInterestRateCurve {
             PodInterestRateCurveKind::Adaptive => InterestRateCurveKind::Adaptive(
                 *bytemuck::from_bytes(&self.union[..PodInterestRateCurveKind::Adaptive.size()]),
             ),
+            PodInterestRateCurveKind::Kinked => InterestRateCurveKind::Kinked(
+                *bytemuck::from_bytes(&self.union[..PodInterestRateCurveKind::Kinked.size()]),
+            ),
         }
     }
 
@@ -106,6 +123,11 @@ impl PodInterestRateCurve {
                     &mut self.union[..PodInterestRateCurveKind::Adaptive.size()],
                 ))
             }
+            PodInterestRateCurveKind::Kinked => {
+                InterestRateKindCurveMut::Kinked(bytemuck::from_bytes_mut(
+                    &mut self.union[..PodInterestRateCurveKind::Kinked.size()],
+                ))
+            }
         }
     }
 }
@@ -167,7 +189,8 @@ pub mod serde {
 pub mod tests {
     use super::*;
     use crate::interest_rate::curve::{
-        adaptative_curve::AdaptiveInterestRateCurve, polyline::PolylineInterestRateCurve,
+        adaptative_curve::AdaptiveInterestRateCurve, kinked::KinkedInterestRateCurve,
+        polyline::PolylineInterestRateCurve,
     };
     use crate::interest_rate::interest_rate_per_second::InterestRatePerSecond;
 
@@ -209,5 +232,16 @@ pub mod tests {
             pod_adaptive.interest_rate_kind_mut(),
             InterestRateKindCurveMut::Adaptive(&mut adaptive)
         );
+        let mut kinked = KinkedInterestRateCurve::try_new(2_00, 4_00, 60_00, 80_00).unwrap();
+        let mut pod_kinked =
+            PodInterestRateCurve::from_interest_rate_kind(InterestRateCurveKind::Kinked(kinked));
+        assert_eq!(
+            pod_kinked.interest_rate_kind(),
+            InterestRateCurveKind::Kinked(kinked)
+        );
+        assert_eq!(
+            pod_kinked.interest_rate_kind_mut(),
+            InterestRateKindCurveMut::Kinked(&mut kinked)
+        );
     }
 }
@@ -1,7 +1,10 @@
 use arch_program::{account::AccountMeta, instruction::Instruction, pubkey::Pubkey};
 use borsh::{BorshDeserialize, BorshSerialize};
 
-use crate::{ixs::AurataInstruction, pda::find_global_config_pda};
+use crate::{
+    ixs::AurataInstruction,
+    pda::{find_curator_market_count_pda, find_global_config_pda},
+};
 
 #[repr(C)]
 #[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
@@ -27,6 +30,20 @@ pub struct UpdateGlobalConfigInstruction {
     pub fee_receiver: Option<Pubkey>,
     #[cfg_attr(feature = "client", serde(default))]
     pub protocol_fee_share_in_bps: Option<u16>,
+    /// Clear the pending admin nomination before applying `nominated_admin`
+    #[cfg_attr(feature = "client", serde(default))]
+    pub cancel_nomination: bool,
+    /// Maximum number of markets a single curator can create, 0 for no limit
+    #[cfg_attr(feature = "client", serde(default))]
+    pub max_markets_per_curator: Option<u16>,
+}
+
+#[repr(C)]
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+pub struct SetCuratorMarketCountInstruction {
+    pub curator: Pubkey,
+    /// Markets already created by the curator
+    pub market_count: u64,
 }
 
 pub fn create_global_config_ix(
@@ -110,3 +127,51 @@ pub fn reedeem_protocol_fees_ix(
         data,
     }
 }
+
+pub fn sweep_fee_dust_ix(autara_program_id: Pubkey, market: Pubkey, admin: Pubkey) -> Instruction {
+    let mut data = Vec::new();
+    let (global_config_pda, _) = find_global_config_pda(&autara_program_id);
+    AurataInstruction::SweepFeeDust
+        .serialize(&mut data)
+        .unwrap();
+    let accounts = vec![
+        AccountMeta::new_readonly(admin, true),
+        AccountMeta::new_readonly(global_config_pda, false),
+        AccountMeta::new(market, false),
+        AccountMeta::new_readonly(autara_program_id, false),
+    ];
+    Instruction {
+        program_id: autara_program_id,
+        accounts,
+        data,
+    }
+}
+
+pub fn set_curator_market_count_ix(
+    autara_program_id: Pubkey,
+    admin: Pubkey,
+    curator: Pubkey,
+    market_count: u64,
+) -> Instruction {
+    let mut data = Vec::new();
+    let (global_config_pda, _) = find_global_config_pda(&autara_program_id);
+    let (curator_market_count_pda, _) = find_curator_market_count_pda(&autara_program_id, &curator);
+    AurataInstruction::SetCuratorMarketCount(SetCuratorMarketCountInstruction {
+        curator,
+        market_count,
+    })
+    .serialize(&mut data)
+    .unwrap();
+    let accounts = vec![
+        AccountMeta::new(admin, true),
+        AccountMeta::new_readonly(global_config_pda, false),
+        AccountMeta::new(curator_market_count_pda, false),
+        AccountMeta::new_readonly(arch_program::system_program::SYSTEM_PROGRAM_ID, false),
+        AccountMeta::new_readonly(autara_program_id, false),
+    ];
+    Instruction {
+        program_id: autara_program_id,
+        accounts,
+        data,
+    }
+}
@@ -3,7 +3,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 
 use crate::pda::borrow_position_seed;
 
-use super::types::AurataInstruction;
+use super::types::{oracle_account_metas, AurataInstruction};
 
 #[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
 #[repr(C)]
@@ -110,25 +110,24 @@ pub fn borrow_apl_ix(
     borrow_position: Pubkey,
     authority_supply_ata: Pubkey,
     market_supply_vault: Pubkey,
-    supply_oracle: Pubkey,
-    collateral_oracle: Pubkey,
+    supply_oracle: Option<Pubkey>,
+    collateral_oracle: Option<Pubkey>,
     amount: u64,
 ) -> Instruction {
     let mut data = Vec::new();
     AurataInstruction::BorrowApl(BorrowAplInstruction { amount })
         .serialize(&mut data)
         .unwrap();
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new(market, false),
         AccountMeta::new(borrow_position, false),
         AccountMeta::new_readonly(authority, true),
         AccountMeta::new(authority_supply_ata, false),
         AccountMeta::new(market_supply_vault, false),
         AccountMeta::new_readonly(apl_token::id(), false),
-        AccountMeta::new_readonly(supply_oracle, false),
-        AccountMeta::new_readonly(collateral_oracle, false),
-        AccountMeta::new_readonly(autara_program_id, false),
     ];
+    accounts.extend(oracle_account_metas(supply_oracle, collateral_oracle));
+    accounts.push(AccountMeta::new_readonly(autara_program_id, false));
     Instruction {
         program_id: autara_program_id,
         accounts,
@@ -143,25 +142,24 @@ pub fn deposit_apl_collateral_ix(
     borrow_position: Pubkey,
     authority_collateral_ata: Pubkey,
     market_collateral_vault: Pubkey,
-    supply_oracle: Pubkey,
-    collateral_oracle: Pubkey,
+    supply_oracle: Option<Pubkey>,
+    collateral_oracle: Option<Pubkey>,
     amount: u64,
 ) -> Instruction {
     let mut data = Vec::new();
     AurataInstruction::DepositAplCollateral(DepositAplCollateralInstruction { amount })
         .serialize(&mut data)
         .unwrap();
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new(market, false),
         AccountMeta::new(borrow_position, false),
         AccountMeta::new_readonly(authority, true),
         AccountMeta::new(authority_collateral_ata, false),
         AccountMeta::new(market_collateral_vault, false),
         AccountMeta::new_readonly(apl_token::id(), false),
-        AccountMeta::new_readonly(supply_oracle, false),
-        AccountMeta::new_readonly(collateral_oracle, false),
-        AccountMeta::new_readonly(autara_program_id, false),
     ];
+    accounts.extend(oracle_account_metas(supply_oracle, collateral_oracle));
+    accounts.push(AccountMeta::new_readonly(autara_program_id, false));
     Instruction {
         program_id: autara_program_id,
         accounts,
@@ -176,8 +174,8 @@ pub fn withdraw_apl_collateral_ix(
     borrow_position: Pubkey,
     authority_collateral_ata: Pubkey,
     market_collateral_vault: Pubkey,
-    supply_oracle: Pubkey,
-    collateral_oracle: Pubkey,
+    supply_oracle: Option<Pubkey>,
+    collateral_oracle: Option<Pubkey>,
     amount: u64,
     withdraw_all: bool,
 ) -> Instruction {
@@ -188,17 +186,16 @@ pub fn withdraw_apl_collateral_ix(
     })
     .serialize(&mut data)
     .unwrap();
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new(market, false),
         AccountMeta::new(borrow_position, false),
         AccountMeta::new_readonly(authority, true),
         AccountMeta::new(authority_collateral_ata, false),
         AccountMeta::new(market_collateral_vault, false),
         AccountMeta::new_readonly(apl_token::id(), false),
-        AccountMeta::new_readonly(supply_oracle, false),
-        AccountMeta::new_readonly(collateral_oracle, false),
-        AccountMeta::new_readonly(autara_program_id, false),
     ];
+    accounts.extend(oracle_account_metas(supply_oracle, collateral_oracle));
+    accounts.push(AccountMeta::new_readonly(autara_program_id, false));
     Instruction {
         program_id: autara_program_id,
         accounts,
@@ -213,8 +210,8 @@ pub fn repay_apl_ix(
     authority: Pubkey,
     authority_supply_ata: Pubkey,
     market_supply_vault: Pubkey,
-    supply_oracle: Pubkey,
-    collateral_oracle: Pubkey,
+    supply_oracle: Option<Pubkey>,
+    collateral_oracle: Option<Pubkey>,
     amount: u64,
     repay_all: bool,
 ) -> Instruction {
@@ -222,17 +219,16 @@ pub fn repay_apl_ix(
     AurataInstruction::RepayApl(RepayAplInstruction { amount, repay_all })
         .serialize(&mut data)
         .unwrap();
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new(market, false),
         AccountMeta::new(borrow_position, false),
         AccountMeta::new_readonly(authority, true),
         AccountMeta::new(authority_supply_ata, false),
         AccountMeta::new(market_supply_vault, false),
         AccountMeta::new_readonly(apl_token::id(), false),
-        AccountMeta::new_readonly(supply_oracle, false),
-        AccountMeta::new_readonly(collateral_oracle, false),
-        AccountMeta::new_readonly(autara_program_id, false),
     ];
+    accounts.extend(oracle_account_metas(supply_oracle, collateral_oracle));
+    accounts.push(AccountMeta::new_readonly(autara_program_id, false));
     Instruction {
         program_id: autara_program_id,
         accounts,
@@ -249,8 +245,8 @@ pub fn withdraw_repay_apl_ix(
     market_supply_vault: Pubkey,
     authority_collateral_ata: Pubkey,
     market_collateral_vault: Pubkey,
-    supply_oracle: Pubkey,
-    collateral_oracle: Pubkey,
+    supply_oracle: Option<Pubkey>,
+    collateral_oracle: Option<Pubkey>,
     ix: WithdrawRepayAplInstruction,
 ) -> Instruction {
     let mut accounts = vec![
@@ -262,10 +258,9 @@ pub fn withdraw_repay_apl_ix(
         AccountMeta::new(authority_collateral_ata, false),
         AccountMeta::new(market_collateral_vault, false),
         AccountMeta::new_readonly(apl_token::id(), false),
-        AccountMeta::new_readonly(supply_oracle, false),
-        AccountMeta::new_readonly(collateral_oracle, false),
-        AccountMeta::new_readonly(autara_program_id, false),
     ];
+    accounts.extend(oracle_account_metas(supply_oracle, collateral_oracle));
+    accounts.push(AccountMeta::new_readonly(autara_program_id, false));
     if let Some(ix_callback) = &ix.ix_callback {
         accounts.extend(ix_callback.accounts.iter().cloned());
     }
@@ -289,8 +284,8 @@ pub fn borrow_deposit_apl_ix(
     market_supply_vault: Pubkey,
     authority_collateral_ata: Pubkey,
     market_collateral_vault: Pubkey,
-    supply_oracle: Pubkey,
-    collateral_oracle: Pubkey,
+    supply_oracle: Option<Pubkey>,
+    collateral_oracle: Option<Pubkey>,
     ix: BorrowDepositAplInstruction,
 ) -> Instruction {
     let mut accounts = vec![
@@ -302,10 +297,9 @@ pub fn borrow_deposit_apl_ix(
         AccountMeta::new(authority_collateral_ata, false),
         AccountMeta::new(market_collateral_vault, false),
         AccountMeta::new_readonly(apl_token::id(), false),
-        AccountMeta::new_readonly(supply_oracle, false),
-        AccountMeta::new_readonly(collateral_oracle, false),
-        AccountMeta::new_readonly(autara_program_id, false),
     ];
+    accounts.extend(oracle_account_metas(supply_oracle, collateral_oracle));
+    accounts.push(AccountMeta::new_readonly(autara_program_id, false));
     if let Some(ix_callback) = &ix.ix_callback {
         accounts.extend(ix_callback.accounts.iter().cloned());
     }
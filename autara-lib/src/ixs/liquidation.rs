@@ -1,7 +1,7 @@
 use arch_program::{account::AccountMeta, instruction::Instruction, pubkey::Pubkey};
 use borsh::{BorshDeserialize, BorshSerialize};
 
-use super::types::AurataInstruction;
+use super::types::{oracle_account_metas, AurataInstruction};
 
 #[repr(C)]
 #[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
@@ -29,8 +29,8 @@ pub fn liquidate_ix(
     liquidator_collateral_ata: Pubkey,
     market_supply_vault: Pubkey,
     market_collateral_vault: Pubkey,
-    supply_oracle: Pubkey,
-    collateral_oracle: Pubkey,
+    supply_oracle: Option<Pubkey>,
+    collateral_oracle: Option<Pubkey>,
     max_borrowed_atoms_to_repay: u64,
     min_collateral_atoms_to_receive: u64,
     ix_callback: Option<Instruction>,
@@ -44,10 +44,9 @@ pub fn liquidate_ix(
         AccountMeta::new(market_supply_vault, false),
         AccountMeta::new(market_collateral_vault, false),
         AccountMeta::new_readonly(apl_token::id(), false),
-        AccountMeta::new_readonly(supply_oracle, false),
-        AccountMeta::new_readonly(collateral_oracle, false),
-        AccountMeta::new_readonly(autara_program_id, false),
     ];
+    accounts.extend(oracle_account_metas(supply_oracle, collateral_oracle));
+    accounts.push(AccountMeta::new_readonly(autara_program_id, false));
     if let Some(callback) = &ix_callback {
         accounts.push(AccountMeta::new_readonly(callback.program_id, false));
         accounts.extend(callback.accounts.iter().cloned());
@@ -83,20 +82,19 @@ pub fn socialize_loss_ix(
     curator: Pubkey,
     receiver_collateral_ata: Pubkey,
     market_collateral_vault: Pubkey,
-    supply_oracle: Pubkey,
-    collateral_oracle: Pubkey,
+    supply_oracle: Option<Pubkey>,
+    collateral_oracle: Option<Pubkey>,
 ) -> Instruction {
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new(market, false),
         AccountMeta::new(borrow_position, false),
         AccountMeta::new_readonly(curator, true),
         AccountMeta::new(receiver_collateral_ata, false),
         AccountMeta::new(market_collateral_vault, false),
         AccountMeta::new_readonly(apl_token::id(), false),
-        AccountMeta::new_readonly(supply_oracle, false),
-        AccountMeta::new_readonly(collateral_oracle, false),
-        AccountMeta::new_readonly(autara_program_id, false),
     ];
+    accounts.extend(oracle_account_metas(supply_oracle, collateral_oracle));
+    accounts.push(AccountMeta::new_readonly(autara_program_id, false));
     let mut data = Vec::new();
     AurataInstruction::SocializeLoss(SocializeLossInstruction {})
         .serialize(&mut data)
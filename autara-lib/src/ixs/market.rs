@@ -1,13 +1,16 @@
 use crate::{
-    interest_rate::interest_rate_kind::InterestRateCurveKind, math::ifixed_point::IFixedPoint,
-    oracle::oracle_config::OracleConfig, pda::find_market_pda, state::market_config::LtvConfig,
+    interest_rate::interest_rate_kind::InterestRateCurveKind,
+    math::ifixed_point::IFixedPoint,
+    oracle::oracle_config::OracleConfig,
+    pda::find_market_pda,
+    state::{market_config::LtvConfig, metadata_uri::MetadataUri},
     token::get_associated_token_address,
 };
 use arch_program::{account::AccountMeta, instruction::Instruction, pubkey::Pubkey};
 use borsh::{BorshDeserialize, BorshSerialize};
 
-use super::types::AurataInstruction;
-use crate::pda::find_global_config_pda;
+use super::types::{oracle_account_metas, AurataInstruction};
+use crate::pda::{find_curator_market_count_pda, find_global_config_pda};
 
 #[repr(C)]
 #[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
@@ -25,6 +28,12 @@ pub struct CreateMarketInstruction {
     pub collateral_oracle_config: OracleConfig,
     pub interest_rate: InterestRateCurveKind,
     pub lending_market_fee_in_bps: u16,
+    /// Uri of the off-chain JSON describing the market, can be left empty
+    #[cfg_attr(feature = "client", serde(default))]
+    pub metadata_uri: MetadataUri,
+    /// Seconds after the creation during which no interest accrues, only settable at creation
+    #[cfg_attr(feature = "client", serde(default))]
+    pub borrow_origination_grace_seconds: u64,
 }
 
 #[repr(C)]
@@ -47,6 +56,32 @@ pub struct UpdateConfigInstruction {
     pub max_utilisation_rate: Option<IFixedPoint>,
     #[cfg_attr(feature = "client", serde(default))]
     pub lending_market_fee_in_bps: Option<u16>,
+    #[cfg_attr(feature = "client", serde(default))]
+    pub min_liquidity_buffer_atoms: Option<u64>,
+    #[cfg_attr(feature = "client", serde(default))]
+    pub borrow_cap_ltv: Option<IFixedPoint>,
+    #[cfg_attr(feature = "client", serde(default))]
+    pub min_collateral_after_liquidation_atoms: Option<u64>,
+    #[cfg_attr(feature = "client", serde(default))]
+    pub warning_ltv: Option<IFixedPoint>,
+    #[cfg_attr(feature = "client", serde(default))]
+    pub borrow_max_staleness_seconds: Option<u64>,
+    /// Seconds over which the max supply moves linearly to `max_supply_atoms`,
+    /// it is applied at once if not set. Only valid along with `max_supply_atoms`
+    #[cfg_attr(feature = "client", serde(default))]
+    pub max_supply_ramp_seconds: Option<u64>,
+    #[cfg_attr(feature = "client", serde(default))]
+    pub allow_donations: Option<bool>,
+    #[cfg_attr(feature = "client", serde(default))]
+    pub min_supply_for_borrow_atoms: Option<u64>,
+    /// Max LTV once the utilisation reaches the max utilisation rate, zero disables it
+    #[cfg_attr(feature = "client", serde(default))]
+    pub max_ltv_at_max_utilisation: Option<IFixedPoint>,
+    /// Programs the leverage callbacks may invoke, an empty list allows any program
+    #[cfg_attr(feature = "client", serde(default))]
+    pub callback_program_allowlist: Option<Vec<Pubkey>>,
+    #[cfg_attr(feature = "client", serde(default))]
+    pub metadata_uri: Option<MetadataUri>,
 }
 
 pub fn create_market_ix(
@@ -66,9 +101,19 @@ pub fn create_market_ix(
         create_market.index,
     );
     create_market.market_bump = market_bump;
+    let supply_oracle = create_market
+        .supply_oracle_config
+        .oracle_provider()
+        .oracle_provider_ref()
+        .oracle_feed_pubkey();
+    let collateral_oracle = create_market
+        .collateral_oracle_config
+        .oracle_provider()
+        .oracle_provider_ref()
+        .oracle_feed_pubkey();
     let ix = AurataInstruction::CreateMarket(create_market);
     ix.serialize(&mut data).unwrap();
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new_readonly(curator, true),
         AccountMeta::new(payer, true),
         AccountMeta::new_readonly(find_global_config_pda(&autara_program_id).0, false),
@@ -86,8 +131,13 @@ pub fn create_market_ix(
         AccountMeta::new_readonly(apl_token::id(), false),
         AccountMeta::new_readonly(apl_associated_token_account::id(), false),
         AccountMeta::new_readonly(arch_program::system_program::SYSTEM_PROGRAM_ID, false),
-        AccountMeta::new_readonly(autara_program_id, false),
+        AccountMeta::new(
+            find_curator_market_count_pda(&autara_program_id, &curator).0,
+            false,
+        ),
     ];
+    accounts.extend(oracle_account_metas(supply_oracle, collateral_oracle));
+    accounts.push(AccountMeta::new_readonly(autara_program_id, false));
     (
         market_pda,
         Instruction {
@@ -103,15 +153,14 @@ pub fn update_config_ix(
     market: Pubkey,
     curator: Pubkey,
     config: UpdateConfigInstruction,
-    supply_oracle_key: Pubkey,
-    collateral_oracle_key: Pubkey,
+    supply_oracle_key: Option<Pubkey>,
+    collateral_oracle_key: Option<Pubkey>,
 ) -> Instruction {
     let supply_oracle = if let Some(oracle) = &config.supply_oracle_config {
         oracle
             .oracle_provider()
             .oracle_provider_ref()
             .oracle_feed_pubkey()
-            .unwrap()
     } else {
         supply_oracle_key
     };
@@ -120,7 +169,6 @@ pub fn update_config_ix(
             .oracle_provider()
             .oracle_provider_ref()
             .oracle_feed_pubkey()
-            .unwrap()
     } else {
         collateral_oracle_key
     };
@@ -129,14 +177,13 @@ pub fn update_config_ix(
         .serialize(&mut data)
         .unwrap();
 
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new(market, false),
         AccountMeta::new_readonly(find_global_config_pda(&autara_program_id).0, false),
         AccountMeta::new_readonly(curator, true),
-        AccountMeta::new_readonly(supply_oracle, false),
-        AccountMeta::new_readonly(collateral_oracle, false),
-        AccountMeta::new_readonly(autara_program_id, false),
     ];
+    accounts.extend(oracle_account_metas(supply_oracle, collateral_oracle));
+    accounts.push(AccountMeta::new_readonly(autara_program_id, false));
     Instruction {
         program_id: autara_program_id,
         accounts,
@@ -169,3 +216,33 @@ pub fn reedeem_curator_fees_ix(
         data,
     }
 }
+
+pub fn sync_market_ix(autara_program_id: Pubkey, market: Pubkey) -> Instruction {
+    let mut data = Vec::new();
+    AurataInstruction::SyncMarket.serialize(&mut data).unwrap();
+    let accounts = vec![
+        AccountMeta::new(market, false),
+        AccountMeta::new_readonly(autara_program_id, false),
+    ];
+    Instruction {
+        program_id: autara_program_id,
+        accounts,
+        data,
+    }
+}
+
+pub fn migrate_account_ix(autara_program_id: Pubkey, account: Pubkey) -> Instruction {
+    let mut data = Vec::new();
+    AurataInstruction::MigrateAccount
+        .serialize(&mut data)
+        .unwrap();
+    let accounts = vec![
+        AccountMeta::new(account, false),
+        AccountMeta::new_readonly(autara_program_id, false),
+    ];
+    Instruction {
+        program_id: autara_program_id,
+        accounts,
+        data,
+    }
+}
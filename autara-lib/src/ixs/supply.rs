@@ -3,7 +3,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 
 use crate::pda::supply_position_seed;
 
-use super::types::AurataInstruction;
+use super::types::{oracle_account_metas, AurataInstruction};
 
 #[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
 #[repr(C)]
@@ -67,25 +67,24 @@ pub fn supply_apl_ix(
     authority: Pubkey,
     authority_supply_ata: Pubkey,
     supply_vault: Pubkey,
-    supply_oracle: Pubkey,
-    collateral_oracle: Pubkey,
+    supply_oracle: Option<Pubkey>,
+    collateral_oracle: Option<Pubkey>,
     amount: u64,
 ) -> Instruction {
     let mut data = Vec::new();
     AurataInstruction::SupplyApl(SupplyAplInstruction { amount })
         .serialize(&mut data)
         .unwrap();
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new(market, false),
         AccountMeta::new(supply_position, false),
         AccountMeta::new_readonly(authority, true),
         AccountMeta::new(authority_supply_ata, false),
         AccountMeta::new(supply_vault, false),
         AccountMeta::new_readonly(apl_token::id(), false),
-        AccountMeta::new_readonly(supply_oracle, false),
-        AccountMeta::new_readonly(collateral_oracle, false),
-        AccountMeta::new_readonly(autara_program_id, false),
     ];
+    accounts.extend(oracle_account_metas(supply_oracle, collateral_oracle));
+    accounts.push(AccountMeta::new_readonly(autara_program_id, false));
     Instruction {
         program_id: autara_program_id,
         accounts,
@@ -100,8 +99,8 @@ pub fn withdraw_supply_ix(
     authority: Pubkey,
     authority_supply_ata: Pubkey,
     market_supply_vault: Pubkey,
-    supply_oracle: Pubkey,
-    collateral_oracle: Pubkey,
+    supply_oracle: Option<Pubkey>,
+    collateral_oracle: Option<Pubkey>,
     amount: u64,
     withdraw_all: bool,
 ) -> Instruction {
@@ -112,17 +111,16 @@ pub fn withdraw_supply_ix(
     })
     .serialize(&mut data)
     .unwrap();
-    let accounts = vec![
+    let mut accounts = vec![
         AccountMeta::new(market, false),
         AccountMeta::new(supply_position, false),
         AccountMeta::new_readonly(authority, true),
         AccountMeta::new(authority_supply_ata, false),
         AccountMeta::new(market_supply_vault, false),
         AccountMeta::new_readonly(apl_token::id(), false),
-        AccountMeta::new_readonly(supply_oracle, false),
-        AccountMeta::new_readonly(collateral_oracle, false),
-        AccountMeta::new_readonly(autara_program_id, false),
     ];
+    accounts.extend(oracle_account_metas(supply_oracle, collateral_oracle));
+    accounts.push(AccountMeta::new_readonly(autara_program_id, false));
     Instruction {
         program_id: autara_program_id,
         accounts,
@@ -1,5 +1,8 @@
+use arch_program::{account::AccountMeta, instruction::Instruction, pubkey::Pubkey};
 use borsh::{BorshDeserialize, BorshSerialize};
 
+use crate::error::{LendingError, LendingResult, LendingResultExt};
+
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
 pub enum AurataInstructionTag {
@@ -8,6 +11,8 @@ pub enum AurataInstructionTag {
     /// Curator is the only one who can update the market config later.
     /// The interest rate model is set at creation and cannot be changed later.
     /// Oracle config can be updated later, as well as other parameters of [MarketConfig](crate::state::market_config::MarketConfig).
+    /// Supply and collateral mints must differ: both vaults are associated token accounts of the
+    /// market, so a single mint would make them the same account and mix supply with collateral.
     CreateMarket,
     /// Create a new supply position for a user in a specific market. Position cannot be transferred.
     /// The authority is the only one who can supply/withdraw to/from this position.
@@ -61,6 +66,18 @@ pub enum AurataInstructionTag {
     /// Donate APL tokens to the supply vault of a market, increasing the total supply and the yield for all suppliers
     /// without receiving any supply shares in return.
     DonateSupply,
+    /// Accrue the interest of a market up to the current time without any other effect.
+    /// Permissionless, used by keepers to keep the market state fresh.
+    SyncMarket,
+    /// Upgrade a market, position or global config account in place to the current layout version.
+    /// Permissionless since a migration only fills the defaults of new fields, no-op if already current.
+    MigrateAccount,
+    /// Credit to suppliers the pending protocol and curator fees worth less than one atom,
+    /// which would otherwise be redeemed for nothing. Only the global admin can call this instruction.
+    SweepFeeDust,
+    /// Create if needed and overwrite the market count of a curator, used to backfill the markets
+    /// created before the count existed. Only the global admin can call this instruction.
+    SetCuratorMarketCount,
 }
 
 impl TryFrom<u8> for AurataInstructionTag {
@@ -88,11 +105,352 @@ impl TryFrom<u8> for AurataInstructionTag {
             17 => Ok(AurataInstructionTag::WithdrawRepayApl),
             18 => Ok(AurataInstructionTag::SocializeLoss),
             19 => Ok(AurataInstructionTag::DonateSupply),
+            20 => Ok(AurataInstructionTag::SyncMarket),
+            21 => Ok(AurataInstructionTag::MigrateAccount),
+            22 => Ok(AurataInstructionTag::SweepFeeDust),
+            23 => Ok(AurataInstructionTag::SetCuratorMarketCount),
             _ => Err(value),
         }
     }
 }
 
+/// Account expected by an instruction, in the order read by the program
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExpectedAccount {
+    pub name: &'static str,
+    pub writable: bool,
+    pub signer: bool,
+    /// Left out when the market has no such account, i.e. the feed of a constant oracle.
+    /// Optional accounts always come last
+    pub optional: bool,
+}
+
+const fn readonly(name: &'static str) -> ExpectedAccount {
+    ExpectedAccount {
+        name,
+        writable: false,
+        signer: false,
+        optional: false,
+    }
+}
+
+const fn writable(name: &'static str) -> ExpectedAccount {
+    ExpectedAccount {
+        name,
+        writable: true,
+        signer: false,
+        optional: false,
+    }
+}
+
+const fn signer(name: &'static str) -> ExpectedAccount {
+    ExpectedAccount {
+        name,
+        writable: false,
+        signer: true,
+        optional: false,
+    }
+}
+
+const fn writable_signer(name: &'static str) -> ExpectedAccount {
+    ExpectedAccount {
+        name,
+        writable: true,
+        signer: true,
+        optional: false,
+    }
+}
+
+const fn oracle(name: &'static str) -> ExpectedAccount {
+    ExpectedAccount {
+        name,
+        writable: false,
+        signer: false,
+        optional: true,
+    }
+}
+
+/// Metas of the oracle feed accounts read by an instruction, none for a constant oracle
+pub fn oracle_account_metas(
+    supply_oracle: Option<Pubkey>,
+    collateral_oracle: Option<Pubkey>,
+) -> impl Iterator<Item = AccountMeta> {
+    [supply_oracle, collateral_oracle]
+        .into_iter()
+        .flatten()
+        .map(|oracle| AccountMeta::new_readonly(oracle, false))
+}
+
+const CREATE_MARKET_ACCOUNTS: &[ExpectedAccount] = &[
+    signer("curator"),
+    writable_signer("payer"),
+    readonly("global_config"),
+    writable("market"),
+    readonly("supply_mint"),
+    writable("supply_vault"),
+    readonly("collateral_mint"),
+    writable("collateral_vault"),
+    readonly("apl_token_program"),
+    readonly("associated_token_program"),
+    readonly("system_program"),
+    writable("curator_market_count"),
+    oracle("supply_oracle"),
+    oracle("collateral_oracle"),
+];
+
+const CREATE_SUPPLY_POSITION_ACCOUNTS: &[ExpectedAccount] = &[
+    writable("market"),
+    writable("supply_position"),
+    signer("authority"),
+    writable_signer("payer"),
+    readonly("system_program"),
+];
+
+const CREATE_BORROW_POSITION_ACCOUNTS: &[ExpectedAccount] = &[
+    writable("market"),
+    writable("borrow_position"),
+    signer("authority"),
+    writable_signer("payer"),
+    readonly("system_program"),
+];
+
+const SUPPLY_POSITION_ACCOUNTS: &[ExpectedAccount] = &[
+    writable("market"),
+    writable("supply_position"),
+    signer("authority"),
+    writable("authority_supply_ata"),
+    writable("market_supply_vault"),
+    readonly("apl_token_program"),
+    oracle("supply_oracle"),
+    oracle("collateral_oracle"),
+];
+
+const BORROW_SUPPLY_ACCOUNTS: &[ExpectedAccount] = &[
+    writable("market"),
+    writable("borrow_position"),
+    signer("authority"),
+    writable("authority_supply_ata"),
+    writable("market_supply_vault"),
+    readonly("apl_token_program"),
+    oracle("supply_oracle"),
+    oracle("collateral_oracle"),
+];
+
+const BORROW_COLLATERAL_ACCOUNTS: &[ExpectedAccount] = &[
+    writable("market"),
+    writable("borrow_position"),
+    signer("authority"),
+    writable("authority_collateral_ata"),
+    writable("market_collateral_vault"),
+    readonly("apl_token_program"),
+    oracle("supply_oracle"),
+    oracle("collateral_oracle"),
+];
+
+const BORROW_SUPPLY_AND_COLLATERAL_ACCOUNTS: &[ExpectedAccount] = &[
+    writable("market"),
+    writable("borrow_position"),
+    signer("authority"),
+    writable("authority_supply_ata"),
+    writable("market_supply_vault"),
+    writable("authority_collateral_ata"),
+    writable("market_collateral_vault"),
+    readonly("apl_token_program"),
+    oracle("supply_oracle"),
+    oracle("collateral_oracle"),
+];
+
+const UPDATE_CONFIG_ACCOUNTS: &[ExpectedAccount] = &[
+    writable("market"),
+    readonly("global_config"),
+    signer("curator"),
+    oracle("updated_supply_oracle"),
+    oracle("updated_collateral_oracle"),
+];
+
+const LIQUIDATE_ACCOUNTS: &[ExpectedAccount] = &[
+    writable("market"),
+    writable("borrow_position"),
+    signer("liquidator"),
+    writable("liquidator_supply_ata"),
+    writable("liquidator_collateral_ata"),
+    writable("market_supply_vault"),
+    writable("market_collateral_vault"),
+    readonly("apl_token_program"),
+    oracle("supply_oracle"),
+    oracle("collateral_oracle"),
+];
+
+const LOG_ACCOUNTS: &[ExpectedAccount] = &[signer("market")];
+
+const CREATE_GLOBAL_CONFIG_ACCOUNTS: &[ExpectedAccount] = &[
+    writable_signer("payer"),
+    writable("global_config"),
+    readonly("system_program"),
+];
+
+const REDEEM_CURATOR_FEES_ACCOUNTS: &[ExpectedAccount] = &[
+    signer("curator"),
+    writable("market"),
+    writable("curator_supply_ata"),
+    writable("market_supply_vault"),
+    readonly("apl_token_program"),
+];
+
+const REDEEM_PROTOCOL_FEES_ACCOUNTS: &[ExpectedAccount] = &[
+    signer("signer"),
+    readonly("global_config"),
+    writable("market"),
+    writable("receiver_supply_ata"),
+    writable("market_supply_vault"),
+    readonly("apl_token_program"),
+];
+
+const UPDATE_GLOBAL_CONFIG_ACCOUNTS: &[ExpectedAccount] =
+    &[signer("signer"), writable("global_config")];
+
+const SOCIALIZE_LOSS_ACCOUNTS: &[ExpectedAccount] = &[
+    writable("market"),
+    writable("borrow_position"),
+    signer("curator"),
+    writable("receiver_collateral_ata"),
+    writable("market_collateral_vault"),
+    readonly("apl_token_program"),
+    oracle("supply_oracle"),
+    oracle("collateral_oracle"),
+];
+
+const DONATE_SUPPLY_ACCOUNTS: &[ExpectedAccount] = &[
+    writable("market"),
+    signer("authority"),
+    writable("authority_supply_ata"),
+    writable("market_supply_vault"),
+    readonly("apl_token_program"),
+];
+
+const SYNC_MARKET_ACCOUNTS: &[ExpectedAccount] = &[writable("market")];
+
+const MIGRATE_ACCOUNT_ACCOUNTS: &[ExpectedAccount] = &[writable("account")];
+
+const SWEEP_FEE_DUST_ACCOUNTS: &[ExpectedAccount] = &[
+    signer("admin"),
+    readonly("global_config"),
+    writable("market"),
+];
+
+const SET_CURATOR_MARKET_COUNT_ACCOUNTS: &[ExpectedAccount] = &[
+    writable_signer("admin"),
+    readonly("global_config"),
+    writable("curator_market_count"),
+    readonly("system_program"),
+];
+
+/// First account of an instruction which does not match what the program expects
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccountMismatch {
+    Missing(usize, ExpectedAccount),
+    NotWritable(usize, ExpectedAccount),
+    NotSigner(usize, ExpectedAccount),
+    Unexpected(usize),
+}
+
+impl std::fmt::Display for AccountMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AccountMismatch::Missing(index, account) => {
+                write!(f, "missing account #{} {}", index, account.name)
+            }
+            AccountMismatch::NotWritable(index, account) => {
+                write!(f, "account #{} {} must be writable", index, account.name)
+            }
+            AccountMismatch::NotSigner(index, account) => {
+                write!(f, "account #{} {} must be a signer", index, account.name)
+            }
+            AccountMismatch::Unexpected(index) => {
+                write!(f, "unexpected account #{}", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AccountMismatch {}
+
+impl AurataInstructionTag {
+    /// Accounts read by the program for this instruction. Trailing accounts such as
+    /// the program itself or the callback accounts are not part of the list
+    pub fn expected_accounts(&self) -> &'static [ExpectedAccount] {
+        match self {
+            AurataInstructionTag::CreateMarket => CREATE_MARKET_ACCOUNTS,
+            AurataInstructionTag::CreateSupplyPosition => CREATE_SUPPLY_POSITION_ACCOUNTS,
+            AurataInstructionTag::CreateBorrowPosition => CREATE_BORROW_POSITION_ACCOUNTS,
+            AurataInstructionTag::SupplyApl | AurataInstructionTag::WithdrawSupply => {
+                SUPPLY_POSITION_ACCOUNTS
+            }
+            AurataInstructionTag::BorrowApl | AurataInstructionTag::RepayApl => {
+                BORROW_SUPPLY_ACCOUNTS
+            }
+            AurataInstructionTag::DepositAplCollateral
+            | AurataInstructionTag::WithdrawAplCollateral => BORROW_COLLATERAL_ACCOUNTS,
+            AurataInstructionTag::BorrowDepositApl | AurataInstructionTag::WithdrawRepayApl => {
+                BORROW_SUPPLY_AND_COLLATERAL_ACCOUNTS
+            }
+            AurataInstructionTag::UpdateConfig => UPDATE_CONFIG_ACCOUNTS,
+            AurataInstructionTag::Liquidate => LIQUIDATE_ACCOUNTS,
+            AurataInstructionTag::Log => LOG_ACCOUNTS,
+            AurataInstructionTag::CreateGlobalConfig => CREATE_GLOBAL_CONFIG_ACCOUNTS,
+            AurataInstructionTag::ReedeemCuratorFees => REDEEM_CURATOR_FEES_ACCOUNTS,
+            AurataInstructionTag::ReedeemProtocolFees => REDEEM_PROTOCOL_FEES_ACCOUNTS,
+            AurataInstructionTag::UpdateGlobalConfig => UPDATE_GLOBAL_CONFIG_ACCOUNTS,
+            AurataInstructionTag::SocializeLoss => SOCIALIZE_LOSS_ACCOUNTS,
+            AurataInstructionTag::DonateSupply => DONATE_SUPPLY_ACCOUNTS,
+            AurataInstructionTag::SyncMarket => SYNC_MARKET_ACCOUNTS,
+            AurataInstructionTag::MigrateAccount => MIGRATE_ACCOUNT_ACCOUNTS,
+            AurataInstructionTag::SweepFeeDust => SWEEP_FEE_DUST_ACCOUNTS,
+            AurataInstructionTag::SetCuratorMarketCount => SET_CURATOR_MARKET_COUNT_ACCOUNTS,
+        }
+    }
+
+    /// Max number of accounts allowed after [Self::expected_accounts]: the program itself,
+    /// needed to log events, and for leverage instructions the callback accounts
+    pub fn max_trailing_accounts(&self) -> Option<usize> {
+        match self {
+            AurataInstructionTag::Log => Some(0),
+            AurataInstructionTag::Liquidate
+            | AurataInstructionTag::BorrowDepositApl
+            | AurataInstructionTag::WithdrawRepayApl => None,
+            _ => Some(1),
+        }
+    }
+
+    /// Check `accounts` provide every expected account with at least the expected privileges,
+    /// and no more trailing accounts than [Self::max_trailing_accounts].
+    /// Optional accounts may be left out, the trailing accounts then come earlier
+    pub fn check_accounts(&self, accounts: &[AccountMeta]) -> Result<(), AccountMismatch> {
+        let expected_accounts = self.expected_accounts();
+        for (index, expected) in expected_accounts.iter().enumerate() {
+            let Some(account) = accounts.get(index) else {
+                if expected.optional {
+                    break;
+                }
+                return Err(AccountMismatch::Missing(index, *expected));
+            };
+            if expected.writable && !account.is_writable {
+                return Err(AccountMismatch::NotWritable(index, *expected));
+            }
+            if expected.signer && !account.is_signer {
+                return Err(AccountMismatch::NotSigner(index, *expected));
+            }
+        }
+        if let Some(max_trailing_accounts) = self.max_trailing_accounts() {
+            let max_len = expected_accounts.len() + max_trailing_accounts;
+            if accounts.len() > max_len {
+                return Err(AccountMismatch::Unexpected(max_len));
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum AurataInstruction {
     CreateMarket(super::market::CreateMarketInstruction),
@@ -115,6 +473,53 @@ pub enum AurataInstruction {
     WithdrawRepayApl(super::borrow::WithdrawRepayAplInstruction),
     SocializeLoss(super::liquidation::SocializeLossInstruction),
     DonateSupply(super::supply::DonateSupplyInstruction),
+    SyncMarket,
+    MigrateAccount,
+    SweepFeeDust,
+    SetCuratorMarketCount(super::admin::SetCuratorMarketCountInstruction),
+}
+
+impl AurataInstruction {
+    /// Arch instruction invoking `program_id` with this instruction as borsh serialized data.
+    /// `accounts` are attached as is and must follow the layout expected by the instruction
+    pub fn to_arch_instruction(
+        &self,
+        program_id: Pubkey,
+        accounts: Vec<AccountMeta>,
+    ) -> Instruction {
+        let mut data = Vec::new();
+        self.serialize(&mut data).unwrap();
+        Instruction {
+            program_id,
+            accounts,
+            data,
+        }
+    }
+
+    /// Reject argument combinations that are ambiguous regardless of the accounts.
+    /// An `all` flag is exclusive with an explicit amount, which must be zero when the flag is set.
+    pub fn validate(&self) -> LendingResult {
+        match self {
+            AurataInstruction::WithdrawSupply(ix) => check_all_flag(ix.amount, ix.withdraw_all),
+            AurataInstruction::WithdrawAplCollateral(ix) => {
+                check_all_flag(ix.amount, ix.withdraw_all)
+            }
+            AurataInstruction::RepayApl(ix) => check_all_flag(ix.amount, ix.repay_all),
+            AurataInstruction::WithdrawRepayApl(ix) => {
+                check_all_flag(ix.repay_amount, ix.repay_all)?;
+                check_all_flag(ix.withdraw_amount, ix.withdraw_all)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn check_all_flag(amount: u64, all: bool) -> LendingResult {
+    if all && amount != 0 {
+        return Err(LendingError::InvalidInstructionArguments.into())
+            .with_msg("amount must be zero when the all flag is set");
+    }
+    Ok(())
 }
 
 impl BorshSerialize for AurataInstruction {
@@ -195,6 +600,15 @@ impl BorshSerialize for AurataInstruction {
                 AurataInstructionTag::DonateSupply.serialize(writer)?;
                 ix.serialize(writer)
             }
+            AurataInstruction::SyncMarket => AurataInstructionTag::SyncMarket.serialize(writer),
+            AurataInstruction::MigrateAccount => {
+                AurataInstructionTag::MigrateAccount.serialize(writer)
+            }
+            AurataInstruction::SweepFeeDust => AurataInstructionTag::SweepFeeDust.serialize(writer),
+            AurataInstruction::SetCuratorMarketCount(ix) => {
+                AurataInstructionTag::SetCuratorMarketCount.serialize(writer)?;
+                ix.serialize(writer)
+            }
         }
     }
 }
@@ -257,6 +671,420 @@ impl BorshDeserialize for AurataInstruction {
             AurataInstructionTag::DonateSupply => Ok(AurataInstruction::DonateSupply(
                 <_>::deserialize_reader(reader)?,
             )),
+            AurataInstructionTag::SyncMarket => Ok(AurataInstruction::SyncMarket),
+            AurataInstructionTag::MigrateAccount => Ok(AurataInstruction::MigrateAccount),
+            AurataInstructionTag::SweepFeeDust => Ok(AurataInstruction::SweepFeeDust),
+            AurataInstructionTag::SetCuratorMarketCount => Ok(
+                AurataInstruction::SetCuratorMarketCount(<_>::deserialize_reader(reader)?),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        event::{AutaraEvent, DonateSupplyEvent},
+        interest_rate::interest_rate_kind::InterestRateCurveKind,
+        ixs::*,
+        math::ifixed_point::IFixedPoint,
+        oracle::oracle_config::tests::{btc_oracle_config, usd_oracle_config},
+        state::market_config::LtvConfig,
+    };
+
+    fn create_market_instruction() -> CreateMarketInstruction {
+        CreateMarketInstruction {
+            market_bump: 254,
+            index: 1,
+            ltv_config: LtvConfig {
+                max_ltv: IFixedPoint::from_num(0.8),
+                unhealthy_ltv: IFixedPoint::from_num(0.9),
+                liquidation_bonus: IFixedPoint::from_num(0.05),
+            },
+            max_utilisation_rate: IFixedPoint::from_num(0.95),
+            supply_oracle_config: usd_oracle_config(),
+            collateral_oracle_config: btc_oracle_config(),
+            interest_rate: InterestRateCurveKind::new_adaptive(),
+            lending_market_fee_in_bps: 1000,
+            metadata_uri: Default::default(),
+            borrow_origination_grace_seconds: 0,
         }
     }
+
+    fn all_instructions() -> Vec<AurataInstruction> {
+        let callback = Instruction {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![AccountMeta::new(Pubkey::new_unique(), false)],
+            data: vec![1, 2, 3],
+        };
+        vec![
+            AurataInstruction::CreateMarket(create_market_instruction()),
+            AurataInstruction::CreateSupplyPosition(CreateSupplyPositionInstruction { bump: 253 }),
+            AurataInstruction::SupplyApl(SupplyAplInstruction { amount: 1 }),
+            AurataInstruction::WithdrawSupply(WithdrawSupplyInstruction {
+                amount: 2,
+                withdraw_all: false,
+            }),
+            AurataInstruction::CreateBorrowPosition(CreateBorrowPositionInstruction { bump: 252 }),
+            AurataInstruction::DepositAplCollateral(DepositAplCollateralInstruction { amount: 4 }),
+            AurataInstruction::WithdrawAplCollateral(WithdrawAplCollateralInstruction {
+                amount: 0,
+                withdraw_all: true,
+            }),
+            AurataInstruction::BorrowApl(BorrowAplInstruction { amount: 3 }),
+            AurataInstruction::RepayApl(RepayAplInstruction {
+                amount: 5,
+                repay_all: false,
+            }),
+            AurataInstruction::UpdateConfig(UpdateConfigInstruction {
+                max_supply_atoms: Some(6),
+                ..Default::default()
+            }),
+            AurataInstruction::Liquidate(LiquidateInstruction {
+                max_borrowed_atoms_to_repay: 7,
+                min_collateral_atoms_to_receive: 8,
+                ix_callback: Some(callback.clone()),
+            }),
+            AurataInstruction::Log,
+            AurataInstruction::CreateGlobalConfig(CreateGlobalConfigInstruction {
+                bump: 251,
+                admin: Pubkey::new_unique(),
+                fee_receiver: Pubkey::new_unique(),
+                protocol_fee_share_in_bps: 2000,
+            }),
+            AurataInstruction::ReedeemCuratorFees,
+            AurataInstruction::ReedeemProtocolFees,
+            AurataInstruction::UpdateGlobalConfig(UpdateGlobalConfigInstruction {
+                nominated_admin: Some(Pubkey::new_unique()),
+                ..Default::default()
+            }),
+            AurataInstruction::BorrowDepositApl(BorrowDepositAplInstruction {
+                deposit_amount: 9,
+                borrow_amount: 10,
+                ix_callback: Some(callback.clone()),
+            }),
+            AurataInstruction::WithdrawRepayApl(WithdrawRepayAplInstruction {
+                repay_amount: 11,
+                withdraw_amount: 0,
+                repay_all: false,
+                withdraw_all: true,
+                ix_callback: None,
+            }),
+            AurataInstruction::SocializeLoss(SocializeLossInstruction {}),
+            AurataInstruction::DonateSupply(DonateSupplyInstruction { amount: 12 }),
+            AurataInstruction::SyncMarket,
+            AurataInstruction::MigrateAccount,
+            AurataInstruction::SweepFeeDust,
+            AurataInstruction::SetCuratorMarketCount(SetCuratorMarketCountInstruction {
+                curator: Pubkey::new_unique(),
+                market_count: 13,
+            }),
+        ]
+    }
+
+    #[test]
+    fn to_arch_instruction_roundtrips_every_variant() {
+        let program_id = Pubkey::new_unique();
+        let accounts = vec![
+            AccountMeta::new(Pubkey::new_unique(), false),
+            AccountMeta::new_readonly(Pubkey::new_unique(), true),
+        ];
+        let instructions = all_instructions();
+        assert_eq!(
+            instructions.len(),
+            AurataInstructionTag::SetCuratorMarketCount as usize + 1
+        );
+        for (tag, instruction) in instructions.iter().enumerate() {
+            let ix = instruction.to_arch_instruction(program_id, accounts.clone());
+            assert_eq!(ix.program_id, program_id);
+            assert_eq!(ix.accounts, accounts);
+            assert_eq!(
+                AurataInstructionTag::try_from(ix.data[0]),
+                AurataInstructionTag::try_from(tag as u8)
+            );
+            let decoded = AurataInstruction::try_from_slice(&ix.data).unwrap();
+            assert_eq!(&decoded, instruction);
+        }
+    }
+
+    #[test]
+    fn check_accounts_of_borrow_instruction() {
+        let ix = borrow_apl_ix(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Some(Pubkey::new_unique()),
+            Some(Pubkey::new_unique()),
+            1,
+        );
+        let tag = AurataInstructionTag::try_from(ix.data[0]).unwrap();
+        assert_eq!(tag, AurataInstructionTag::BorrowApl);
+        tag.check_accounts(&ix.accounts).unwrap();
+
+        let mut missing_authority = ix.accounts.clone();
+        missing_authority.remove(2);
+        let err = tag.check_accounts(&missing_authority).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "account #2 authority must be a signer".to_string()
+        );
+
+        let truncated = &ix.accounts[..5];
+        let err = tag.check_accounts(truncated).unwrap_err();
+        assert_eq!(
+            err,
+            AccountMismatch::Missing(5, readonly("apl_token_program"))
+        );
+        assert_eq!(err.to_string(), "missing account #5 apl_token_program");
+    }
+
+    #[test]
+    fn check_accounts_allows_leaving_out_constant_oracles() {
+        let key = Pubkey::new_unique;
+        let program = key();
+        let ix = borrow_apl_ix(
+            program,
+            key(),
+            key(),
+            key(),
+            key(),
+            key(),
+            None,
+            Some(key()),
+            1,
+        );
+        let tag = AurataInstructionTag::BorrowApl;
+        assert_eq!(ix.accounts.len(), tag.expected_accounts().len());
+        assert_eq!(ix.accounts.last().unwrap().pubkey, program);
+        tag.check_accounts(&ix.accounts).unwrap();
+        let ix = borrow_apl_ix(program, key(), key(), key(), key(), key(), None, None, 1);
+        tag.check_accounts(&ix.accounts).unwrap();
+        // without the oracles, only as many trailing accounts fit as with them
+        let mut accounts = ix.accounts.clone();
+        accounts.extend((0..3).map(|_| AccountMeta::new(key(), false)));
+        assert_eq!(
+            tag.check_accounts(&accounts).unwrap_err(),
+            AccountMismatch::Unexpected(tag.expected_accounts().len() + 1)
+        );
+    }
+
+    /// One instruction per tag, built with the `*_ix` builders used by clients
+    fn all_built_instructions() -> Vec<Instruction> {
+        let program = Pubkey::new_unique();
+        let key = Pubkey::new_unique;
+        let oracle = || Some(Pubkey::new_unique());
+        let callback = Instruction {
+            program_id: key(),
+            accounts: vec![AccountMeta::new(key(), false)],
+            data: vec![],
+        };
+        vec![
+            create_market_ix(
+                create_market_instruction(),
+                key(),
+                key(),
+                program,
+                key(),
+                key(),
+            )
+            .1,
+            create_supply_position_ix(program, key(), key(), key()).1,
+            supply_apl_ix(
+                program,
+                key(),
+                key(),
+                key(),
+                key(),
+                key(),
+                oracle(),
+                oracle(),
+                1,
+            ),
+            withdraw_supply_ix(
+                program,
+                key(),
+                key(),
+                key(),
+                key(),
+                key(),
+                oracle(),
+                oracle(),
+                1,
+                false,
+            ),
+            create_borrow_position_ix(program, key(), key(), key()).1,
+            deposit_apl_collateral_ix(
+                program,
+                key(),
+                key(),
+                key(),
+                key(),
+                key(),
+                oracle(),
+                oracle(),
+                1,
+            ),
+            withdraw_apl_collateral_ix(
+                program,
+                key(),
+                key(),
+                key(),
+                key(),
+                key(),
+                oracle(),
+                oracle(),
+                1,
+                false,
+            ),
+            borrow_apl_ix(
+                program,
+                key(),
+                key(),
+                key(),
+                key(),
+                key(),
+                oracle(),
+                oracle(),
+                1,
+            ),
+            repay_apl_ix(
+                program,
+                key(),
+                key(),
+                key(),
+                key(),
+                key(),
+                oracle(),
+                oracle(),
+                1,
+                false,
+            ),
+            update_config_ix(
+                program,
+                key(),
+                key(),
+                UpdateConfigInstruction::default(),
+                oracle(),
+                oracle(),
+            ),
+            liquidate_ix(
+                program,
+                key(),
+                key(),
+                key(),
+                key(),
+                key(),
+                key(),
+                key(),
+                oracle(),
+                oracle(),
+                1,
+                0,
+                Some(callback.clone()),
+            ),
+            log_ix(
+                &program,
+                &key(),
+                AutaraEvent::DonateSupply(DonateSupplyEvent {
+                    market: key(),
+                    donor: key(),
+                    mint: key(),
+                    amount: 1,
+                }),
+            ),
+            create_global_config_ix(program, key(), key(), key(), 2000).1,
+            reedeem_curator_fees_ix(program, key(), key(), key(), key()),
+            reedeem_protocol_fees_ix(program, key(), key(), key(), key()),
+            update_global_config_ix(program, key(), UpdateGlobalConfigInstruction::default()),
+            borrow_deposit_apl_ix(
+                program,
+                key(),
+                key(),
+                key(),
+                key(),
+                key(),
+                key(),
+                key(),
+                oracle(),
+                oracle(),
+                BorrowDepositAplInstruction {
+                    deposit_amount: 1,
+                    borrow_amount: 1,
+                    ix_callback: Some(callback.clone()),
+                },
+            ),
+            withdraw_repay_apl_ix(
+                program,
+                key(),
+                key(),
+                key(),
+                key(),
+                key(),
+                key(),
+                key(),
+                oracle(),
+                oracle(),
+                WithdrawRepayAplInstruction {
+                    repay_amount: 1,
+                    withdraw_amount: 1,
+                    repay_all: false,
+                    withdraw_all: false,
+                    ix_callback: Some(callback),
+                },
+            ),
+            socialize_loss_ix(
+                program,
+                key(),
+                key(),
+                key(),
+                key(),
+                key(),
+                oracle(),
+                oracle(),
+            ),
+            donate_supply_ix(program, key(), key(), key(), key(), 1),
+            sync_market_ix(program, key()),
+            migrate_account_ix(program, key()),
+            sweep_fee_dust_ix(program, key(), key()),
+            set_curator_market_count_ix(program, key(), key(), 1),
+        ]
+    }
+
+    #[test]
+    fn every_built_instruction_matches_its_expected_accounts() {
+        let instructions = all_built_instructions();
+        assert_eq!(
+            instructions.len(),
+            AurataInstructionTag::SetCuratorMarketCount as usize + 1
+        );
+        for (index, ix) in instructions.iter().enumerate() {
+            let tag = AurataInstructionTag::try_from(ix.data[0]).unwrap();
+            assert_eq!(tag, AurataInstructionTag::try_from(index as u8).unwrap());
+            tag.check_accounts(&ix.accounts)
+                .unwrap_or_else(|err| panic!("{tag:?}: {err}"));
+            if tag.max_trailing_accounts().is_some() {
+                assert_eq!(
+                    ix.accounts.len(),
+                    tag.expected_accounts().len() + tag.max_trailing_accounts().unwrap(),
+                    "{tag:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn check_accounts_rejects_unexpected_accounts() {
+        let ix = sync_market_ix(Pubkey::new_unique(), Pubkey::new_unique());
+        let mut accounts = ix.accounts.clone();
+        accounts.push(AccountMeta::new(Pubkey::new_unique(), false));
+        let err = AurataInstructionTag::SyncMarket
+            .check_accounts(&accounts)
+            .unwrap_err();
+        assert_eq!(err, AccountMismatch::Unexpected(2));
+        assert_eq!(err.to_string(), "unexpected account #2");
+    }
 }
@@ -1,6 +1,6 @@
 use crate::{
-    error::LendingResult,
-    math::{safe_math::SafeMath, ufixed_point::UFixedPoint},
+    error::{LendingError, LendingResult, LendingResultExt},
+    math::{ifixed_point::IFixedPoint, safe_math::SafeMath, ufixed_point::UFixedPoint},
 };
 
 pub const ONE_IN_BPS: u32 = 10_000;
@@ -17,12 +17,87 @@ pub const fn bps_to_fixed_point(bps: u64) -> UFixedPoint {
     UFixedPoint::from_u64_u64_ratio(bps as _, ONE_IN_BPS as _)
 }
 
+/// Returns the `bps` share of `fixed`, the share never exceeds `fixed`
 pub fn bps_from_fixed_point(bps: u64, fixed: UFixedPoint) -> LendingResult<UFixedPoint> {
+    if bps > ONE_IN_BPS as u64 {
+        return Err(LendingError::BpsOutOfRange.into()).with_msg("bps share exceeds 100%");
+    }
     fixed
         .safe_mul(bps)
         .map(|r| r.safe_div(ONE_IN_BPS as u64).expect("should not overflow"))
 }
 
+/// A value expressed in basis points, 1 bps = 0.01%
+///
+/// On-chain accounts and instructions keep storing raw `u16`, this wrapper is used at the
+/// API boundary so that percent, bps and fixed point fractions can not be mixed up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(
+    feature = "client",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct Bps(u16);
+
+impl Bps {
+    pub const ZERO: Bps = Bps(0);
+    pub const ONE: Bps = Bps(ONE_IN_BPS as u16);
+
+    pub const fn new(bps: u16) -> Self {
+        Bps(bps)
+    }
+
+    pub const fn from_percent(percent: u8) -> Self {
+        Bps(percent_to_bps(percent as u64) as u16)
+    }
+
+    pub const fn get(self) -> u16 {
+        self.0
+    }
+
+    pub const fn to_percent(self) -> u16 {
+        bps_to_percent(self.0 as u64) as u16
+    }
+
+    pub const fn to_fixed_point(self) -> UFixedPoint {
+        bps_to_fixed_point(self.0 as u64)
+    }
+
+    pub fn to_ifixed_point(self) -> IFixedPoint {
+        IFixedPoint::from_i64_u64_ratio(self.0 as i64, ONE_IN_BPS as u64)
+    }
+
+    /// Converts a fraction to bps, rounding to the nearest bps so that
+    /// `Bps::from_fixed_point(bps.to_fixed_point()) == bps`
+    pub fn from_fixed_point(value: UFixedPoint) -> LendingResult<Self> {
+        let bps = value
+            .safe_mul(ONE_IN_BPS as u64)
+            .and_then(|bps| bps.safe_add(UFixedPoint::from_u64_u64_ratio(1, 2)))
+            .and_then(|bps| bps.as_u64_rounded_down())?;
+        u16::try_from(bps)
+            .map(Bps)
+            .map_err(|_| LendingError::CastOverflow.into())
+            .with_msg("bps value does not fit in u16")
+    }
+
+    /// Converts a fraction to bps, rounding to the nearest bps
+    pub fn from_ifixed_point(value: IFixedPoint) -> LendingResult<Self> {
+        Self::from_fixed_point(UFixedPoint::try_from(value)?)
+    }
+}
+
+impl From<Bps> for u16 {
+    fn from(value: Bps) -> Self {
+        value.0
+    }
+}
+
+impl std::fmt::Display for Bps {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} bps", self.0)
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
@@ -62,4 +137,51 @@ pub mod tests {
         let result = bps_from_fixed_point(1, UFixedPoint::from_u64(2));
         assert_eq!(result, Ok(UFixedPoint::from_u64_u64_ratio(2, 10_000)));
     }
+
+    #[test]
+    fn bps_from_fixed_point_rejects_share_above_one() {
+        let fixed = UFixedPoint::from_u64(2);
+        assert_eq!(bps_from_fixed_point(ONE_IN_BPS as u64, fixed), Ok(fixed));
+        assert_eq!(
+            bps_from_fixed_point(ONE_IN_BPS as u64 + 1, fixed).unwrap_err(),
+            LendingError::BpsOutOfRange
+        );
+        assert_eq!(
+            bps_from_fixed_point(u16::MAX as u64, fixed).unwrap_err(),
+            LendingError::BpsOutOfRange
+        );
+    }
+
+    #[test]
+    fn bps_round_trips_through_fixed_point() {
+        for raw in [0, 1, 50, 100, 999, 5000, 10_000, u16::MAX] {
+            let bps = Bps::new(raw);
+            assert_eq!(Bps::from_fixed_point(bps.to_fixed_point()), Ok(bps));
+            assert_eq!(Bps::from_ifixed_point(bps.to_ifixed_point()), Ok(bps));
+        }
+    }
+
+    #[test]
+    fn bps_percent_conversions() {
+        assert_eq!(Bps::from_percent(15), Bps::new(1500));
+        assert_eq!(Bps::from_percent(100), Bps::ONE);
+        assert_eq!(Bps::new(1550).to_percent(), 15);
+        assert_eq!(Bps::ONE.to_fixed_point(), UFixedPoint::one());
+        assert_eq!(
+            Bps::from_fixed_point(UFixedPoint::from_u64_u64_ratio(12_345, 1_000_000)),
+            Ok(Bps::new(123))
+        );
+        assert_eq!(
+            Bps::from_fixed_point(UFixedPoint::from_u64_u64_ratio(12_355, 1_000_000)),
+            Ok(Bps::new(124))
+        );
+    }
+
+    #[test]
+    fn bps_from_fixed_point_rejects_out_of_range() {
+        let err = Bps::from_fixed_point(UFixedPoint::from_u64(7)).unwrap_err();
+        assert_eq!(err.error, LendingError::CastOverflow);
+        let err = Bps::from_ifixed_point(IFixedPoint::lit("-0.1")).unwrap_err();
+        assert_eq!(err.error, LendingError::CastOverflow);
+    }
 }
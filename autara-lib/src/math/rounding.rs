@@ -1,5 +1,41 @@
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "client",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
 pub enum RoundingMode {
     RoundDown,
     RoundUp,
 }
+
+/// Rounding direction applied by the supply vault for each operation.
+/// Atoms paid out by the vault are rounded down and atoms owed to it are rounded up.
+/// Minted shares come out of a fixed point division and are truncated to the
+/// 64 fractional bits of [`super::ufixed_point::UFixedPoint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "client",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct RoundingPolicy {
+    /// Supply shares minted for the deposited atoms
+    pub supply_deposit: RoundingMode,
+    /// Atoms paid out for the burned supply shares
+    pub supply_withdraw: RoundingMode,
+    /// Debt shares minted for the borrowed atoms
+    pub borrow: RoundingMode,
+    /// Atoms owed for the burned debt shares
+    pub repay: RoundingMode,
+    /// Fee shares minted out of the accrued interest
+    pub fee_accrual: RoundingMode,
+}
+
+pub const ROUNDING_POLICY: RoundingPolicy = RoundingPolicy {
+    supply_deposit: RoundingMode::RoundDown,
+    supply_withdraw: RoundingMode::RoundDown,
+    borrow: RoundingMode::RoundDown,
+    repay: RoundingMode::RoundUp,
+    fee_accrual: RoundingMode::RoundDown,
+};
@@ -156,6 +156,25 @@ impl SharesTracker {
         Ok(())
     }
 
+    /// Cancel `shares` and spread their value over the remaining holders,
+    /// leaving the total atoms unchanged
+    pub fn redistribute_shares(&mut self, shares: UFixedPoint) -> LendingResult<()> {
+        let value = shares.safe_mul(self.atoms_per_share)?;
+        let remaining_shares = self
+            .total_shares
+            .safe_sub(shares)
+            .map_err(|_| LendingError::SharesOverflow)?;
+        if remaining_shares.is_zero() {
+            return Err(LendingError::CantModifySharePriceIfZeroShares.into())
+                .with_msg("redistribute_shares");
+        }
+        self.total_shares = remaining_shares;
+        self.atoms_per_share = self
+            .atoms_per_share
+            .safe_add(value.safe_div(remaining_shares)?)?;
+        Ok(())
+    }
+
     pub fn socialize_loss_atoms(&mut self, atoms: u64) -> LendingResult<()> {
         if self.total_shares.is_zero() {
             return Err(LendingError::CantModifySharePriceIfZeroShares.into())
@@ -1,12 +1,13 @@
 #[cfg(feature = "client")]
 pub mod client {
-    use std::ops::Deref;
+    use std::{collections::VecDeque, ops::Deref};
 
     use crate::{
         error::LendingResult,
+        event::AutaraEvent,
         state::{
             borrow_position::BorrowPosition, market::Market, market_wrapper::MarketWrapper,
-            supply_position::SupplyPosition,
+            supply_position::SupplyPosition, supply_vault::SupplyVaultSummary,
         },
     };
 
@@ -21,6 +22,8 @@ pub mod client {
         pub supply_usd: f64,
         pub borrow_usd: f64,
         pub collateral_usd: f64,
+        pub utilisation_headroom: f64,
+        pub borrowable_within_utilisation_cap: f64,
     }
 
     #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -59,6 +62,9 @@ pub mod client {
             let supply_usd = supply * supply_price;
             let borrow_usd = borrow * supply_price;
             let collateral_usd = collateral * self.collateral_oracle().rate().to_float();
+            let borrowable_within_utilisation_cap =
+                self.market().borrowable_atoms_within_utilisation_cap()? as f64
+                    / 10f64.powi(self.market().supply_vault().mint_decimals() as i32);
             Ok(LiquiditySummary {
                 supply_mint: self.market().supply_vault().mint().to_string(),
                 collateral_mint: self.market().collateral_vault().mint().to_string(),
@@ -68,6 +74,8 @@ pub mod client {
                 supply_usd,
                 borrow_usd,
                 collateral_usd,
+                utilisation_headroom: self.market().utilisation_headroom()?.to_float(),
+                borrowable_within_utilisation_cap,
             })
         }
 
@@ -110,4 +118,177 @@ pub mod client {
             })
         }
     }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct CuratorFeePoint {
+        pub unix_timestamp: i64,
+        pub pending_curator_fee_atoms: u64,
+        /// Cumulative curator fees redeemed since the timeline started
+        pub redeemed_curator_fee_atoms: u64,
+    }
+
+    /// Rolling history of the pending and redeemed curator fees of a market,
+    /// built from successive supply vault snapshots.
+    /// A drop of the pending fees between two snapshots is counted as redeemed,
+    /// redeem events give the exact amount when they are observed.
+    #[derive(Debug, Clone)]
+    pub struct CuratorFeeTimeline {
+        capacity: usize,
+        redeemed_curator_fee_atoms: u64,
+        points: VecDeque<CuratorFeePoint>,
+    }
+
+    impl CuratorFeeTimeline {
+        pub fn new(capacity: usize) -> Self {
+            Self {
+                capacity: capacity.max(1),
+                redeemed_curator_fee_atoms: 0,
+                points: VecDeque::with_capacity(capacity),
+            }
+        }
+
+        pub fn points(&self) -> impl Iterator<Item = &CuratorFeePoint> {
+            self.points.iter()
+        }
+
+        pub fn last(&self) -> Option<&CuratorFeePoint> {
+            self.points.back()
+        }
+
+        /// Records a snapshot taken after a `sync_clock`
+        pub fn observe_summary(&mut self, summary: &SupplyVaultSummary) {
+            let redeemed = self.last().map_or(0, |last| {
+                last.pending_curator_fee_atoms
+                    .saturating_sub(summary.pending_curator_fee_atoms)
+            });
+            self.push(summary, redeemed);
+        }
+
+        /// Records a curator fee redemption, any other event is ignored
+        pub fn observe_redeem(&mut self, event: &AutaraEvent) {
+            if let AutaraEvent::ReedeemCuratorFees(event) = event {
+                self.push(&event.supply_vault_snapshot, event.fee_amount);
+            }
+        }
+
+        /// Changes of the pending and redeemed fees between successive points,
+        /// as `(elapsed_seconds, pending_delta, redeemed_delta)`
+        pub fn deltas(&self) -> impl Iterator<Item = (i64, i64, u64)> + '_ {
+            self.points
+                .iter()
+                .zip(self.points.iter().skip(1))
+                .map(|(previous, next)| {
+                    (
+                        next.unix_timestamp - previous.unix_timestamp,
+                        next.pending_curator_fee_atoms as i64
+                            - previous.pending_curator_fee_atoms as i64,
+                        next.redeemed_curator_fee_atoms - previous.redeemed_curator_fee_atoms,
+                    )
+                })
+        }
+
+        fn push(&mut self, summary: &SupplyVaultSummary, redeemed_atoms: u64) {
+            let unix_timestamp = summary.last_update_unix_timestamp;
+            // out of order observations would be double counted with the inferred redemptions
+            if self
+                .last()
+                .is_some_and(|last| last.unix_timestamp > unix_timestamp)
+            {
+                return;
+            }
+            self.redeemed_curator_fee_atoms = self
+                .redeemed_curator_fee_atoms
+                .saturating_add(redeemed_atoms);
+            let point = CuratorFeePoint {
+                unix_timestamp,
+                pending_curator_fee_atoms: summary.pending_curator_fee_atoms,
+                redeemed_curator_fee_atoms: self.redeemed_curator_fee_atoms,
+            };
+            if self
+                .last()
+                .is_some_and(|last| last.unix_timestamp == unix_timestamp)
+            {
+                self.points.pop_back();
+            } else if self.points.len() == self.capacity {
+                self.points.pop_front();
+            }
+            self.points.push_back(point);
+        }
+    }
+
+    #[cfg(test)]
+    pub mod tests {
+        use arch_program::pubkey::Pubkey;
+
+        use super::*;
+        use crate::event::ReedeemFeeEvent;
+
+        fn summary(unix_timestamp: i64, pending_curator_fee_atoms: u64) -> SupplyVaultSummary {
+            SupplyVaultSummary {
+                last_update_unix_timestamp: unix_timestamp,
+                pending_curator_fee_atoms,
+                ..Default::default()
+            }
+        }
+
+        fn redeem_event(
+            fee_amount: u64,
+            supply_vault_snapshot: SupplyVaultSummary,
+        ) -> ReedeemFeeEvent {
+            ReedeemFeeEvent {
+                market: Pubkey::new_unique(),
+                fee_receiver: Pubkey::new_unique(),
+                fee_amount,
+                mint: Pubkey::new_unique(),
+                supply_vault_snapshot,
+            }
+        }
+
+        #[test]
+        fn curator_fee_timeline_records_accrual_and_redemptions() {
+            let mut timeline = CuratorFeeTimeline::new(3);
+            timeline.observe_summary(&summary(100, 10));
+            timeline.observe_summary(&summary(160, 40));
+            timeline.observe_redeem(&AutaraEvent::ReedeemCuratorFees(redeem_event(
+                45,
+                summary(170, 0),
+            )));
+            assert_eq!(
+                timeline.deltas().collect::<Vec<_>>(),
+                vec![(60, 30, 0), (10, -40, 45)]
+            );
+            // stale observations are ignored
+            timeline.observe_summary(&summary(150, 5));
+            assert_eq!(timeline.last().unwrap().unix_timestamp, 170);
+            // a redemption which was not observed is inferred from the pending drop
+            timeline.observe_summary(&summary(200, 20));
+            timeline.observe_summary(&summary(260, 8));
+            assert_eq!(timeline.points().count(), 3);
+            assert_eq!(
+                timeline.deltas().collect::<Vec<_>>(),
+                vec![(30, 20, 0), (60, -12, 12)]
+            );
+            assert_eq!(
+                timeline.last(),
+                Some(&CuratorFeePoint {
+                    unix_timestamp: 260,
+                    pending_curator_fee_atoms: 8,
+                    redeemed_curator_fee_atoms: 57,
+                })
+            );
+        }
+
+        #[test]
+        fn curator_fee_timeline_ignores_protocol_fee_redemptions() {
+            let mut timeline = CuratorFeeTimeline::new(3);
+            timeline.observe_summary(&summary(100, 10));
+            timeline.observe_redeem(&AutaraEvent::ReedeemProtocolFees(redeem_event(
+                30,
+                summary(110, 10),
+            )));
+            assert_eq!(timeline.points().count(), 1);
+            assert_eq!(timeline.last().unwrap().redeemed_curator_fee_atoms, 0);
+        }
+    }
 }
@@ -1,6 +1,10 @@
 use crate::{
     error::LendingResult,
-    math::{ifixed_point::IFixedPoint, safe_math::SafeMath},
+    math::{
+        bps::{Bps, ONE_IN_BPS},
+        ifixed_point::IFixedPoint,
+        safe_math::SafeMath,
+    },
     oracle::oracle_price::OracleRate,
 };
 
@@ -47,6 +51,33 @@ impl LiquidationResultWithBonus {
         self.collateral_atoms_to_liquidate
             .safe_add(self.collateral_atoms_liquidation_bonus)
     }
+
+    /// Collateral atoms the liquidator keeps when the protocol takes `protocol_fee_bps` of the
+    /// liquidation bonus, the protocol slice being rounded down. A share above 100% takes
+    /// the whole bonus
+    pub fn liquidator_net_collateral(&self, protocol_fee_bps: Bps) -> u64 {
+        let bps = (protocol_fee_bps.get() as u128).min(ONE_IN_BPS as u128);
+        let protocol_slice =
+            (self.collateral_atoms_liquidation_bonus as u128 * bps / ONE_IN_BPS as u128) as u64;
+        self.collateral_atoms_to_liquidate
+            .saturating_add(self.collateral_atoms_liquidation_bonus - protocol_slice)
+    }
+
+    /// Bonus received by the liquidator relative to the collateral matching the repaid debt.
+    ///
+    /// It is the configured liquidation bonus, unless the collateral left once the debt is
+    /// covered is not enough to pay it: the bonus is then capped to all the remaining
+    /// collateral, and vanishes when the collateral only covers the debt.
+    /// Zero when no collateral is liquidated
+    pub fn effective_bonus_ratio(&self) -> LendingResult<IFixedPoint> {
+        if self.collateral_atoms_to_liquidate == 0 {
+            return Ok(IFixedPoint::zero());
+        }
+        IFixedPoint::from_ratio(
+            self.collateral_atoms_liquidation_bonus,
+            self.collateral_atoms_to_liquidate,
+        )
+    }
 }
 
 /// Caller should assert `ltv` and `new_ltv_after_liquidation_fee` < 1
@@ -133,6 +164,7 @@ pub mod tests {
     use std::u64;
 
     use crate::{
+        assert_eq_float,
         math::ifixed_point::IFixedPoint,
         oracle::oracle_price::OracleRate,
         state::{
@@ -320,6 +352,40 @@ pub mod tests {
         );
     }
 
+    #[test]
+    pub fn effective_bonus_ratio_is_capped_by_remaining_collateral() {
+        let setup = LiquidationSetup::new();
+        let collateral_atoms = BTC(1.);
+        let fee = IFixedPoint::lit("0.1");
+        let liquidate = |borrowed_atoms: u64| {
+            compute_liquidation_with_fee(
+                borrowed_atoms,
+                setup.borrow_decimals,
+                &setup.supply_oracle,
+                collateral_atoms,
+                setup.collateral_decimals,
+                &setup.collateral_oracle,
+                setup.desired_ltv,
+                fee,
+                u64::MAX,
+            )
+            .unwrap()
+        };
+        let full = liquidate(USDC(80000.)).effective_bonus_ratio().unwrap();
+        assert_eq_float!(full.to_float(), fee.to_float(), 0.0001);
+        let reduced = liquidate(USDC(95000.)).effective_bonus_ratio().unwrap();
+        assert!(reduced > IFixedPoint::zero());
+        assert!(reduced < fee);
+        assert_eq_float!(reduced.to_float(), 5_000. / 95_000., 0.0001);
+        // the collateral only covers the debt, leaving a rounding dust as bonus
+        let break_even = liquidate(USDC(100000.)).effective_bonus_ratio().unwrap();
+        assert!(break_even < IFixedPoint::lit("0.0000001"));
+        assert!(LiquidationResultWithBonus::default()
+            .effective_bonus_ratio()
+            .unwrap()
+            .is_zero());
+    }
+
     #[test]
     pub fn liquidation_reduces_ltv() {
         let setup = LiquidationSetup::new();
@@ -433,6 +499,32 @@ pub mod tests {
         }
     }
 
+    #[test]
+    pub fn liquidator_net_collateral_after_protocol_fee() {
+        let result = LiquidationResultWithBonus {
+            borrowed_atoms_to_repay: 1_000_000,
+            collateral_atoms_to_liquidate: 500_000,
+            collateral_atoms_liquidation_bonus: 25_003,
+        };
+        let total = result.total_collateral_atoms_to_liquidate().unwrap();
+        for (bps, protocol_slice) in [
+            (0, 0),
+            (1, 2),
+            (1_000, 2_500),
+            (5_000, 12_501),
+            (10_000, 25_003),
+        ] {
+            assert_eq!(
+                result.liquidator_net_collateral(Bps::new(bps)),
+                total - protocol_slice
+            );
+        }
+        assert_eq!(
+            result.liquidator_net_collateral(Bps::new(u16::MAX)),
+            result.collateral_atoms_to_liquidate
+        );
+    }
+
     #[test]
     pub fn adjust_max_repay_scales_proportionally() {
         let mut result = LiquidationResultWithBonus {
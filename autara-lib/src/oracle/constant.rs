@@ -0,0 +1,63 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use bytemuck::{Pod, Zeroable};
+
+use crate::{
+    error::LendingResult,
+    math::ifixed_point::IFixedPoint,
+    oracle::{
+        oracle_price::OracleRate,
+        oracle_provider::{AccountView, OracleLoader, UncheckedOracleRate},
+    },
+};
+
+/// Fixed price without any feed, for assets pegged to the other side of the market
+/// such as a stablecoin against another one
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Pod, Zeroable, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(
+    feature = "client",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct ConstantProvider {
+    pub price: IFixedPoint,
+}
+
+impl ConstantProvider {
+    /// The configured price with no confidence interval, never stale
+    pub fn rate(&self) -> UncheckedOracleRate {
+        UncheckedOracleRate::new(OracleRate::new(self.price, IFixedPoint::zero()), i64::MAX)
+    }
+}
+
+impl OracleLoader for ConstantProvider {
+    /// A constant oracle has no feed account, the given one is ignored
+    fn load_oracle_price<D: std::ops::Deref<Target = [u8]>>(
+        &self,
+        _view: AccountView<D>,
+    ) -> LendingResult<UncheckedOracleRate> {
+        Ok(self.rate())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::oracle::oracle_config::OracleValidationConfig;
+    use arch_program::pubkey::Pubkey;
+
+    #[test]
+    fn constant_price_ignores_the_account() {
+        let provider = ConstantProvider {
+            price: IFixedPoint::one(),
+        };
+        let key = Pubkey::new_unique();
+        let rate = provider
+            .load_oracle_price((&key, [0u8; 0].as_slice(), &key).into())
+            .unwrap()
+            .validate(&OracleValidationConfig::default(), i64::MAX - 1)
+            .unwrap();
+        assert_eq!(rate.rate(), IFixedPoint::one());
+        assert_eq!(rate.confidence(), IFixedPoint::zero());
+    }
+}
@@ -1,6 +1,9 @@
 pub mod chaos;
+pub mod constant;
 pub mod oracle_config;
 pub mod oracle_price;
 pub mod oracle_provider;
 pub mod pod_oracle_provider;
 pub mod pyth;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
@@ -3,10 +3,10 @@ use bytemuck::{Pod, Zeroable};
 
 use crate::{
     error::{LendingError, LendingResult, LendingResultExt},
-    math::ifixed_point::IFixedPoint,
+    math::{bps::Bps, ifixed_point::IFixedPoint},
     oracle::{
         oracle_price::OracleRate,
-        oracle_provider::{AccountView, OracleLoader},
+        oracle_provider::{AccountView, OracleLoader, UncheckedOracleRate},
         pod_oracle_provider::PodOracleProvider,
     },
     padding::Padding,
@@ -15,6 +15,9 @@ use crate::{
 
 crate::validate_struct!(OracleConfig, 264);
 
+/// Max age of an oracle price when the validation config does not set one
+pub const DEFAULT_ORACLE_MAX_AGE_SECONDS: u64 = 60;
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Pod, Zeroable, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
 #[cfg_attr(
@@ -27,7 +30,13 @@ pub struct OracleConfig {
     oracle_provider: PodOracleProvider,
     /// Config to sanitize oracle feed
     validation_config: OracleValidationConfig,
-    pad: Padding<160>,
+    /// Non zero when the feed quotes the inverse of the pair needed by the market
+    #[cfg_attr(feature = "client", serde(default))]
+    invert: u8,
+    /// Non zero when the EMA price of the feed is used instead of the spot one, Pyth only
+    #[cfg_attr(feature = "client", serde(default))]
+    use_ema: u8,
+    pad: Padding<158>,
 }
 
 impl OracleConfig {
@@ -38,6 +47,8 @@ impl OracleConfig {
         Self {
             oracle_provider: oracle_provider.into(),
             validation_config,
+            invert: 0,
+            use_ema: 0,
             pad: Padding::default(),
         }
     }
@@ -50,8 +61,23 @@ impl OracleConfig {
                         .with_msg("required_signatures must be greater than 0");
                 }
             }
+            super::oracle_provider::OracleProviderRef::Constant(constant_provider)
+                if constant_provider.price <= IFixedPoint::zero() =>
+            {
+                return Err(LendingError::InvalidOracleConfig.into())
+                    .with_msg("constant price must be positive");
+            }
             _ => {}
         }
+        if self.uses_ema_price()
+            && !matches!(
+                self.oracle_provider.oracle_provider_ref(),
+                super::oracle_provider::OracleProviderRef::Pyth(_)
+            )
+        {
+            return Err(LendingError::InvalidOracleConfig.into())
+                .with_msg("EMA price is only available for pyth oracles");
+        }
         Ok(())
     }
 
@@ -66,6 +92,8 @@ impl OracleConfig {
                 ),
             ),
             validation_config: OracleValidationConfig::default(),
+            invert: 0,
+            use_ema: 0,
             pad: Padding::default(),
         }
     }
@@ -87,10 +115,71 @@ impl OracleConfig {
                 ),
             ),
             validation_config: OracleValidationConfig::default(),
+            invert: 0,
+            use_ema: 0,
+            pad: Padding::default(),
+        }
+    }
+
+    /// Fixed `price` loaded without reading any oracle account
+    pub fn new_constant(price: IFixedPoint) -> Self {
+        Self {
+            oracle_provider: PodOracleProvider::from_oracle_provider(
+                crate::oracle::oracle_provider::OracleProvider::Constant(
+                    crate::oracle::constant::ConstantProvider { price },
+                ),
+            ),
+            validation_config: OracleValidationConfig::default(),
+            invert: 0,
+            use_ema: 0,
             pad: Padding::default(),
         }
     }
 
+    /// Constant oracles have no feed account to read
+    pub fn is_constant(&self) -> bool {
+        matches!(
+            self.oracle_provider.oracle_provider_ref(),
+            super::oracle_provider::OracleProviderRef::Constant(_)
+        )
+    }
+
+    /// Same config with prices older than `max_age` seconds rejected as stale,
+    /// 0 falls back to [DEFAULT_ORACLE_MAX_AGE_SECONDS]
+    pub fn with_max_age(mut self, max_age: u64) -> Self {
+        self.validation_config.max_age = PodOption::new(max_age);
+        self
+    }
+
+    /// Same config with prices whose confidence exceeds `max_confidence` of the price rejected,
+    /// zero disables the check
+    pub fn with_max_confidence(mut self, max_confidence: Bps) -> Self {
+        self.validation_config.min_relative_confidence =
+            PodOption::new(max_confidence.to_ifixed_point());
+        self
+    }
+
+    /// Same config with the loaded rates inverted, for feeds quoting the inverse pair
+    pub fn with_inverted_rate(mut self, invert: bool) -> Self {
+        self.invert = u8::from(invert);
+        self
+    }
+
+    pub fn is_inverted(&self) -> bool {
+        self.invert != 0
+    }
+
+    /// Same config with the EMA price of the feed used instead of the spot one,
+    /// still checked for staleness and confidence
+    pub fn with_ema_price(mut self, use_ema: bool) -> Self {
+        self.use_ema = u8::from(use_ema);
+        self
+    }
+
+    pub fn uses_ema_price(&self) -> bool {
+        self.use_ema != 0
+    }
+
     pub fn oracle_provider(&self) -> &PodOracleProvider {
         &self.oracle_provider
     }
@@ -99,20 +188,51 @@ impl OracleConfig {
         &self.validation_config
     }
 
+    /// Loads the rate from the provider, inverted when the feed quotes the inverse pair.
+    /// `view` is the feed account, none is read for a constant oracle
+    pub fn load_oracle_price<D: std::ops::Deref<Target = [u8]>>(
+        &self,
+        view: Option<AccountView<D>>,
+    ) -> LendingResult<UncheckedOracleRate> {
+        let unchecked_price = match (self.oracle_provider.oracle_provider_ref(), view) {
+            (super::oracle_provider::OracleProviderRef::Constant(constant), _) => constant.rate(),
+            (_, None) => {
+                return Err(LendingError::MissingOracleAccount.into())
+                    .with_msg("only constant oracles can be loaded without their feed account");
+            }
+            (super::oracle_provider::OracleProviderRef::Pyth(pyth), Some(view))
+                if self.uses_ema_price() =>
+            {
+                pyth.load_ema_oracle_price(view)?
+            }
+            (provider, Some(view)) => provider.load_oracle_price(view)?,
+        };
+        if self.is_inverted() {
+            return unchecked_price.inverse();
+        }
+        Ok(unchecked_price)
+    }
+
+    /// Loads the rate and validates it against `validation_config`, or against the
+    /// validation config of the oracle when not overridden
     pub fn load_and_validate_oracle_rate<D: std::ops::Deref<Target = [u8]>>(
         &self,
-        view: AccountView<D>,
+        view: Option<AccountView<D>>,
         unix_timestamp: i64,
+        validation_config: Option<&OracleValidationConfig>,
     ) -> LendingResult<OracleRate> {
-        let unchecked_price = self.oracle_provider.load_oracle_price(view)?;
-        unchecked_price.validate(&self.validation_config, unix_timestamp)
+        let unchecked_price = self.load_oracle_price(view)?;
+        unchecked_price.validate(
+            validation_config.unwrap_or(&self.validation_config),
+            unix_timestamp,
+        )
     }
 
     pub fn load_oracle_rate_unchecked<D: std::ops::Deref<Target = [u8]>>(
         &self,
-        view: AccountView<D>,
+        view: Option<AccountView<D>>,
     ) -> LendingResult<OracleRate> {
-        let unchecked_price = self.oracle_provider.load_oracle_price(view)?;
+        let unchecked_price = self.load_oracle_price(view)?;
         Ok(unchecked_price.unsafe_rate())
     }
 }
@@ -125,7 +245,8 @@ impl OracleConfig {
     serde(rename_all = "camelCase")
 )]
 pub struct OracleValidationConfig {
-    /// Max age in seconds from which an oracle is considered as stale
+    /// Max age in seconds from which an oracle is considered as stale,
+    /// [DEFAULT_ORACLE_MAX_AGE_SECONDS] when not set
     max_age: PodOption<u64>,
     /// Level from which relative confidence should stay under.
     /// Ex: If price is 150 +/- 1.6, relative confidence is 1.6/150 ~ 1.06%
@@ -135,7 +256,7 @@ pub struct OracleValidationConfig {
 impl Default for OracleValidationConfig {
     fn default() -> Self {
         Self {
-            max_age: PodOption::new(60),
+            max_age: PodOption::new(DEFAULT_ORACLE_MAX_AGE_SECONDS),
             min_relative_confidence: PodOption::new(IFixedPoint::lit("0.05")),
         }
     }
@@ -149,13 +270,22 @@ impl OracleValidationConfig {
         }
     }
 
-    pub fn max_age(&self) -> Option<u64> {
-        self.max_age.as_ref().copied()
+    pub fn max_age(&self) -> u64 {
+        self.max_age
+            .as_ref()
+            .copied()
+            .unwrap_or(DEFAULT_ORACLE_MAX_AGE_SECONDS)
     }
 
     pub fn min_relative_confidence(&self) -> Option<&IFixedPoint> {
         self.min_relative_confidence.as_ref()
     }
+
+    /// Same config with `max_age` lowered to `max_age` when it is stricter
+    pub fn with_stricter_max_age(mut self, max_age: u64) -> Self {
+        self.max_age = PodOption::new(self.max_age().min(max_age));
+        self
+    }
 }
 
 #[cfg(test)]
@@ -163,8 +293,14 @@ pub mod tests {
     use arch_program::pubkey::Pubkey;
 
     use crate::{
+        assert_eq_float,
         math::ifixed_point::IFixedPoint,
-        oracle::{oracle_price::OracleRate, oracle_provider::OracleProvider, pyth::PythProvider},
+        oracle::{
+            oracle_price::OracleRate,
+            oracle_provider::OracleProvider,
+            pyth::PythProvider,
+            test_utils::{OracleScenario, SCENARIO_PUBLISH_TIME},
+        },
     };
 
     use super::*;
@@ -193,6 +329,181 @@ pub mod tests {
         OracleRate::new(IFixedPoint::from_num(1.0), IFixedPoint::from_num(0.001))
     }
 
+    #[test]
+    fn inverted_feed_matches_direct_feed() {
+        let owner = Pubkey(BTC_FEED_ID);
+        let key = Pubkey::new_unique();
+        let now = SCENARIO_PUBLISH_TIME;
+        // 100_000 +/- 10 USD per BTC
+        let direct = OracleScenario::new(BTC_FEED_ID).with_price(100_000, 10, 0);
+        // 0.00001 +/- 0.000000001 BTC per USD
+        let inverse = OracleScenario::new(BTC_FEED_ID).with_price(100_000, 10, -10);
+        let direct_rate = btc_oracle_config()
+            .load_and_validate_oracle_rate(
+                Some((&key, direct.to_bytes(), &owner).into()),
+                now,
+                None,
+            )
+            .unwrap();
+        let inverted_config = btc_oracle_config().with_inverted_rate(true);
+        assert!(inverted_config.is_inverted());
+        let inverted_rate = inverted_config
+            .load_and_validate_oracle_rate(
+                Some((&key, inverse.to_bytes(), &owner).into()),
+                now,
+                None,
+            )
+            .unwrap();
+        assert_eq_float!(
+            inverted_rate.rate().to_float(),
+            direct_rate.rate().to_float()
+        );
+        let direct_value = direct_rate.collateral_value(100_000_000, 8).unwrap();
+        let inverted_value = inverted_rate.collateral_value(100_000_000, 8).unwrap();
+        // the inverted confidence is the widest side of the interval, so slightly more conservative
+        assert!(inverted_value <= direct_value);
+        assert_eq_float!(inverted_value.to_float(), direct_value.to_float(), 1e-6);
+    }
+
+    #[test]
+    fn stale_price_is_rejected_with_the_configured_max_age() {
+        let owner = Pubkey(BTC_FEED_ID);
+        let key = Pubkey::new_unique();
+        let now = SCENARIO_PUBLISH_TIME + 1_000;
+        let config = OracleConfig::new_pyth(BTC_FEED_ID, owner).with_max_age(10);
+        assert_eq!(config.validation_config().max_age(), 10);
+        let fresh = OracleScenario::new(BTC_FEED_ID).published_at(now - 10);
+        config
+            .load_and_validate_oracle_rate(Some((&key, fresh.to_bytes(), &owner).into()), now, None)
+            .unwrap();
+        let stale = OracleScenario::new(BTC_FEED_ID).stale(now, 10);
+        assert_eq!(
+            config
+                .load_and_validate_oracle_rate(
+                    Some((&key, stale.to_bytes(), &owner).into()),
+                    now,
+                    None
+                )
+                .unwrap_err(),
+            LendingError::OracleRateTooOld
+        );
+
+        // an unset max age falls back to the default one
+        let config = OracleConfig::new_pyth(BTC_FEED_ID, owner).with_max_age(0);
+        assert_eq!(
+            config.validation_config().max_age(),
+            DEFAULT_ORACLE_MAX_AGE_SECONDS
+        );
+        let stale = OracleScenario::new(BTC_FEED_ID).stale(now, DEFAULT_ORACLE_MAX_AGE_SECONDS);
+        assert_eq!(
+            config
+                .load_and_validate_oracle_rate(
+                    Some((&key, stale.to_bytes(), &owner).into()),
+                    now,
+                    None
+                )
+                .unwrap_err(),
+            LendingError::OracleRateTooOld
+        );
+    }
+
+    #[test]
+    fn price_with_too_wide_confidence_is_rejected() {
+        let owner = Pubkey(BTC_FEED_ID);
+        let key = Pubkey::new_unique();
+        let now = SCENARIO_PUBLISH_TIME;
+        let load = |config: OracleConfig, scenario: OracleScenario| {
+            config
+                .load_and_validate_oracle_rate(
+                    Some((&key, scenario.to_bytes(), &owner).into()),
+                    now,
+                    None,
+                )
+                .map_err(|err| *err)
+        };
+        // 100 +/- 0.05, a 5 bps confidence
+        let tight = OracleScenario::new(BTC_FEED_ID);
+        let blown_out = tight.wide_confidence();
+        let config = OracleConfig::new_pyth(BTC_FEED_ID, owner);
+        assert!(load(config.with_max_confidence(Bps::new(5)), tight).is_ok());
+        assert_eq!(
+            load(config.with_max_confidence(Bps::new(4)), tight),
+            Err(LendingError::OracleRateRelativeConfidenceTooLow)
+        );
+        assert_eq!(
+            load(config, blown_out),
+            Err(LendingError::OracleRateRelativeConfidenceTooLow)
+        );
+        assert_eq!(
+            load(config.with_max_confidence(Bps::from_percent(49)), blown_out),
+            Err(LendingError::OracleRateRelativeConfidenceTooLow)
+        );
+        assert!(load(config.with_max_confidence(Bps::ZERO), blown_out).is_ok());
+    }
+
+    #[test]
+    fn ema_config_loads_the_ema_price() {
+        let owner = Pubkey(BTC_FEED_ID);
+        let key = Pubkey::new_unique();
+        let now = SCENARIO_PUBLISH_TIME + 1_000;
+        let scenario = OracleScenario::new(BTC_FEED_ID)
+            .with_price(100_000, 10, 0)
+            .with_ema_price(90_000, 10);
+        let config = OracleConfig::new_pyth(BTC_FEED_ID, owner).with_max_age(10);
+        let ema_config = config.with_ema_price(true);
+        assert!(!config.uses_ema_price());
+        assert!(ema_config.uses_ema_price());
+        ema_config.validate().unwrap();
+        let load = |config: OracleConfig, scenario: OracleScenario, now: i64| {
+            config
+                .load_and_validate_oracle_rate(
+                    Some((&key, scenario.to_bytes(), &owner).into()),
+                    now,
+                    None,
+                )
+                .map_err(|err| *err)
+        };
+        let spot = load(config, scenario.published_at(now), now).unwrap();
+        let ema = load(ema_config, scenario.published_at(now), now).unwrap();
+        assert_eq_float!(spot.rate().to_float(), 100_000.);
+        assert_eq_float!(ema.rate().to_float(), 90_000.);
+        // the EMA goes through the same staleness and confidence checks
+        assert_eq!(
+            load(ema_config, scenario.stale(now, 10), now),
+            Err(LendingError::OracleRateTooOld)
+        );
+        assert_eq!(
+            load(
+                ema_config,
+                scenario.published_at(now).with_ema_price(90_000, 45_000),
+                now
+            ),
+            Err(LendingError::OracleRateRelativeConfidenceTooLow)
+        );
+        // only pyth feeds publish an EMA
+        assert_eq!(
+            OracleConfig::new_constant(IFixedPoint::one())
+                .with_ema_price(true)
+                .validate()
+                .unwrap_err(),
+            LendingError::InvalidOracleConfig
+        );
+    }
+
+    #[test]
+    fn only_constant_oracles_load_without_feed_account() {
+        let constant = OracleConfig::new_constant(IFixedPoint::from_num(2))
+            .load_oracle_rate_unchecked::<&[u8]>(None)
+            .unwrap();
+        assert_eq!(constant.rate(), IFixedPoint::from_num(2));
+        assert_eq!(
+            usd_oracle_config()
+                .load_oracle_rate_unchecked::<&[u8]>(None)
+                .unwrap_err(),
+            LendingError::MissingOracleAccount
+        );
+    }
+
     pub fn usd_oracle_config() -> OracleConfig {
         OracleConfig::new(
             OracleProvider::Pyth(PythProvider {
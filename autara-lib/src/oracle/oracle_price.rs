@@ -105,6 +105,17 @@ impl OracleRate {
     pub fn relative_confidence(&self) -> LendingResult<IFixedPoint> {
         self.confidence.safe_div(self.rate)
     }
+
+    /// Rate of the inverse pair, ex: USD per BTC from BTC per USD.
+    /// The confidence is the widest side of the inverted interval, `1 / (rate - confidence) - 1 / rate`,
+    /// so that the inverted bounds still contain the original ones
+    pub fn inverse(&self) -> LendingResult<Self> {
+        let rate = IFixedPoint::one().safe_div(self.rate)?;
+        let confidence = self
+            .confidence
+            .safe_div(self.rate.safe_mul(self.lower_bound_rate()?)?)?;
+        Self::try_new(rate, confidence)
+    }
 }
 
 impl std::fmt::Display for OracleRate {
@@ -115,6 +126,8 @@ impl std::fmt::Display for OracleRate {
 
 #[cfg(test)]
 pub mod tests {
+    use crate::assert_eq_float;
+
     use super::*;
 
     #[test]
@@ -145,6 +158,27 @@ pub mod tests {
         assert_eq!(upper, IFixedPoint::lit("105"));
     }
 
+    #[test]
+    fn inverse_bounds_contain_inverted_bounds() {
+        let oracle = OracleRate::new(IFixedPoint::lit("4"), IFixedPoint::lit("1"));
+        let inverse = oracle.inverse().unwrap();
+        assert_eq!(inverse.rate(), IFixedPoint::lit("0.25"));
+        // 1 / 3 - 1 / 4
+        assert_eq_float!(inverse.confidence().to_float(), 1. / 12.);
+        assert_eq_float!(inverse.upper_bound_rate().unwrap().to_float(), 1. / 3.);
+        // the lower side is widened from 1 / 5 to stay symmetric
+        assert!(inverse.lower_bound_rate().unwrap().to_float() < 1. / 5.);
+    }
+
+    #[test]
+    fn inverse_rejects_confidence_above_half_rate() {
+        let oracle = OracleRate::new(IFixedPoint::lit("100"), IFixedPoint::lit("60"));
+        assert_eq!(
+            oracle.inverse().unwrap_err(),
+            LendingError::OracleConfidenceExceedsRate
+        );
+    }
+
     #[test]
     fn collateral_value_uses_lower_bound() {
         let oracle = OracleRate::new(IFixedPoint::lit("100"), IFixedPoint::lit("10"));
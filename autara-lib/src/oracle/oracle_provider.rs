@@ -7,6 +7,7 @@ use crate::{
     error::{LendingError, LendingResult, LendingResultExt},
     oracle::{
         chaos::{ChaosProvider, PRICE_CONFIG_SEED},
+        constant::ConstantProvider,
         oracle_config::OracleValidationConfig,
         oracle_price::OracleRate,
         pyth::PythProvider,
@@ -23,6 +24,7 @@ use crate::{
 pub enum OracleProvider {
     Pyth(PythProvider),
     Chaos(ChaosProvider),
+    Constant(ConstantProvider),
 }
 
 #[repr(C)]
@@ -46,6 +48,22 @@ impl UncheckedOracleRate {
         self.rate
     }
 
+    /// Same publication with the rate of the inverse pair, see [OracleRate::inverse]
+    pub fn inverse(&self) -> LendingResult<Self> {
+        Ok(Self {
+            rate: self.rate.inverse()?,
+            publish_time: self.publish_time,
+        })
+    }
+
+    /// Seconds elapsed since the rate was published, zero if published in the future
+    pub fn age(&self, unix_timestamp: i64) -> LendingResult<u64> {
+        Ok(unix_timestamp
+            .checked_sub(self.publish_time)
+            .ok_or(LendingError::SubtractionOverflow)?
+            .max(0) as u64)
+    }
+
     pub fn validate(
         &self,
         config: &OracleValidationConfig,
@@ -60,11 +78,8 @@ impl UncheckedOracleRate {
         if self.rate.rate().is_zero() {
             return Err(LendingError::OracleRateIsNull.into());
         }
-        let age = unix_timestamp
-            .checked_sub(self.publish_time)
-            .ok_or(LendingError::SubtractionOverflow)?
-            .max(0) as u64;
-        if config.max_age().is_some_and(|max_age| age > max_age) {
+        let age = self.age(unix_timestamp)?;
+        if age > config.max_age() {
             return Err(LendingError::OracleRateTooOld.into());
         }
         let relative_confidence = self.rate.relative_confidence()?;
@@ -90,6 +105,7 @@ impl OracleProvider {
         match self {
             OracleProvider::Pyth(provider) => OracleProviderRef::Pyth(provider),
             OracleProvider::Chaos(provider) => OracleProviderRef::Chaos(provider),
+            OracleProvider::Constant(provider) => OracleProviderRef::Constant(provider),
         }
     }
 }
@@ -97,6 +113,7 @@ impl OracleProvider {
 pub enum OracleProviderRef<'a> {
     Pyth(&'a PythProvider),
     Chaos(&'a ChaosProvider),
+    Constant(&'a ConstantProvider),
 }
 
 impl<'a> OracleProviderRef<'a> {
@@ -112,6 +129,7 @@ impl<'a> OracleProviderRef<'a> {
                 )
                 .0,
             ),
+            OracleProviderRef::Constant(_) => None,
         }
     }
 }
@@ -124,6 +142,9 @@ impl<'a> OracleLoader for OracleProviderRef<'a> {
         match self {
             OracleProviderRef::Pyth(pyth_provider) => pyth_provider.load_oracle_price(view),
             OracleProviderRef::Chaos(chaos_provider) => chaos_provider.load_oracle_price(view),
+            OracleProviderRef::Constant(constant_provider) => {
+                constant_provider.load_oracle_price(view)
+            }
         }
     }
 }
@@ -157,6 +178,19 @@ where
     }
 }
 
+impl<'a, 'b> AccountView<'a, RefWrapper<'a, 'b>>
+where
+    'a: 'b,
+{
+    /// Same as [AccountView::try_from] for an oracle account left out when the oracle is
+    /// constant, see [super::oracle_config::OracleConfig::load_oracle_price]
+    pub fn try_from_optional(
+        account_info: Option<&'b AccountInfo<'a>>,
+    ) -> Result<Option<Self>, LendingError> {
+        account_info.map(Self::try_from).transpose()
+    }
+}
+
 pub struct RefWrapper<'a, 'b>(std::cell::Ref<'b, &'a mut [u8]>);
 
 impl<'a, 'b> Deref for RefWrapper<'a, 'b> {
@@ -6,6 +6,7 @@ use crate::{
     math::const_max::const_max_usizes,
     oracle::{
         chaos::ChaosProvider,
+        constant::ConstantProvider,
         oracle_provider::{
             AccountView, OracleLoader, OracleProvider, OracleProviderRef, UncheckedOracleRate,
         },
@@ -19,6 +20,7 @@ use crate::{
 pub enum PodOracleProviderKind {
     Pyth = 0,
     Chaos = 1,
+    Constant = 2,
 }
 
 impl PodOracleProviderKind {
@@ -26,6 +28,7 @@ impl PodOracleProviderKind {
         match self {
             PodOracleProviderKind::Pyth => std::mem::size_of::<PythProvider>(),
             PodOracleProviderKind::Chaos => std::mem::size_of::<ChaosProvider>(),
+            PodOracleProviderKind::Constant => std::mem::size_of::<ConstantProvider>(),
         }
     }
 }
@@ -35,6 +38,7 @@ unsafe impl Pod for PodOracleProviderKind {}
 const POD_UNION_SIZE: usize = const_max_usizes(&[
     PodOracleProviderKind::Pyth.size(),
     PodOracleProviderKind::Chaos.size(),
+    PodOracleProviderKind::Constant.size(),
 ]);
 
 crate::validate_struct!(PodOracleProvider, 80);
@@ -66,6 +70,14 @@ impl PodOracleProvider {
                     union,
                 }
             }
+            OracleProvider::Constant(constant_provider) => {
+                union[..PodOracleProviderKind::Constant.size()]
+                    .copy_from_slice(bytemuck::bytes_of(&constant_provider));
+                PodOracleProvider {
+                    kind: PodOracleProviderKind::Constant,
+                    union,
+                }
+            }
         }
     }
 
@@ -77,6 +89,9 @@ impl PodOracleProvider {
             PodOracleProviderKind::Chaos => OracleProvider::Chaos(*bytemuck::from_bytes(
                 &self.union[..PodOracleProviderKind::Chaos.size()],
             )),
+            PodOracleProviderKind::Constant => OracleProvider::Constant(*bytemuck::from_bytes(
+                &self.union[..PodOracleProviderKind::Constant.size()],
+            )),
         }
     }
 
@@ -88,6 +103,9 @@ impl PodOracleProvider {
             PodOracleProviderKind::Chaos => OracleProviderRef::Chaos(bytemuck::from_bytes(
                 &self.union[..PodOracleProviderKind::Chaos.size()],
             )),
+            PodOracleProviderKind::Constant => OracleProviderRef::Constant(bytemuck::from_bytes(
+                &self.union[..PodOracleProviderKind::Constant.size()],
+            )),
         }
     }
 }
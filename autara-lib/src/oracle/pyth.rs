@@ -23,10 +23,20 @@ pub struct PythProvider {
     pub program_id: Pubkey,
 }
 
-impl OracleLoader for PythProvider {
-    fn load_oracle_price<D: std::ops::Deref<Target = [u8]>>(
+impl PythProvider {
+    /// Same as [OracleLoader::load_oracle_price] with the exponentially weighted
+    /// moving average price of the feed instead of the spot one
+    pub fn load_ema_oracle_price<D: std::ops::Deref<Target = [u8]>>(
+        &self,
+        view: AccountView<D>,
+    ) -> LendingResult<UncheckedOracleRate> {
+        self.load_price_data(view, true)
+    }
+
+    fn load_price_data<D: std::ops::Deref<Target = [u8]>>(
         &self,
         view: AccountView<D>,
+        use_ema: bool,
     ) -> LendingResult<UncheckedOracleRate> {
         if *view.owner != self.program_id {
             return Err(LendingError::InvalidPythOracleAccount.into())
@@ -35,6 +45,15 @@ impl OracleLoader for PythProvider {
         let pyth_price: &PythPrice = match bytemuck::try_from_bytes::<PythPriceAccount>(&view.data)
         {
             Ok(account) => &account.pyth_price,
+            // Feeds created before the price move bound was stored end at the
+            // authority. They are bound to it like current ones, and the oracle
+            // program grows them on their next push.
+            Err(_) if view.data.len() == PYTH_PRICE_ACCOUNT_WITHOUT_BOUND_LEN => {
+                bytemuck::try_from_bytes::<PythPrice>(
+                    &view.data[..core::mem::size_of::<PythPrice>()],
+                )
+                .map_err(|_| LendingError::InvalidPythOracleAccount)?
+            }
             // Legacy feed accounts created before the trailing `authority`
             // field was added are exactly one `PythPrice`. Only host-side
             // clients tolerate them (existing testnet feeds predate the
@@ -50,21 +69,31 @@ impl OracleLoader for PythProvider {
         if pyth_price.id != self.feed_id {
             return Err(LendingError::InvalidOracleFeedId.into());
         }
-        if pyth_price.price.expo > MAX_EXPONENT_ABS || pyth_price.price.expo < -MAX_EXPONENT_ABS {
+        let price_data = if use_ema {
+            &pyth_price.ema_price
+        } else {
+            &pyth_price.price
+        };
+        if price_data.expo > MAX_EXPONENT_ABS || price_data.expo < -MAX_EXPONENT_ABS {
             return Err(LendingError::InvalidPythOracleAccount.into()).with_msg("Invalid exponent");
         }
-        let expo = pyth_price.price.expo as i8;
+        let expo = price_data.expo as i8;
         Ok(UncheckedOracleRate::new(
-            OracleRate::try_from_price_expo_conf(
-                pyth_price.price.price,
-                pyth_price.price.conf,
-                expo,
-            )?,
-            pyth_price.price.publish_time,
+            OracleRate::try_from_price_expo_conf(price_data.price, price_data.conf, expo)?,
+            price_data.publish_time,
         ))
     }
 }
 
+impl OracleLoader for PythProvider {
+    fn load_oracle_price<D: std::ops::Deref<Target = [u8]>>(
+        &self,
+        view: AccountView<D>,
+    ) -> LendingResult<UncheckedOracleRate> {
+        self.load_price_data(view, false)
+    }
+}
+
 #[derive(Debug, Pod, Zeroable, Clone, Copy)]
 #[repr(C)]
 pub struct PythPriceAccount {
@@ -72,8 +101,17 @@ pub struct PythPriceAccount {
     /// The only key allowed to update this feed. Bound to the creating signer
     /// by the oracle program when the feed account is first initialized.
     pub authority: Pubkey,
+    /// Largest price move in bps the oracle program accepts per update window,
+    /// set by the authority when the feed is created. Zero only for feeds grown
+    /// from the previous layout, until their next push sets it
+    pub max_price_move_bps: u16,
+    pub _padding: [u8; 6],
 }
 
+/// Size of the feed accounts created before `max_price_move_bps` was stored
+pub const PYTH_PRICE_ACCOUNT_WITHOUT_BOUND_LEN: usize =
+    core::mem::size_of::<PythPrice>() + core::mem::size_of::<Pubkey>();
+
 #[derive(Debug, Pod, Zeroable, Clone, Copy)]
 #[repr(C)]
 pub struct PythPrice {
@@ -137,7 +175,13 @@ pub struct Metadata {
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use crate::math::ifixed_point::IFixedPoint;
+    use crate::{
+        math::ifixed_point::IFixedPoint,
+        oracle::{
+            oracle_config::OracleValidationConfig,
+            test_utils::{OracleScenario, SCENARIO_PUBLISH_TIME},
+        },
+    };
 
     fn create_test_pubkey() -> Pubkey {
         Pubkey::from([1u8; 32])
@@ -155,33 +199,9 @@ pub mod tests {
     }
 
     fn create_pyth_price_account(feed_id: [u8; 32], price: u64, conf: u64, expo: i64) -> Vec<u8> {
-        let pyth_price = PythPrice {
-            id: feed_id,
-            price: PriceData {
-                price,
-                conf,
-                expo,
-                publish_time: 1234567890,
-            },
-            ema_price: PriceData {
-                price: 0,
-                conf: 0,
-                expo: 0,
-                publish_time: 0,
-            },
-            metadata: Metadata {
-                slot: 100,
-                proof_available_time: 1234567890,
-                prev_publish_time: 1234567889,
-            },
-        };
-
-        let account = PythPriceAccount {
-            pyth_price,
-            authority: create_test_pubkey(),
-        };
-        let bytes = bytemuck::bytes_of(&account);
-        bytes.to_vec()
+        OracleScenario::new(feed_id)
+            .with_price(price, conf, expo)
+            .to_bytes()
     }
 
     /// Legacy testnet feed accounts predate the trailing `authority` field and
@@ -204,6 +224,24 @@ pub mod tests {
         assert_eq!(oracle_rate.rate(), IFixedPoint::from(100));
     }
 
+    /// Feeds pushed before the price move bound was stored are bound to their
+    /// authority and load everywhere until their next push grows them
+    #[test]
+    fn test_load_oracle_price_layout_without_bound() {
+        let provider = create_pyth_provider();
+        let key = create_test_pubkey();
+        let owner = create_test_pubkey();
+
+        let full = create_pyth_price_account(create_test_feed_id(), 10000000000u64, 5000000u64, -8);
+        let without_bound = full[..PYTH_PRICE_ACCOUNT_WITHOUT_BOUND_LEN].to_vec();
+
+        let oracle_rate = provider
+            .load_oracle_price((&key, without_bound, &owner).into())
+            .unwrap()
+            .unsafe_rate();
+        assert_eq!(oracle_rate.rate(), IFixedPoint::from(100));
+    }
+
     #[test]
     fn test_load_oracle_price_success() {
         let provider = create_pyth_provider();
@@ -266,19 +304,45 @@ pub mod tests {
         let key = create_test_pubkey();
         let owner = create_test_pubkey();
 
-        // Create price data with exponent more negative than MAX_EXPONENT
-        let price_data = create_pyth_price_account(
-            create_test_feed_id(),
-            10000000000u64,
-            5000000u64,
-            -(MAX_EXPONENT_ABS + 1), // Too negative
-        );
+        let price_data = OracleScenario::new(create_test_feed_id())
+            .extreme_exponent()
+            .to_bytes();
 
         let result = provider.load_oracle_price((&key, price_data, &owner).into());
         assert!(result.is_err());
         assert_eq!(*result.unwrap_err(), LendingError::InvalidPythOracleAccount);
     }
 
+    #[test]
+    fn test_load_and_validate_oracle_price_scenarios() {
+        let provider = create_pyth_provider();
+        let key = create_test_pubkey();
+        let owner = create_test_pubkey();
+        let config = OracleValidationConfig::new(60, 0.05.into());
+        let now = SCENARIO_PUBLISH_TIME + 30;
+        let load_and_validate = |scenario: OracleScenario| {
+            provider
+                .load_oracle_price((&key, scenario.to_bytes(), &owner).into())
+                .and_then(|rate| rate.validate(&config, now))
+                .map_err(|err| *err)
+        };
+        let feed = OracleScenario::new(create_test_feed_id());
+
+        assert!(load_and_validate(feed).is_ok());
+        assert_eq!(
+            load_and_validate(feed.stale(now, 60)),
+            Err(LendingError::OracleRateTooOld)
+        );
+        assert_eq!(
+            load_and_validate(feed.wide_confidence()),
+            Err(LendingError::OracleRateRelativeConfidenceTooLow)
+        );
+        assert_eq!(
+            load_and_validate(feed.zero_price()),
+            Err(LendingError::OracleRateIsNull)
+        );
+    }
+
     #[test]
     fn test_load_oracle_price_max_valid_expo() {
         let provider = create_pyth_provider();
@@ -0,0 +1,116 @@
+use arch_program::pubkey::Pubkey;
+
+use crate::{
+    constant::MAX_EXPONENT_ABS,
+    oracle::pyth::{Metadata, PriceData, PythPrice, PythPriceAccount},
+};
+
+/// Publish time of the feeds built by [OracleScenario] unless set otherwise
+pub const SCENARIO_PUBLISH_TIME: i64 = 1_234_567_890;
+
+/// Builder of Pyth feed accounts covering the oracle edge cases.
+/// Starts from a price of 100 with a 0.05 confidence published at [SCENARIO_PUBLISH_TIME].
+///
+/// Negative prices have no scenario as the price of the feed layout is unsigned
+#[derive(Debug, Clone, Copy)]
+pub struct OracleScenario {
+    pub feed_id: [u8; 32],
+    pub price: u64,
+    pub conf: u64,
+    pub expo: i64,
+    pub publish_time: i64,
+    /// Price and confidence of the EMA, same as the spot ones when not set
+    pub ema: Option<(u64, u64)>,
+    pub authority: Pubkey,
+}
+
+impl OracleScenario {
+    pub fn new(feed_id: [u8; 32]) -> Self {
+        Self {
+            feed_id,
+            price: 10_000_000_000,
+            conf: 5_000_000,
+            expo: -8,
+            publish_time: SCENARIO_PUBLISH_TIME,
+            ema: None,
+            authority: Pubkey::from([1; 32]),
+        }
+    }
+
+    pub fn with_price(mut self, price: u64, conf: u64, expo: i64) -> Self {
+        self.price = price;
+        self.conf = conf;
+        self.expo = expo;
+        self
+    }
+
+    /// EMA price and confidence diverging from the spot ones, same exponent and publish time
+    pub fn with_ema_price(mut self, price: u64, conf: u64) -> Self {
+        self.ema = Some((price, conf));
+        self
+    }
+
+    pub fn published_at(mut self, publish_time: i64) -> Self {
+        self.publish_time = publish_time;
+        self
+    }
+
+    /// Published one second too early to be valid at `unix_timestamp` with `max_age`
+    pub fn stale(self, unix_timestamp: i64, max_age: u64) -> Self {
+        self.published_at(unix_timestamp - max_age as i64 - 1)
+    }
+
+    /// Confidence of half the price, above any sensible relative confidence limit
+    pub fn wide_confidence(mut self) -> Self {
+        self.conf = self.price / 2;
+        self
+    }
+
+    pub fn zero_price(mut self) -> Self {
+        self.price = 0;
+        self.conf = 0;
+        self
+    }
+
+    /// Exponent just outside of the range supported by the protocol
+    pub fn extreme_exponent(mut self) -> Self {
+        self.expo = -(MAX_EXPONENT_ABS + 1);
+        self
+    }
+
+    pub fn account(&self) -> PythPriceAccount {
+        let price = PriceData {
+            price: self.price,
+            conf: self.conf,
+            expo: self.expo,
+            publish_time: self.publish_time,
+        };
+        let ema_price = match self.ema {
+            Some((ema, ema_conf)) => PriceData {
+                price: ema,
+                conf: ema_conf,
+                ..price
+            },
+            None => price,
+        };
+        PythPriceAccount {
+            pyth_price: PythPrice {
+                id: self.feed_id,
+                price,
+                ema_price,
+                metadata: Metadata {
+                    slot: 0,
+                    proof_available_time: self.publish_time,
+                    prev_publish_time: self.publish_time,
+                },
+            },
+            authority: self.authority,
+            max_price_move_bps: 0,
+            _padding: [0; 6],
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bytemuck::bytes_of(&self.account()).to_vec()
+    }
+}
@@ -118,3 +118,21 @@ pub fn global_config_seed_with_bump(bump: &[u8; 1]) -> [&[u8]; 2] {
 pub fn find_global_config_pda(program_id: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(&global_config_seed(), program_id)
 }
+
+#[inline(always)]
+pub fn curator_market_count_seed(curator: &Pubkey) -> [&[u8]; 2] {
+    [b"curator_market_count", curator.as_ref()]
+}
+
+#[inline(always)]
+pub fn curator_market_count_seed_with_bump<'a>(
+    curator: &'a Pubkey,
+    bump: &'a [u8; 1],
+) -> [&'a [u8]; 3] {
+    [b"curator_market_count", curator.as_ref(), bump]
+}
+
+#[inline(always)]
+pub fn find_curator_market_count_pda(program_id: &Pubkey, curator: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&curator_market_count_seed(curator), program_id)
+}
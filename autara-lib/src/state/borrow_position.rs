@@ -3,13 +3,17 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use bytemuck::{Pod, Zeroable};
 
 use crate::{
-    error::{LendingError, LendingResultExt},
+    error::{ErrorWithContext, LendingError, LendingResultExt},
     math::{ifixed_point::IFixedPoint, safe_math::SafeMath, ufixed_point::UFixedPoint},
     operation::liquidation::LiquidationResultWithBonus,
     padding::Padding,
 };
 
-use super::super::error::LendingResult;
+use super::{
+    super::error::LendingResult,
+    market::Market,
+    version::{VersionedAccount, CURRENT_ACCOUNT_VERSION},
+};
 
 crate::validate_struct!(BorrowPosition, 224);
 
@@ -31,7 +35,15 @@ pub struct BorrowPosition {
     initial_borrowed_atoms: u64,
     /// Track the total borrow shares of supply vault owned by this position
     borrowed_shares: UFixedPoint,
-    pad: Padding<128>,
+    /// Highest amount of borrow shares ever owned by this position
+    peak_borrowed_shares: UFixedPoint,
+    /// Interest repaid by this position, i.e. repaid atoms in excess of the principal
+    interest_paid_atoms: u64,
+    /// Layout version of the account, see [super::version]
+    version: u8,
+    /// Non zero while an instruction on this position runs a user callback
+    reentrancy_guard: u8,
+    pad: Padding<102>,
 }
 
 impl BorrowPosition {
@@ -41,6 +53,9 @@ impl BorrowPosition {
         self.collateral_deposited_atoms = 0;
         self.initial_borrowed_atoms = 0;
         self.borrowed_shares = UFixedPoint::zero();
+        self.peak_borrowed_shares = UFixedPoint::zero();
+        self.interest_paid_atoms = 0;
+        self.version = CURRENT_ACCOUNT_VERSION;
     }
 
     #[inline(always)]
@@ -58,6 +73,16 @@ impl BorrowPosition {
         self.borrowed_shares
     }
 
+    #[inline(always)]
+    pub fn peak_borrowed_shares(&self) -> UFixedPoint {
+        self.peak_borrowed_shares
+    }
+
+    #[inline(always)]
+    pub fn interest_paid_atoms(&self) -> u64 {
+        self.interest_paid_atoms
+    }
+
     #[inline(always)]
     pub fn collateral_deposited_atoms(&self) -> u64 {
         self.collateral_deposited_atoms
@@ -68,8 +93,35 @@ impl BorrowPosition {
         self.initial_borrowed_atoms
     }
 
+    #[inline(always)]
+    pub fn is_reentrancy_guarded(&self) -> bool {
+        self.reentrancy_guard != 0
+    }
+
+    /// Raised around the user callback of `BorrowDepositApl` and `WithdrawRepayApl` so that
+    /// the callback cannot re-enter the program on this position before the outer
+    /// instruction completes
+    pub fn set_reentrancy_guard(&mut self, guarded: bool) {
+        self.reentrancy_guard = guarded as u8;
+    }
+
+    pub fn check_not_reentrant(&self) -> LendingResult {
+        if self.is_reentrancy_guarded() {
+            return Err(LendingError::ReentrancyDetected.into())
+                .with_msg("borrow position is used by an instruction in progress");
+        }
+        Ok(())
+    }
+
     pub fn deposit_collateral(&mut self, atoms: u64) -> LendingResult {
-        self.collateral_deposited_atoms = self.collateral_deposited_atoms.safe_add(atoms)?;
+        self.collateral_deposited_atoms = self
+            .collateral_deposited_atoms
+            .safe_add(atoms)
+            .map_err(|err| ErrorWithContext {
+                error: LendingError::CollateralVaultOverflow,
+                ..err
+            })
+            .with_msg("collateral deposited by the position overflows")?;
         Ok(())
     }
 
@@ -84,6 +136,7 @@ impl BorrowPosition {
     pub fn borrow(&mut self, atoms: u64, shares: UFixedPoint) -> LendingResult {
         self.initial_borrowed_atoms = self.initial_borrowed_atoms.safe_add(atoms)?;
         self.borrowed_shares = self.borrowed_shares.safe_add(shares)?;
+        self.peak_borrowed_shares = self.peak_borrowed_shares.max(self.borrowed_shares);
         Ok(())
     }
 
@@ -111,6 +164,16 @@ impl BorrowPosition {
         self.borrowed_shares = UFixedPoint::zero();
     }
 
+    /// Accounts the part of `repaid_atoms` which exceeds the principal repaid since
+    /// `initial_borrowed_atoms_before` as interest paid
+    pub fn record_interest_paid(&mut self, repaid_atoms: u64, initial_borrowed_atoms_before: u64) {
+        let principal_repaid =
+            initial_borrowed_atoms_before.saturating_sub(self.initial_borrowed_atoms);
+        self.interest_paid_atoms = self
+            .interest_paid_atoms
+            .saturating_add(repaid_atoms.saturating_sub(principal_repaid));
+    }
+
     pub fn liquidate(
         &mut self,
         shares_liquidated: UFixedPoint,
@@ -137,6 +200,48 @@ pub struct BorrowPositionHealth {
     pub collateral_atoms: u64,
     pub borrow_value: IFixedPoint,
     pub collateral_value: IFixedPoint,
+    /// Whether the LTV reached the market warning LTV without being unhealthy yet
+    pub is_in_warning_zone: bool,
+}
+
+impl BorrowPositionHealth {
+    /// Value which can still be borrowed before reaching `max_ltv`.
+    /// Negative when the position is above `max_ltv`
+    pub fn borrowing_power(&self, max_ltv: IFixedPoint) -> LendingResult<IFixedPoint> {
+        self.collateral_value
+            .safe_mul(max_ltv)?
+            .safe_sub(self.borrow_value)
+    }
+}
+
+/// [BorrowPositionHealth] with amounts scaled by the decimals of the market tokens,
+/// ready to be displayed
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "client",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct BorrowPositionHealthView {
+    pub ltv: f64,
+    pub borrowed_ui: f64,
+    pub collateral_ui: f64,
+    pub borrow_value: f64,
+    pub collateral_value: f64,
+}
+
+impl From<(&BorrowPositionHealth, &Market)> for BorrowPositionHealthView {
+    fn from((health, market): (&BorrowPositionHealth, &Market)) -> Self {
+        Self {
+            ltv: health.ltv.to_float(),
+            borrowed_ui: market.supply_token_info().ui_amount(health.borrowed_atoms),
+            collateral_ui: market
+                .collateral_token_info()
+                .ui_amount(health.collateral_atoms),
+            borrow_value: health.borrow_value.to_float(),
+            collateral_value: health.collateral_value.to_float(),
+        }
+    }
 }
 
 #[repr(C)]
@@ -147,9 +252,36 @@ pub struct LiquidationResultWithCtx {
     pub health_after_liquidation: BorrowPositionHealth,
 }
 
+impl VersionedAccount for BorrowPosition {
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+
+    fn migrate_from(&mut self, version: u8) -> LendingResult {
+        if version == 0 {
+            // The peak was not tracked before version 1, the current debt is the best lower bound
+            self.peak_borrowed_shares = self.peak_borrowed_shares.max(self.borrowed_shares);
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        assert_eq_float,
+        oracle::oracle_config::tests::{default_btc_oracle_rate, default_usd_oracle_rate},
+        state::{
+            collateral_vault::tests::BTC, market::tests::create_empty_btc_usdc_market,
+            market_config::tests::test_config, supply_position::SupplyPosition,
+            supply_vault::tests::USDC,
+        },
+    };
     use arch_program::pubkey::Pubkey;
 
     fn create_position() -> BorrowPosition {
@@ -225,6 +357,79 @@ mod tests {
         assert!(pos.borrowed_shares() < shares_before);
     }
 
+    #[test]
+    fn peak_borrowed_shares_tracks_maximum_debt() {
+        let mut pos = create_position();
+        pos.borrow(1000, UFixedPoint::from_u64(1000)).unwrap();
+        pos.borrow(2000, UFixedPoint::from_u64(2000)).unwrap();
+        assert_eq!(pos.peak_borrowed_shares(), UFixedPoint::from_u64(3000));
+        pos.repay(UFixedPoint::from_u64(2500)).unwrap();
+        assert_eq!(pos.borrowed_shares(), UFixedPoint::from_u64(500));
+        assert_eq!(pos.peak_borrowed_shares(), UFixedPoint::from_u64(3000));
+        pos.borrow(1000, UFixedPoint::from_u64(1000)).unwrap();
+        assert_eq!(pos.peak_borrowed_shares(), UFixedPoint::from_u64(3000));
+        pos.repay_all();
+        assert_eq!(pos.peak_borrowed_shares(), UFixedPoint::from_u64(3000));
+        pos.borrow(4000, UFixedPoint::from_u64(4000)).unwrap();
+        assert_eq!(pos.peak_borrowed_shares(), UFixedPoint::from_u64(4000));
+    }
+
+    #[test]
+    fn legacy_position_migration_preserves_fields_and_fills_defaults() {
+        let mut pos = create_position();
+        pos.deposit_collateral(10000).unwrap();
+        pos.borrow(5000, UFixedPoint::from_u64(5000)).unwrap();
+        // A position written before versioning has a zeroed version and no peak tracked
+        let mut legacy = pos;
+        legacy.version = 0;
+        legacy.peak_borrowed_shares = UFixedPoint::zero();
+        let mut legacy_bytes = bytemuck::bytes_of(&legacy).to_vec();
+        let migrated = bytemuck::from_bytes_mut::<BorrowPosition>(&mut legacy_bytes);
+        assert_eq!(migrated.version(), 0);
+        assert!(migrated.migrate().unwrap());
+        assert_eq!(migrated.version(), CURRENT_ACCOUNT_VERSION);
+        assert_eq!(migrated.authority(), pos.authority());
+        assert_eq!(migrated.market(), pos.market());
+        assert_eq!(migrated.collateral_deposited_atoms(), 10000);
+        assert_eq!(migrated.initial_borrowed_atoms(), 5000);
+        assert_eq!(migrated.borrowed_shares(), UFixedPoint::from_u64(5000));
+        assert_eq!(migrated.peak_borrowed_shares(), UFixedPoint::from_u64(5000));
+        assert_eq!(migrated.interest_paid_atoms(), 0);
+        assert!(!migrated.migrate().unwrap());
+    }
+
+    #[test]
+    fn health_view_scales_amounts_by_token_decimals() {
+        let mut market = create_empty_btc_usdc_market();
+        let mut supply_position = SupplyPosition::default();
+        market.lend(&mut supply_position, USDC(100_000.)).unwrap();
+        let mut pos = create_position();
+        market.deposit_collateral(&mut pos, BTC(0.5)).unwrap();
+        let supply_oracle = default_usd_oracle_rate();
+        let collateral_oracle = default_btc_oracle_rate();
+        market
+            .borrow(
+                &mut pos,
+                USDC(12_345.67),
+                &supply_oracle,
+                &collateral_oracle,
+            )
+            .unwrap();
+        let health = market
+            .borrow_position_health(&pos, &collateral_oracle, &supply_oracle)
+            .unwrap();
+        let view = BorrowPositionHealthView::from((&health, &market));
+        assert_eq!(view.collateral_ui, 0.5);
+        assert_eq_float!(view.borrowed_ui, 12_345.67, 1e-9);
+        assert_eq_float!(view.ltv, health.ltv.to_float(), 1e-12);
+        assert_eq_float!(
+            view.collateral_value,
+            health.collateral_value.to_float(),
+            1e-12
+        );
+        assert_eq_float!(view.borrow_value, health.borrow_value.to_float(), 1e-12);
+    }
+
     #[test]
     fn multiple_deposits_accumulate() {
         let mut pos = create_position();
@@ -257,4 +462,34 @@ mod tests {
         assert_eq!(pos.authority(), &new_auth);
         assert_eq!(pos.market(), &new_market);
     }
+
+    #[test]
+    fn borrowing_power_shrinks_to_zero_at_max_ltv() {
+        let ltv_config = *test_config().ltv_config();
+        let max_ltv = ltv_config.collateral_factor();
+        assert_eq!(max_ltv, ltv_config.max_ltv);
+        let collateral_value = IFixedPoint::from_num(1_000.);
+        let health = |borrow_value: IFixedPoint| BorrowPositionHealth {
+            collateral_value,
+            borrow_value,
+            ..Default::default()
+        };
+        let max_borrow_value = collateral_value.safe_mul(max_ltv).unwrap();
+        let healthy = health(IFixedPoint::from_num(500.))
+            .borrowing_power(max_ltv)
+            .unwrap();
+        assert!(healthy > IFixedPoint::zero());
+        assert_eq!(
+            healthy,
+            max_borrow_value
+                .safe_sub(IFixedPoint::from_num(500.))
+                .unwrap()
+        );
+        let at_max_ltv = health(max_borrow_value).borrowing_power(max_ltv).unwrap();
+        assert!(at_max_ltv.is_zero());
+        let above_max_ltv = health(IFixedPoint::from_num(850.))
+            .borrowing_power(max_ltv)
+            .unwrap();
+        assert!(above_max_ltv.is_negative());
+    }
 }
@@ -3,10 +3,11 @@ use bytemuck::{Pod, Zeroable};
 
 use crate::{
     constant::MAX_EXPONENT_ABS,
-    error::{LendingError, LendingResultExt},
-    math::safe_math::SafeMath,
+    error::{ErrorWithContext, LendingError, LendingResultExt},
+    math::{ifixed_point::IFixedPoint, safe_math::SafeMath},
     oracle::{oracle_config::OracleConfig, pod_oracle_provider::PodOracleProvider},
     padding::Padding,
+    state::metadata_uri::MetadataUri,
 };
 
 use super::super::error::LendingResult;
@@ -31,7 +32,13 @@ pub struct CollateralVault {
     total_collateral_atoms: u64,
     /// The oracle configuration to manage the price of the collateral token
     oracle_config: OracleConfig,
-    pad: Padding<192>,
+    /// Max LTV in effect once the utilisation reaches the max utilisation rate, the max LTV
+    /// being lowered linearly from zero utilisation. Zero keeps the max LTV unchanged
+    max_ltv_at_max_utilisation: IFixedPoint,
+    /// Uri of the off-chain metadata of the market, stored here as the market config is full
+    #[cfg_attr(feature = "client", serde(default))]
+    metadata_uri: MetadataUri,
+    pad: Padding<112>,
 }
 
 impl CollateralVault {
@@ -42,7 +49,7 @@ impl CollateralVault {
         vault: Pubkey,
         oracle_config: OracleConfig,
     ) -> LendingResult {
-        if mint_decimals as i64 > MAX_EXPONENT_ABS {
+        if mint_decimals > MAX_EXPONENT_ABS as u64 {
             return Err(LendingError::UnsupportedMintDecimals.into()).with_msg("collateral vault");
         }
         oracle_config.validate()?;
@@ -81,8 +88,31 @@ impl CollateralVault {
         self.total_collateral_atoms
     }
 
+    pub fn max_ltv_at_max_utilisation(&self) -> IFixedPoint {
+        self.max_ltv_at_max_utilisation
+    }
+
+    pub(super) fn set_max_ltv_at_max_utilisation(&mut self, max_ltv: IFixedPoint) {
+        self.max_ltv_at_max_utilisation = max_ltv;
+    }
+
+    pub fn metadata_uri(&self) -> &MetadataUri {
+        &self.metadata_uri
+    }
+
+    pub(super) fn set_metadata_uri(&mut self, metadata_uri: MetadataUri) {
+        self.metadata_uri = metadata_uri;
+    }
+
     pub(super) fn deposit_collateral(&mut self, atoms: u64) -> LendingResult {
-        self.total_collateral_atoms = self.total_collateral_atoms.safe_add(atoms)?;
+        self.total_collateral_atoms = self
+            .total_collateral_atoms
+            .safe_add(atoms)
+            .map_err(|err| ErrorWithContext {
+                error: LendingError::CollateralVaultOverflow,
+                ..err
+            })
+            .with_msg("total collateral of the vault overflows")?;
         Ok(())
     }
 
@@ -115,6 +145,8 @@ pub mod tests {
             vault: Pubkey::new_unique(),
             oracle_config: btc_oracle_config(),
             total_collateral_atoms: 0,
+            max_ltv_at_max_utilisation: IFixedPoint::zero(),
+            metadata_uri: MetadataUri::default(),
             pad: Padding::default(),
         }
     }
@@ -134,4 +166,44 @@ pub mod tests {
         let result = vault.withdraw_collateral(BTC(2.));
         assert!(result.is_err());
     }
+
+    #[test]
+    fn deposit_overflowing_total_collateral_is_rejected() {
+        let mut vault = create_btc_collateral_vault();
+        vault.deposit_collateral(u64::MAX - 1).unwrap();
+        let err = vault.deposit_collateral(2).unwrap_err();
+        assert_eq!(err, LendingError::CollateralVaultOverflow);
+        assert!(!err.stack.is_empty());
+        assert_eq!(err.msg.len(), 1);
+        assert_eq!(vault.total_collateral_atoms(), u64::MAX - 1);
+        vault.deposit_collateral(1).unwrap();
+        assert_eq!(vault.total_collateral_atoms(), u64::MAX);
+    }
+
+    #[test]
+    fn initialize_rejects_out_of_range_decimals() {
+        for mint_decimals in [MAX_EXPONENT_ABS as u64 + 1, u64::MAX] {
+            let mut vault = CollateralVault::zeroed();
+            let err = vault
+                .initialize(
+                    Pubkey::new_unique(),
+                    mint_decimals,
+                    Pubkey::new_unique(),
+                    btc_oracle_config(),
+                )
+                .unwrap_err();
+            assert_eq!(err, LendingError::UnsupportedMintDecimals);
+            assert_eq!(err.msg[0].0, "collateral vault");
+        }
+        let mut vault = CollateralVault::zeroed();
+        vault
+            .initialize(
+                Pubkey::new_unique(),
+                MAX_EXPONENT_ABS as u64,
+                Pubkey::new_unique(),
+                btc_oracle_config(),
+            )
+            .unwrap();
+        assert_eq!(vault.mint_decimals(), MAX_EXPONENT_ABS as u8);
+    }
 }
@@ -0,0 +1,107 @@
+use arch_program::pubkey::Pubkey;
+use bytemuck::{Pod, Zeroable};
+
+use crate::{
+    error::{LendingError, LendingResult, LendingResultExt},
+    padding::Padding,
+};
+
+use super::version::{VersionedAccount, CURRENT_ACCOUNT_VERSION};
+
+crate::validate_struct!(CuratorMarketCount, 64);
+
+/// Number of markets created by a curator, used to enforce
+/// [GlobalConfig::max_markets_per_curator](super::global_config::GlobalConfig::max_markets_per_curator)
+#[repr(C)]
+#[derive(Default, Pod, Zeroable, Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "client",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct CuratorMarketCount {
+    /// The curator whose markets are counted
+    curator: Pubkey,
+    /// Markets created by the curator
+    market_count: u64,
+    /// Layout version of the account, see [super::version]
+    version: u8,
+    pad: Padding<23>,
+}
+
+impl CuratorMarketCount {
+    pub fn initialize(&mut self, curator: Pubkey) {
+        self.curator = curator;
+        self.market_count = 0;
+        self.version = CURRENT_ACCOUNT_VERSION;
+    }
+
+    pub fn curator(&self) -> &Pubkey {
+        &self.curator
+    }
+
+    pub fn market_count(&self) -> u64 {
+        self.market_count
+    }
+
+    /// Count one more market, failing if the curator already created `max_markets` of them.
+    /// A zero `max_markets` does not limit the number of markets
+    pub fn record_market(&mut self, max_markets: u16) -> LendingResult {
+        if max_markets != 0 && self.market_count >= max_markets as u64 {
+            return Err(LendingError::CuratorMarketLimitReached.into())
+                .with_msg("curator reached the maximum number of markets");
+        }
+        self.market_count += 1;
+        Ok(())
+    }
+
+    /// Overwrite the count, used by the admin to backfill the markets a curator created
+    /// before the counter existed
+    pub fn set_market_count(&mut self, market_count: u64) {
+        self.market_count = market_count;
+    }
+}
+
+impl VersionedAccount for CuratorMarketCount {
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+
+    fn migrate_from(&mut self, _version: u8) -> LendingResult {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn record_market_up_to_the_limit() {
+        let mut count = CuratorMarketCount::default();
+        count.initialize(Pubkey::new_unique());
+        count.record_market(2).unwrap();
+        count.record_market(2).unwrap();
+        assert_eq!(
+            count.record_market(2).unwrap_err(),
+            LendingError::CuratorMarketLimitReached
+        );
+        assert_eq!(count.market_count(), 2);
+        // raising the limit allows more markets
+        count.record_market(3).unwrap();
+        assert_eq!(count.market_count(), 3);
+    }
+
+    #[test]
+    fn zero_limit_is_unlimited() {
+        let mut count = CuratorMarketCount::default();
+        for _ in 0..100 {
+            count.record_market(0).unwrap();
+        }
+        assert_eq!(count.market_count(), 100);
+    }
+}
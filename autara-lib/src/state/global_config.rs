@@ -3,10 +3,13 @@ use bytemuck::{Pod, Zeroable};
 
 use crate::{
     error::{LendingError, LendingResult},
+    math::bps::Bps,
     padding::Padding,
     pod_option::PodOption,
 };
 
+use super::version::{VersionedAccount, CURRENT_ACCOUNT_VERSION};
+
 crate::validate_struct!(GlobalConfig, 256, 2);
 
 #[repr(C)]
@@ -25,7 +28,12 @@ pub struct GlobalConfig {
     fee_receiver: Pubkey,
     /// The share of the protocol fee taken on interest fee
     protocol_fee_share_in_bps: u16,
-    pad: Padding<158>,
+    /// Layout version of the account, see [super::version]
+    version: u8,
+    pad_max_markets: Padding<1>,
+    /// Maximum number of markets a single curator can create, 0 for no limit
+    max_markets_per_curator: u16,
+    pad: Padding<154>,
 }
 
 impl GlobalConfig {
@@ -35,6 +43,9 @@ impl GlobalConfig {
             fee_receiver,
             protocol_fee_share_in_bps,
             nominated_admin: PodOption::default(),
+            version: CURRENT_ACCOUNT_VERSION,
+            pad_max_markets: Padding::default(),
+            max_markets_per_curator: 0,
             pad: Padding::default(),
         }
     }
@@ -48,6 +59,7 @@ impl GlobalConfig {
         self.admin = admin;
         self.fee_receiver = fee_receiver;
         self.protocol_fee_share_in_bps = protocol_fee_share_in_bps;
+        self.version = CURRENT_ACCOUNT_VERSION;
     }
 
     pub fn admin(&self) -> &Pubkey {
@@ -58,6 +70,10 @@ impl GlobalConfig {
         &self.fee_receiver
     }
 
+    pub fn nominated_admin(&self) -> Option<&Pubkey> {
+        self.nominated_admin.as_ref()
+    }
+
     pub fn can_upgrade_nomination(&self, key: &Pubkey) -> bool {
         self.nominated_admin.as_ref().is_some_and(|n| n == key)
     }
@@ -79,6 +95,10 @@ impl GlobalConfig {
         self.nominated_admin.set(nominated_admin);
     }
 
+    pub fn cancel_nomination(&mut self) {
+        self.nominated_admin.take();
+    }
+
     pub fn can_redeem_fees(&self, key: &Pubkey) -> bool {
         &self.admin == key || &self.fee_receiver == key
     }
@@ -87,17 +107,39 @@ impl GlobalConfig {
         &self.admin == key
     }
 
-    pub fn update_protocol_fee_share_in_bps(&mut self, new_fee: u16) -> LendingResult {
-        if new_fee > 10_000 {
+    pub fn update_protocol_fee_share_in_bps(&mut self, new_fee: Bps) -> LendingResult {
+        if new_fee > Bps::ONE {
             return Err(LendingError::FeeTooHigh.into());
         }
-        self.protocol_fee_share_in_bps = new_fee;
+        self.protocol_fee_share_in_bps = new_fee.get();
         Ok(())
     }
 
     pub fn protocol_fee_share_in_bps(&self) -> u16 {
         self.protocol_fee_share_in_bps
     }
+
+    pub fn max_markets_per_curator(&self) -> u16 {
+        self.max_markets_per_curator
+    }
+
+    pub fn set_max_markets_per_curator(&mut self, max_markets_per_curator: u16) {
+        self.max_markets_per_curator = max_markets_per_curator;
+    }
+}
+
+impl VersionedAccount for GlobalConfig {
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+
+    fn migrate_from(&mut self, _version: u8) -> LendingResult {
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -125,13 +167,17 @@ pub mod tests {
         assert!(!config.can_update_config(&fee_receiver));
 
         config.set_fee_receiver(Pubkey::new_unique());
-        config.update_protocol_fee_share_in_bps(1500).unwrap();
+        config
+            .update_protocol_fee_share_in_bps(Bps::new(1500))
+            .unwrap();
 
         assert_ne!(config.fee_receiver(), &fee_receiver);
         assert_eq!(config.protocol_fee_share_in_bps(), 1500);
 
         // Fee above 100% (10_000 bps) should fail
-        assert!(config.update_protocol_fee_share_in_bps(10_001).is_err());
+        assert!(config
+            .update_protocol_fee_share_in_bps(Bps::new(10_001))
+            .is_err());
         assert_eq!(config.protocol_fee_share_in_bps(), 1500); // unchanged
 
         let nominated_admin = Pubkey::new_unique();
@@ -149,4 +195,18 @@ pub mod tests {
         assert!(config.can_update_config(&nominated_admin));
         assert!(!config.can_update_config(&admin));
     }
+
+    #[test]
+    fn cancelled_nomination_cannot_be_accepted() {
+        let mut config = test_global_config();
+        let admin = *config.admin();
+        let nominated_admin = Pubkey::new_unique();
+        config.set_nominated_admin(nominated_admin);
+        assert_eq!(config.nominated_admin(), Some(&nominated_admin));
+        config.cancel_nomination();
+        assert_eq!(config.nominated_admin(), None);
+        assert!(!config.can_upgrade_nomination(&nominated_admin));
+        assert!(config.upgrade_nomination().is_err());
+        assert_eq!(config.admin(), &admin);
+    }
 }
@@ -1,13 +1,21 @@
+use std::collections::BTreeMap;
+
 use arch_program::pubkey::Pubkey;
 use bytemuck::{Pod, Zeroable};
 
 use crate::{
     interest_rate::interest_rate_kind::InterestRateCurveKind,
-    math::{ifixed_point::IFixedPoint, rounding::RoundingMode, safe_math::SafeMath},
+    math::{
+        ifixed_point::IFixedPoint, rounding::RoundingMode, safe_math::SafeMath,
+        ufixed_point::UFixedPoint,
+    },
     operation::liquidation::{compute_liquidation_with_fee, LiquidationResultWithBonus},
     oracle::{oracle_config::OracleConfig, oracle_price::OracleRate},
     pda::market_seed_with_bump,
-    state::{borrow_position::LiquidationResultWithCtx, market_config::MarketConfig},
+    state::{
+        borrow_position::LiquidationResultWithCtx,
+        market_config::{max_min_collateral_after_liquidation_atoms, MarketConfig, MaxSupplyRamp},
+    },
     token::TokenInfo,
 };
 
@@ -15,12 +23,17 @@ use super::{
     super::error::{LendingError, LendingResult, LendingResultExt},
     borrow_position::{BorrowPosition, BorrowPositionHealth},
     collateral_vault::CollateralVault,
+    metadata_uri::MetadataUri,
     supply_position::SupplyPosition,
-    supply_vault::SupplyVault,
+    supply_vault::{SupplyVault, SupplyVaultSummary, MAX_CALLBACK_PROGRAMS},
+    version::VersionedAccount,
 };
 
 crate::validate_struct!(Market, 1448);
 
+/// Atoms by which share rounding can make the value of a supply position not withdrawable
+const MAX_WITHDRAW_ROUNDING_ATOMS: u64 = 1;
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Pod, Zeroable)]
 #[cfg_attr(
@@ -34,6 +47,15 @@ pub struct Market {
     supply_vault: SupplyVault,
 }
 
+/// Protocol fees accrued by a market since its creation, redeemed or not
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarketProtocolRevenue {
+    pub market: Pubkey,
+    /// Mint the fees are accrued in
+    pub supply_token: TokenInfo,
+    pub fee_atoms: u64,
+}
+
 impl Market {
     #[inline(always)]
     pub fn supply_token_info(&self) -> TokenInfo {
@@ -109,44 +131,182 @@ impl Market {
         } else {
             borrow_value.safe_div(collateral_value).track_caller()?
         };
+        let is_in_warning_zone = self.config.warning_ltv().is_some_and(|warning_ltv| {
+            ltv >= warning_ltv && ltv < self.config.ltv_config().unhealthy_ltv
+        });
         Ok(BorrowPositionHealth {
             ltv,
             collateral_atoms: borrow_position.collateral_deposited_atoms(),
             borrowed_atoms,
             borrow_value,
             collateral_value,
+            is_in_warning_zone,
         })
     }
 
+    /// Interest paid by the position so far plus the interest currently accrued on its debt
+    pub fn borrow_lifetime_interest(&self, borrow_position: &BorrowPosition) -> LendingResult<u64> {
+        let borrowed_atoms = self
+            .supply_vault
+            .borrow_shares_to_atoms(borrow_position.borrowed_shares())
+            .track_caller()?;
+        let accrued_interest =
+            borrowed_atoms.saturating_sub(borrow_position.initial_borrowed_atoms());
+        borrow_position
+            .interest_paid_atoms()
+            .safe_add(accrued_interest)
+    }
+
     pub fn supply_position_info(&self, supply_position: &SupplyPosition) -> LendingResult<u64> {
         self.supply_vault
             .supply_shares_tracker()
             .shares_to_atoms(supply_position.shares(), RoundingMode::RoundDown)
     }
 
+    /// Largest amount of atoms [Market::withdraw] accepts for `supply_position`, bounded by
+    /// the value of the position and the liquidity of the vault. Share rounding can make
+    /// this bound fail by an atom, so it is reconciled against a copy of the market
+    pub fn max_withdrawable_supply(&self, supply_position: &SupplyPosition) -> LendingResult<u64> {
+        let available_atoms = self
+            .supply_vault
+            .total_supply()?
+            .saturating_sub(self.supply_vault.total_borrow()?);
+        let mut atoms = self
+            .supply_position_info(supply_position)?
+            .min(available_atoms);
+        for _ in 0..=MAX_WITHDRAW_ROUNDING_ATOMS {
+            let mut market = *self;
+            let mut supply_position = *supply_position;
+            if atoms == 0 || market.withdraw(&mut supply_position, atoms).is_ok() {
+                return Ok(atoms);
+            }
+            atoms -= 1;
+        }
+        Err(LendingError::WithdrawalExceedsReserves.into())
+            .with_msg("no withdrawable amount within the rounding tolerance")
+    }
+
+    /// Max LTV lowered linearly with the utilisation when the curator set a max LTV at
+    /// max utilisation, so that positions tighten when there is less liquidity to liquidate into
+    pub fn effective_max_ltv(&self) -> LendingResult<IFixedPoint> {
+        let max_ltv = self.config.ltv_config().max_ltv;
+        let max_ltv_at_cap = self.collateral_vault.max_ltv_at_max_utilisation();
+        if max_ltv_at_cap.is_zero() {
+            return Ok(max_ltv);
+        }
+        let max_utilisation_rate = self.config.max_utilisation_rate();
+        let utilisation_rate = self
+            .supply_vault
+            .utilisation_rate()?
+            .min(max_utilisation_rate);
+        let reduction = max_ltv
+            .safe_sub(max_ltv_at_cap.min(max_ltv))?
+            .safe_mul(utilisation_rate)?
+            .safe_div(max_utilisation_rate)?;
+        max_ltv.safe_sub(reduction)
+    }
+
+    /// Enables the utilisation sensitive max LTV, zero disables it
+    /// The value is checked against `max_ltv` in [`Market::validate`] so that both can be
+    /// updated together
+    pub fn update_max_ltv_at_max_utilisation(&mut self, max_ltv: IFixedPoint) -> LendingResult {
+        if max_ltv.is_negative() {
+            return Err(LendingError::InvalidLtvConfig.into())
+                .with_msg("max ltv at max utilisation must be non-negative");
+        }
+        self.collateral_vault
+            .set_max_ltv_at_max_utilisation(max_ltv);
+        Ok(())
+    }
+
+    /// Checks the constraints spanning the config and the vaults, to be run once all the
+    /// fields of an update have been applied
+    pub fn validate(&self) -> LendingResult {
+        self.config.validate()?;
+        self.supply_vault.oracle_config().validate()?;
+        self.collateral_vault.oracle_config().validate()?;
+        if self.collateral_vault.max_ltv_at_max_utilisation() > self.config.ltv_config().max_ltv {
+            return Err(LendingError::InvalidLtvConfig.into())
+                .with_msg("max ltv at max utilisation must not exceed max ltv");
+        }
+        if self.config.min_collateral_after_liquidation_atoms()
+            > max_min_collateral_after_liquidation_atoms(self.collateral_vault.mint_decimals())
+        {
+            return Err(LendingError::MinCollateralAfterLiquidationTooHigh.into())
+                .with_msg("min collateral after liquidation is above one collateral token");
+        }
+        self.metadata_uri().validate()
+    }
+
+    /// Uri of the off-chain JSON describing the market, empty if the curator did not set one
+    pub fn metadata_uri(&self) -> &MetadataUri {
+        self.collateral_vault.metadata_uri()
+    }
+
+    pub fn update_metadata_uri(&mut self, metadata_uri: MetadataUri) -> LendingResult {
+        metadata_uri.validate()?;
+        self.collateral_vault.set_metadata_uri(metadata_uri);
+        Ok(())
+    }
+
+    /// Restricts the programs the leverage callbacks may invoke, an empty list allows any
+    pub fn update_callback_program_allowlist(&mut self, programs: &[Pubkey]) -> LendingResult {
+        if programs.len() > MAX_CALLBACK_PROGRAMS {
+            return Err(LendingError::InvalidInstructionArguments.into()).with_msg(
+                "callback program allowlist has more than MAX_CALLBACK_PROGRAMS programs",
+            );
+        }
+        if programs.contains(&Pubkey::default()) {
+            return Err(LendingError::InvalidInstructionArguments.into())
+                .with_msg("callback program allowlist contains the default key");
+        }
+        let mut allowlist = [Pubkey::default(); MAX_CALLBACK_PROGRAMS];
+        allowlist[..programs.len()].copy_from_slice(programs);
+        self.supply_vault.set_callback_program_allowlist(allowlist);
+        Ok(())
+    }
+
+    pub fn check_callback_program(&self, program_id: &Pubkey) -> LendingResult {
+        let mut allowlist = self.supply_vault.callback_program_allowlist().peekable();
+        if allowlist.peek().is_none() {
+            return Ok(());
+        }
+        if !allowlist.any(|allowed| allowed == program_id) {
+            return Err(LendingError::CallbackProgramNotAllowed.into())
+                .with_msg("callback program is not in the allowlist of the market");
+        }
+        Ok(())
+    }
+
+    /// Max LTV in effect once `borrow_atoms` more are borrowed, see [Self::effective_max_ltv]
+    pub fn effective_max_ltv_after_borrow(&self, borrow_atoms: u64) -> LendingResult<IFixedPoint> {
+        let mut market = *self;
+        market.supply_vault.borrow(borrow_atoms)?;
+        market.effective_max_ltv()
+    }
+
     pub fn position_checks_on_ltv_increase(
         &self,
         position: &BorrowPositionHealth,
     ) -> LendingResult {
-        if position.ltv > self.config.ltv_config().max_ltv {
+        if position.ltv > self.effective_max_ltv()? {
             return Err(LendingError::MaxLtvReached.into());
         }
         Ok(())
     }
 
+    /// Feed accounts of the supply and collateral oracles, none for a constant oracle
     #[cfg(feature = "client")]
-    pub fn get_oracle_keys(&self) -> (Pubkey, Pubkey) {
+    pub fn get_oracle_keys(&self) -> (Option<Pubkey>, Option<Pubkey>) {
         (
             self.supply_vault
                 .oracle_provider()
                 .oracle_provider_ref()
-                .oracle_feed_pubkey()
-                .unwrap(),
+                .oracle_feed_pubkey(),
             self.collateral_vault
                 .oracle_provider()
                 .oracle_provider_ref()
-                .oracle_feed_pubkey()
-                .unwrap(),
+                .oracle_feed_pubkey(),
         )
     }
 
@@ -185,7 +345,29 @@ impl Market {
         &mut self.config
     }
 
+    /// Max supply in effect at `unix_timestamp`, following the ramp of the last update
+    pub fn max_supply_atoms(&self, unix_timestamp: i64) -> u64 {
+        self.supply_vault
+            .max_supply_ramp()
+            .max_supply_atoms(self.config.max_supply_atoms(), unix_timestamp)
+    }
+
+    /// Moves the max supply linearly from `start_atoms` to the configured max supply over
+    /// `ramp_seconds`, zero applying the configured max supply at once
+    pub fn ramp_max_supply(&mut self, start_atoms: u64, ramp_seconds: u64, unix_timestamp: i64) {
+        self.supply_vault.set_max_supply_ramp(MaxSupplyRamp::new(
+            start_atoms,
+            unix_timestamp,
+            ramp_seconds,
+        ));
+    }
+
     pub fn sync_clock(&mut self, unix_timestamp: i64) -> LendingResult {
+        let interest_accrual_start = self.config.interest_accrual_start_unix_timestamp();
+        if self.supply_vault.last_update_unix_timestamp() < interest_accrual_start {
+            self.supply_vault
+                .skip_interest_until(unix_timestamp.min(interest_accrual_start));
+        }
         self.supply_vault.sync_clock(
             unix_timestamp,
             self.config.lending_market_fee_fixed(),
@@ -193,6 +375,75 @@ impl Market {
         )
     }
 
+    /// Whether interest was last accrued more than `staleness_seconds` before `unix_timestamp`
+    pub fn needs_sync(&self, unix_timestamp: i64, staleness_seconds: u64) -> bool {
+        let elapsed = unix_timestamp.saturating_sub(self.supply_vault.last_update_unix_timestamp());
+        elapsed > 0 && elapsed as u64 > staleness_seconds
+    }
+
+    /// Keys of the `markets` which need a sync at `unix_timestamp`, see [`Market::needs_sync`]
+    pub fn markets_needing_sync<'a>(
+        markets: impl IntoIterator<Item = (Pubkey, &'a Market)>,
+        unix_timestamp: i64,
+        staleness_seconds: u64,
+    ) -> Vec<Pubkey> {
+        markets
+            .into_iter()
+            .filter(|(_, market)| market.needs_sync(unix_timestamp, staleness_seconds))
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    /// Supply vault summary as it would be after syncing the clock to `unix_timestamp`,
+    /// a timestamp before the last update previews the current summary
+    pub fn preview_supply_vault_summary(
+        &self,
+        unix_timestamp: i64,
+    ) -> LendingResult<SupplyVaultSummary> {
+        let mut market = *self;
+        market.sync_clock(unix_timestamp.max(self.supply_vault.last_update_unix_timestamp()))?;
+        market.supply_vault.get_summary()
+    }
+
+    /// Sums the pending protocol fees of `markets` previewed at `unix_timestamp`, grouped by
+    /// supply mint since fees are accrued in supply atoms
+    pub fn pending_protocol_fees_by_mint<'a>(
+        markets: impl IntoIterator<Item = &'a Market>,
+        unix_timestamp: i64,
+    ) -> LendingResult<BTreeMap<Pubkey, u64>> {
+        let mut fees = BTreeMap::<Pubkey, u64>::new();
+        for market in markets {
+            let pending = market
+                .preview_supply_vault_summary(unix_timestamp)?
+                .pending_protocol_fee_atoms;
+            let total = fees.entry(*market.supply_vault.mint()).or_default();
+            *total = total.safe_add(pending)?;
+        }
+        Ok(fees)
+    }
+
+    /// Protocol fees accrued by each of `markets` since its creation previewed at
+    /// `unix_timestamp`, in atoms of the supply mint of the market
+    pub fn protocol_revenue_by_market<'a>(
+        markets: impl IntoIterator<Item = (Pubkey, &'a Market)>,
+        unix_timestamp: i64,
+    ) -> LendingResult<Vec<MarketProtocolRevenue>> {
+        markets
+            .into_iter()
+            .map(|(key, market)| {
+                let mut market = *market;
+                market.sync_clock(
+                    unix_timestamp.max(market.supply_vault.last_update_unix_timestamp()),
+                )?;
+                Ok(MarketProtocolRevenue {
+                    market: key,
+                    supply_token: market.supply_token_info(),
+                    fee_atoms: market.supply_vault.lifetime_protocol_fee_atoms(),
+                })
+            })
+            .collect()
+    }
+
     pub fn redeem_protocol_fees(&mut self) -> LendingResult<u64> {
         let atoms = self.supply_vault.redeem_protocol_fees()?;
         if self.supply_vault.utilisation_rate()? > IFixedPoint::one() {
@@ -211,7 +462,29 @@ impl Market {
         Ok(atoms)
     }
 
+    /// Utilisation left before reaching the max utilisation rate of the market
+    pub fn utilisation_headroom(&self) -> LendingResult<IFixedPoint> {
+        self.supply_vault
+            .utilisation_headroom(self.config.max_utilisation_rate())
+    }
+
+    /// Atoms which can be borrowed before reaching the max utilisation rate of the market
+    pub fn borrowable_atoms_within_utilisation_cap(&self) -> LendingResult<u64> {
+        self.supply_vault
+            .borrowable_atoms_within_utilisation_cap(self.config.max_utilisation_rate())
+    }
+
+    /// Credit to suppliers the pending protocol and curator fees worth less than one atom,
+    /// giving them up for good even though interest could later make them redeemable.
+    /// Returns the swept fee shares
+    pub fn sweep_fee_dust(&mut self) -> LendingResult<UFixedPoint> {
+        self.supply_vault.sweep_fee_dust()
+    }
+
     pub fn donate_supply_atoms(&mut self, atoms: u64) -> LendingResult {
+        if !self.config.allow_donations() {
+            return Err(LendingError::DonationsDisabled.into());
+        }
         self.supply_vault.donate_supply(atoms)
     }
 
@@ -222,7 +495,9 @@ impl Market {
     ) -> LendingResult {
         let shares = self.supply_vault.lend(atoms).track_caller()?;
         supply_position.lend(atoms, shares)?;
-        if self.supply_vault.total_supply()? > self.config.max_supply_atoms() {
+        let max_supply_atoms =
+            self.max_supply_atoms(self.supply_vault.last_update_unix_timestamp());
+        if self.supply_vault.total_supply()? > max_supply_atoms {
             return Err(LendingError::MaxSupplyReached.into());
         }
         Ok(())
@@ -299,6 +574,9 @@ impl Market {
         supply_oracle: &OracleRate,
         collateral_oracle: &OracleRate,
     ) -> LendingResult {
+        if self.supply_vault.total_supply()? < self.config.min_supply_for_borrow_atoms() {
+            return Err(LendingError::InsufficientMarketDepth.into());
+        }
         let shares = self.supply_vault.borrow(borrow_atoms).track_caller()?;
         borrow_position
             .borrow(borrow_atoms, shares)
@@ -307,9 +585,19 @@ impl Market {
             .borrow_position_health(borrow_position, collateral_oracle, supply_oracle)
             .track_caller()?;
         self.position_checks_on_ltv_increase(&health)?;
+        if health.ltv > self.config.borrow_cap_ltv() {
+            return Err(LendingError::BorrowCapLtvReached.into());
+        }
         if self.supply_vault.utilisation_rate()? > self.config.max_utilisation_rate() {
             return Err(LendingError::MaxUtilisationRateReached.into());
         }
+        let available_atoms = self
+            .supply_vault
+            .total_supply()?
+            .saturating_sub(self.supply_vault.total_borrow()?);
+        if available_atoms < self.config.min_liquidity_buffer_atoms() {
+            return Err(LendingError::MinLiquidityBufferReached.into());
+        }
         Ok(())
     }
 
@@ -319,7 +607,9 @@ impl Market {
         atoms: u64,
     ) -> LendingResult {
         let shares = self.supply_vault.repay_atoms(atoms).track_caller()?;
+        let initial_borrowed_atoms = borrow_position.initial_borrowed_atoms();
         borrow_position.repay(shares).track_caller()?;
+        borrow_position.record_interest_paid(atoms, initial_borrowed_atoms);
         Ok(())
     }
 
@@ -328,7 +618,9 @@ impl Market {
             .supply_vault
             .repay_shares(borrow_position.borrowed_shares())
             .track_caller()?;
+        let initial_borrowed_atoms = borrow_position.initial_borrowed_atoms();
         borrow_position.repay_all();
+        borrow_position.record_interest_paid(atoms, initial_borrowed_atoms);
         Ok(atoms)
     }
 
@@ -347,6 +639,7 @@ impl Market {
                 max_repay_atoms,
             )
             .track_caller()?;
+        let total_borrow_before = self.supply_vault.total_borrow()?;
         let (atoms_repaid, shares_repaid) = self
             .supply_vault
             .repay_atoms_capped(
@@ -356,16 +649,23 @@ impl Market {
             .track_caller()?;
         // because of rounding we need to adjust the liquidation result
         liquidation.adjust_for_max_repay(atoms_repaid);
+        let initial_borrowed_atoms = borrow_position.initial_borrowed_atoms();
         borrow_position.liquidate(
             shares_repaid,
             liquidation.total_collateral_atoms_to_liquidate()?,
         )?;
+        borrow_position.record_interest_paid(atoms_repaid, initial_borrowed_atoms);
         let health_after = self
             .borrow_position_health(borrow_position, collateral_oracle, supply_oracle)
             .track_caller()?;
         if health_after.ltv > health_before.ltv {
             return Err(LendingError::InvalidLiquidationLtvShouldDecrease.into());
         }
+        // a liquidation must always repay some debt
+        if self.supply_vault.total_borrow()? >= total_borrow_before {
+            return Err(LendingError::LiquidationInvariantViolated.into())
+                .with_msg("total borrow should decrease on liquidation");
+        }
         Ok(LiquidationResultWithCtx {
             liquidation_result_with_bonus: liquidation,
             health_before_liquidation: health_before,
@@ -395,22 +695,51 @@ impl Market {
             liquidation.adjust_for_max_repay(max_repay_atoms);
             liquidation
         } else {
-            compute_liquidation_with_fee(
-                health_before.borrowed_atoms,
-                self.supply_vault.mint_decimals(),
-                supply_oracle,
-                borrow_position.collateral_deposited_atoms(),
-                self.collateral_vault.mint_decimals(),
-                collateral_oracle,
-                self.config.ltv_config().target_ltv_after_liquidation(),
-                self.config.ltv_config().liquidation_bonus,
-                max_repay_atoms,
-            )
-            .track_caller()?
+            let compute_with_target_ltv = |target_ltv: IFixedPoint| {
+                compute_liquidation_with_fee(
+                    health_before.borrowed_atoms,
+                    self.supply_vault.mint_decimals(),
+                    supply_oracle,
+                    borrow_position.collateral_deposited_atoms(),
+                    self.collateral_vault.mint_decimals(),
+                    collateral_oracle,
+                    target_ltv,
+                    self.config.ltv_config().liquidation_bonus,
+                    max_repay_atoms,
+                )
+                .track_caller()
+            };
+            let liquidation =
+                compute_with_target_ltv(self.config.ltv_config().target_ltv_after_liquidation())?;
+            if self.leaves_dust_collateral(&health_before, &liquidation)? {
+                // close the debt entirely instead of leaving a dust position behind. The close
+                // is limited by the collateral left and by `max_repay_atoms`, it is still
+                // applied when limited so that the position can always be liquidated
+                compute_with_target_ltv(IFixedPoint::zero())?
+            } else {
+                liquidation
+            }
         };
         Ok((health_before, liquidation))
     }
 
+    /// Whether the liquidation leaves some debt backed by less, but not zero, collateral than
+    /// `min_collateral_after_liquidation_atoms`. Seizing all the collateral leaves no dust
+    fn leaves_dust_collateral(
+        &self,
+        health_before: &BorrowPositionHealth,
+        liquidation: &LiquidationResultWithBonus,
+    ) -> LendingResult<bool> {
+        if liquidation.borrowed_atoms_to_repay >= health_before.borrowed_atoms {
+            return Ok(false);
+        }
+        let collateral_after = health_before
+            .collateral_atoms
+            .saturating_sub(liquidation.total_collateral_atoms_to_liquidate()?);
+        Ok(collateral_after != 0
+            && collateral_after < self.config.min_collateral_after_liquidation_atoms())
+    }
+
     pub(super) fn socialize_loss(
         &mut self,
         borrow_position: &mut BorrowPosition,
@@ -433,6 +762,20 @@ impl Market {
     }
 }
 
+impl VersionedAccount for Market {
+    fn version(&self) -> u8 {
+        self.config.version()
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.config.set_version(version);
+    }
+
+    fn migrate_from(&mut self, version: u8) -> LendingResult {
+        self.config.migrate_from(version)
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
 
@@ -517,6 +860,63 @@ pub mod tests {
         );
     }
 
+    #[test]
+    pub fn borrow_cap_ltv_limits_borrows_but_not_withdrawals() {
+        let mut market = create_btc_usdc_market();
+        let mut borrow_position = BorrowPosition::default();
+        let collateral_oracle = default_btc_oracle_rate();
+        let supply_oracle = default_usd_oracle_rate();
+        market
+            .config_mut()
+            .update_borrow_cap_ltv(IFixedPoint::lit("0.85"))
+            .unwrap();
+        assert!(market.config().validate().is_err());
+        market
+            .config_mut()
+            .update_borrow_cap_ltv(IFixedPoint::lit("0.5"))
+            .unwrap();
+        market.config().validate().unwrap();
+        market
+            .deposit_collateral(&mut borrow_position, BTC(1.))
+            .unwrap();
+        market
+            .borrow(
+                &mut borrow_position,
+                USDC(49_000.),
+                &supply_oracle,
+                &collateral_oracle,
+            )
+            .unwrap();
+        // ~50.5% is still below max_ltv but above the borrow cap
+        assert_eq!(
+            market
+                .clone()
+                .borrow(
+                    &mut borrow_position.clone(),
+                    USDC(1_500.),
+                    &supply_oracle,
+                    &collateral_oracle,
+                )
+                .err()
+                .unwrap(),
+            LendingError::BorrowCapLtvReached
+        );
+        // withdrawals are only bounded by max_ltv
+        market
+            .withdraw_collateral(
+                &mut borrow_position,
+                BTC(0.3),
+                &collateral_oracle,
+                &supply_oracle,
+            )
+            .unwrap();
+        let health = market
+            .borrow_position_health(&borrow_position, &collateral_oracle, &supply_oracle)
+            .unwrap();
+        assert!(health.ltv > market.config().borrow_cap_ltv());
+        assert!(health.ltv < market.config().ltv_config().max_ltv);
+    }
+
     #[test]
     pub fn cant_borrow_more_than_max_utilisation_rate() {
         let mut market = create_btc_usdc_market();
@@ -657,6 +1057,189 @@ pub mod tests {
         assert!(ltv_after_liquidation > market.config.ltv_config().unhealthy_ltv);
     }
 
+    #[test]
+    pub fn liquidation_leaving_dust_collateral_closes_the_debt() {
+        let mut market = create_btc_usdc_market();
+        let mut borrow_position = BorrowPosition::default();
+        let collateral_oracle = default_btc_oracle_rate();
+        let supply_oracle = default_usd_oracle_rate();
+        market
+            .deposit_collateral(&mut borrow_position, BTC(0.5))
+            .unwrap();
+        market
+            .borrow(
+                &mut borrow_position,
+                USDC(20_000.),
+                &supply_oracle,
+                &collateral_oracle,
+            )
+            .unwrap();
+        let supply_oracle =
+            OracleRate::new(IFixedPoint::from_num(2.3), IFixedPoint::from_num(0.001));
+        let partial = market
+            .clone()
+            .liquidate(
+                &mut borrow_position.clone(),
+                &collateral_oracle,
+                &supply_oracle,
+                u64::MAX,
+            )
+            .unwrap();
+        assert!(partial.health_after_liquidation.borrowed_atoms > 0);
+        assert!(partial.health_after_liquidation.collateral_atoms < BTC(0.499));
+        market
+            .config_mut()
+            .update_min_collateral_after_liquidation_atoms(BTC(0.499));
+        // a capped repayment is still priced as the full close, with the configured bonus
+        let capped = market
+            .clone()
+            .liquidate(
+                &mut borrow_position.clone(),
+                &collateral_oracle,
+                &supply_oracle,
+                USDC(100.),
+            )
+            .unwrap()
+            .liquidation_result_with_bonus;
+        assert_eq!(capped.borrowed_atoms_to_repay, USDC(100.));
+        assert_eq_float!(
+            capped.effective_bonus_ratio().unwrap().to_float(),
+            0.05,
+            0.001
+        );
+        let full_close = market
+            .liquidate(
+                &mut borrow_position,
+                &collateral_oracle,
+                &supply_oracle,
+                u64::MAX,
+            )
+            .unwrap();
+        assert_eq!(
+            full_close
+                .liquidation_result_with_bonus
+                .borrowed_atoms_to_repay,
+            full_close.health_before_liquidation.borrowed_atoms
+        );
+        assert!(borrow_position.borrowed_shares().is_zero());
+        assert_eq!(full_close.health_after_liquidation.ltv, IFixedPoint::zero());
+        // the collateral left is the owner's and can be withdrawn
+        assert!(borrow_position.collateral_deposited_atoms() > 0);
+    }
+
+    #[test]
+    pub fn near_underwater_liquidation_seizes_all_the_collateral() {
+        let mut market = create_btc_usdc_market();
+        market
+            .config_mut()
+            .update_min_collateral_after_liquidation_atoms(BTC(0.45));
+        let mut borrow_position = BorrowPosition::default();
+        let collateral_oracle = default_btc_oracle_rate();
+        market
+            .deposit_collateral(&mut borrow_position, BTC(0.5))
+            .unwrap();
+        market
+            .borrow(
+                &mut borrow_position,
+                USDC(20_000.),
+                &default_usd_oracle_rate(),
+                &collateral_oracle,
+            )
+            .unwrap();
+        // ltv of 0.98, the collateral cannot pay the whole liquidation bonus
+        let supply_oracle =
+            OracleRate::new(IFixedPoint::from_num(2.45), IFixedPoint::from_num(0.0));
+        let liquidation = market
+            .liquidate(
+                &mut borrow_position,
+                &collateral_oracle,
+                &supply_oracle,
+                u64::MAX,
+            )
+            .unwrap();
+        assert!(liquidation.health_before_liquidation.ltv < IFixedPoint::one());
+        assert_eq!(borrow_position.collateral_deposited_atoms(), 0);
+        assert!(borrow_position.borrowed_shares().is_zero());
+        let bonus = liquidation
+            .liquidation_result_with_bonus
+            .effective_bonus_ratio()
+            .unwrap();
+        assert!(bonus > IFixedPoint::zero());
+        assert!(bonus < market.config.ltv_config().liquidation_bonus);
+    }
+
+    #[test]
+    pub fn warning_zone_lies_between_warning_and_unhealthy_ltv() {
+        let mut market = create_btc_usdc_market();
+        market
+            .config_mut()
+            .update_warning_ltv(IFixedPoint::lit("0.85"))
+            .unwrap();
+        market.config().validate().unwrap();
+        let mut borrow_position = BorrowPosition::default();
+        let collateral_oracle = default_btc_oracle_rate();
+        market
+            .deposit_collateral(&mut borrow_position, BTC(0.5))
+            .unwrap();
+        market
+            .borrow(
+                &mut borrow_position,
+                USDC(20_000.),
+                &default_usd_oracle_rate(),
+                &collateral_oracle,
+            )
+            .unwrap();
+        let health_at_price = |market: &Market, price: f64| {
+            let supply_oracle =
+                OracleRate::new(IFixedPoint::from_num(price), IFixedPoint::from_num(0.001));
+            market
+                .borrow_position_health(&borrow_position, &collateral_oracle, &supply_oracle)
+                .unwrap()
+        };
+        let below = health_at_price(&market, 1.);
+        assert!(below.ltv < IFixedPoint::lit("0.85"));
+        assert!(!below.is_in_warning_zone);
+        let within = health_at_price(&market, 2.15);
+        assert!(within.ltv >= IFixedPoint::lit("0.85"));
+        assert!(within.ltv < IFixedPoint::lit("0.9"));
+        assert!(within.is_in_warning_zone);
+        let above = health_at_price(&market, 2.3);
+        assert!(above.ltv >= IFixedPoint::lit("0.9"));
+        assert!(!above.is_in_warning_zone);
+        // without a warning ltv no position is ever in the warning zone
+        market
+            .config_mut()
+            .update_warning_ltv(IFixedPoint::zero())
+            .unwrap();
+        assert!(!health_at_price(&market, 2.15).is_in_warning_zone);
+        // the warning ltv must lie between max and unhealthy ltv
+        market
+            .config_mut()
+            .update_warning_ltv(IFixedPoint::lit("0.95"))
+            .unwrap();
+        assert_eq!(
+            market.config().validate().unwrap_err(),
+            LendingError::InvalidLtvConfig
+        );
+    }
+
+    #[test]
+    pub fn validate_caps_min_collateral_after_liquidation() {
+        let mut market = create_btc_usdc_market();
+        // the collateral has 8 decimals, so the cap is 1 BTC
+        market
+            .config_mut()
+            .update_min_collateral_after_liquidation_atoms(BTC(1.));
+        market.validate().unwrap();
+        market
+            .config_mut()
+            .update_min_collateral_after_liquidation_atoms(BTC(1.) + 1);
+        assert_eq!(
+            market.validate().unwrap_err(),
+            LendingError::MinCollateralAfterLiquidationTooHigh
+        );
+    }
+
     #[test]
     pub fn can_fully_liquidate_unhealthy_position() {
         let mut market = create_btc_usdc_market();
@@ -1059,6 +1642,67 @@ pub mod tests {
         assert!(supplier_one.shares() > supplier_two.shares());
     }
 
+    #[test]
+    pub fn borrow_lifetime_interest_includes_paid_and_accrued_interest() {
+        let one_year = 365 * 24 * 3600;
+        let mut market = create_empty_btc_usdc_market();
+        let mut supplier = SupplyPosition::default();
+        let mut borrow_position = BorrowPosition::default();
+        let collateral_oracle = default_btc_oracle_rate();
+        let supply_oracle = default_usd_oracle_rate();
+        market.lend(&mut supplier, USDC(100_000.)).unwrap();
+        market
+            .deposit_collateral(&mut borrow_position, BTC(10.))
+            .unwrap();
+        market
+            .borrow(
+                &mut borrow_position,
+                USDC(50_000.),
+                &supply_oracle,
+                &collateral_oracle,
+            )
+            .unwrap();
+        assert_eq!(
+            market.borrow_lifetime_interest(&borrow_position).unwrap(),
+            0
+        );
+        market.sync_clock(one_year).unwrap();
+        let debt = market
+            .supply_vault()
+            .borrow_shares_to_atoms(borrow_position.borrowed_shares())
+            .unwrap();
+        let accrued = debt - USDC(50_000.);
+        assert!(accrued > 0);
+        assert_eq!(
+            market.borrow_lifetime_interest(&borrow_position).unwrap(),
+            accrued
+        );
+        // half of the repayment is interest, the rest stays accrued on the remaining debt
+        market.repay(&mut borrow_position, debt / 2).unwrap();
+        assert!(borrow_position.interest_paid_atoms().abs_diff(accrued / 2) <= 1);
+        assert!(
+            market
+                .borrow_lifetime_interest(&borrow_position)
+                .unwrap()
+                .abs_diff(accrued)
+                <= 1
+        );
+        market.sync_clock(2 * one_year).unwrap();
+        let lifetime_interest = market.borrow_lifetime_interest(&borrow_position).unwrap();
+        assert!(lifetime_interest > accrued);
+        market.repay_all(&mut borrow_position).unwrap();
+        assert!(
+            borrow_position
+                .interest_paid_atoms()
+                .abs_diff(lifetime_interest)
+                <= 1
+        );
+        assert_eq!(
+            market.borrow_lifetime_interest(&borrow_position).unwrap(),
+            borrow_position.interest_paid_atoms()
+        );
+    }
+
     #[test]
     pub fn withdraw_fails_when_utilization_exceeds_100_percent() {
         let mut market = create_empty_btc_usdc_market();
@@ -1084,6 +1728,379 @@ pub mod tests {
         );
     }
 
+    #[test]
+    pub fn no_interest_accrues_during_grace_period() {
+        let grace_seconds = 3600;
+        let mut market = create_btc_usdc_market();
+        market
+            .config_mut()
+            .set_borrow_origination_grace_seconds(grace_seconds);
+        let mut borrow_position = BorrowPosition::default();
+        market
+            .deposit_collateral(&mut borrow_position, BTC(1.))
+            .unwrap();
+        market
+            .borrow(
+                &mut borrow_position,
+                USDC(50_000.),
+                &default_usd_oracle_rate(),
+                &default_btc_oracle_rate(),
+            )
+            .unwrap();
+        let mut without_grace = market;
+        without_grace
+            .config_mut()
+            .set_borrow_origination_grace_seconds(0);
+        let borrowed = market.supply_vault().total_borrow().unwrap();
+        market.sync_clock(grace_seconds as i64 / 2).unwrap();
+        assert_eq!(market.supply_vault().total_borrow().unwrap(), borrowed);
+        market.sync_clock(grace_seconds as i64).unwrap();
+        assert_eq!(market.supply_vault().total_borrow().unwrap(), borrowed);
+        assert_eq!(
+            market.supply_vault().last_update_unix_timestamp(),
+            grace_seconds as i64
+        );
+        // interest accrues once the grace period is over, as if the market started then
+        market.sync_clock(2 * grace_seconds as i64).unwrap();
+        without_grace.sync_clock(grace_seconds as i64).unwrap();
+        assert!(market.supply_vault().total_borrow().unwrap() > borrowed);
+        assert_eq!(
+            market.supply_vault().total_borrow().unwrap(),
+            without_grace.supply_vault().total_borrow().unwrap()
+        );
+    }
+
+    #[test]
+    pub fn borrow_keeps_min_liquidity_buffer_withdrawable() {
+        let buffer = USDC(2_000.);
+        let mut market = create_empty_btc_usdc_market();
+        market
+            .config_mut()
+            .update_max_utilisation_rate(IFixedPoint::lit("0.99"))
+            .unwrap();
+        market
+            .config_mut()
+            .update_min_liquidity_buffer_atoms(buffer);
+        let mut supplier = SupplyPosition::default();
+        let mut borrow_position = BorrowPosition::default();
+        let collateral_oracle = default_btc_oracle_rate();
+        let supply_oracle = default_usd_oracle_rate();
+        market.lend(&mut supplier, USDC(100_000.)).unwrap();
+        market
+            .deposit_collateral(&mut borrow_position, BTC(100.))
+            .unwrap();
+        // 98.5% utilisation is allowed by the max utilisation rate but eats into the buffer
+        assert_eq!(
+            market
+                .clone()
+                .borrow(
+                    &mut borrow_position.clone(),
+                    USDC(98_500.),
+                    &supply_oracle,
+                    &collateral_oracle,
+                )
+                .err()
+                .unwrap(),
+            LendingError::MinLiquidityBufferReached
+        );
+        market
+            .borrow(
+                &mut borrow_position,
+                USDC(100_000.) - buffer,
+                &supply_oracle,
+                &collateral_oracle,
+            )
+            .unwrap();
+        assert_eq!(
+            market
+                .clone()
+                .borrow(
+                    &mut borrow_position.clone(),
+                    1,
+                    &supply_oracle,
+                    &collateral_oracle
+                )
+                .err()
+                .unwrap(),
+            LendingError::MinLiquidityBufferReached
+        );
+        market.withdraw(&mut supplier, buffer).unwrap();
+    }
+
+    #[test]
+    pub fn borrow_requires_min_supply() {
+        let mut market = create_empty_btc_usdc_market();
+        market
+            .config_mut()
+            .update_min_supply_for_borrow_atoms(USDC(10_000.));
+        let mut supplier = SupplyPosition::default();
+        let mut borrow_position = BorrowPosition::default();
+        let collateral_oracle = default_btc_oracle_rate();
+        let supply_oracle = default_usd_oracle_rate();
+        market
+            .deposit_collateral(&mut borrow_position, BTC(1.))
+            .unwrap();
+        market.lend(&mut supplier, USDC(5_000.)).unwrap();
+        assert_eq!(
+            market
+                .borrow(
+                    &mut borrow_position.clone(),
+                    USDC(100.),
+                    &supply_oracle,
+                    &collateral_oracle,
+                )
+                .unwrap_err(),
+            LendingError::InsufficientMarketDepth
+        );
+        market.lend(&mut supplier, USDC(5_000.)).unwrap();
+        market
+            .borrow(
+                &mut borrow_position,
+                USDC(100.),
+                &supply_oracle,
+                &collateral_oracle,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    pub fn callback_program_allowlist() {
+        let mut market = create_btc_usdc_market();
+        let (allowed, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        market.check_callback_program(&other).unwrap();
+        market
+            .update_callback_program_allowlist(&[allowed])
+            .unwrap();
+        market.check_callback_program(&allowed).unwrap();
+        assert_eq!(
+            market.check_callback_program(&other).unwrap_err(),
+            LendingError::CallbackProgramNotAllowed
+        );
+        assert_eq!(
+            market
+                .update_callback_program_allowlist(
+                    &[Pubkey::new_unique(); MAX_CALLBACK_PROGRAMS + 1]
+                )
+                .unwrap_err(),
+            LendingError::InvalidInstructionArguments
+        );
+        assert_eq!(
+            market
+                .update_callback_program_allowlist(&[Pubkey::default()])
+                .unwrap_err(),
+            LendingError::InvalidInstructionArguments
+        );
+        market.update_callback_program_allowlist(&[]).unwrap();
+        market.check_callback_program(&other).unwrap();
+    }
+
+    #[test]
+    pub fn utilisation_sensitive_max_ltv() {
+        let mut market = create_btc_usdc_market();
+        let collateral_oracle = default_btc_oracle_rate();
+        let supply_oracle = default_usd_oracle_rate();
+        let max_ltv = market.config().ltv_config().max_ltv;
+        let mut above_max_ltv = market;
+        above_max_ltv
+            .update_max_ltv_at_max_utilisation(max_ltv.safe_add(IFixedPoint::lit("0.01")).unwrap())
+            .unwrap();
+        assert_eq!(
+            above_max_ltv.validate().unwrap_err(),
+            LendingError::InvalidLtvConfig
+        );
+        assert_eq!(
+            market
+                .update_max_ltv_at_max_utilisation(IFixedPoint::lit("-0.01"))
+                .unwrap_err(),
+            LendingError::InvalidLtvConfig
+        );
+        market
+            .update_max_ltv_at_max_utilisation(IFixedPoint::lit("0.4"))
+            .unwrap();
+        market.validate().unwrap();
+        // no borrow yet, the max ltv applies
+        assert_eq!(market.effective_max_ltv().unwrap(), max_ltv);
+
+        let mut whale = BorrowPosition::default();
+        market.deposit_collateral(&mut whale, BTC(300.)).unwrap();
+        let mut borrow_whale = |market: &mut Market, atoms| {
+            market
+                .borrow(&mut whale, atoms, &supply_oracle, &collateral_oracle)
+                .unwrap();
+        };
+        // half of the max utilisation rate, half of the reduction
+        borrow_whale(&mut market, USDC(4_750_000.));
+        assert_eq_float!(market.effective_max_ltv().unwrap().to_float(), 0.6, 1e-6);
+
+        let mut borrower = BorrowPosition::default();
+        market.deposit_collateral(&mut borrower, BTC(1.)).unwrap();
+        assert_eq!(
+            market
+                .clone()
+                .borrow(
+                    &mut borrower.clone(),
+                    USDC(65_000.),
+                    &supply_oracle,
+                    &collateral_oracle,
+                )
+                .unwrap_err(),
+            LendingError::MaxLtvReached
+        );
+        let mut insensitive_market = market;
+        insensitive_market
+            .update_max_ltv_at_max_utilisation(IFixedPoint::zero())
+            .unwrap();
+        assert_eq!(insensitive_market.effective_max_ltv().unwrap(), max_ltv);
+        insensitive_market
+            .borrow(
+                &mut borrower.clone(),
+                USDC(65_000.),
+                &supply_oracle,
+                &collateral_oracle,
+            )
+            .unwrap();
+
+        // close to the max utilisation rate, close to the max ltv at max utilisation
+        borrow_whale(&mut market, USDC(4_650_000.));
+        assert_eq_float!(
+            market.effective_max_ltv().unwrap().to_float(),
+            0.8 - 0.4 * 0.94 / 0.95,
+            1e-6
+        );
+    }
+
+    #[test]
+    pub fn pending_protocol_fees_are_grouped_by_supply_mint() {
+        let one_year = 365 * 24 * 3600;
+        let mut markets = [create_btc_usdc_market(), create_btc_usdc_market()];
+        for (i, market) in markets.iter_mut().enumerate() {
+            let mut borrow_position = BorrowPosition::default();
+            market
+                .deposit_collateral(&mut borrow_position, BTC(10.))
+                .unwrap();
+            market
+                .borrow(
+                    &mut borrow_position,
+                    USDC(100_000.) * (i as u64 + 1),
+                    &default_usd_oracle_rate(),
+                    &default_btc_oracle_rate(),
+                )
+                .unwrap();
+        }
+        let pending = markets.map(|market| {
+            market
+                .preview_supply_vault_summary(one_year)
+                .unwrap()
+                .pending_protocol_fee_atoms
+        });
+        assert!(pending[0] > 0);
+        assert!(pending[1] > pending[0]);
+        // previewing does not mutate the market
+        assert_eq!(
+            markets[0]
+                .supply_vault
+                .get_summary()
+                .unwrap()
+                .pending_protocol_fee_atoms,
+            0
+        );
+
+        let fees = Market::pending_protocol_fees_by_mint(
+            [&markets[0], &markets[1], &markets[0]],
+            one_year,
+        )
+        .unwrap();
+        assert_eq!(fees.len(), 2);
+        assert_eq!(fees[markets[0].supply_vault.mint()], 2 * pending[0]);
+        assert_eq!(fees[markets[1].supply_vault.mint()], pending[1]);
+    }
+
+    #[test]
+    pub fn protocol_revenue_is_tracked_per_market() {
+        let one_year = 365 * 24 * 3600;
+        let mut markets = [create_btc_usdc_market(), create_btc_usdc_market()];
+        for (i, market) in markets.iter_mut().enumerate() {
+            let mut borrow_position = BorrowPosition::default();
+            market
+                .deposit_collateral(&mut borrow_position, BTC(10.))
+                .unwrap();
+            market
+                .borrow(
+                    &mut borrow_position,
+                    USDC(100_000.) * (i as u64 + 1),
+                    &default_usd_oracle_rate(),
+                    &default_btc_oracle_rate(),
+                )
+                .unwrap();
+        }
+        let keys = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let revenue =
+            Market::protocol_revenue_by_market(keys.iter().copied().zip(markets.iter()), one_year)
+                .unwrap();
+        let pending = markets.map(|market| {
+            market
+                .preview_supply_vault_summary(one_year)
+                .unwrap()
+                .pending_protocol_fee_atoms
+        });
+        assert_eq!(
+            revenue,
+            vec![
+                MarketProtocolRevenue {
+                    market: keys[0],
+                    supply_token: markets[0].supply_token_info(),
+                    fee_atoms: pending[0],
+                },
+                MarketProtocolRevenue {
+                    market: keys[1],
+                    supply_token: markets[1].supply_token_info(),
+                    fee_atoms: pending[1],
+                },
+            ]
+        );
+        assert!(pending[0] > 0);
+        assert!(pending[1] > pending[0]);
+
+        // redeeming the fees does not reduce the revenue of the market
+        markets[0].sync_clock(one_year).unwrap();
+        assert_eq!(markets[0].redeem_protocol_fees().unwrap(), pending[0]);
+        let revenue =
+            Market::protocol_revenue_by_market([(keys[0], &markets[0])], 2 * one_year).unwrap();
+        assert!(revenue[0].fee_atoms > pending[0]);
+        assert_eq!(
+            revenue[0].fee_atoms,
+            pending[0]
+                + markets[0]
+                    .preview_supply_vault_summary(2 * one_year)
+                    .unwrap()
+                    .pending_protocol_fee_atoms
+        );
+    }
+
+    #[test]
+    pub fn markets_needing_sync_are_older_than_staleness() {
+        let now = 1_000_000;
+        let last_updates = [now - 10, now - 3600, now - 60, now - 61, now];
+        let markets = last_updates.map(|last_update| {
+            let mut market = create_btc_usdc_market();
+            market.sync_clock(last_update).unwrap();
+            (Pubkey::new_unique(), market)
+        });
+        let stale = Market::markets_needing_sync(
+            markets.iter().map(|(key, market)| (*key, market)),
+            now,
+            60,
+        );
+        assert_eq!(stale, vec![markets[1].0, markets[3].0]);
+        assert!(Market::markets_needing_sync(
+            markets.iter().map(|(key, market)| (*key, market)),
+            now,
+            3600
+        )
+        .is_empty());
+        assert!(!markets[0].1.needs_sync(now - 100, 0));
+    }
+
     #[test]
     pub fn liquidation_reduces_ltv() {
         let mut market = create_btc_usdc_market();
@@ -1164,7 +2181,10 @@ pub mod tests {
     #[test]
     pub fn max_supply_limit_enforced() {
         let mut market = create_empty_btc_usdc_market();
-        market.config_mut().update_max_supply_atoms(USDC(100_000.));
+        market
+            .config_mut()
+            .update_max_supply_atoms(USDC(100_000.))
+            .unwrap();
         let mut supplier = SupplyPosition::default();
         market.lend(&mut supplier, USDC(100_000.)).unwrap();
         let mut supplier_two = SupplyPosition::default();
@@ -1174,6 +2194,45 @@ pub mod tests {
         );
     }
 
+    #[test]
+    pub fn max_supply_ramps_down_linearly() {
+        let mut market = create_empty_btc_usdc_market();
+        market
+            .config_mut()
+            .update_max_supply_atoms(USDC(100_000.))
+            .unwrap();
+        let mut supplier = SupplyPosition::default();
+        market.lend(&mut supplier, USDC(40_000.)).unwrap();
+        let max_supply_before = market.max_supply_atoms(0);
+        market
+            .config_mut()
+            .update_max_supply_atoms(USDC(50_000.))
+            .unwrap();
+        market.ramp_max_supply(max_supply_before, 100, 0);
+        assert_eq!(market.max_supply_atoms(0), USDC(100_000.));
+        assert_eq!(market.max_supply_atoms(50), USDC(75_000.));
+        assert_eq!(market.max_supply_atoms(100), USDC(50_000.));
+        assert_eq!(market.max_supply_atoms(1_000), USDC(50_000.));
+        // deposits are capped by the interpolated value
+        market.sync_clock(50).unwrap();
+        let mut supplier_two = SupplyPosition::default();
+        assert_eq!(
+            market
+                .clone()
+                .lend(&mut supplier_two, USDC(40_000.))
+                .err()
+                .unwrap(),
+            LendingError::MaxSupplyReached
+        );
+        market.lend(&mut supplier_two, USDC(30_000.)).unwrap();
+        // an update without ramp applies at once
+        market.ramp_max_supply(market.max_supply_atoms(50), 0, 50);
+        assert_eq!(market.max_supply_atoms(50), USDC(50_000.));
+        // the ramp also raises the cap gradually
+        let ramp = MaxSupplyRamp::new(USDC(50_000.), 0, 10);
+        assert_eq!(ramp.max_supply_atoms(USDC(150_000.), 5), USDC(100_000.));
+    }
+
     #[test]
     pub fn position_health_with_price_drop() {
         let mut market = create_btc_usdc_market();
@@ -1268,6 +2327,59 @@ pub mod tests {
         assert!(supplier.shares().is_zero());
     }
 
+    #[test]
+    pub fn max_withdrawable_supply_is_always_withdrawable() {
+        let collateral_oracle = default_btc_oracle_rate();
+        let supply_oracle = default_usd_oracle_rate();
+        for deposit in [1, 7, 999, USDC(0.33), USDC(1_234.567891), USDC(1_000_000.)] {
+            for borrow_percent in [0, 13, 50, 94] {
+                for elapsed in [0, 1, 3_601, 86_399, 31_536_001] {
+                    let mut market = create_empty_btc_usdc_market();
+                    let mut suppliers = [SupplyPosition::default(); 3];
+                    for (i, supplier) in suppliers.iter_mut().enumerate() {
+                        market
+                            .lend(supplier, deposit * (i as u64 + 1) + i as u64)
+                            .unwrap();
+                    }
+                    let mut borrow_position = BorrowPosition::default();
+                    market
+                        .deposit_collateral(&mut borrow_position, BTC(100.))
+                        .unwrap();
+                    let borrowed =
+                        market.supply_vault.total_supply().unwrap() * borrow_percent / 100;
+                    if borrowed > 0 {
+                        market
+                            .borrow(
+                                &mut borrow_position,
+                                borrowed,
+                                &supply_oracle,
+                                &collateral_oracle,
+                            )
+                            .unwrap();
+                    }
+                    market.sync_clock(elapsed).unwrap();
+                    if elapsed % 2 == 1 {
+                        market.donate_supply_atoms(deposit / 3 + 1).unwrap();
+                    }
+                    for supplier in suppliers.iter_mut() {
+                        let max = market.max_withdrawable_supply(supplier).unwrap();
+                        let value = market.supply_position_info(supplier).unwrap();
+                        assert!(max <= value);
+                        if max < value {
+                            assert!(market
+                                .clone()
+                                .withdraw(&mut supplier.clone(), max + 1)
+                                .is_err());
+                        }
+                        if max > 0 {
+                            market.withdraw(supplier, max).unwrap();
+                        }
+                    }
+                }
+            }
+        }
+    }
+
     #[test]
     pub fn repay_all_clears_debt() {
         let mut market = create_btc_usdc_market();
@@ -1442,6 +2554,28 @@ pub mod tests {
                 // Repay should always succeed for amounts <= borrowed
                 prop_assert!(market.repay(&mut borrow_position, repay_amount).is_ok());
             }
+
+            #[test]
+            fn liquidation_always_decreases_total_borrow(
+                supply_price_in_bps in 23_000u64..100_000u64,
+                supply_confidence_in_bps in 0u64..100u64,
+                max_repay in 1u64..100_000_000_000u64,
+            ) {
+                let mut market = create_btc_usdc_market();
+                let mut borrow_position = BorrowPosition::default();
+                let collateral_oracle = default_btc_oracle_rate();
+                market.deposit_collateral(&mut borrow_position, BTC(0.5)).unwrap();
+                market.borrow(&mut borrow_position, USDC(20_000.), &default_usd_oracle_rate(), &collateral_oracle).unwrap();
+                let supply_oracle = OracleRate::new(
+                    IFixedPoint::from_i64_u64_ratio(supply_price_in_bps as i64, 10_000),
+                    IFixedPoint::from_i64_u64_ratio(supply_confidence_in_bps as i64, 10_000),
+                );
+                let total_borrow_before = market.supply_vault.total_borrow().unwrap();
+                match market.liquidate(&mut borrow_position, &collateral_oracle, &supply_oracle, max_repay) {
+                    Ok(_) => prop_assert!(market.supply_vault.total_borrow().unwrap() < total_borrow_before),
+                    Err(err) => prop_assert_ne!(err.error, LendingError::LiquidationInvariantViolated),
+                }
+            }
         }
     }
 }
@@ -5,13 +5,15 @@ use bytemuck::{Pod, Zeroable};
 use crate::{
     error::{LendingError, LendingResult, LendingResultExt},
     math::{
-        bps::{bps_to_fixed_point, percent_to_bps},
+        bps::{bps_to_fixed_point, percent_to_bps, Bps, ONE_IN_BPS},
         ifixed_point::IFixedPoint,
         safe_math::SafeMath,
         ufixed_point::UFixedPoint,
     },
-    padding::Padding,
-    state::global_config::GlobalConfig,
+    state::{
+        global_config::GlobalConfig,
+        version::{VersionedAccount, CURRENT_ACCOUNT_VERSION},
+    },
 };
 
 crate::validate_struct!(MarketConfig, 192);
@@ -26,12 +28,17 @@ crate::validate_struct!(MarketConfig, 192);
 pub struct MarketConfig {
     bump: [u8; 1],
     index: [u8; 1],
-    pad_1: Padding<2>,
+    /// Layout version of the market account, see [super::version]
+    version: u8,
+    /// Non zero when the curator disabled supply donations, zero so that markets
+    /// created before the flag keep accepting them
+    donations_disabled: u8,
     /// Total fee in bps which is charged on lending
     lending_market_fee_in_bps: u16,
     /// Share of the fee which is sent to the protocol
     protocol_fee_share_in_bps: u16,
-    /// Curator of the market, can update the market config
+    /// Curator of the market, can update the market config.
+    /// Fixed at creation, markets have no curator handover
     curator: Pubkey,
     /// Loan to value configuration of the market
     ltv_config: LtvConfig,
@@ -40,7 +47,31 @@ pub struct MarketConfig {
     max_utilisation_rate: IFixedPoint,
     /// Maximum atoms which can be supplied to the market
     max_supply_atoms: u64,
-    pad_2: Padding<80>,
+    /// Unix timestamp at which the market was created
+    created_at_unix_timestamp: i64,
+    /// Atoms of supply which must remain available in the vault after a borrow
+    /// so that suppliers can always withdraw
+    min_liquidity_buffer_atoms: u64,
+    /// Seconds after the market creation during which no interest accrues.
+    /// It applies to the whole market and not per borrow, so only borrows opened
+    /// early in the life of the market benefit from it. Only set at creation so that
+    /// interest owed to suppliers cannot be waived afterwards
+    borrow_origination_grace_seconds: u64,
+    /// Stricter LTV up to which new borrows are allowed, zero means `max_ltv`.
+    /// Only enforced on borrow so that withdrawals of existing positions are unaffected
+    borrow_cap_ltv: IFixedPoint,
+    /// Partial liquidations leaving less collateral than this are turned into a full
+    /// repayment of the debt, so that no uneconomical dust position is left to liquidate
+    min_collateral_after_liquidation_atoms: u64,
+    /// LTV from which borrowers are warned that their position nears liquidation,
+    /// zero means no warning. Only read off-chain for notifications
+    warning_ltv: IFixedPoint,
+    /// Max age of the oracles when borrowing, zero means only the oracle configs apply.
+    /// Stricter than the oracle configs so that de-risking operations tolerate some oracle lag
+    borrow_max_staleness_seconds: u64,
+    /// Atoms which must be supplied to the market before it accepts borrows, so that
+    /// brand new markets do not run with degenerate utilisation rates
+    min_supply_for_borrow_atoms: u64,
 }
 
 pub const MAX_LTV_WITH_LIQUIDATION_BONUS: IFixedPoint = IFixedPoint::lit("0.99");
@@ -49,6 +80,16 @@ pub const MIN_LIQUIDATION_BONUS: IFixedPoint = IFixedPoint::lit("0.001");
 pub const MAX_UTILISATION_RATE: IFixedPoint = IFixedPoint::lit("0.99");
 pub const MAX_LENDING_MARKET_FEE_IN_BPS: u16 = percent_to_bps(20) as u16;
 pub const FEE_PERCENT_FOR_PROTOCOL_IN_BPS: u16 = percent_to_bps(50) as u16;
+pub const MAX_BORROW_ORIGINATION_GRACE_SECONDS: u64 = 30 * 24 * 3600;
+/// Cap of the dust threshold of partial liquidations, in whole collateral tokens so that it
+/// does not depend on the decimals of the collateral mint
+pub const MAX_MIN_COLLATERAL_AFTER_LIQUIDATION_TOKENS: u64 = 1;
+
+/// [MAX_MIN_COLLATERAL_AFTER_LIQUIDATION_TOKENS] in atoms of a collateral mint with `mint_decimals`
+pub fn max_min_collateral_after_liquidation_atoms(mint_decimals: u8) -> u64 {
+    MAX_MIN_COLLATERAL_AFTER_LIQUIDATION_TOKENS
+        .saturating_mul(10u64.saturating_pow(mint_decimals as u32))
+}
 
 pub const TARGET_LTV_LIQUIDATION_MARGIN: IFixedPoint = IFixedPoint::lit("0.90");
 
@@ -93,11 +134,91 @@ impl MarketConfig {
         self.max_supply_atoms
     }
 
+    #[inline(always)]
+    pub fn min_supply_for_borrow_atoms(&self) -> u64 {
+        self.min_supply_for_borrow_atoms
+    }
+
+    #[inline(always)]
+    pub fn allow_donations(&self) -> bool {
+        self.donations_disabled == 0
+    }
+
+    #[inline(always)]
+    pub fn created_at_unix_timestamp(&self) -> i64 {
+        self.created_at_unix_timestamp
+    }
+
+    #[inline(always)]
+    pub fn min_liquidity_buffer_atoms(&self) -> u64 {
+        self.min_liquidity_buffer_atoms
+    }
+
+    #[inline(always)]
+    pub fn borrow_origination_grace_seconds(&self) -> u64 {
+        self.borrow_origination_grace_seconds
+    }
+
+    /// LTV up to which new borrows are allowed, never above `max_ltv`
+    pub fn borrow_cap_ltv(&self) -> IFixedPoint {
+        if self.borrow_cap_ltv.is_zero() {
+            self.ltv_config.max_ltv
+        } else {
+            self.borrow_cap_ltv.min(self.ltv_config.max_ltv)
+        }
+    }
+
+    /// LTV from which a position is in the warning zone, if any
+    pub fn warning_ltv(&self) -> Option<IFixedPoint> {
+        if self.warning_ltv.is_zero() {
+            None
+        } else {
+            Some(self.warning_ltv)
+        }
+    }
+
+    /// Max age of the oracles when borrowing, if any
+    pub fn borrow_max_staleness_seconds(&self) -> Option<u64> {
+        if self.borrow_max_staleness_seconds == 0 {
+            None
+        } else {
+            Some(self.borrow_max_staleness_seconds)
+        }
+    }
+
+    #[inline(always)]
+    pub fn min_collateral_after_liquidation_atoms(&self) -> u64 {
+        self.min_collateral_after_liquidation_atoms
+    }
+
+    /// Unix timestamp from which interest starts accruing
+    pub fn interest_accrual_start_unix_timestamp(&self) -> i64 {
+        self.created_at_unix_timestamp.saturating_add(
+            i64::try_from(self.borrow_origination_grace_seconds).unwrap_or(i64::MAX),
+        )
+    }
+
+    /// Split of the interest paid by borrowers between the protocol, the curator
+    /// and the suppliers
+    pub fn fee_breakdown(&self) -> FeeBreakdown {
+        let total_fee_bps = self.lending_market_fee_in_bps;
+        // the protocol share is rounded down as on accrual, the curator gets the rest
+        let protocol_bps =
+            (total_fee_bps as u32 * self.protocol_fee_share_in_bps as u32 / ONE_IN_BPS) as u16;
+        FeeBreakdown {
+            total_fee_bps,
+            protocol_bps,
+            curator_bps: total_fee_bps - protocol_bps,
+            supplier_retained_bps: (ONE_IN_BPS as u16).saturating_sub(total_fee_bps),
+        }
+    }
+
     #[inline(always)]
     pub fn lending_market_fee_fixed(&self) -> UFixedPoint {
         bps_to_fixed_point(self.lending_market_fee_in_bps() as u64)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize(
         &mut self,
         bump: u8,
@@ -106,16 +227,19 @@ impl MarketConfig {
         ltv_config: &LtvConfig,
         max_utilisation_rate: IFixedPoint,
         max_supply_atoms: u64,
-        lending_market_fee_in_bps: u16,
+        lending_market_fee: Bps,
         global_config: &GlobalConfig,
+        unix_timestamp: i64,
     ) -> LendingResult {
         self.update_ltv(ltv_config)?;
         self.update_max_utilisation_rate(max_utilisation_rate)?;
-        self.set_lending_market_fee(lending_market_fee_in_bps)?;
+        self.set_lending_market_fee(lending_market_fee)?;
+        self.update_max_supply_atoms(max_supply_atoms)?;
         self.bump = [bump];
         self.index = [index];
         self.curator = *curator;
-        self.max_supply_atoms = max_supply_atoms;
+        self.created_at_unix_timestamp = unix_timestamp;
+        self.version = CURRENT_ACCOUNT_VERSION;
         self.sync_global_config(global_config);
         Ok(())
     }
@@ -124,15 +248,88 @@ impl MarketConfig {
         self.protocol_fee_share_in_bps = global_config.protocol_fee_share_in_bps();
     }
 
-    pub fn update_max_supply_atoms(&mut self, max_supply_atoms: u64) {
+    /// A zero cap would reject every deposit, `u64::MAX` is used for an uncapped market
+    pub fn update_max_supply_atoms(&mut self, max_supply_atoms: u64) -> LendingResult {
+        if max_supply_atoms == 0 {
+            return Err(LendingError::InvalidMaxSupply.into())
+                .with_msg("max supply must be greater than zero");
+        }
         self.max_supply_atoms = max_supply_atoms;
+        Ok(())
+    }
+
+    pub fn update_min_supply_for_borrow_atoms(&mut self, min_supply_for_borrow_atoms: u64) {
+        self.min_supply_for_borrow_atoms = min_supply_for_borrow_atoms;
+    }
+
+    pub fn update_allow_donations(&mut self, allow_donations: bool) {
+        self.donations_disabled = u8::from(!allow_donations);
+    }
+
+    pub fn update_min_liquidity_buffer_atoms(&mut self, min_liquidity_buffer_atoms: u64) {
+        self.min_liquidity_buffer_atoms = min_liquidity_buffer_atoms;
     }
 
-    pub fn set_lending_market_fee(&mut self, lending_market_fee_in_bps: u16) -> LendingResult {
-        if lending_market_fee_in_bps > MAX_LENDING_MARKET_FEE_IN_BPS {
+    pub fn update_borrow_max_staleness_seconds(&mut self, seconds: u64) {
+        self.borrow_max_staleness_seconds = seconds;
+    }
+
+    pub fn update_min_collateral_after_liquidation_atoms(&mut self, atoms: u64) {
+        self.min_collateral_after_liquidation_atoms = atoms;
+    }
+
+    /// Only to be called when creating the market, see [MarketConfig::validate] for the cap
+    pub fn set_borrow_origination_grace_seconds(&mut self, grace_seconds: u64) {
+        self.borrow_origination_grace_seconds = grace_seconds;
+    }
+
+    /// The cap is checked against `max_ltv` in [`MarketConfig::validate`] so that both
+    /// can be updated together
+    pub fn update_borrow_cap_ltv(&mut self, borrow_cap_ltv: IFixedPoint) -> LendingResult {
+        if borrow_cap_ltv.is_negative() {
+            return Err(LendingError::InvalidLtvConfig.into())
+                .with_msg("borrow cap ltv must be non-negative");
+        }
+        self.borrow_cap_ltv = borrow_cap_ltv;
+        Ok(())
+    }
+
+    /// Sets the warning LTV, zero disables the warning zone
+    pub fn update_warning_ltv(&mut self, warning_ltv: IFixedPoint) -> LendingResult {
+        if warning_ltv.is_negative() {
+            return Err(LendingError::InvalidLtvConfig.into())
+                .with_msg("warning ltv must be non-negative");
+        }
+        self.warning_ltv = warning_ltv;
+        Ok(())
+    }
+
+    /// Checks the constraints spanning several fields, to be run once all the
+    /// fields of an update have been applied
+    pub fn validate(&self) -> LendingResult {
+        if self.borrow_cap_ltv > self.ltv_config.max_ltv {
+            return Err(LendingError::InvalidLtvConfig.into())
+                .with_msg("borrow cap ltv must not exceed max ltv");
+        }
+        if let Some(warning_ltv) = self.warning_ltv() {
+            if warning_ltv < self.ltv_config.max_ltv || warning_ltv >= self.ltv_config.unhealthy_ltv
+            {
+                return Err(LendingError::InvalidLtvConfig.into())
+                    .with_msg("warning ltv must be between max ltv and unhealthy ltv");
+            }
+        }
+        if self.borrow_origination_grace_seconds > MAX_BORROW_ORIGINATION_GRACE_SECONDS {
+            return Err(LendingError::InvalidInstructionArguments.into())
+                .with_msg("borrow origination grace period is longer than 30 days");
+        }
+        Ok(())
+    }
+
+    pub fn set_lending_market_fee(&mut self, lending_market_fee: Bps) -> LendingResult {
+        if lending_market_fee.get() > MAX_LENDING_MARKET_FEE_IN_BPS {
             return Err(LendingError::FeeTooHigh.into());
         }
-        self.lending_market_fee_in_bps = lending_market_fee_in_bps;
+        self.lending_market_fee_in_bps = lending_market_fee.get();
         Ok(())
     }
 
@@ -148,42 +345,73 @@ impl MarketConfig {
     }
 
     pub fn update_ltv(&mut self, ltv_config: &LtvConfig) -> LendingResult {
-        if ltv_config.max_ltv.is_negative()
-            || ltv_config.unhealthy_ltv.is_negative()
-            || ltv_config.liquidation_bonus.is_negative()
-        {
-            return Err(LendingError::InvalidLtvConfig.into())
-                .with_msg("ltv values must be non-negative");
-        }
         // cant reduce the unhealthy ltv, it could make some positions immediately unhealthy
         if self.ltv_config.unhealthy_ltv > ltv_config.unhealthy_ltv {
             return Err(LendingError::InvalidLtvConfig.into())
                 .with_msg("cannot reduce unhealthy ltv");
         }
-        // cant have max ltv greater than unhealthy ltv
-        if ltv_config.max_ltv >= ltv_config.unhealthy_ltv {
-            return Err(LendingError::InvalidLtvConfig.into())
-                .with_msg("max ltv must be less than unhealthy ltv");
+        ltv_config.validate()?;
+        self.ltv_config = *ltv_config;
+        Ok(())
+    }
+}
+
+/// Share of the borrow interest, in bps, going to each party of a market
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "client",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct FeeBreakdown {
+    /// Fee taken on the interest, split between the protocol and the curator
+    pub total_fee_bps: u16,
+    pub protocol_bps: u16,
+    pub curator_bps: u16,
+    /// Share of the interest left to the suppliers
+    pub supplier_retained_bps: u16,
+}
+
+/// Linear ramp of the max supply from `start_atoms` at `start_unix_timestamp` to the
+/// configured max supply at `end_unix_timestamp`, so that lowering the cap does not
+/// block deposits abruptly. A zeroed ramp is over and the configured max supply applies
+#[repr(C)]
+#[derive(
+    Debug, Clone, Copy, Pod, Zeroable, BorshSerialize, BorshDeserialize, PartialEq, Eq, Default,
+)]
+#[cfg_attr(
+    feature = "client",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct MaxSupplyRamp {
+    pub start_atoms: u64,
+    pub start_unix_timestamp: i64,
+    pub end_unix_timestamp: i64,
+}
+
+impl MaxSupplyRamp {
+    pub fn new(start_atoms: u64, start_unix_timestamp: i64, ramp_seconds: u64) -> Self {
+        Self {
+            start_atoms,
+            start_unix_timestamp,
+            end_unix_timestamp: start_unix_timestamp.saturating_add_unsigned(ramp_seconds),
         }
-        // cant have liquidation bonus greater than 10% or lower than 0.5%
-        if ltv_config.liquidation_bonus > MAX_LIQUIDATION_BONUS
-            || ltv_config.liquidation_bonus < MIN_LIQUIDATION_BONUS
-        {
-            return Err(LendingError::InvalidLtvConfig.into())
-                .with_msg("liquidation bonus out of range");
+    }
+
+    /// Max supply at `unix_timestamp` when ramping towards `target_atoms`
+    pub fn max_supply_atoms(&self, target_atoms: u64, unix_timestamp: i64) -> u64 {
+        if unix_timestamp >= self.end_unix_timestamp {
+            return target_atoms;
         }
-        let one_plus_liquidation_bonus =
-            IFixedPoint::one().safe_add(ltv_config.liquidation_bonus)?;
-        let unhealthy_ltv_with_liquidation_bonus = ltv_config
-            .unhealthy_ltv
-            .safe_mul(one_plus_liquidation_bonus)?;
-        // we must ensure that there is enough margin for liquidation bonus
-        if unhealthy_ltv_with_liquidation_bonus > MAX_LTV_WITH_LIQUIDATION_BONUS {
-            return Err(LendingError::InvalidLtvConfig.into())
-                .with_msg("insufficient margin for liquidation bonus");
+        if unix_timestamp <= self.start_unix_timestamp {
+            return self.start_atoms;
         }
-        self.ltv_config = *ltv_config;
-        Ok(())
+        let elapsed = (unix_timestamp - self.start_unix_timestamp) as i128;
+        let duration = (self.end_unix_timestamp - self.start_unix_timestamp) as i128;
+        let start = self.start_atoms as i128;
+        let delta = target_atoms as i128 - start;
+        (start + delta * elapsed / duration) as u64
     }
 }
 
@@ -216,6 +444,44 @@ pub struct LtvConfig {
 }
 
 impl LtvConfig {
+    /// Value which can be borrowed per unit of collateral value, i.e. the max LTV
+    #[inline(always)]
+    pub fn collateral_factor(&self) -> IFixedPoint {
+        self.max_ltv
+    }
+
+    /// Checks the config is consistent on its own, whatever the market it is applied to
+    pub fn validate(&self) -> LendingResult {
+        if self.max_ltv.is_negative()
+            || self.unhealthy_ltv.is_negative()
+            || self.liquidation_bonus.is_negative()
+        {
+            return Err(LendingError::InvalidLtvConfig.into())
+                .with_msg("ltv values must be non-negative");
+        }
+        // cant have max ltv greater than unhealthy ltv
+        if self.max_ltv >= self.unhealthy_ltv {
+            return Err(LendingError::InvalidLtvConfig.into())
+                .with_msg("max ltv must be less than unhealthy ltv");
+        }
+        // cant have liquidation bonus greater than 10% or lower than 0.5%
+        if self.liquidation_bonus > MAX_LIQUIDATION_BONUS
+            || self.liquidation_bonus < MIN_LIQUIDATION_BONUS
+        {
+            return Err(LendingError::InvalidLtvConfig.into())
+                .with_msg("liquidation bonus out of range");
+        }
+        let one_plus_liquidation_bonus = IFixedPoint::one().safe_add(self.liquidation_bonus)?;
+        let unhealthy_ltv_with_liquidation_bonus =
+            self.unhealthy_ltv.safe_mul(one_plus_liquidation_bonus)?;
+        // we must ensure that there is enough margin for liquidation bonus
+        if unhealthy_ltv_with_liquidation_bonus > MAX_LTV_WITH_LIQUIDATION_BONUS {
+            return Err(LendingError::InvalidLtvConfig.into())
+                .with_msg("insufficient margin for liquidation bonus");
+        }
+        Ok(())
+    }
+
     pub fn target_ltv_after_liquidation(&self) -> IFixedPoint {
         self.unhealthy_ltv
             .safe_mul(TARGET_LTV_LIQUIDATION_MARGIN)
@@ -224,6 +490,20 @@ impl LtvConfig {
     }
 }
 
+impl VersionedAccount for MarketConfig {
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+
+    fn migrate_from(&mut self, _version: u8) -> LendingResult {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
@@ -233,7 +513,8 @@ pub mod tests {
         MarketConfig {
             bump: [0; 1],
             index: [0; 1],
-            pad_1: Padding::default(),
+            version: CURRENT_ACCOUNT_VERSION,
+            donations_disabled: 0,
             curator: Pubkey::new_unique(),
             ltv_config: LtvConfig {
                 max_ltv: IFixedPoint::from(0.8),
@@ -244,10 +525,41 @@ pub mod tests {
             lending_market_fee_in_bps: percent_to_bps(10) as u16,
             protocol_fee_share_in_bps: percent_to_bps(50) as u16,
             max_supply_atoms: u64::MAX,
-            pad_2: Padding::default(),
+            created_at_unix_timestamp: 0,
+            min_liquidity_buffer_atoms: 0,
+            borrow_origination_grace_seconds: 0,
+            borrow_cap_ltv: IFixedPoint::zero(),
+            min_collateral_after_liquidation_atoms: 0,
+            warning_ltv: IFixedPoint::zero(),
+            borrow_max_staleness_seconds: 0,
+            min_supply_for_borrow_atoms: 0,
         }
     }
 
+    #[test]
+    fn fee_breakdown_splits_interest() {
+        // 10% total fee with 50% going to the protocol
+        let market_config = test_config();
+        assert_eq!(
+            market_config.fee_breakdown(),
+            FeeBreakdown {
+                total_fee_bps: 1_000,
+                protocol_bps: 500,
+                curator_bps: 500,
+                supplier_retained_bps: 9_000,
+            }
+        );
+        let mut market_config = test_config();
+        market_config.protocol_fee_share_in_bps = 3_333;
+        let breakdown = market_config.fee_breakdown();
+        assert_eq!(breakdown.protocol_bps, 333);
+        assert_eq!(breakdown.curator_bps, 667);
+        assert_eq!(
+            breakdown.protocol_bps + breakdown.curator_bps + breakdown.supplier_retained_bps,
+            ONE_IN_BPS as u16
+        );
+    }
+
     #[test]
     fn test_initialization() {
         let mut market_config = MarketConfig::default();
@@ -259,7 +571,7 @@ pub mod tests {
         };
         let max_utilisation_rate = IFixedPoint::from(0.95);
         let max_supply_atoms = 1_000_000;
-        let lending_market_fee_in_bps = percent_to_bps(10) as u16;
+        let lending_market_fee = Bps::from_percent(10);
         let global_config = test_global_config();
 
         let result = market_config.initialize(
@@ -269,11 +581,13 @@ pub mod tests {
             &ltv_config,
             max_utilisation_rate,
             max_supply_atoms,
-            lending_market_fee_in_bps,
+            lending_market_fee,
             &global_config,
+            1_700_000_000,
         );
 
         assert!(result.is_ok());
+        assert_eq!(market_config.created_at_unix_timestamp(), 1_700_000_000);
         assert_eq!(market_config.bump(), &[1]);
         assert_eq!(market_config.curator(), &curator);
         assert_eq!(market_config.ltv_config(), &ltv_config);
@@ -281,7 +595,7 @@ pub mod tests {
         assert_eq!(market_config.max_supply_atoms(), max_supply_atoms);
         assert_eq!(
             market_config.lending_market_fee_in_bps(),
-            lending_market_fee_in_bps
+            lending_market_fee.get()
         );
         assert_eq!(
             market_config.protocol_fee_in_bps(),
@@ -289,18 +603,40 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn initialization_rejects_zero_max_supply() {
+        let mut market_config = MarketConfig::default();
+        let ltv_config = LtvConfig {
+            max_ltv: IFixedPoint::from(0.8),
+            unhealthy_ltv: IFixedPoint::from(0.9),
+            liquidation_bonus: IFixedPoint::from(0.05),
+        };
+        let result = market_config.initialize(
+            1,
+            0,
+            &Pubkey::new_unique(),
+            &ltv_config,
+            IFixedPoint::from(0.95),
+            0,
+            Bps::from_percent(10),
+            &test_global_config(),
+            1_700_000_000,
+        );
+        assert_eq!(result.unwrap_err(), LendingError::InvalidMaxSupply);
+    }
+
     #[test]
     fn test_set_lending_market_fee() {
         let mut market_config = test_config();
 
         // Valid fee
-        let valid_fee = percent_to_bps(15) as u16;
+        let valid_fee = Bps::from_percent(15);
         let result = market_config.set_lending_market_fee(valid_fee);
         assert!(result.is_ok());
-        assert_eq!(market_config.lending_market_fee_in_bps(), valid_fee);
+        assert_eq!(market_config.lending_market_fee_in_bps(), valid_fee.get());
 
         // Fee too high
-        let invalid_fee = MAX_LENDING_MARKET_FEE_IN_BPS + 1;
+        let invalid_fee = Bps::new(MAX_LENDING_MARKET_FEE_IN_BPS + 1);
         let result = market_config.set_lending_market_fee(invalid_fee);
         assert!(matches!(
             result.unwrap_err().error,
@@ -392,6 +728,40 @@ pub mod tests {
         ));
     }
 
+    #[test]
+    fn ltv_config_validates_standalone() {
+        let ltv = |max_ltv: f64, unhealthy_ltv: f64, liquidation_bonus: f64| LtvConfig {
+            max_ltv: IFixedPoint::from(max_ltv),
+            unhealthy_ltv: IFixedPoint::from(unhealthy_ltv),
+            liquidation_bonus: IFixedPoint::from(liquidation_bonus),
+        };
+        ltv(0.8, 0.9, 0.05).validate().unwrap();
+        ltv(0.0, 0.9, 0.001).validate().unwrap();
+        ltv(0.8, 0.9, 0.1).validate().unwrap();
+
+        let invalid = [
+            // negative values
+            ltv(-0.01, 0.9, 0.05),
+            ltv(0.5, -0.01, 0.05),
+            ltv(0.5, 0.9, -0.01),
+            // max ltv not below unhealthy ltv
+            ltv(0.9, 0.9, 0.05),
+            ltv(0.95, 0.9, 0.05),
+            // liquidation bonus out of range
+            ltv(0.8, 0.9, 0.0005),
+            ltv(0.8, 0.9, 0.15),
+            // not enough margin for the liquidation bonus
+            ltv(0.85, 0.95, 0.09),
+        ];
+        for config in invalid {
+            assert_eq!(
+                config.validate().unwrap_err(),
+                LendingError::InvalidLtvConfig,
+                "{config:?}"
+            );
+        }
+    }
+
     #[test]
     fn test_update_ltv_rejects_negative_max_ltv() {
         let mut market_config = test_config();
@@ -443,13 +813,13 @@ pub mod tests {
         let mut global_config = test_global_config();
 
         // Change the protocol fee share in global config
-        let new_fee = percent_to_bps(15) as u16;
+        let new_fee = Bps::from_percent(15);
         global_config
             .update_protocol_fee_share_in_bps(new_fee)
             .unwrap();
 
         market_config.sync_global_config(&global_config);
-        assert_eq!(market_config.protocol_fee_in_bps(), new_fee);
+        assert_eq!(market_config.protocol_fee_in_bps(), new_fee.get());
     }
 
     #[test]
@@ -457,7 +827,14 @@ pub mod tests {
         let mut market_config = test_config();
         let new_max_supply = 5_000_000;
 
-        market_config.update_max_supply_atoms(new_max_supply);
+        market_config
+            .update_max_supply_atoms(new_max_supply)
+            .unwrap();
+        assert_eq!(market_config.max_supply_atoms(), new_max_supply);
+        assert_eq!(
+            market_config.update_max_supply_atoms(0).unwrap_err(),
+            LendingError::InvalidMaxSupply
+        );
         assert_eq!(market_config.max_supply_atoms(), new_max_supply);
     }
 
@@ -3,10 +3,12 @@ use std::ops::{Deref, DerefMut};
 use arch_program::pubkey::Pubkey;
 
 use crate::{
-    error::LendingResult,
+    constant::SECONDS_PER_YEAR,
+    error::{LendingError, LendingResult, LendingResultExt},
     event::{DoubleMarketTransactionEvent, SingleMarketTransactionEvent},
+    math::{ifixed_point::IFixedPoint, safe_math::SafeMath, ufixed_point::UFixedPoint},
     operation::liquidation::LiquidationResultWithBonus,
-    oracle::{oracle_price::OracleRate, oracle_provider::{AccountView, OracleLoader}},
+    oracle::{oracle_price::OracleRate, oracle_provider::AccountView},
     state::borrow_position::LiquidationResultWithCtx,
 };
 
@@ -16,6 +18,209 @@ use super::{
     supply_position::SupplyPosition,
 };
 
+/// Standing of a single user in a market, combining its supply and borrow positions
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "client",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct UserMarketSummary {
+    pub supplied_atoms: u64,
+    pub supplied_value: IFixedPoint,
+    /// `None` if the user has no borrow position in this market
+    pub borrow_position_health: Option<BorrowPositionHealth>,
+    /// Operations the user can perform, as if with empty positions when it has none
+    pub actions: PositionActions,
+}
+
+/// Operations currently allowed for a user on a market
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "client",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct PositionActions {
+    pub can_supply: bool,
+    pub can_withdraw_supply: bool,
+    pub can_deposit_collateral: bool,
+    pub can_withdraw_collateral: bool,
+    pub can_borrow: bool,
+    pub can_repay: bool,
+    pub is_liquidatable: bool,
+}
+
+/// Seconds elapsed since each oracle of a market was last published
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "client",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct OracleAges {
+    pub supply_seconds: u64,
+    pub collateral_seconds: u64,
+}
+
+impl OracleAges {
+    pub fn load<A: Deref<Target = [u8]>, B: Deref<Target = [u8]>>(
+        market: &Market,
+        supply_oracle: Option<AccountView<A>>,
+        collateral_oracle: Option<AccountView<B>>,
+        unix_timestamp: i64,
+    ) -> LendingResult<Self> {
+        Ok(Self {
+            supply_seconds: market
+                .supply_vault()
+                .oracle_config()
+                .load_oracle_price(supply_oracle)?
+                .age(unix_timestamp)?,
+            collateral_seconds: market
+                .collateral_vault()
+                .oracle_config()
+                .load_oracle_price(collateral_oracle)?
+                .age(unix_timestamp)?,
+        })
+    }
+}
+
+/// Overall risk of a market, combining its utilisation, solvency and oracle health
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "client",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct RiskSummary {
+    pub utilisation_rate: IFixedPoint,
+    /// See [MarketWrapper::aggregate_solvency]
+    pub aggregate_solvency: IFixedPoint,
+    pub oracle_ages: OracleAges,
+    /// Number of the given positions which can currently be liquidated
+    pub liquidatable_positions: u64,
+    /// Cumulative debt socialized across the suppliers, in supply atoms
+    pub socialized_loss_atoms: u64,
+}
+
+/// Model of the yearly share of supplied value expected to be lost to socialized bad debt,
+/// used to discount the supply APY into a risk adjusted yield
+pub trait HaircutModel {
+    /// `bad_debt_value` is the debt not covered by collateral across the considered positions
+    /// and `total_supply_value` the value supplied to the market, both priced by the oracles
+    fn expected_loss_rate(&self, bad_debt_value: f64, total_supply_value: f64) -> f64;
+}
+
+/// Assumes the current bad debt is entirely socialized to the suppliers within a year
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SocializedBadDebtHaircut;
+
+impl HaircutModel for SocializedBadDebtHaircut {
+    fn expected_loss_rate(&self, bad_debt_value: f64, total_supply_value: f64) -> f64 {
+        if total_supply_value <= 0. {
+            return 0.;
+        }
+        (bad_debt_value / total_supply_value).clamp(0., 1.)
+    }
+}
+
+/// Amounts needed to bring a borrow position down to a target LTV
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "client",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct DeleverageAmounts {
+    pub withdraw_collateral_atoms: u64,
+    pub repay_atoms: u64,
+    pub repay_all: bool,
+}
+
+/// Market limit which can reject a new borrow, in the order `borrow` checks them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "client",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub enum BorrowConstraint {
+    MinSupplyDepth,
+    MaxLtv,
+    BorrowCapLtv,
+    MaxUtilisation,
+    MinLiquidityBuffer,
+}
+
+/// Atoms a position can borrow before hitting a single [BorrowConstraint]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "client",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct BorrowConstraintLimit {
+    pub constraint: BorrowConstraint,
+    pub max_borrow_atoms: u64,
+}
+
+impl BorrowConstraintLimit {
+    /// Atoms by which `borrow_atoms` goes over this limit, zero if it fits
+    pub fn excess_atoms(&self, borrow_atoms: u64) -> u64 {
+        borrow_atoms.saturating_sub(self.max_borrow_atoms)
+    }
+}
+
+/// Every limit a planned borrow is checked against, see [MarketWrapper::borrow_constraints]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "client",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct BorrowConstraintReport {
+    pub borrow_atoms: u64,
+    pub limits: [BorrowConstraintLimit; 5],
+}
+
+impl BorrowConstraintReport {
+    /// The tightest limit, i.e. the one capping how much can be borrowed
+    pub fn binding_constraint(&self) -> BorrowConstraintLimit {
+        *self
+            .limits
+            .iter()
+            .min_by_key(|limit| limit.max_borrow_atoms)
+            .expect("limits are not empty")
+    }
+
+    pub fn max_borrow_atoms(&self) -> u64 {
+        self.binding_constraint().max_borrow_atoms
+    }
+
+    /// Limits the planned borrow goes over
+    pub fn rejections(&self) -> impl Iterator<Item = &BorrowConstraintLimit> {
+        self.limits
+            .iter()
+            .filter(|limit| limit.excess_atoms(self.borrow_atoms) > 0)
+    }
+
+    pub fn is_allowed(&self) -> bool {
+        self.rejections().next().is_none()
+    }
+}
+
+/// Side of a market priced by its own oracle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "client",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub enum MarketSide {
+    Supply,
+    Collateral,
+}
+
 /// A wrapper around Market to ensure oracles are loaded and validated before any operations
 /// requiring oracles
 #[repr(C)]
@@ -34,18 +239,67 @@ pub struct MarketWrapper<M> {
 impl<M: Deref<Target = Market>> MarketWrapper<M> {
     pub fn try_new<A: Deref<Target = [u8]>, B: Deref<Target = [u8]>>(
         market: M,
-        supply_oracle: AccountView<A>,
-        collateral_oracle: AccountView<B>,
+        supply_oracle: Option<AccountView<A>>,
+        collateral_oracle: Option<AccountView<B>>,
         unix_timestamp: i64,
     ) -> LendingResult<Self> {
-        let supply_oracle = market
-            .supply_vault()
-            .oracle_config()
-            .load_and_validate_oracle_rate(supply_oracle, unix_timestamp)?;
-        let collateral_oracle = market
-            .collateral_vault()
-            .oracle_config()
-            .load_and_validate_oracle_rate(collateral_oracle, unix_timestamp)?;
+        Self::try_new_with_max_age(
+            market,
+            supply_oracle,
+            collateral_oracle,
+            unix_timestamp,
+            None,
+        )
+    }
+
+    /// Same as [MarketWrapper::try_new] with the oracles also checked against the
+    /// borrow staleness of the market, to be used by operations taking new risk
+    pub fn try_new_for_borrow<A: Deref<Target = [u8]>, B: Deref<Target = [u8]>>(
+        market: M,
+        supply_oracle: Option<AccountView<A>>,
+        collateral_oracle: Option<AccountView<B>>,
+        unix_timestamp: i64,
+    ) -> LendingResult<Self> {
+        let max_age = market.config().borrow_max_staleness_seconds();
+        Self::try_new_with_max_age(
+            market,
+            supply_oracle,
+            collateral_oracle,
+            unix_timestamp,
+            max_age,
+        )
+    }
+
+    /// Loads and validates both oracles, with their max age lowered to `max_age` if stricter
+    fn try_new_with_max_age<A: Deref<Target = [u8]>, B: Deref<Target = [u8]>>(
+        market: M,
+        supply_oracle: Option<AccountView<A>>,
+        collateral_oracle: Option<AccountView<B>>,
+        unix_timestamp: i64,
+        max_age: Option<u64>,
+    ) -> LendingResult<Self> {
+        let supply_config = market.supply_vault().oracle_config();
+        let collateral_config = market.collateral_vault().oracle_config();
+        let supply_validation = max_age.map(|max_age| {
+            supply_config
+                .validation_config()
+                .with_stricter_max_age(max_age)
+        });
+        let collateral_validation = max_age.map(|max_age| {
+            collateral_config
+                .validation_config()
+                .with_stricter_max_age(max_age)
+        });
+        let supply_oracle = supply_config.load_and_validate_oracle_rate(
+            supply_oracle,
+            unix_timestamp,
+            supply_validation.as_ref(),
+        )?;
+        let collateral_oracle = collateral_config.load_and_validate_oracle_rate(
+            collateral_oracle,
+            unix_timestamp,
+            collateral_validation.as_ref(),
+        )?;
         Ok(Self {
             market,
             supply_oracle,
@@ -56,8 +310,8 @@ impl<M: Deref<Target = Market>> MarketWrapper<M> {
     /// Load oracles without timestamp validation (skips staleness and confidence checks)
     pub fn new_unchecked<A: Deref<Target = [u8]>, B: Deref<Target = [u8]>>(
         market: M,
-        supply_oracle: AccountView<A>,
-        collateral_oracle: AccountView<B>,
+        supply_oracle: Option<AccountView<A>>,
+        collateral_oracle: Option<AccountView<B>>,
     ) -> LendingResult<Self> {
         let supply_oracle = market
             .supply_vault()
@@ -78,19 +332,15 @@ impl<M: Deref<Target = Market>> MarketWrapper<M> {
     /// Returns `(wrapper, is_stale)` where `is_stale` is true when unchecked loading was used.
     pub fn try_new_or_unchecked<A: Deref<Target = [u8]>, B: Deref<Target = [u8]>>(
         market: M,
-        supply_oracle: AccountView<A>,
-        collateral_oracle: AccountView<B>,
+        supply_oracle: Option<AccountView<A>>,
+        collateral_oracle: Option<AccountView<B>>,
         unix_timestamp: i64,
     ) -> LendingResult<(Self, bool)> {
         let supply_config = market.supply_vault().oracle_config();
         let collateral_config = market.collateral_vault().oracle_config();
 
-        let supply_unchecked = supply_config
-            .oracle_provider()
-            .load_oracle_price(supply_oracle)?;
-        let collateral_unchecked = collateral_config
-            .oracle_provider()
-            .load_oracle_price(collateral_oracle)?;
+        let supply_unchecked = supply_config.load_oracle_price(supply_oracle)?;
+        let collateral_unchecked = collateral_config.load_oracle_price(collateral_oracle)?;
 
         let supply_validated = supply_unchecked
             .validate(supply_config.validation_config(), unix_timestamp);
@@ -127,6 +377,17 @@ impl<M: Deref<Target = Market>> MarketWrapper<M> {
         &self.supply_oracle
     }
 
+    /// Replace the loaded oracle rate of one side of the market. The rate is not validated
+    /// against the oracle config, so this is only available for client side simulations
+    #[cfg(any(test, feature = "client"))]
+    pub fn with_oracle_rate(mut self, side: MarketSide, rate: OracleRate) -> Self {
+        match side {
+            MarketSide::Supply => self.supply_oracle = rate,
+            MarketSide::Collateral => self.collateral_oracle = rate,
+        }
+        self
+    }
+
     pub fn borrow_position_health(
         &self,
         borrow_position: &BorrowPosition,
@@ -138,6 +399,328 @@ impl<M: Deref<Target = Market>> MarketWrapper<M> {
         )
     }
 
+    /// Health of the position against the given oracle rates instead of the loaded ones,
+    /// to simulate price moves without mutating the wrapper
+    pub fn health_at_prices(
+        &self,
+        borrow_position: &BorrowPosition,
+        supply_price: &OracleRate,
+        collateral_price: &OracleRate,
+    ) -> LendingResult<BorrowPositionHealth> {
+        self.market
+            .borrow_position_health(borrow_position, collateral_price, supply_price)
+    }
+
+    pub fn user_market_summary(
+        &self,
+        supply_position: Option<&SupplyPosition>,
+        borrow_position: Option<&BorrowPosition>,
+    ) -> LendingResult<UserMarketSummary> {
+        let supplied_atoms = supply_position
+            .map(|position| self.market.supply_position_info(position))
+            .transpose()?
+            .unwrap_or_default();
+        let supplied_value = self
+            .supply_oracle
+            .collateral_value(supplied_atoms, self.market.supply_token_info().decimals)?;
+        let borrow_position_health = borrow_position
+            .map(|position| self.borrow_position_health(position))
+            .transpose()?;
+        let actions = self.available_actions(
+            borrow_position.unwrap_or(&BorrowPosition::default()),
+            supply_position.unwrap_or(&SupplyPosition::default()),
+        )?;
+        Ok(UserMarketSummary {
+            supplied_atoms,
+            supplied_value,
+            borrow_position_health,
+            actions,
+        })
+    }
+
+    /// Value of the pending `(curator, protocol)` fees, priced conservatively with the
+    /// supply oracle
+    pub fn pending_fees_value(&self) -> LendingResult<(IFixedPoint, IFixedPoint)> {
+        let summary = self.market.supply_vault().get_summary()?;
+        let decimals = self.market.supply_token_info().decimals;
+        Ok((
+            self.supply_oracle
+                .collateral_value(summary.pending_curator_fee_atoms, decimals)?,
+            self.supply_oracle
+                .collateral_value(summary.pending_protocol_fee_atoms, decimals)?,
+        ))
+    }
+
+    /// Ratio between the value of all the collateral deposited in the market and the value
+    /// of all its debt, priced conservatively. The market is solvent while it is above 1.
+    /// Returns `IFixedPoint::MAX` when nothing is borrowed
+    pub fn aggregate_solvency(&self) -> LendingResult<IFixedPoint> {
+        let total_borrow = self.market.supply_vault().total_borrow()?;
+        if total_borrow == 0 {
+            return Ok(IFixedPoint::MAX);
+        }
+        let debt_value = self
+            .supply_oracle
+            .borrow_value(total_borrow, self.market.supply_vault().mint_decimals())?;
+        let collateral_value = self.collateral_oracle.collateral_value(
+            self.market.collateral_vault().total_collateral_atoms(),
+            self.market.collateral_vault().mint_decimals(),
+        )?;
+        collateral_value.safe_div(debt_value).track_caller()
+    }
+
+    /// Summary of the market risk, the liquidatable count only covers the given `positions`
+    pub fn risk_summary<'a>(
+        &self,
+        positions: impl IntoIterator<Item = &'a BorrowPosition>,
+        oracle_ages: OracleAges,
+    ) -> LendingResult<RiskSummary> {
+        let unhealthy_ltv = self.market.config().ltv_config().unhealthy_ltv;
+        let mut liquidatable_positions = 0;
+        for position in positions {
+            let health = self.borrow_position_health(position)?;
+            if health.borrowed_atoms > 0 && health.ltv >= unhealthy_ltv {
+                liquidatable_positions += 1;
+            }
+        }
+        let supply_vault = self.market.supply_vault();
+        Ok(RiskSummary {
+            utilisation_rate: supply_vault.utilisation_rate()?,
+            aggregate_solvency: self.aggregate_solvency()?,
+            oracle_ages,
+            liquidatable_positions,
+            socialized_loss_atoms: supply_vault.socialized_loss_atoms(),
+        })
+    }
+
+    /// Yearly interest the current borrows pay at the last borrow rate, relative to the
+    /// cumulative debt socialized across the suppliers. Both sides are in supply atoms.
+    /// Returns `IFixedPoint::MAX` when no loss was ever socialized
+    pub fn interest_coverage(&self) -> LendingResult<IFixedPoint> {
+        let supply_vault = self.market.supply_vault();
+        let socialized_loss_atoms = supply_vault.socialized_loss_atoms();
+        if socialized_loss_atoms == 0 {
+            return Ok(IFixedPoint::MAX);
+        }
+        let summary = supply_vault.get_summary()?;
+        summary
+            .borrow_interest_rate
+            .0
+            .safe_mul(SECONDS_PER_YEAR)?
+            .safe_mul(summary.total_borrow)?
+            .safe_div(socialized_loss_atoms)
+            .track_caller()
+    }
+
+    /// Evaluate every limit borrowing `borrow_atoms` more against `borrow_position` is checked
+    /// against, without mutating the market. Limits are exact up to one atom of rounding.
+    /// The LTV limits use the max LTV in effect once `borrow_atoms` are borrowed
+    pub fn borrow_constraints(
+        &self,
+        borrow_position: &BorrowPosition,
+        borrow_atoms: u64,
+    ) -> LendingResult<BorrowConstraintReport> {
+        let market = self.market();
+        let config = market.config();
+        let supply_vault = market.supply_vault();
+        let total_supply = supply_vault.total_supply()?;
+        let health = self.borrow_position_health(borrow_position)?;
+        let ltv_limit = |max_ltv: IFixedPoint| -> LendingResult<u64> {
+            let borrowing_power = health.borrowing_power(max_ltv)?;
+            if borrowing_power.is_negative() {
+                return Ok(0);
+            }
+            self.supply_oracle
+                .borrow_atoms(borrowing_power, market.supply_token_info().decimals)?
+                .as_u64_rounded_down()
+        };
+        let min_supply_depth = if total_supply < config.min_supply_for_borrow_atoms() {
+            0
+        } else {
+            u64::MAX
+        };
+        let available_atoms = total_supply.saturating_sub(supply_vault.total_borrow()?);
+        let limit = |constraint, max_borrow_atoms| BorrowConstraintLimit {
+            constraint,
+            max_borrow_atoms,
+        };
+        Ok(BorrowConstraintReport {
+            borrow_atoms,
+            limits: [
+                limit(BorrowConstraint::MinSupplyDepth, min_supply_depth),
+                limit(
+                    BorrowConstraint::MaxLtv,
+                    ltv_limit(market.effective_max_ltv_after_borrow(borrow_atoms)?)?,
+                ),
+                limit(
+                    BorrowConstraint::BorrowCapLtv,
+                    ltv_limit(config.borrow_cap_ltv())?,
+                ),
+                limit(
+                    BorrowConstraint::MaxUtilisation,
+                    market.borrowable_atoms_within_utilisation_cap()?,
+                ),
+                limit(
+                    BorrowConstraint::MinLiquidityBuffer,
+                    available_atoms.saturating_sub(config.min_liquidity_buffer_atoms()),
+                ),
+            ],
+        })
+    }
+
+    /// Borrow APR in percent the interest rate curve gives at the current utilisation,
+    /// which is what the next `sync_clock` applies rather than the last applied rate
+    pub fn current_borrow_apr(&self) -> LendingResult<f64> {
+        let rate = self.market.supply_vault().current_borrow_interest_rate()?;
+        Ok(rate.approximate_apr()? * 100.)
+    }
+
+    /// Estimated seconds before `borrow_position` becomes liquidatable at current oracle prices,
+    /// with its debt compounding at the last borrow rate of the market.
+    /// `Some(0)` if it is already liquidatable and `None` if it never becomes liquidatable
+    pub fn seconds_until_liquidation(
+        &self,
+        borrow_position: &BorrowPosition,
+    ) -> LendingResult<Option<u64>> {
+        let health = self.borrow_position_health(borrow_position)?;
+        if health.borrowed_atoms == 0 {
+            return Ok(None);
+        }
+        let unhealthy_ltv = self.market.config().ltv_config().unhealthy_ltv;
+        if health.ltv >= unhealthy_ltv {
+            return Ok(Some(0));
+        }
+        let rate_per_second = self.market.supply_vault().last_borrow_interest_rate().0;
+        if rate_per_second <= IFixedPoint::zero() {
+            return Ok(None);
+        }
+        // ltv * exp(rate * t) = unhealthy_ltv
+        let seconds =
+            (unhealthy_ltv.to_float() / health.ltv.to_float()).ln() / rate_per_second.to_float();
+        if !seconds.is_finite() {
+            return Ok(None);
+        }
+        Ok(Some(seconds.ceil() as u64))
+    }
+
+    /// Collateral price, net of the oracle confidence, from which the debt of `borrow_position`
+    /// exceeds its collateral value and the position can be socialized. Zero without debt
+    pub fn socialization_collateral_price(
+        &self,
+        borrow_position: &BorrowPosition,
+    ) -> LendingResult<IFixedPoint> {
+        let health = self.borrow_position_health(borrow_position)?;
+        if health.borrowed_atoms == 0 {
+            return Ok(IFixedPoint::zero());
+        }
+        // ltv is linear in the inverse of the collateral price, so ltv reaches 1 at price * ltv
+        self.collateral_oracle
+            .lower_bound_rate()?
+            .safe_mul(health.ltv)
+    }
+
+    /// Supply APY discounted by the loss rate `haircut_model` expects from the bad debt of
+    /// `positions`, i.e. the debt value they hold above their collateral value
+    pub fn supply_apy_after_haircut<'a>(
+        &self,
+        positions: impl IntoIterator<Item = &'a BorrowPosition>,
+        haircut_model: &impl HaircutModel,
+    ) -> LendingResult<f64> {
+        let summary = self.market.supply_vault().get_summary()?;
+        let gross_apy = summary.lending_interest_rate.approximate_apy()?;
+        let mut bad_debt_value = 0.;
+        for position in positions {
+            let health = self.borrow_position_health(position)?;
+            if health.borrow_value > health.collateral_value {
+                bad_debt_value += health
+                    .borrow_value
+                    .safe_sub(health.collateral_value)?
+                    .to_float();
+            }
+        }
+        let total_supply_value = self
+            .supply_oracle
+            .collateral_value(
+                summary.total_supply,
+                self.market.supply_token_info().decimals,
+            )?
+            .to_float();
+        let loss_rate = haircut_model.expected_loss_rate(bad_debt_value, total_supply_value);
+        Ok((1. + gross_apy) * (1. - loss_rate) - 1.)
+    }
+
+    /// Which operations the user can currently perform on this market, useful to
+    /// drive UI states without re-implementing the on-chain checks. Withdrawing supply and
+    /// borrowing are allowed when [Market::max_withdrawable_supply] and
+    /// [Self::borrow_constraints] leave at least one atom to withdraw or borrow
+    pub fn available_actions(
+        &self,
+        borrow_position: &BorrowPosition,
+        supply_position: &SupplyPosition,
+    ) -> LendingResult<PositionActions> {
+        let market = self.market();
+        let config = market.config();
+        let supply_vault_summary = market.supply_vault().get_summary()?;
+        let health = self.borrow_position_health(borrow_position)?;
+        let has_debt = health.borrowed_atoms > 0;
+        let below_max_ltv = health.ltv < market.effective_max_ltv()?;
+        Ok(PositionActions {
+            can_supply: supply_vault_summary.total_supply
+                < market.max_supply_atoms(supply_vault_summary.last_update_unix_timestamp),
+            can_withdraw_supply: market.max_withdrawable_supply(supply_position)? > 0,
+            can_deposit_collateral: true,
+            can_withdraw_collateral: health.collateral_atoms > 0 && below_max_ltv,
+            can_borrow: self
+                .borrow_constraints(borrow_position, 0)?
+                .max_borrow_atoms()
+                > 0,
+            can_repay: has_debt,
+            is_liquidatable: has_debt && health.ltv >= config.ltv_config().unhealthy_ltv,
+        })
+    }
+
+    /// Computes the collateral to withdraw and the debt to repay so that the position
+    /// ends up at `target_ltv`, assuming the withdrawn collateral is swapped into the
+    /// supply token at oracle price
+    pub fn deleverage_amounts(
+        &self,
+        borrow_position: &BorrowPosition,
+        target_ltv: IFixedPoint,
+    ) -> LendingResult<DeleverageAmounts> {
+        if target_ltv.is_negative() || target_ltv >= IFixedPoint::one() {
+            return Err(LendingError::InvalidLtvConfig.into())
+                .with_msg("target ltv should be in [0, 1)");
+        }
+        let health = self.borrow_position_health(borrow_position)?;
+        if health.ltv <= target_ltv {
+            return Ok(DeleverageAmounts::default());
+        }
+        // (borrow_value - v) / (collateral_value - v) = target_ltv
+        let value_to_swap = health
+            .borrow_value
+            .safe_sub(target_ltv.safe_mul(health.collateral_value)?)?
+            .safe_div(IFixedPoint::one().safe_sub(target_ltv)?)?
+            .min(health.borrow_value);
+        let withdraw_collateral_atoms = self
+            .collateral_oracle
+            .collateral_atoms(value_to_swap, self.market.collateral_token_info().decimals)?
+            .as_u64_rounded_up()?
+            .min(health.collateral_atoms);
+        let repay_all = value_to_swap >= health.borrow_value;
+        let repay_atoms = if repay_all {
+            health.borrowed_atoms
+        } else {
+            self.supply_oracle
+                .borrow_atoms(value_to_swap, self.market.supply_token_info().decimals)?
+                .as_u64_rounded_down()?
+        };
+        Ok(DeleverageAmounts {
+            withdraw_collateral_atoms,
+            repay_atoms,
+            repay_all,
+        })
+    }
+
     pub fn get_single_market_transaction_event(
         &self,
         market: &Pubkey,
@@ -206,6 +789,64 @@ impl<M: Deref<Target = Market>> MarketWrapper<M> {
             max_repay_atoms,
         )
     }
+
+    /// Supply shares worth the collateral seized by liquidating `borrow_position`, so that
+    /// liquidators can compare the token payout with a supply position credit. The seized
+    /// collateral is priced at the lower bound of the collateral oracle and converted to
+    /// supply atoms at the upper bound of the supply oracle
+    pub fn preview_liquidation_as_shares(
+        &self,
+        borrow_position: &BorrowPosition,
+        max_repay_atoms: u64,
+    ) -> LendingResult<UFixedPoint> {
+        let mut market = self.owned();
+        let mut borrow_position = *borrow_position;
+        let seized_atoms = market
+            .liquidate(&mut borrow_position, max_repay_atoms)?
+            .liquidation_result_with_bonus
+            .total_collateral_atoms_to_liquidate()?;
+        let seized_value = self
+            .collateral_oracle
+            .collateral_value(seized_atoms, self.market.collateral_vault().mint_decimals())?;
+        let credited_atoms = self
+            .supply_oracle
+            .borrow_atoms(seized_value, self.market.supply_vault().mint_decimals())?
+            .as_u64_rounded_down()?;
+        let mut credited_position = SupplyPosition::default();
+        market.lend(&mut credited_position, credited_atoms)?;
+        Ok(credited_position.shares())
+    }
+
+    /// Value of the collateral bonus paid to the liquidator by `liquidation`, priced
+    /// conservatively with the collateral oracle
+    pub fn liquidation_bonus_value(
+        &self,
+        liquidation: &LiquidationResultWithBonus,
+    ) -> LendingResult<IFixedPoint> {
+        self.collateral_oracle.collateral_value(
+            liquidation.collateral_atoms_liquidation_bonus,
+            self.market.collateral_vault().mint_decimals(),
+        )
+    }
+
+    /// Liquidatable `positions` whose liquidation preview pays a bonus worth at least
+    /// `min_bonus_value`, so that keepers skip liquidations which do not cover their costs
+    pub fn liquidatable_profitable<'a>(
+        &self,
+        positions: impl IntoIterator<Item = (Pubkey, &'a BorrowPosition)>,
+        min_bonus_value: IFixedPoint,
+    ) -> Vec<(Pubkey, LiquidationResultWithBonus)> {
+        positions
+            .into_iter()
+            .filter_map(|(key, position)| {
+                let (_, liquidation) = self
+                    .compute_liquidation_result_with_fee(position, u64::MAX)
+                    .ok()?;
+                let bonus_value = self.liquidation_bonus_value(&liquidation).ok()?;
+                (bonus_value >= min_bonus_value).then_some((key, liquidation))
+            })
+            .collect()
+    }
 }
 
 impl<M: DerefMut<Target = Market>> MarketWrapper<M> {
@@ -299,8 +940,8 @@ impl<M: DerefMut<Target = Market>> MarketWrapper<M> {
 impl Market {
     pub fn wrapper<A: Deref<Target = [u8]>, B: Deref<Target = [u8]>>(
         &self,
-        supply_oracle: AccountView<A>,
-        collateral_oracle: AccountView<B>,
+        supply_oracle: Option<AccountView<A>>,
+        collateral_oracle: Option<AccountView<B>>,
         unix_timestamp: i64,
     ) -> LendingResult<MarketWrapper<&Self>> {
         MarketWrapper::try_new(self, supply_oracle, collateral_oracle, unix_timestamp)
@@ -308,12 +949,22 @@ impl Market {
 
     pub fn wrapper_mut<A: Deref<Target = [u8]>, B: Deref<Target = [u8]>>(
         &mut self,
-        supply_oracle: AccountView<A>,
-        collateral_oracle: AccountView<B>,
+        supply_oracle: Option<AccountView<A>>,
+        collateral_oracle: Option<AccountView<B>>,
         unix_timestamp: i64,
     ) -> LendingResult<MarketWrapper<&mut Self>> {
         MarketWrapper::try_new(self, supply_oracle, collateral_oracle, unix_timestamp)
     }
+
+    /// See [MarketWrapper::try_new_for_borrow]
+    pub fn borrow_wrapper_mut<A: Deref<Target = [u8]>, B: Deref<Target = [u8]>>(
+        &mut self,
+        supply_oracle: Option<AccountView<A>>,
+        collateral_oracle: Option<AccountView<B>>,
+        unix_timestamp: i64,
+    ) -> LendingResult<MarketWrapper<&mut Self>> {
+        MarketWrapper::try_new_for_borrow(self, supply_oracle, collateral_oracle, unix_timestamp)
+    }
 }
 
 #[repr(transparent)]
@@ -349,10 +1000,17 @@ pub mod tests {
         assert_eq_float,
         constant::SECONDS_PER_YEAR,
         error::LendingError,
-        oracle::oracle_config::tests::{default_btc_oracle_rate, default_usd_oracle_rate},
+        interest_rate::interest_rate_kind::InterestRateCurveKind,
+        oracle::{
+            oracle_config::tests::{
+                btc_oracle_config, default_btc_oracle_rate, default_usd_oracle_rate, BTC_FEED_ID,
+                USD_FEED_ID,
+            },
+            test_utils::{OracleScenario, SCENARIO_PUBLISH_TIME},
+        },
         state::{
             collateral_vault::tests::BTC, market::tests::create_empty_btc_usdc_market,
-            supply_vault::tests::USDC,
+            market_config::MarketConfig, supply_vault::tests::USDC,
         },
     };
 
@@ -366,6 +1024,137 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn borrow_constraints_report_the_binding_limit() {
+        let check = |configure: fn(&mut MarketConfig),
+                     lend_atoms: u64,
+                     borrow_atoms: u64,
+                     expected: Option<(BorrowConstraint, LendingError)>| {
+            let mut market = btc_usd_market();
+            configure(market.market_mut().config_mut());
+            market
+                .lend(&mut SupplyPosition::default(), lend_atoms)
+                .unwrap();
+            let mut borrow_position = BorrowPosition::default();
+            market
+                .deposit_collateral(&mut borrow_position, BTC(1.))
+                .unwrap();
+            let report = market
+                .borrow_constraints(&borrow_position, borrow_atoms)
+                .unwrap();
+            let result = market
+                .clone()
+                .borrow(&mut borrow_position.clone(), borrow_atoms);
+            let Some((constraint, error)) = expected else {
+                assert!(report.is_allowed());
+                result.unwrap();
+                return;
+            };
+            let binding = report.binding_constraint();
+            assert_eq!(binding.constraint, constraint);
+            assert!(binding.excess_atoms(borrow_atoms) > 0);
+            assert!(!report.is_allowed());
+            assert_eq!(result.unwrap_err(), error);
+            if constraint != BorrowConstraint::MinSupplyDepth {
+                // the report says how much can still be borrowed
+                market
+                    .borrow(&mut borrow_position, report.max_borrow_atoms())
+                    .unwrap();
+            }
+        };
+        // 1 BTC of collateral is worth ~79,900 USDC at the max ltv of 80%
+        check(|_| {}, USDC(50_000.), USDC(10_000.), None);
+        check(
+            |config| config.update_min_supply_for_borrow_atoms(USDC(100_000.)),
+            USDC(50_000.),
+            USDC(1_000.),
+            Some((
+                BorrowConstraint::MinSupplyDepth,
+                LendingError::InsufficientMarketDepth,
+            )),
+        );
+        check(
+            |_| {},
+            USDC(1_000_000.),
+            USDC(90_000.),
+            Some((BorrowConstraint::MaxLtv, LendingError::MaxLtvReached)),
+        );
+        check(
+            |config| {
+                config
+                    .update_borrow_cap_ltv(IFixedPoint::lit("0.5"))
+                    .unwrap()
+            },
+            USDC(1_000_000.),
+            USDC(60_000.),
+            Some((
+                BorrowConstraint::BorrowCapLtv,
+                LendingError::BorrowCapLtvReached,
+            )),
+        );
+        check(
+            |_| {},
+            USDC(50_000.),
+            USDC(49_000.),
+            Some((
+                BorrowConstraint::MaxUtilisation,
+                LendingError::MaxUtilisationRateReached,
+            )),
+        );
+        check(
+            |config| config.update_min_liquidity_buffer_atoms(USDC(10_000.)),
+            USDC(50_000.),
+            USDC(45_000.),
+            Some((
+                BorrowConstraint::MinLiquidityBuffer,
+                LendingError::MinLiquidityBufferReached,
+            )),
+        );
+    }
+
+    #[test]
+    fn current_borrow_apr_matches_next_sync_clock() {
+        let mut market = btc_usd_market();
+        let supply_vault = *market.market().supply_vault();
+        market
+            .market_mut()
+            .initlize_supply_vault(
+                *supply_vault.mint(),
+                supply_vault.mint_decimals() as u64,
+                *supply_vault.vault(),
+                *supply_vault.oracle_config(),
+                InterestRateCurveKind::new_adaptive(),
+                0,
+            )
+            .unwrap();
+        market.sync_clock(1).unwrap();
+        let mut supply_position = SupplyPosition::zeroed();
+        let mut borrow_position = BorrowPosition::zeroed();
+        market.lend(&mut supply_position, USDC(1_000_000.)).unwrap();
+        market
+            .deposit_collateral(&mut borrow_position, BTC(10.))
+            .unwrap();
+        market.borrow(&mut borrow_position, USDC(500_000.)).unwrap();
+        let stale_apr = market
+            .market()
+            .supply_vault()
+            .last_borrow_interest_rate()
+            .approximate_apr()
+            .unwrap()
+            * 100.;
+        let current_apr = market.current_borrow_apr().unwrap();
+        assert!(current_apr > stale_apr);
+        market.sync_clock(2).unwrap();
+        let applied_apr = market
+            .market()
+            .supply_vault()
+            .last_borrow_interest_rate()
+            .approximate_apr()
+            .unwrap()
+            * 100.;
+        assert_eq_float!(current_apr, applied_apr, 0.0001);
+    }
+
     #[test]
     fn x_deposit_then_withdraw_step_by_step() {
         let mut market = btc_usd_market();
@@ -457,6 +1246,146 @@ pub mod tests {
         );
     }
 
+    #[test]
+    pub fn oracle_rate_override_changes_position_health() {
+        let mut market = btc_usd_market();
+        let mut supply_position = SupplyPosition::zeroed();
+        let mut borrow_position = BorrowPosition::zeroed();
+        market.lend(&mut supply_position, USDC(100_000.)).unwrap();
+        market
+            .deposit_collateral(&mut borrow_position, BTC(1.))
+            .unwrap();
+        market.borrow(&mut borrow_position, USDC(50_000.)).unwrap();
+        let health = market.borrow_position_health(&borrow_position).unwrap();
+        let crashed_btc_rate = OracleRate::new(
+            IFixedPoint::from_num(80_000.0),
+            IFixedPoint::from_num(100.0),
+        );
+        let simulated = market
+            .owned()
+            .with_oracle_rate(MarketSide::Collateral, crashed_btc_rate);
+        let simulated_health = simulated.borrow_position_health(&borrow_position).unwrap();
+        assert_eq!(simulated.collateral_oracle(), &crashed_btc_rate);
+        assert_eq!(simulated.supply_oracle(), market.supply_oracle());
+        assert_eq!(simulated_health.borrowed_atoms, health.borrowed_atoms);
+        assert_eq_float!(
+            simulated_health.ltv.to_float(),
+            health.ltv.to_float() / 0.8,
+            0.001
+        );
+    }
+
+    #[test]
+    pub fn health_at_lower_collateral_price_has_higher_ltv() {
+        let mut market = btc_usd_market();
+        let mut supply_position = SupplyPosition::zeroed();
+        let mut borrow_position = BorrowPosition::zeroed();
+        market.lend(&mut supply_position, USDC(100_000.)).unwrap();
+        market
+            .deposit_collateral(&mut borrow_position, BTC(1.))
+            .unwrap();
+        market.borrow(&mut borrow_position, USDC(50_000.)).unwrap();
+        let health = market.borrow_position_health(&borrow_position).unwrap();
+        let current = market
+            .health_at_prices(
+                &borrow_position,
+                market.supply_oracle(),
+                market.collateral_oracle(),
+            )
+            .unwrap();
+        assert_eq!(current, health);
+        let dropped_btc_rate = OracleRate::new(
+            IFixedPoint::from_num(70_000.0),
+            IFixedPoint::from_num(100.0),
+        );
+        let dropped = market
+            .health_at_prices(&borrow_position, market.supply_oracle(), &dropped_btc_rate)
+            .unwrap();
+        assert_eq!(dropped.borrowed_atoms, health.borrowed_atoms);
+        assert_eq!(dropped.borrow_value, health.borrow_value);
+        assert_eq_float!(
+            dropped.ltv.to_float(),
+            health.ltv.to_float() * 99_900. / 69_900.,
+            0.001
+        );
+        // the loaded rates are left untouched
+        assert_eq!(market.collateral_oracle(), &default_btc_oracle_rate());
+    }
+
+    #[test]
+    pub fn ema_collateral_oracle_computes_health_from_the_ema_price() {
+        let now = SCENARIO_PUBLISH_TIME;
+        let key = Pubkey::new_unique();
+        let usd_owner = Pubkey(USD_FEED_ID);
+        let btc_owner = Pubkey(BTC_FEED_ID);
+        let usd = OracleScenario::new(USD_FEED_ID)
+            .with_price(100_000_000, 100_000, -8)
+            .to_bytes();
+        // spot BTC at 100_000 while the EMA still lags at 80_000
+        let btc = OracleScenario::new(BTC_FEED_ID)
+            .with_price(100_000, 10, 0)
+            .with_ema_price(80_000, 10)
+            .to_bytes();
+        let mut market = create_empty_btc_usdc_market();
+        let mut supply_position = SupplyPosition::zeroed();
+        let mut borrow_position = BorrowPosition::zeroed();
+        let spot_health = {
+            let mut spot = market
+                .wrapper_mut(
+                    Some((&key, usd.as_slice(), &usd_owner).into()),
+                    Some((&key, btc.as_slice(), &btc_owner).into()),
+                    now,
+                )
+                .unwrap();
+            spot.lend(&mut supply_position, USDC(100_000.)).unwrap();
+            spot.deposit_collateral(&mut borrow_position, BTC(1.))
+                .unwrap();
+            spot.borrow(&mut borrow_position, USDC(50_000.)).unwrap();
+            spot.borrow_position_health(&borrow_position).unwrap()
+        };
+        market.set_collateral_oracle_config(btc_oracle_config().with_ema_price(true));
+        let ema = market
+            .wrapper(
+                Some((&key, usd.as_slice(), &usd_owner).into()),
+                Some((&key, btc.as_slice(), &btc_owner).into()),
+                now,
+            )
+            .unwrap();
+        assert_eq_float!(ema.collateral_oracle().rate().to_float(), 80_000.);
+        let ema_health = ema.borrow_position_health(&borrow_position).unwrap();
+        assert_eq!(ema_health.borrowed_atoms, spot_health.borrowed_atoms);
+        assert_eq_float!(
+            ema_health.ltv.to_float(),
+            spot_health.ltv.to_float() * 99_990. / 79_990.,
+            0.001
+        );
+    }
+
+    #[test]
+    pub fn aggregate_solvency_drops_below_one_when_collateral_crashes() {
+        let mut market = btc_usd_market();
+        assert_eq!(market.aggregate_solvency().unwrap(), IFixedPoint::MAX);
+        let mut supply_position = SupplyPosition::zeroed();
+        let mut borrow_position = BorrowPosition::zeroed();
+        market.lend(&mut supply_position, USDC(1_000_000.)).unwrap();
+        market
+            .deposit_collateral(&mut borrow_position, BTC(1.))
+            .unwrap();
+        market.borrow(&mut borrow_position, USDC(50_000.)).unwrap();
+        let solvency = market.aggregate_solvency().unwrap();
+        assert_eq_float!(solvency.to_float(), 99_900. / 50_050., 0.001);
+        let crashed = market.owned().with_oracle_rate(
+            MarketSide::Collateral,
+            OracleRate::new(
+                IFixedPoint::from_num(40_000.0),
+                IFixedPoint::from_num(100.0),
+            ),
+        );
+        let crashed_solvency = crashed.aggregate_solvency().unwrap();
+        assert!(crashed_solvency < IFixedPoint::one());
+        assert_eq_float!(crashed_solvency.to_float(), 39_900. / 50_050., 0.001);
+    }
+
     #[test]
     pub fn check_cant_liquidated_healthy_position() {
         let mut market = btc_usd_market();
@@ -613,6 +1542,270 @@ pub mod tests {
         assert!(repaid_atoms > 0);
     }
 
+    #[test]
+    pub fn liquidatable_profitable_filters_small_bonuses() {
+        let mut market = btc_usd_market();
+        let mut supply_position = SupplyPosition::zeroed();
+        market.lend(&mut supply_position, USDC(1_000_000.)).unwrap();
+        let mut positions = [BorrowPosition::zeroed(); 3];
+        let keys = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        for (position, (collateral, borrowed)) in
+            positions
+                .iter_mut()
+                .zip([(1., 70_000.), (0.01, 700.), (1., 30_000.)])
+        {
+            market
+                .deposit_collateral(position, BTC(collateral))
+                .unwrap();
+            market.borrow(position, USDC(borrowed)).unwrap();
+        }
+        market.collateral_oracle = OracleRate::new(75_000.into(), 0.into());
+        let bonus_value = |position: &BorrowPosition| {
+            let (_, liquidation) = market
+                .compute_liquidation_result_with_fee(position, u64::MAX)
+                .unwrap();
+            market.liquidation_bonus_value(&liquidation).unwrap()
+        };
+        let large_bonus = bonus_value(&positions[0]);
+        let small_bonus = bonus_value(&positions[1]);
+        assert!(small_bonus > IFixedPoint::zero());
+        assert!(large_bonus > small_bonus);
+
+        let all = keys.into_iter().zip(positions.iter());
+        let liquidatable = market.liquidatable_profitable(all.clone(), IFixedPoint::zero());
+        assert_eq!(
+            liquidatable.iter().map(|(key, _)| *key).collect::<Vec<_>>(),
+            vec![keys[0], keys[1]]
+        );
+        let profitable = market.liquidatable_profitable(all, large_bonus);
+        assert_eq!(profitable.len(), 1);
+        assert_eq!(profitable[0].0, keys[0]);
+        assert_eq!(
+            market.liquidation_bonus_value(&profitable[0].1).unwrap(),
+            large_bonus
+        );
+    }
+
+    #[test]
+    pub fn interest_coverage_decreases_after_socialized_loss() {
+        let mut market = btc_usd_market();
+        let mut supply_position = SupplyPosition::zeroed();
+        market.lend(&mut supply_position, USDC(1_000_000.)).unwrap();
+        let mut positions = [BorrowPosition::zeroed(); 3];
+        for position in positions.iter_mut() {
+            market.deposit_collateral(position, BTC(1.)).unwrap();
+            market.borrow(position, USDC(50_000.)).unwrap();
+        }
+        assert_eq!(market.interest_coverage().unwrap(), IFixedPoint::MAX);
+
+        market.collateral_oracle = OracleRate::new(0.001.into(), 0.into());
+        market.socialize_loss(&mut positions[0]).unwrap();
+        let coverage_after_first_loss = market.interest_coverage().unwrap();
+        assert!(coverage_after_first_loss > IFixedPoint::zero());
+        assert!(coverage_after_first_loss < IFixedPoint::MAX);
+
+        market.socialize_loss(&mut positions[1]).unwrap();
+        assert!(market.interest_coverage().unwrap() < coverage_after_first_loss);
+    }
+
+    #[test]
+    pub fn preview_liquidation_as_shares_matches_credited_shares() {
+        let mut market = btc_usd_market();
+        let mut supply_position = SupplyPosition::zeroed();
+        let mut borrow_position = BorrowPosition::zeroed();
+        market.lend(&mut supply_position, USDC(1_000_000.)).unwrap();
+        market
+            .deposit_collateral(&mut borrow_position, BTC(1.))
+            .unwrap();
+        market.borrow(&mut borrow_position, USDC(75_000.)).unwrap();
+        market.collateral_oracle =
+            OracleRate::new(IFixedPoint::from_num(80_000.0), IFixedPoint::from_num(10.0));
+
+        let max_repay = USDC(10_000.);
+        let previewed_shares = market
+            .preview_liquidation_as_shares(&borrow_position, max_repay)
+            .unwrap();
+        let liquidation = market
+            .liquidate(&mut borrow_position, max_repay)
+            .unwrap()
+            .liquidation_result_with_bonus;
+        assert_eq!(liquidation.borrowed_atoms_to_repay, max_repay);
+        // the repaid debt is priced at 1.001 and the seized collateral at 79_990
+        assert_eq!(
+            liquidation.total_collateral_atoms_to_liquidate().unwrap(),
+            (10_000. * 1.001 * 1.05 / 79_990. * 1e8) as u64
+        );
+        // the seized collateral converts back to supply atoms at 1.001 so only the 5% bonus
+        // is left, credited at the initial share price of one share per atom
+        assert_eq_float!(previewed_shares.to_float(), 10_000. * 1.05 * 1e6, 1e-6);
+    }
+
+    #[test]
+    pub fn risk_summary_of_stressed_market() {
+        let mut market = btc_usd_market();
+        let mut supply_position = SupplyPosition::zeroed();
+        market.lend(&mut supply_position, USDC(1_000_000.)).unwrap();
+        let mut positions = [BorrowPosition::zeroed(); 3];
+        for (position, borrowed) in positions.iter_mut().zip([50_000., 50_000., 20_000.]) {
+            market.deposit_collateral(position, BTC(1.)).unwrap();
+            market.borrow(position, USDC(borrowed)).unwrap();
+        }
+        market.collateral_oracle = OracleRate::new(0.001.into(), 0.into());
+        market.socialize_loss(&mut positions[0]).unwrap();
+        market.collateral_oracle = OracleRate::new(
+            IFixedPoint::from_num(55_000.0),
+            IFixedPoint::from_num(100.0),
+        );
+        let oracle_ages = OracleAges {
+            supply_seconds: 5,
+            collateral_seconds: 120,
+        };
+        let summary = market.risk_summary(&positions[1..], oracle_ages).unwrap();
+        assert_eq!(
+            summary.utilisation_rate,
+            market.market().supply_vault().utilisation_rate().unwrap()
+        );
+        assert_eq_float!(
+            summary.aggregate_solvency.to_float(),
+            3. * 54_900. / 70_070.,
+            0.001
+        );
+        assert_eq!(summary.oracle_ages, oracle_ages);
+        assert_eq!(summary.liquidatable_positions, 1);
+        assert_eq!(summary.socialized_loss_atoms, USDC(50_000.));
+    }
+
+    #[test]
+    pub fn position_becomes_socializable_at_socialization_collateral_price() {
+        let mut market = btc_usd_market();
+        let mut supply_position = SupplyPosition::zeroed();
+        let mut borrow_position = BorrowPosition::zeroed();
+        market.lend(&mut supply_position, USDC(100_000.)).unwrap();
+        assert!(market
+            .socialization_collateral_price(&borrow_position)
+            .unwrap()
+            .is_zero());
+        market
+            .deposit_collateral(&mut borrow_position, BTC(1.))
+            .unwrap();
+        market.borrow(&mut borrow_position, USDC(50_000.)).unwrap();
+        let price = market
+            .socialization_collateral_price(&borrow_position)
+            .unwrap();
+        assert_eq_float!(price.to_float(), 50_050., 0.0001);
+        let at_price = |price: IFixedPoint| {
+            market
+                .owned()
+                .with_oracle_rate(MarketSide::Collateral, OracleRate::new(price, 0.into()))
+        };
+        assert_eq!(
+            at_price(price.safe_mul(IFixedPoint::lit("1.001")).unwrap())
+                .socialize_loss(&mut borrow_position.clone())
+                .unwrap_err(),
+            LendingError::CannotSocializeDebtForHealthyPosition
+        );
+        at_price(price.safe_mul(IFixedPoint::lit("0.999")).unwrap())
+            .socialize_loss(&mut borrow_position.clone())
+            .unwrap();
+    }
+
+    #[test]
+    pub fn seconds_until_liquidation_orders_positions_by_ltv() {
+        let mut market = btc_usd_market();
+        let mut supply_position = SupplyPosition::zeroed();
+        market.lend(&mut supply_position, USDC(1_000_000.)).unwrap();
+        let mut positions = [BorrowPosition::zeroed(); 4];
+        for (position, borrowed) in positions.iter_mut().zip([70_000., 40_000., 0.]) {
+            market.deposit_collateral(position, BTC(1.)).unwrap();
+            if borrowed > 0. {
+                market.borrow(position, USDC(borrowed)).unwrap();
+            }
+        }
+        market
+            .deposit_collateral(&mut positions[3], BTC(1.))
+            .unwrap();
+        market.borrow(&mut positions[3], USDC(79_000.)).unwrap();
+        market.sync_clock(1).unwrap();
+        let mut crashed = market.owned();
+        crashed.collateral_oracle = OracleRate::new(80_000.into(), 0.into());
+        assert_eq!(
+            crashed.seconds_until_liquidation(&positions[3]).unwrap(),
+            Some(0)
+        );
+        let riskier = market.seconds_until_liquidation(&positions[0]).unwrap();
+        let safer = market.seconds_until_liquidation(&positions[1]).unwrap();
+        assert!(riskier.unwrap() < safer.unwrap());
+        assert_eq!(
+            market.seconds_until_liquidation(&positions[2]).unwrap(),
+            None
+        );
+        // at 10% apy the debt grows by ln(unhealthy_ltv / ltv) / ln(1.1) years
+        let ltv = market
+            .borrow_position_health(&positions[0])
+            .unwrap()
+            .ltv
+            .to_float();
+        let expected_years = (0.9 / ltv).ln() / 1.1f64.ln();
+        assert_eq_float!(
+            riskier.unwrap() as f64 / SECONDS_PER_YEAR as f64,
+            expected_years,
+            0.001
+        );
+        market.sync_clock(1 + riskier.unwrap() as i64).unwrap();
+        let health = market.borrow_position_health(&positions[0]).unwrap();
+        assert_eq_float!(
+            health.ltv.to_float(),
+            market
+                .market()
+                .config()
+                .ltv_config()
+                .unhealthy_ltv
+                .to_float(),
+            0.001
+        );
+    }
+
+    #[test]
+    pub fn underwater_market_has_lower_risk_adjusted_apy() {
+        let mut market = btc_usd_market();
+        let mut supply_position = SupplyPosition::zeroed();
+        let mut borrow_position = BorrowPosition::zeroed();
+        market.lend(&mut supply_position, USDC(1_000_000.)).unwrap();
+        market
+            .deposit_collateral(&mut borrow_position, BTC(1.))
+            .unwrap();
+        market.borrow(&mut borrow_position, USDC(79_000.)).unwrap();
+        market.sync_clock(1).unwrap();
+        let gross_apy = market
+            .market()
+            .supply_vault()
+            .get_summary()
+            .unwrap()
+            .lending_interest_rate
+            .approximate_apy()
+            .unwrap();
+        assert!(gross_apy > 0.);
+        let healthy_apy = market
+            .supply_apy_after_haircut([&borrow_position], &SocializedBadDebtHaircut)
+            .unwrap();
+        assert_eq_float!(healthy_apy, gross_apy);
+        market.collateral_oracle = OracleRate::new(50_000.into(), 0.into());
+        let underwater_apy = market
+            .supply_apy_after_haircut([&borrow_position], &SocializedBadDebtHaircut)
+            .unwrap();
+        assert!(underwater_apy < healthy_apy);
+        // roughly 29k of bad debt over 1M supplied
+        assert_eq_float!(
+            underwater_apy,
+            (1. + gross_apy) * (1. - 29_000. / 1_000_000.) - 1.,
+            0.01
+        );
+    }
+
     #[test]
     pub fn borrow_near_max_ltv_then_price_drop_triggers_liquidation() {
         let mut market = btc_usd_market();
@@ -753,4 +1946,192 @@ pub mod tests {
         let health_after = market.borrow_position_health(&borrow).unwrap();
         assert!(health_after.ltv < health_before.ltv);
     }
+
+    #[test]
+    pub fn user_market_summary_for_supplier_and_borrower() {
+        let mut market = btc_usd_market();
+        let mut supply = SupplyPosition::zeroed();
+        let mut borrow = BorrowPosition::zeroed();
+        market.lend(&mut supply, USDC(100_000.)).unwrap();
+        market.deposit_collateral(&mut borrow, BTC(1.)).unwrap();
+        market.borrow(&mut borrow, USDC(40_000.)).unwrap();
+        let summary = market
+            .user_market_summary(Some(&supply), Some(&borrow))
+            .unwrap();
+        assert_eq!(summary.supplied_atoms, USDC(100_000.));
+        assert_eq_float!(summary.supplied_value.to_float(), 100_000. * 0.999);
+        let health = summary.borrow_position_health.unwrap();
+        assert_eq!(health, market.borrow_position_health(&borrow).unwrap());
+        assert_eq!(health.borrowed_atoms, USDC(40_000.));
+        assert_eq!(health.collateral_atoms, BTC(1.));
+        assert!(health.ltv > 0.into());
+
+        assert_eq!(
+            summary.actions,
+            PositionActions {
+                can_supply: true,
+                can_withdraw_supply: true,
+                can_deposit_collateral: true,
+                can_withdraw_collateral: true,
+                can_borrow: true,
+                can_repay: true,
+                is_liquidatable: false,
+            }
+        );
+        assert_eq!(
+            summary.actions,
+            market.available_actions(&borrow, &supply).unwrap()
+        );
+
+        let empty = market.user_market_summary(None, None).unwrap();
+        assert_eq!(
+            empty,
+            UserMarketSummary {
+                actions: PositionActions {
+                    can_supply: true,
+                    can_deposit_collateral: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    pub fn pending_fees_value_uses_supply_price() {
+        let mut market = btc_usd_market();
+        let mut supply = SupplyPosition::zeroed();
+        let mut borrow = BorrowPosition::zeroed();
+        market.lend(&mut supply, USDC(1_000_000.)).unwrap();
+        market.deposit_collateral(&mut borrow, BTC(10.)).unwrap();
+        market.borrow(&mut borrow, USDC(500_000.)).unwrap();
+        market.sync_clock(365 * 24 * 3600).unwrap();
+        let summary = market.market().supply_vault().get_summary().unwrap();
+        assert!(summary.pending_curator_fee_atoms > 0);
+        assert!(summary.pending_protocol_fee_atoms > 0);
+        let (curator_value, protocol_value) = market.pending_fees_value().unwrap();
+        assert_eq_float!(
+            curator_value.to_float(),
+            summary.pending_curator_fee_atoms as f64 * 0.999 / 1e6
+        );
+        assert_eq_float!(
+            protocol_value.to_float(),
+            summary.pending_protocol_fee_atoms as f64 * 0.999 / 1e6
+        );
+    }
+
+    #[test]
+    pub fn deleverage_amounts_reach_target_ltv() {
+        let mut market = btc_usd_market();
+        let mut supply = SupplyPosition::zeroed();
+        let mut borrow = BorrowPosition::zeroed();
+        market.lend(&mut supply, USDC(1_000_000.)).unwrap();
+        market.deposit_collateral(&mut borrow, BTC(1.)).unwrap();
+        market.borrow(&mut borrow, USDC(70_000.)).unwrap();
+        let target_ltv = IFixedPoint::lit("0.5");
+        let amounts = market.deleverage_amounts(&borrow, target_ltv).unwrap();
+        assert!(!amounts.repay_all);
+        // (70070 - v) / (99900 - v) = 0.5 => v = 40240
+        assert_eq_float!(
+            amounts.repay_atoms as f64,
+            USDC(40_240. / 1.001) as f64,
+            0.0001
+        );
+        assert_eq_float!(
+            amounts.withdraw_collateral_atoms as f64,
+            BTC(40_240. / 99_900.) as f64,
+            0.0001
+        );
+        market.repay(&mut borrow, amounts.repay_atoms).unwrap();
+        market
+            .withdraw_collateral(&mut borrow, amounts.withdraw_collateral_atoms)
+            .unwrap();
+        let health = market.borrow_position_health(&borrow).unwrap();
+        assert_eq_float!(health.ltv.to_float(), 0.5, 0.0001);
+
+        let nothing_to_do = market
+            .deleverage_amounts(&borrow, IFixedPoint::lit("0.6"))
+            .unwrap();
+        assert_eq!(nothing_to_do, DeleverageAmounts::default());
+        let full = market
+            .deleverage_amounts(&borrow, IFixedPoint::zero())
+            .unwrap();
+        assert!(full.repay_all);
+        assert_eq!(
+            market
+                .deleverage_amounts(&borrow, IFixedPoint::one())
+                .unwrap_err(),
+            LendingError::InvalidLtvConfig
+        );
+    }
+
+    #[test]
+    pub fn available_actions_for_healthy_position() {
+        let mut market = btc_usd_market();
+        let mut supply = SupplyPosition::zeroed();
+        let mut borrow = BorrowPosition::zeroed();
+        market.lend(&mut supply, USDC(1_000_000.)).unwrap();
+        market.deposit_collateral(&mut borrow, BTC(1.)).unwrap();
+        market.borrow(&mut borrow, USDC(10_000.)).unwrap();
+        let actions = market.available_actions(&borrow, &supply).unwrap();
+        assert_eq!(
+            actions,
+            PositionActions {
+                can_supply: true,
+                can_withdraw_supply: true,
+                can_deposit_collateral: true,
+                can_withdraw_collateral: true,
+                can_borrow: true,
+                can_repay: true,
+                is_liquidatable: false,
+            }
+        );
+    }
+
+    #[test]
+    pub fn available_actions_follow_the_borrow_constraints() {
+        let mut market = btc_usd_market();
+        let mut supply = SupplyPosition::zeroed();
+        let mut borrow = BorrowPosition::zeroed();
+        market.lend(&mut supply, USDC(100_000.)).unwrap();
+        market.deposit_collateral(&mut borrow, BTC(1.)).unwrap();
+        market.borrow(&mut borrow, USDC(10_000.)).unwrap();
+        // the position is healthy and the vault holds liquidity, but all of it is reserved
+        market
+            .market_mut()
+            .config_mut()
+            .update_min_liquidity_buffer_atoms(USDC(90_000.));
+        let actions = market.available_actions(&borrow, &supply).unwrap();
+        assert!(!actions.can_borrow);
+        assert!(actions.can_withdraw_supply);
+        assert!(actions.can_withdraw_collateral);
+        assert_eq!(
+            market
+                .user_market_summary(Some(&supply), Some(&borrow))
+                .unwrap()
+                .actions,
+            actions
+        );
+    }
+
+    #[test]
+    pub fn available_actions_at_max_ltv() {
+        let mut market = btc_usd_market();
+        let mut supply = SupplyPosition::zeroed();
+        let mut borrow = BorrowPosition::zeroed();
+        market.lend(&mut supply, USDC(1_000_000.)).unwrap();
+        market.deposit_collateral(&mut borrow, BTC(1.)).unwrap();
+        market.borrow(&mut borrow, USDC(70_000.)).unwrap();
+        // 70070 / 85000 ~ 82% is between max_ltv and unhealthy_ltv
+        market.collateral_oracle = OracleRate::new(85_000.into(), 0.into());
+        let health = market.borrow_position_health(&borrow).unwrap();
+        assert!(health.ltv >= market.market().config().ltv_config().max_ltv);
+        assert!(health.ltv < market.market().config().ltv_config().unhealthy_ltv);
+        let actions = market.available_actions(&borrow, &supply).unwrap();
+        assert!(!actions.can_borrow);
+        assert!(!actions.can_withdraw_collateral);
+        assert!(actions.can_repay);
+        assert!(actions.can_deposit_collateral);
+        assert!(!actions.is_liquidatable);
+    }
 }
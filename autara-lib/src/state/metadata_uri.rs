@@ -0,0 +1,131 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use bytemuck::{Pod, Zeroable};
+
+use crate::error::{LendingError, LendingResult, LendingResultExt};
+
+/// Max length in bytes of a market metadata uri
+pub const MAX_METADATA_URI_LEN: usize = 64;
+
+/// Uri of the off-chain JSON describing a market to frontends (name, icon, risk description),
+/// stored zero padded. Only read off-chain, an empty uri means no metadata
+#[repr(transparent)]
+#[derive(Clone, Copy, PartialEq, Eq, Pod, Zeroable, BorshSerialize, BorshDeserialize)]
+pub struct MetadataUri([u8; MAX_METADATA_URI_LEN]);
+
+impl MetadataUri {
+    pub fn try_new(uri: &str) -> LendingResult<Self> {
+        if uri.len() > MAX_METADATA_URI_LEN {
+            return Err(LendingError::InvalidInstructionArguments.into())
+                .with_msg("metadata uri is longer than MAX_METADATA_URI_LEN bytes");
+        }
+        let mut bytes = [0; MAX_METADATA_URI_LEN];
+        bytes[..uri.len()].copy_from_slice(uri.as_bytes());
+        let metadata_uri = Self(bytes);
+        metadata_uri.validate()?;
+        Ok(metadata_uri)
+    }
+
+    /// Checks the uri is valid utf8 followed by zero padding only, as instruction data
+    /// is deserialized without going through [MetadataUri::try_new]
+    pub fn validate(&self) -> LendingResult {
+        let len = self.len();
+        if self.0[len..].iter().any(|byte| *byte != 0) {
+            return Err(LendingError::InvalidInstructionArguments.into())
+                .with_msg("metadata uri has non-zero bytes after its terminator");
+        }
+        if std::str::from_utf8(&self.0[..len]).is_err() {
+            return Err(LendingError::InvalidInstructionArguments.into())
+                .with_msg("metadata uri is not valid utf8");
+        }
+        Ok(())
+    }
+
+    fn len(&self) -> usize {
+        self.0
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(MAX_METADATA_URI_LEN)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Uri without its padding, empty if the stored bytes are not a valid uri
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.0[..self.len()]).unwrap_or_default()
+    }
+}
+
+impl Default for MetadataUri {
+    fn default() -> Self {
+        Self::zeroed()
+    }
+}
+
+impl std::fmt::Debug for MetadataUri {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+#[cfg(feature = "client")]
+pub mod serde {
+    use super::MetadataUri;
+
+    impl serde::Serialize for MetadataUri {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: serde::Serializer,
+        {
+            self.as_str().serialize(serializer)
+        }
+    }
+
+    impl<'de> serde::Deserialize<'de> for MetadataUri {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: serde::Deserializer<'de>,
+        {
+            let uri = String::deserialize(deserializer)?;
+            MetadataUri::try_new(&uri).map_err(|err| serde::de::Error::custom(format!("{err:?}")))
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_uri_round_trips() {
+        let uri = "https://autara.finance/markets/btc-usdc.json";
+        let metadata_uri = MetadataUri::try_new(uri).unwrap();
+        assert_eq!(metadata_uri.as_str(), uri);
+        assert!(!metadata_uri.is_empty());
+        assert!(MetadataUri::default().is_empty());
+        assert_eq!(MetadataUri::default().as_str(), "");
+        let full = "a".repeat(MAX_METADATA_URI_LEN);
+        assert_eq!(MetadataUri::try_new(&full).unwrap().as_str(), full);
+    }
+
+    #[test]
+    fn invalid_metadata_uri_is_rejected() {
+        let too_long = "a".repeat(MAX_METADATA_URI_LEN + 1);
+        assert_eq!(
+            MetadataUri::try_new(&too_long).unwrap_err(),
+            LendingError::InvalidInstructionArguments
+        );
+        assert_eq!(
+            MetadataUri::try_new("ipfs://\0cid").unwrap_err(),
+            LendingError::InvalidInstructionArguments
+        );
+        let mut bytes = [0; MAX_METADATA_URI_LEN];
+        bytes[0] = 0xff;
+        assert_eq!(
+            MetadataUri(bytes).validate().unwrap_err(),
+            LendingError::InvalidInstructionArguments
+        );
+        assert_eq!(MetadataUri(bytes).as_str(), "");
+    }
+}
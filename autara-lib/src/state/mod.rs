@@ -1,11 +1,14 @@
 pub mod borrow_position;
 pub mod collateral_vault;
+pub mod curator_market_count;
 pub mod global_config;
 pub mod market;
 pub mod market_config;
 pub mod market_wrapper;
+pub mod metadata_uri;
 pub mod supply_position;
 pub mod supply_vault;
+pub mod version;
 
 // Autara Lending Accounts are discriminated by their size.
 const _: () = const {
@@ -14,6 +17,7 @@ const _: () = const {
         size_of::<supply_position::SupplyPosition>(),
         size_of::<market::Market>(),
         size_of::<global_config::GlobalConfig>(),
+        size_of::<curator_market_count::CuratorMarketCount>(),
     ];
     validate_all_different_sizes(accounts_size);
 };
@@ -7,7 +7,10 @@ use crate::{
     padding::Padding,
 };
 
-use super::super::error::LendingResult;
+use super::{
+    super::error::LendingResult,
+    version::{VersionedAccount, CURRENT_ACCOUNT_VERSION},
+};
 
 crate::validate_struct!(SupplyPosition, 216);
 
@@ -27,7 +30,9 @@ pub struct SupplyPosition {
     deposited_atoms: u64,
     /// Track the total lending shares of supply vault owned by this position
     shares: UFixedPoint,
-    pad: Padding<128>,
+    /// Layout version of the account, see [super::version]
+    version: u8,
+    pad: Padding<127>,
 }
 
 impl SupplyPosition {
@@ -37,6 +42,7 @@ impl SupplyPosition {
             market,
             deposited_atoms: 0,
             shares: UFixedPoint::zero(),
+            version: CURRENT_ACCOUNT_VERSION,
             pad: Padding::default(),
         }
     }
@@ -46,6 +52,7 @@ impl SupplyPosition {
         self.market = market;
         self.deposited_atoms = 0;
         self.shares = UFixedPoint::zero();
+        self.version = CURRENT_ACCOUNT_VERSION;
     }
 
     #[inline(always)]
@@ -96,6 +103,20 @@ impl SupplyPosition {
     }
 }
 
+impl VersionedAccount for SupplyPosition {
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+
+    fn migrate_from(&mut self, _version: u8) -> LendingResult {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -3,7 +3,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use bytemuck::{Pod, Zeroable};
 
 use crate::{
-    constant::MAX_EXPONENT_ABS,
+    constant::{MAX_EXPONENT_ABS, SECONDS_PER_YEAR},
     error::{LendingError, LendingResult, LendingResultExt},
     interest_rate::{
         interest_rate_kind::InterestRateCurveKind,
@@ -12,11 +12,16 @@ use crate::{
         pod_interest_rate::PodInterestRateCurve,
     },
     math::{
-        bps::bps_from_fixed_point, ifixed_point::IFixedPoint, rounding::RoundingMode,
-        safe_math::SafeMath, shares_tracker::SharesTracker, ufixed_point::UFixedPoint,
+        bps::bps_from_fixed_point,
+        ifixed_point::IFixedPoint,
+        rounding::{RoundingMode, ROUNDING_POLICY},
+        safe_math::SafeMath,
+        shares_tracker::SharesTracker,
+        ufixed_point::UFixedPoint,
     },
     oracle::{oracle_config::OracleConfig, pod_oracle_provider::PodOracleProvider},
     padding::Padding,
+    state::market_config::MaxSupplyRamp,
 };
 
 crate::validate_struct!(SupplyVault, 720);
@@ -51,9 +56,27 @@ pub struct SupplyVault {
     pending_protocol_fee_shares: UFixedPoint,
     /// The pending curator fee shares to be redeemed
     pending_curator_fee_shares: UFixedPoint,
-    pad: Padding<192>,
+    /// Cumulative atoms of debt socialized across the suppliers since the market creation,
+    /// saturating as it is only informative
+    socialized_loss_atoms: u64,
+    /// Ramp of the max supply set by the last update of the max supply of the market
+    max_supply_ramp: MaxSupplyRamp,
+    /// Programs the leverage callbacks may invoke, default keys are empty slots.
+    /// No program set allows any program. Set by the curator like the market config, but
+    /// stored here since [MarketConfig](super::market_config::MarketConfig) has no padding left
+    callback_program_allowlist: [Pubkey; MAX_CALLBACK_PROGRAMS],
+    /// Cumulative atoms credited to the suppliers by interest accrual, net of the fees
+    interest_earned_atoms: u64,
+    /// Cumulative atoms credited to the suppliers by donations
+    donated_atoms: u64,
+    /// Cumulative atoms of protocol fees accrued since the market creation, redeemed or not
+    protocol_fee_atoms: u64,
+    pad: Padding<8>,
 }
 
+/// Max number of programs in the callback allowlist of a market
+pub const MAX_CALLBACK_PROGRAMS: usize = 4;
+
 impl SupplyVault {
     pub fn initialize(
         &mut self,
@@ -64,12 +87,10 @@ impl SupplyVault {
         interest_rate: InterestRateCurveKind,
         timestamp: i64,
     ) -> LendingResult {
-        if mint_decimals as i64 > MAX_EXPONENT_ABS {
+        if mint_decimals > MAX_EXPONENT_ABS as u64 {
             return Err(LendingError::UnsupportedMintDecimals.into()).with_msg("supply vault");
         }
-        if !interest_rate.is_valid() {
-            return Err(LendingError::InvalidCurve.into());
-        }
+        interest_rate.validate()?;
         oracle_config.validate()?;
         self.mint = mint;
         self.mint_decimals = mint_decimals;
@@ -102,12 +123,46 @@ impl SupplyVault {
         &self.borrow_shares_tracker
     }
 
+    /// Atoms currently redeemable for one supply share
+    pub fn supply_exchange_rate(&self) -> UFixedPoint {
+        self.supply_shares_tracker.atoms_per_share()
+    }
+
+    /// Atoms currently owed for one borrow share
+    pub fn borrow_exchange_rate(&self) -> UFixedPoint {
+        self.borrow_shares_tracker.atoms_per_share()
+    }
+
     pub fn utilisation_rate(&self) -> LendingResult<IFixedPoint> {
         let total_supply = self.total_supply()?;
         let total_borrowed = self.total_borrow()?;
         Self::compute_utilisation_rate(total_borrowed, total_supply)
     }
 
+    /// Utilisation left before reaching `max_utilisation_rate`, zero when already above it
+    pub fn utilisation_headroom(
+        &self,
+        max_utilisation_rate: IFixedPoint,
+    ) -> LendingResult<IFixedPoint> {
+        let headroom = max_utilisation_rate.safe_sub(self.utilisation_rate()?)?;
+        if headroom.is_negative() {
+            return Ok(IFixedPoint::zero());
+        }
+        Ok(headroom)
+    }
+
+    /// Atoms which can be borrowed before the utilisation exceeds `max_utilisation_rate`,
+    /// regardless of any other borrow limit
+    pub fn borrowable_atoms_within_utilisation_cap(
+        &self,
+        max_utilisation_rate: IFixedPoint,
+    ) -> LendingResult<u64> {
+        let max_borrow = max_utilisation_rate
+            .safe_mul(IFixedPoint::from_u64(self.total_supply()?))?
+            .as_u64_rounded_down()?;
+        Ok(max_borrow.saturating_sub(self.total_borrow()?))
+    }
+
     pub fn interest_rate_curve(&self) -> &PodInterestRateCurve {
         &self.interest_rate_curve
     }
@@ -122,6 +177,32 @@ impl SupplyVault {
             .total_atoms(RoundingMode::RoundUp)
     }
 
+    pub fn socialized_loss_atoms(&self) -> u64 {
+        self.socialized_loss_atoms
+    }
+
+    pub fn pending_protocol_fee_shares(&self) -> UFixedPoint {
+        self.pending_protocol_fee_shares
+    }
+
+    pub fn pending_curator_fee_shares(&self) -> UFixedPoint {
+        self.pending_curator_fee_shares
+    }
+
+    /// Protocol fees accrued since the market creation, valued in supply atoms when accrued
+    pub fn lifetime_protocol_fee_atoms(&self) -> u64 {
+        self.protocol_fee_atoms
+    }
+
+    /// Split of the atoms credited to the suppliers since the market creation between
+    /// interest and donations
+    pub fn yield_attribution(&self) -> SupplierYieldAttribution {
+        SupplierYieldAttribution {
+            interest_atoms: self.interest_earned_atoms,
+            donated_atoms: self.donated_atoms,
+        }
+    }
+
     pub fn oracle_provider(&self) -> &PodOracleProvider {
         self.oracle_config.oracle_provider()
     }
@@ -139,10 +220,68 @@ impl SupplyVault {
         self.oracle_config = oracle_config;
     }
 
+    pub fn max_supply_ramp(&self) -> &MaxSupplyRamp {
+        &self.max_supply_ramp
+    }
+
+    pub(super) fn set_max_supply_ramp(&mut self, max_supply_ramp: MaxSupplyRamp) {
+        self.max_supply_ramp = max_supply_ramp;
+    }
+
+    pub fn callback_program_allowlist(&self) -> impl Iterator<Item = &Pubkey> {
+        self.callback_program_allowlist
+            .iter()
+            .filter(|program_id| **program_id != Pubkey::default())
+    }
+
+    pub(super) fn set_callback_program_allowlist(
+        &mut self,
+        allowlist: [Pubkey; MAX_CALLBACK_PROGRAMS],
+    ) {
+        self.callback_program_allowlist = allowlist;
+    }
+
+    pub fn last_update_unix_timestamp(&self) -> i64 {
+        self.last_update_unix_timestamp
+    }
+
     pub fn last_borrow_interest_rate(&self) -> InterestRatePerSecond {
         self.last_borrow_interest_rate
     }
 
+    /// Borrow rate the curve gives at the current utilisation, i.e. the rate the next
+    /// `sync_clock` starts accruing at. Samples a copy so the curve state is left untouched
+    pub fn current_borrow_interest_rate(&self) -> LendingResult<InterestRatePerSecond> {
+        let utilisation_rate = self.utilisation_rate()?;
+        let mut curve = self.interest_rate_curve;
+        curve
+            .interest_rate_kind_mut()
+            .borrow_rate_per_second(MarketBorrowRateParameters {
+                utilisation_rate: &utilisation_rate,
+                elapsed_seconds_since_last_update: 0,
+            })
+    }
+
+    /// Borrow APY compounding the last stored borrow rate over a year. Only reads the
+    /// stored state, so it needs no clock sync
+    pub fn current_borrow_apy(&self) -> LendingResult<IFixedPoint> {
+        self.last_borrow_interest_rate
+            .coumpounding_interest_rate_during_elapsed_seconds(SECONDS_PER_YEAR)
+            .map(|interest_rate| interest_rate.rate())
+    }
+
+    /// Supply APY: the borrow APY earned on the borrowed share of the supply, net of
+    /// the `lending_market_fee` taken on interests
+    pub fn current_supply_apy(
+        &self,
+        lending_market_fee: UFixedPoint,
+    ) -> LendingResult<IFixedPoint> {
+        let kept_by_suppliers = IFixedPoint::one().safe_sub(lending_market_fee)?;
+        self.current_borrow_apy()?
+            .safe_mul(self.utilisation_rate()?)?
+            .safe_mul(kept_by_suppliers)
+    }
+
     pub fn get_summary(&self) -> LendingResult<SupplyVaultSummary> {
         let total_supply = self.total_supply()?;
         let total_borrow = self.total_borrow()?;
@@ -152,12 +291,14 @@ impl SupplyVault {
             total_supply,
             total_borrow,
             utilisation_rate,
-            pending_curator_fee_atoms: self
-                .supply_shares_tracker
-                .shares_to_atoms(self.pending_curator_fee_shares, RoundingMode::RoundDown)?,
-            pending_protocol_fee_atoms: self
-                .supply_shares_tracker
-                .shares_to_atoms(self.pending_protocol_fee_shares, RoundingMode::RoundDown)?,
+            pending_curator_fee_atoms: self.supply_shares_tracker.shares_to_atoms(
+                self.pending_curator_fee_shares,
+                ROUNDING_POLICY.supply_withdraw,
+            )?,
+            pending_protocol_fee_atoms: self.supply_shares_tracker.shares_to_atoms(
+                self.pending_protocol_fee_shares,
+                ROUNDING_POLICY.supply_withdraw,
+            )?,
             borrow_interest_rate: self.last_borrow_interest_rate,
             lending_interest_rate: self
                 .last_borrow_interest_rate
@@ -180,12 +321,21 @@ impl SupplyVault {
 }
 
 impl SupplyVault {
+    /// Move the last update forward to `unix_timestamp` without accruing any interest
+    pub(super) fn skip_interest_until(&mut self, unix_timestamp: i64) {
+        self.last_update_unix_timestamp = self.last_update_unix_timestamp.max(unix_timestamp);
+    }
+
     pub(super) fn sync_clock(
         &mut self,
         unix_timestamp: i64,
         lending_market_fee: UFixedPoint,
         fee_percent_for_protocol_in_bps: u16,
     ) -> LendingResult {
+        if unix_timestamp < self.last_update_unix_timestamp {
+            return Err(LendingError::ClockWentBackward.into())
+                .with_msg("timestamp is before the last update");
+        }
         if unix_timestamp > self.last_update_unix_timestamp {
             let elapsed = (unix_timestamp - self.last_update_unix_timestamp) as u64;
             let utilisation_rate = self.utilisation_rate().track_caller()?;
@@ -204,6 +354,7 @@ impl SupplyVault {
             self.borrow_shares_tracker
                 .apply_interest_rate(borrow_rate_during_elapsed)
                 .track_caller()?;
+            let total_supply_before = self.total_supply()?;
             let lending_interest_rate_during_elapsed = borrow_rate_during_elapsed
                 .adjust_for_utilisation_rate(utilisation_rate)
                 .track_caller()?;
@@ -214,9 +365,23 @@ impl SupplyVault {
                     lending_market_fee,
                 )
                 .track_caller()?;
+            let fee_atoms = self
+                .supply_shares_tracker
+                .shares_to_atoms(fee_shares, RoundingMode::RoundUp)?;
+            let interest_earned_atoms = self
+                .total_supply()?
+                .saturating_sub(total_supply_before)
+                .saturating_sub(fee_atoms);
+            self.interest_earned_atoms = self
+                .interest_earned_atoms
+                .saturating_add(interest_earned_atoms);
             let protocol_fee_shares =
                 bps_from_fixed_point(fee_percent_for_protocol_in_bps as u64, fee_shares)?;
             let curator_fee_shares = fee_shares.safe_sub(protocol_fee_shares)?;
+            self.protocol_fee_atoms = self.protocol_fee_atoms.saturating_add(
+                self.supply_shares_tracker
+                    .shares_to_atoms(protocol_fee_shares, RoundingMode::RoundDown)?,
+            );
             self.pending_protocol_fee_shares = self
                 .pending_protocol_fee_shares
                 .safe_add(protocol_fee_shares)?;
@@ -235,7 +400,7 @@ impl SupplyVault {
 
     pub(super) fn withdraw_shares(&mut self, shares: UFixedPoint) -> LendingResult<u64> {
         self.supply_shares_tracker
-            .withdraw_shares(shares, RoundingMode::RoundDown)
+            .withdraw_shares(shares, ROUNDING_POLICY.supply_withdraw)
     }
 
     pub(super) fn withdraw_atoms(&mut self, atoms: u64) -> LendingResult<UFixedPoint> {
@@ -248,7 +413,7 @@ impl SupplyVault {
 
     pub(super) fn repay_shares(&mut self, shares: UFixedPoint) -> LendingResult<u64> {
         self.borrow_shares_tracker
-            .withdraw_shares(shares, RoundingMode::RoundUp)
+            .withdraw_shares(shares, ROUNDING_POLICY.repay)
     }
 
     pub(super) fn repay_atoms(&mut self, atoms: u64) -> LendingResult<UFixedPoint> {
@@ -261,7 +426,7 @@ impl SupplyVault {
         max_shares: UFixedPoint,
     ) -> LendingResult<(u64, UFixedPoint)> {
         self.borrow_shares_tracker
-            .withdraw_atoms_capped(atoms, max_shares, RoundingMode::RoundUp)
+            .withdraw_atoms_capped(atoms, max_shares, ROUNDING_POLICY.repay)
     }
 
     pub(super) fn redeem_protocol_fees(&mut self) -> LendingResult<u64> {
@@ -276,16 +441,52 @@ impl SupplyVault {
         self.withdraw_shares(shares)
     }
 
+    /// Credit to suppliers the pending fee shares worth less than one atom, which would
+    /// otherwise be redeemed for nothing. Returns the swept shares.
+    /// The swept shares are given up for good: fees worth less than one atom now are not
+    /// kept around until interest makes them redeemable
+    pub(super) fn sweep_fee_dust(&mut self) -> LendingResult<UFixedPoint> {
+        let is_dust = |shares: UFixedPoint| -> LendingResult<bool> {
+            Ok(!shares.is_zero()
+                && self
+                    .supply_shares_tracker
+                    .shares_to_atoms(shares, RoundingMode::RoundDown)?
+                    == 0)
+        };
+        let mut dust = UFixedPoint::zero();
+        let sweep_protocol = is_dust(self.pending_protocol_fee_shares)?;
+        if sweep_protocol {
+            dust = dust.safe_add(self.pending_protocol_fee_shares)?;
+        }
+        let sweep_curator = is_dust(self.pending_curator_fee_shares)?;
+        if sweep_curator {
+            dust = dust.safe_add(self.pending_curator_fee_shares)?;
+        }
+        if dust.is_zero() {
+            return Ok(dust);
+        }
+        self.supply_shares_tracker.redistribute_shares(dust)?;
+        if sweep_protocol {
+            self.pending_protocol_fee_shares = UFixedPoint::zero();
+        }
+        if sweep_curator {
+            self.pending_curator_fee_shares = UFixedPoint::zero();
+        }
+        Ok(dust)
+    }
+
     pub(super) fn socialize_loss(&mut self, debt_shares: UFixedPoint) -> LendingResult<u64> {
         let debt = self
             .borrow_shares_tracker
-            .withdraw_shares(debt_shares, RoundingMode::RoundUp)?;
+            .withdraw_shares(debt_shares, ROUNDING_POLICY.repay)?;
         self.supply_shares_tracker.socialize_loss_atoms(debt)?;
+        self.socialized_loss_atoms = self.socialized_loss_atoms.saturating_add(debt);
         Ok(debt)
     }
 
     pub(super) fn donate_supply(&mut self, atoms: u64) -> LendingResult {
         self.supply_shares_tracker.donate_atoms(atoms)?;
+        self.donated_atoms = self.donated_atoms.saturating_add(atoms);
         Ok(())
     }
 }
@@ -310,14 +511,44 @@ pub struct SupplyVaultSummary {
     pub lending_interest_rate: InterestRatePerSecond,
 }
 
+/// Atoms credited to the suppliers, by source
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "client",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "camelCase")
+)]
+pub struct SupplierYieldAttribution {
+    pub interest_atoms: u64,
+    pub donated_atoms: u64,
+}
+
+impl SupplierYieldAttribution {
+    pub fn total_atoms(&self) -> u64 {
+        self.interest_atoms.saturating_add(self.donated_atoms)
+    }
+
+    /// Share of the yield which came from donations, zero without any yield
+    pub fn donated_ratio(&self) -> f64 {
+        match self.total_atoms() {
+            0 => 0.,
+            total => self.donated_atoms as f64 / total as f64,
+        }
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use arch_program::pubkey::Pubkey;
 
     use crate::{
+        assert_eq_float,
         constant::SECONDS_PER_YEAR,
-        interest_rate::interest_rate_kind::InterestRateCurveKind,
-        math::bps::{bps_to_fixed_point, percent_to_bps},
+        interest_rate::{interest_rate::InterestRate, interest_rate_kind::InterestRateCurveKind},
+        math::{
+            bps::{bps_to_fixed_point, percent_to_bps, ONE_IN_BPS},
+            shares_tracker::SharesTracker,
+        },
         oracle::oracle_config::tests::usd_oracle_config,
     };
 
@@ -343,6 +574,12 @@ pub mod tests {
             last_update_unix_timestamp: 0,
             pending_protocol_fee_shares: UFixedPoint::zero(),
             pending_curator_fee_shares: UFixedPoint::zero(),
+            socialized_loss_atoms: 0,
+            max_supply_ramp: MaxSupplyRamp::default(),
+            callback_program_allowlist: Default::default(),
+            interest_earned_atoms: 0,
+            donated_atoms: 0,
+            protocol_fee_atoms: 0,
             pad: Padding::default(),
         }
     }
@@ -369,6 +606,29 @@ pub mod tests {
         assert_eq!(repay, borrow);
     }
 
+    #[test]
+    pub fn current_apy_from_stored_rate() {
+        let mut vault = create_usdc_supply_vault();
+        // the fixture stores a 1000% APY rate, compounded back exactly
+        assert_eq_float!(vault.current_borrow_apy().unwrap().to_float(), 10., 0.0001);
+        // nothing borrowed, nothing earned
+        assert!(vault
+            .current_supply_apy(UFixedPoint::zero())
+            .unwrap()
+            .is_zero());
+        vault.lend(USDC(1000.)).unwrap();
+        vault.borrow(USDC(500.)).unwrap();
+        // once synced, the stored rate is the 10% APY of the curve
+        let fee = bps_to_fixed_point(percent_to_bps(10));
+        vault.sync_clock(1, fee, 0).unwrap();
+        assert_eq_float!(vault.current_borrow_apy().unwrap().to_float(), 0.1, 0.0001);
+        assert_eq_float!(
+            vault.current_supply_apy(fee).unwrap().to_float(),
+            0.1 * 0.5 * 0.9,
+            0.0001
+        );
+    }
+
     #[test]
     pub fn check_utilisation_rate() {
         let mut vault = create_usdc_supply_vault();
@@ -398,6 +658,31 @@ pub mod tests {
         assert_eq!(total_borrow_interest, (borrow as f64 * 0.1) as u64);
     }
 
+    #[test]
+    pub fn exchange_rates_rise_with_interest() {
+        let mut vault = create_usdc_supply_vault();
+        assert_eq!(vault.supply_exchange_rate(), UFixedPoint::from_u64(1));
+        assert_eq!(vault.borrow_exchange_rate(), UFixedPoint::from_u64(1));
+        vault.lend(100000000).unwrap();
+        vault.borrow(50000000).unwrap();
+        vault
+            .sync_clock(SECONDS_PER_YEAR as i64, UFixedPoint::zero(), 0)
+            .unwrap();
+        let supply_exchange_rate = vault.supply_exchange_rate();
+        let borrow_exchange_rate = vault.borrow_exchange_rate();
+        assert!(supply_exchange_rate > UFixedPoint::from_u64(1));
+        assert!(borrow_exchange_rate > supply_exchange_rate);
+        assert_eq!(
+            vault
+                .supply_shares_tracker()
+                .shares_to_atoms(UFixedPoint::from_u64(1_000_000), RoundingMode::RoundDown),
+            UFixedPoint::from_u64(1_000_000)
+                .safe_mul(supply_exchange_rate)
+                .unwrap()
+                .as_u64_rounded_down()
+        );
+    }
+
     #[test]
     pub fn check_update_with_fee() {
         let mut vault = create_usdc_supply_vault();
@@ -437,6 +722,286 @@ pub mod tests {
         );
     }
 
+    #[test]
+    pub fn zero_interest_rate_accrues_nothing() {
+        let curve = InterestRateCurveKind::Fixed(InterestRatePerSecond::new(IFixedPoint::zero()));
+        curve.validate().unwrap();
+        let mut vault = SupplyVault {
+            interest_rate_curve: curve.into(),
+            ..create_usdc_supply_vault()
+        };
+        vault.lend(USDC(1_000_000.)).unwrap();
+        vault.borrow(USDC(900_000.)).unwrap();
+        let fee = bps_to_fixed_point(percent_to_bps(10));
+        for now in [1, SECONDS_PER_YEAR as i64 / 2, SECONDS_PER_YEAR as i64] {
+            vault
+                .sync_clock(now, fee, percent_to_bps(50) as u16)
+                .unwrap();
+        }
+        assert_eq!(vault.last_update_unix_timestamp, SECONDS_PER_YEAR as i64);
+        assert!(vault.last_borrow_interest_rate.0.is_zero());
+        assert_eq!(vault.total_supply().unwrap(), USDC(1_000_000.));
+        assert_eq!(vault.total_borrow().unwrap(), USDC(900_000.));
+        assert_eq!(vault.pending_protocol_fee_shares, UFixedPoint::zero());
+        assert_eq!(vault.pending_curator_fee_shares, UFixedPoint::zero());
+    }
+
+    #[test]
+    pub fn yield_attribution_sums_to_supplier_gain() {
+        let mut vault = create_usdc_supply_vault();
+        let deposit = USDC(1_000_000.);
+        vault.lend(deposit).unwrap();
+        vault.borrow(USDC(500_000.)).unwrap();
+        let fee = bps_to_fixed_point(percent_to_bps(10));
+        vault
+            .sync_clock(SECONDS_PER_YEAR as i64, fee, percent_to_bps(50) as u16)
+            .unwrap();
+        let summary = vault.get_summary().unwrap();
+        let fee_atoms = summary.pending_protocol_fee_atoms + summary.pending_curator_fee_atoms;
+        assert!(fee_atoms > 0);
+        vault.donate_supply(USDC(1_000.)).unwrap();
+        let attribution = vault.yield_attribution();
+        assert!(attribution.interest_atoms > 0);
+        assert_eq!(attribution.donated_atoms, USDC(1_000.));
+        let supplier_gain = vault.total_supply().unwrap() - deposit - fee_atoms;
+        // the protocol and curator fees are each rounded to atoms
+        assert!(attribution.total_atoms().abs_diff(supplier_gain) <= 2);
+        assert_eq_float!(
+            attribution.donated_ratio(),
+            USDC(1_000.) as f64 / supplier_gain as f64,
+            0.0001
+        );
+    }
+
+    #[test]
+    pub fn fixed_apr_accrues_whatever_the_utilisation() {
+        let curve = InterestRateCurveKind::new_fixed_apr(0.05);
+        curve.validate().unwrap();
+        for borrowed in [USDC(100_000.), USDC(900_000.)] {
+            let mut vault = SupplyVault {
+                interest_rate_curve: curve.into(),
+                ..create_usdc_supply_vault()
+            };
+            vault.lend(USDC(1_000_000.)).unwrap();
+            vault.borrow(borrowed).unwrap();
+            vault
+                .sync_clock(SECONDS_PER_YEAR as i64, UFixedPoint::zero(), 0)
+                .unwrap();
+            assert_eq_float!(
+                vault.last_borrow_interest_rate.approximate_apr().unwrap(),
+                0.05,
+                0.0001
+            );
+            assert_eq_float!(
+                vault.total_borrow().unwrap() as f64 / borrowed as f64,
+                0.05f64.exp(),
+                0.0001
+            );
+        }
+    }
+
+    #[test]
+    pub fn sync_clock_rejects_protocol_fee_share_above_one() {
+        let new_vault = || {
+            let mut vault = create_usdc_supply_vault();
+            vault.lend(USDC(1_000_000.)).unwrap();
+            vault.borrow(USDC(500_000.)).unwrap();
+            vault
+        };
+        let fee = bps_to_fixed_point(percent_to_bps(10));
+        let mut vault = new_vault();
+        assert_eq!(
+            vault
+                .sync_clock(SECONDS_PER_YEAR as i64, fee, ONE_IN_BPS as u16 + 1)
+                .unwrap_err(),
+            LendingError::BpsOutOfRange
+        );
+        assert_eq!(vault.pending_protocol_fee_shares, UFixedPoint::zero());
+        // a 100% protocol share leaves nothing to the curator
+        let mut vault = new_vault();
+        vault
+            .sync_clock(SECONDS_PER_YEAR as i64, fee, ONE_IN_BPS as u16)
+            .unwrap();
+        assert!(vault.pending_protocol_fee_shares > UFixedPoint::zero());
+        assert_eq!(vault.pending_curator_fee_shares, UFixedPoint::zero());
+    }
+
+    #[test]
+    pub fn sweep_fee_dust_keeps_owed_fees() {
+        let mut vault = create_usdc_supply_vault();
+        let lender_shares = vault.lend(USDC(1_000_000.)).unwrap();
+        vault.borrow(USDC(500_000.)).unwrap();
+        let fee = bps_to_fixed_point(percent_to_bps(10));
+        // many one second accruals, the protocol share of 1 bps never reaches a full atom
+        for now in 1..=10 {
+            vault.sync_clock(now, fee, 1).unwrap();
+        }
+        let to_atoms = |vault: &SupplyVault, shares| {
+            vault
+                .supply_shares_tracker
+                .shares_to_atoms(shares, RoundingMode::RoundDown)
+                .unwrap()
+        };
+        let protocol_dust = vault.pending_protocol_fee_shares;
+        let curator_fee_shares = vault.pending_curator_fee_shares;
+        assert!(!protocol_dust.is_zero());
+        assert_eq!(to_atoms(&vault, protocol_dust), 0);
+        let curator_fee_atoms = to_atoms(&vault, curator_fee_shares);
+        assert!(curator_fee_atoms > 0);
+        let total_supply = vault.total_supply().unwrap();
+        let lender_atoms = to_atoms(&vault, lender_shares);
+
+        assert_eq!(vault.sweep_fee_dust().unwrap(), protocol_dust);
+        assert_eq!(vault.pending_protocol_fee_shares, UFixedPoint::zero());
+        assert_eq!(vault.pending_curator_fee_shares, curator_fee_shares);
+        assert!(to_atoms(&vault, curator_fee_shares) >= curator_fee_atoms);
+        assert!(to_atoms(&vault, lender_shares) >= lender_atoms);
+        assert!(vault.total_supply().unwrap().abs_diff(total_supply) <= 1);
+        // nothing left to sweep
+        assert_eq!(vault.sweep_fee_dust().unwrap(), UFixedPoint::zero());
+        assert_eq!(vault.redeem_curator_fees().unwrap(), curator_fee_atoms);
+    }
+
+    #[test]
+    pub fn utilisation_headroom_at_low_and_near_cap_utilisation() {
+        let max_utilisation_rate = IFixedPoint::from_num(0.75);
+        let mut vault = create_usdc_supply_vault();
+        vault.lend(USDC(1_000_000.)).unwrap();
+        vault.borrow(USDC(100_000.)).unwrap();
+        crate::assert_eq_float!(
+            vault
+                .utilisation_headroom(max_utilisation_rate)
+                .unwrap()
+                .to_float(),
+            0.65
+        );
+        assert_eq!(
+            vault
+                .borrowable_atoms_within_utilisation_cap(max_utilisation_rate)
+                .unwrap(),
+            USDC(650_000.)
+        );
+
+        vault.borrow(USDC(645_000.)).unwrap();
+        crate::assert_eq_float!(
+            vault
+                .utilisation_headroom(max_utilisation_rate)
+                .unwrap()
+                .to_float(),
+            0.005
+        );
+        let borrowable = vault
+            .borrowable_atoms_within_utilisation_cap(max_utilisation_rate)
+            .unwrap();
+        assert_eq!(borrowable, USDC(5_000.));
+        vault.borrow(borrowable).unwrap();
+        assert!(vault.utilisation_rate().unwrap() <= max_utilisation_rate);
+
+        // interest pushes the utilisation above the cap
+        vault
+            .sync_clock(SECONDS_PER_YEAR as i64, UFixedPoint::zero(), 0)
+            .unwrap();
+        assert!(vault.utilisation_rate().unwrap() > max_utilisation_rate);
+        assert_eq!(
+            vault.utilisation_headroom(max_utilisation_rate).unwrap(),
+            IFixedPoint::zero()
+        );
+        assert_eq!(
+            vault
+                .borrowable_atoms_within_utilisation_cap(max_utilisation_rate)
+                .unwrap(),
+            0
+        );
+    }
+
+    /// Asserts that `rounded` is `exact` rounded in the direction of `mode`
+    fn assert_rounded(mode: RoundingMode, rounded: u64, exact: UFixedPoint) {
+        match mode {
+            RoundingMode::RoundDown => assert!(UFixedPoint::from_u64(rounded) <= exact),
+            RoundingMode::RoundUp => assert!(UFixedPoint::from_u64(rounded) >= exact),
+        }
+        assert_eq!(rounded, exact.as_u64_rounded(mode).unwrap());
+    }
+
+    /// Asserts that the value of minted shares is below (round down) or above
+    /// (round up) the atoms they were minted for
+    fn assert_minted(mode: RoundingMode, minted_value: UFixedPoint, atoms: IFixedPoint) {
+        let minted_value = IFixedPoint::try_from(minted_value).unwrap();
+        match mode {
+            RoundingMode::RoundDown => assert!(minted_value <= atoms),
+            RoundingMode::RoundUp => assert!(minted_value >= atoms),
+        }
+    }
+
+    #[test]
+    pub fn vault_operations_follow_rounding_policy() {
+        let mut vault = create_usdc_supply_vault();
+        vault.lend(USDC(1000.)).unwrap();
+        vault.borrow(USDC(700.)).unwrap();
+        vault
+            .sync_clock(
+                SECONDS_PER_YEAR as i64 / 3,
+                bps_to_fixed_point(percent_to_bps(10)),
+                percent_to_bps(50) as u16,
+            )
+            .unwrap();
+        let supply_atoms_per_share = vault.supply_shares_tracker.atoms_per_share();
+        let borrow_atoms_per_share = vault.borrow_shares_tracker.atoms_per_share();
+        assert_ne!(supply_atoms_per_share, UFixedPoint::from_u64(1));
+        assert_ne!(borrow_atoms_per_share, UFixedPoint::from_u64(1));
+
+        let deposit = 123_456_789;
+        let supply_shares = vault.lend(deposit).unwrap();
+        assert_minted(
+            ROUNDING_POLICY.supply_deposit,
+            supply_shares.safe_mul(supply_atoms_per_share).unwrap(),
+            IFixedPoint::from_u64(deposit),
+        );
+        let withdrawn = vault.withdraw_shares(supply_shares).unwrap();
+        assert_rounded(
+            ROUNDING_POLICY.supply_withdraw,
+            withdrawn,
+            supply_shares.safe_mul(supply_atoms_per_share).unwrap(),
+        );
+        assert!(withdrawn <= deposit);
+
+        let borrow = 98_765_431;
+        let borrow_shares = vault.borrow(borrow).unwrap();
+        assert_minted(
+            ROUNDING_POLICY.borrow,
+            borrow_shares.safe_mul(borrow_atoms_per_share).unwrap(),
+            IFixedPoint::from_u64(borrow),
+        );
+        let repaid = vault.repay_shares(borrow_shares).unwrap();
+        assert_rounded(
+            ROUNDING_POLICY.repay,
+            repaid,
+            borrow_shares.safe_mul(borrow_atoms_per_share).unwrap(),
+        );
+    }
+
+    #[test]
+    pub fn fee_accrual_follows_rounding_policy() {
+        let mut tracker = SharesTracker::new();
+        tracker.deposit_atoms(USDC(1234.5678)).unwrap();
+        let total_atoms_before = tracker.total_atoms(RoundingMode::RoundDown).unwrap();
+        let interest_rate = InterestRate::new(IFixedPoint::lit("0.0731"));
+        let fee = bps_to_fixed_point(percent_to_bps(15));
+        let fee_shares = tracker
+            .apply_interest_rate_with_fee(interest_rate, fee)
+            .unwrap();
+        let fee_atoms = interest_rate
+            .interest(total_atoms_before)
+            .unwrap()
+            .safe_mul(fee)
+            .unwrap();
+        assert_minted(
+            ROUNDING_POLICY.fee_accrual,
+            fee_shares.safe_mul(tracker.atoms_per_share()).unwrap(),
+            fee_atoms,
+        );
+    }
+
     #[test]
     pub fn check_donate_and_socialize() {
         let mut vault = create_usdc_supply_vault();
@@ -563,6 +1128,7 @@ pub mod tests {
         vault.socialize_loss(debt_shares).unwrap();
         let supply_after = vault.total_supply().unwrap();
         assert_eq!(supply_after, supply_before - debt);
+        assert_eq!(vault.socialized_loss_atoms(), debt);
     }
 
     #[test]
@@ -583,10 +1149,20 @@ pub mod tests {
         vault.borrow(USDC(500_000.)).unwrap();
         vault.sync_clock(100, UFixedPoint::zero(), 0).unwrap();
         assert_eq!(vault.last_update_unix_timestamp, 100);
-        vault.sync_clock(50, UFixedPoint::zero(), 0).unwrap();
+        let total_borrow = vault.total_borrow().unwrap();
+        // same timestamp is a no-op
+        vault.sync_clock(100, UFixedPoint::zero(), 0).unwrap();
+        assert_eq!(vault.total_borrow().unwrap(), total_borrow);
+        // backward clock is reported and leaves the vault untouched
+        assert_eq!(
+            vault.sync_clock(50, UFixedPoint::zero(), 0).unwrap_err(),
+            LendingError::ClockWentBackward
+        );
         assert_eq!(vault.last_update_unix_timestamp, 100);
+        assert_eq!(vault.total_borrow().unwrap(), total_borrow);
         vault.sync_clock(200, UFixedPoint::zero(), 0).unwrap();
         assert_eq!(vault.last_update_unix_timestamp, 200);
+        assert!(vault.total_borrow().unwrap() > total_borrow);
     }
 
     mod prop_tests {
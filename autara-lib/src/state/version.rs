@@ -0,0 +1,109 @@
+use crate::error::{LendingError, LendingResult, LendingResultExt};
+
+/// Layout version written in every Autara account on initialization.
+/// Accounts created before the version byte was introduced read as version 0,
+/// since the byte was carved out of zeroed padding.
+pub const CURRENT_ACCOUNT_VERSION: u8 = 1;
+
+/// Account whose layout is versioned and can be upgraded in place.
+/// Accounts keep the same size across versions, new fields are carved out of the padding
+/// so a migration only has to fill the defaults of the fields introduced by each version.
+pub trait VersionedAccount {
+    fn version(&self) -> u8;
+
+    fn set_version(&mut self, version: u8);
+
+    /// Upgrade the account from `version` to `version + 1`
+    fn migrate_from(&mut self, version: u8) -> LendingResult;
+
+    fn is_current_version(&self) -> bool {
+        self.version() == CURRENT_ACCOUNT_VERSION
+    }
+
+    /// Apply every migration required to bring the account to [CURRENT_ACCOUNT_VERSION].
+    /// Returns whether the account was upgraded, migrating a current account is a no-op.
+    fn migrate(&mut self) -> LendingResult<bool> {
+        let mut version = self.version();
+        if version > CURRENT_ACCOUNT_VERSION {
+            return Err(LendingError::UnsupportedAccountVersion.into())
+                .with_msg("account version is newer than the program");
+        }
+        if version == CURRENT_ACCOUNT_VERSION {
+            return Ok(false);
+        }
+        while version < CURRENT_ACCOUNT_VERSION {
+            self.migrate_from(version)?;
+            version += 1;
+        }
+        self.set_version(version);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use arch_program::pubkey::Pubkey;
+
+    use super::*;
+    use crate::state::{
+        curator_market_count::CuratorMarketCount, global_config::GlobalConfig,
+        market::tests::create_empty_btc_usdc_market, supply_position::SupplyPosition,
+    };
+
+    fn assert_migrates_to_current(account: &mut impl VersionedAccount) {
+        account.set_version(0);
+        assert!(!account.is_current_version());
+        assert!(account.migrate().unwrap());
+        assert_eq!(account.version(), CURRENT_ACCOUNT_VERSION);
+        assert!(!account.migrate().unwrap());
+        account.set_version(CURRENT_ACCOUNT_VERSION + 1);
+        assert_eq!(
+            account.migrate().unwrap_err(),
+            LendingError::UnsupportedAccountVersion
+        );
+    }
+
+    #[test]
+    fn initialized_accounts_are_current() {
+        let market = create_empty_btc_usdc_market();
+        assert!(market.is_current_version());
+        let supply_position = SupplyPosition::new(Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(supply_position.is_current_version());
+        let global_config = GlobalConfig::new(Pubkey::new_unique(), Pubkey::new_unique(), 100);
+        assert!(global_config.is_current_version());
+        let mut curator_market_count = CuratorMarketCount::default();
+        curator_market_count.initialize(Pubkey::new_unique());
+        assert!(curator_market_count.is_current_version());
+    }
+
+    #[test]
+    fn legacy_accounts_migrate_to_current_version() {
+        let mut market = create_empty_btc_usdc_market();
+        let config_before = *market.config();
+        assert_migrates_to_current(&mut market);
+        assert_eq!(market.config().curator(), config_before.curator());
+        assert_eq!(
+            market.config().lending_market_fee_in_bps(),
+            config_before.lending_market_fee_in_bps()
+        );
+
+        let authority = Pubkey::new_unique();
+        let mut supply_position = SupplyPosition::new(authority, Pubkey::new_unique());
+        assert_migrates_to_current(&mut supply_position);
+        assert_eq!(supply_position.authority(), &authority);
+
+        let admin = Pubkey::new_unique();
+        let mut global_config = GlobalConfig::new(admin, Pubkey::new_unique(), 100);
+        assert_migrates_to_current(&mut global_config);
+        assert_eq!(global_config.admin(), &admin);
+        assert_eq!(global_config.protocol_fee_share_in_bps(), 100);
+
+        let curator = Pubkey::new_unique();
+        let mut curator_market_count = CuratorMarketCount::default();
+        curator_market_count.initialize(curator);
+        curator_market_count.record_market(0).unwrap();
+        assert_migrates_to_current(&mut curator_market_count);
+        assert_eq!(curator_market_count.curator(), &curator);
+        assert_eq!(curator_market_count.market_count(), 1);
+    }
+}
@@ -10,6 +10,11 @@ impl TokenInfo {
     pub fn get_associated_token_address(&self, owner: &Pubkey) -> Pubkey {
         get_associated_token_address(owner, &self.mint)
     }
+
+    /// Amount of tokens represented by `atoms`, scaled by the mint decimals
+    pub fn ui_amount(&self, atoms: u64) -> f64 {
+        atoms as f64 / 10f64.powi(self.decimals as i32)
+    }
 }
 
 pub fn create_ata_ix(
@@ -1,5 +1,7 @@
 use arch_sdk::{with_secret_key_file, AsyncArchRpcClient, Config};
-use autara_lib::oracle::pyth::{PythPrice, PythPriceAccount};
+use autara_lib::oracle::pyth::{
+    PythPrice, PythPriceAccount, PYTH_PRICE_ACCOUNT_WITHOUT_BOUND_LEN,
+};
 use autara_pyth::get_pyth_account;
 use std::mem::size_of;
 
@@ -42,6 +44,8 @@ async fn main() {
             Ok(info) => {
                 let layout = if info.data.len() == size_of::<PythPriceAccount>() {
                     "NEW(PythPriceAccount)"
+                } else if info.data.len() == PYTH_PRICE_ACCOUNT_WITHOUT_BOUND_LEN {
+                    "NO_BOUND(PythPriceAccount without max_price_move_bps)"
                 } else if info.data.len() == size_of::<PythPrice>() {
                     "LEGACY(PythPrice)"
                 } else {
@@ -2,6 +2,7 @@
 
 mod metrics;
 
+pub use autara_oracle::{DEFAULT_MAX_PRICE_MOVE_BPS, NO_PRICE_MOVE_BOUND};
 pub use metrics::{start_metrics_server, PusherMetrics, HEALTH_MAX_STALE_SECS};
 
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
@@ -36,6 +37,27 @@ pub fn push_interval_from_env() -> Duration {
     Duration::from_secs(secs)
 }
 
+/// Optional oracle circuit breaker: the `MAX_PRICE_MOVE_BPS` env var if set
+/// (and a valid u16). It is stored when the pusher creates a feed, later pushes
+/// must repeat the same bound. Unset creates feeds with the oracle program's
+/// default bound.
+pub fn max_price_move_bps_from_env() -> Option<u16> {
+    std::env::var("MAX_PRICE_MOVE_BPS")
+        .ok()
+        .and_then(|v| v.parse::<u16>().ok())
+}
+
+/// Oracle instruction data: the price, followed by the circuit-breaker bound
+/// when one is configured. The oracle program keeps the bound a feed was
+/// created with.
+fn oracle_instruction_data(oracle: &PythPrice, max_price_move_bps: Option<u16>) -> Vec<u8> {
+    let mut data = bytemuck::bytes_of(oracle).to_vec();
+    if let Some(max_price_move_bps) = max_price_move_bps {
+        data.extend_from_slice(&max_price_move_bps.to_le_bytes());
+    }
+    data
+}
+
 pub async fn fetch_and_push_feeds(
     client: &AsyncArchRpcClient,
     autara_oracle_program_id: &Pubkey,
@@ -110,6 +132,7 @@ async fn push_once(
             }
         }
     };
+    let max_price_move_bps = max_price_move_bps_from_env();
     let ixs = match price_result
         .parsed
         .into_iter()
@@ -132,7 +155,7 @@ async fn push_once(
                         false,
                     ),
                 ],
-                data: bytemuck::bytes_of(&oracle_account).to_vec(),
+                data: oracle_instruction_data(&oracle_account, max_price_move_bps),
             })
         })
         .collect::<Result<Vec<_>, anyhow::Error>>()
@@ -198,6 +221,8 @@ pub struct AutaraPythPusherClient {
     pub client: AsyncArchRpcClient,
     pub autara_oracle_program_id: Pubkey,
     pub network: Network,
+    /// Circuit-breaker bound to create feeds with, see [`oracle_instruction_data`]
+    pub max_price_move_bps: Option<u16>,
 }
 
 impl AutaraPythPusherClient {
@@ -219,7 +244,7 @@ impl AutaraPythPusherClient {
                     false,
                 ),
             ],
-            data: bytemuck::bytes_of(oracle).to_vec(),
+            data: oracle_instruction_data(oracle, self.max_price_move_bps),
         }];
         build_and_send_tx(&self.client, &key, signer, &ixs, self.network).await
     }
@@ -2,9 +2,26 @@ use arch_program::{
     account::AccountInfo, program::invoke_signed_unchecked, program_error::ProgramError,
     pubkey::Pubkey, rent::minimum_rent, system_instruction,
 };
-use autara_lib::oracle::pyth::{PythPrice, PythPriceAccount};
+use autara_lib::oracle::pyth::{
+    PriceData, PythPrice, PythPriceAccount, PYTH_PRICE_ACCOUNT_WITHOUT_BOUND_LEN,
+};
 use std::mem::size_of;
 
+/// Returned when an update moves the price further than the circuit breaker
+/// stored in the feed account allows.
+pub const PRICE_MOVE_TOO_LARGE: u32 = 1;
+/// Returned when an update carries a circuit-breaker bound other than the one
+/// the feed was created with.
+pub const PRICE_MOVE_BOUND_MISMATCH: u32 = 2;
+/// The stored bound applies to each window of this many seconds elapsed since
+/// the stored price, so a feed that went quiet may catch up proportionally.
+pub const PRICE_MOVE_WINDOW_SECONDS: i64 = 60;
+/// Bound stored for feeds created without one.
+pub const DEFAULT_MAX_PRICE_MOVE_BPS: u16 = 1_000;
+/// Bound disabling the circuit breaker, for feeds that must be able to jump
+/// freely such as test feeds crashing prices on purpose.
+pub const NO_PRICE_MOVE_BOUND: u16 = u16::MAX;
+
 #[cfg(feature = "entrypoint")]
 arch_program::entrypoint!(process_instruction);
 
@@ -21,8 +38,7 @@ pub fn process_instruction<'a>(
     if !signer.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
-    let pyth_data: &PythPrice = bytemuck::try_from_bytes(instruction_data)
-        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    let (pyth_data, max_price_move_bps) = parse_instruction_data(instruction_data)?;
     let just_created = ensure_feed_account(program_id, accounts, signer, oracle, &pyth_data.id)?;
     let mut oracle_bytes_mut = oracle.try_borrow_mut_data()?;
     let oracle_data: &mut PythPriceAccount = bytemuck::from_bytes_mut(&mut oracle_bytes_mut);
@@ -32,9 +48,31 @@ pub fn process_instruction<'a>(
         pyth_data,
         just_created,
         clock().unix_timestamp,
+        max_price_move_bps,
     )
 }
 
+/// The instruction data is a `PythPrice`, optionally followed by a
+/// little-endian `u16` maximum price move in bps, stored when the feed is
+/// created.
+fn parse_instruction_data(
+    instruction_data: &[u8],
+) -> Result<(&PythPrice, Option<u16>), ProgramError> {
+    let price_len = size_of::<PythPrice>();
+    if instruction_data.len() < price_len {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let (price_bytes, rest) = instruction_data.split_at(price_len);
+    let pyth_data: &PythPrice =
+        bytemuck::try_from_bytes(price_bytes).map_err(|_| ProgramError::InvalidInstructionData)?;
+    let max_price_move_bps = match rest {
+        [] => None,
+        [lo, hi] => Some(u16::from_le_bytes([*lo, *hi])),
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+    Ok((pyth_data, max_price_move_bps))
+}
+
 /// Creates a new feed account, or migrates a pre-authority (120-byte) feed up to
 /// `PythPriceAccount` size. Returns whether the caller should treat this as a
 /// freshly created feed (bind `authority` to the signer).
@@ -66,46 +104,120 @@ fn ensure_feed_account<'a>(
     // one `PythPrice`. Grow in place on the next push; the pusher signer becomes
     // the feed authority (use a stable SIGNER_KEY_B64 before upgrading).
     if oracle.data_len() == size_of::<PythPrice>() {
-        let required = minimum_rent(new_len);
-        let current = oracle.lamports();
-        if current < required {
-            invoke_signed_unchecked(
-                &system_instruction::transfer(signer.key, oracle.key, required - current),
-                accounts,
-                &[],
-            )?;
-        }
-        oracle.realloc(new_len, true)?;
+        grow_feed_account(accounts, signer, oracle, new_len)?;
         return Ok(true);
     }
 
+    // Feeds created before the circuit-breaker bound was stored keep their
+    // authority; the zeroed bound is set by this push.
+    if oracle.data_len() == PYTH_PRICE_ACCOUNT_WITHOUT_BOUND_LEN {
+        grow_feed_account(accounts, signer, oracle, new_len)?;
+        return Ok(false);
+    }
+
     if oracle.data_len() != new_len {
         return Err(ProgramError::InvalidAccountData);
     }
     Ok(false)
 }
 
+fn grow_feed_account<'a>(
+    accounts: &'a [AccountInfo<'a>],
+    signer: &AccountInfo<'a>,
+    oracle: &AccountInfo<'a>,
+    new_len: usize,
+) -> Result<(), ProgramError> {
+    let required = minimum_rent(new_len);
+    let current = oracle.lamports();
+    if current < required {
+        invoke_signed_unchecked(
+            &system_instruction::transfer(signer.key, oracle.key, required - current),
+            accounts,
+            &[],
+        )?;
+    }
+    oracle.realloc(new_len, true)
+}
+
 /// Writes `pyth_data` into the oracle account, enforcing the authority
 /// binding: the signer that creates a feed becomes its authority, and every
 /// later update must be signed by that same authority.
+///
+/// The circuit-breaker bound is stored when the feed is created, from
+/// `max_price_move_bps` or [`DEFAULT_MAX_PRICE_MOVE_BPS`]. Later updates may
+/// only repeat it, and are rejected if they move the price by more than the
+/// bound for each [`PRICE_MOVE_WINDOW_SECONDS`] elapsed since the stored price.
 pub fn apply_price_update(
     oracle_data: &mut PythPriceAccount,
     signer: &Pubkey,
     pyth_data: &PythPrice,
     just_created: bool,
     unix_timestamp: i64,
+    max_price_move_bps: Option<u16>,
 ) -> Result<(), ProgramError> {
+    if max_price_move_bps == Some(0) {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if !just_created && oracle_data.authority != *signer {
+        return Err(ProgramError::IncorrectAuthority);
+    }
+    // a feed grown from the previous layout gets its bound like a new one
+    let stored_bound = oracle_data.max_price_move_bps;
+    let bound = if just_created || stored_bound == 0 {
+        max_price_move_bps.unwrap_or(DEFAULT_MAX_PRICE_MOVE_BPS)
+    } else if max_price_move_bps.is_some_and(|bound| bound != stored_bound) {
+        return Err(ProgramError::Custom(PRICE_MOVE_BOUND_MISMATCH));
+    } else {
+        stored_bound
+    };
+    let previous = &oracle_data.pyth_price.price;
+    let elapsed_windows = unix_timestamp
+        .saturating_sub(previous.publish_time)
+        .div_euclid(PRICE_MOVE_WINDOW_SECONDS)
+        .saturating_add(1)
+        .max(1);
+    if !just_created
+        && bound != NO_PRICE_MOVE_BOUND
+        && previous.price != 0
+        && exceeds_price_move(
+            previous,
+            &pyth_data.price,
+            bound as u128 * elapsed_windows as u128,
+        )
+    {
+        return Err(ProgramError::Custom(PRICE_MOVE_TOO_LARGE));
+    }
     if just_created {
         oracle_data.authority = *signer;
-    } else if oracle_data.authority != *signer {
-        return Err(ProgramError::IncorrectAuthority);
     }
+    oracle_data.max_price_move_bps = bound;
     oracle_data.pyth_price = *pyth_data;
     oracle_data.pyth_price.price.publish_time = unix_timestamp;
     oracle_data.pyth_price.ema_price.publish_time = unix_timestamp;
     Ok(())
 }
 
+/// Whether `next` is more than `max_price_move_bps` away from `previous`.
+/// Both prices are brought to the smaller exponent first; a move that cannot
+/// be represented is treated as too large.
+fn exceeds_price_move(previous: &PriceData, next: &PriceData, max_price_move_bps: u128) -> bool {
+    let rescale = |data: &PriceData, expo: i64| -> Option<u128> {
+        let shift = u32::try_from(data.expo.checked_sub(expo)?).ok()?;
+        (data.price as u128).checked_mul(10u128.checked_pow(shift)?)
+    };
+    let expo = previous.expo.min(next.expo);
+    let (Some(previous), Some(next)) = (rescale(previous, expo), rescale(next, expo)) else {
+        return true;
+    };
+    let max_move = previous
+        .checked_mul(max_price_move_bps)
+        .map(|bound| bound / 10_000);
+    match max_move {
+        Some(max_move) => previous.abs_diff(next) > max_move,
+        None => true,
+    }
+}
+
 use arch_program::clock::Clock;
 
 pub fn clock() -> Clock {
@@ -136,7 +248,15 @@ mod tests {
     #[test]
     fn feed_creation_sets_authority() {
         let mut account = PythPriceAccount::zeroed();
-        apply_price_update(&mut account, &CREATOR, &price([7u8; 32], 100), true, 1000).unwrap();
+        apply_price_update(
+            &mut account,
+            &CREATOR,
+            &price([7u8; 32], 100),
+            true,
+            1000,
+            None,
+        )
+        .unwrap();
         assert_eq!(account.authority, CREATOR);
         assert_eq!(account.pyth_price.price.price, 100);
         assert_eq!(account.pyth_price.price.publish_time, 1000);
@@ -145,8 +265,24 @@ mod tests {
     #[test]
     fn authorized_push_succeeds() {
         let mut account = PythPriceAccount::zeroed();
-        apply_price_update(&mut account, &CREATOR, &price([7u8; 32], 100), true, 1000).unwrap();
-        apply_price_update(&mut account, &CREATOR, &price([7u8; 32], 200), false, 2000).unwrap();
+        apply_price_update(
+            &mut account,
+            &CREATOR,
+            &price([7u8; 32], 100),
+            true,
+            1000,
+            None,
+        )
+        .unwrap();
+        apply_price_update(
+            &mut account,
+            &CREATOR,
+            &price([7u8; 32], 200),
+            false,
+            2000,
+            None,
+        )
+        .unwrap();
         assert_eq!(account.authority, CREATOR);
         assert_eq!(account.pyth_price.price.price, 200);
         assert_eq!(account.pyth_price.price.publish_time, 2000);
@@ -155,9 +291,24 @@ mod tests {
     #[test]
     fn unauthorized_push_fails() {
         let mut account = PythPriceAccount::zeroed();
-        apply_price_update(&mut account, &CREATOR, &price([7u8; 32], 100), true, 1000).unwrap();
-        let err = apply_price_update(&mut account, &INTRUDER, &price([7u8; 32], 1), false, 2000)
-            .unwrap_err();
+        apply_price_update(
+            &mut account,
+            &CREATOR,
+            &price([7u8; 32], 100),
+            true,
+            1000,
+            None,
+        )
+        .unwrap();
+        let err = apply_price_update(
+            &mut account,
+            &INTRUDER,
+            &price([7u8; 32], 1),
+            false,
+            2000,
+            None,
+        )
+        .unwrap_err();
         assert_eq!(err, ProgramError::IncorrectAuthority);
         // the rejected update must not have touched the stored price
         assert_eq!(account.authority, CREATOR);
@@ -172,11 +323,289 @@ mod tests {
         let mut account = PythPriceAccount::zeroed();
         // Simulate preserved legacy price bytes + zeroed authority after realloc.
         account.pyth_price = price([7u8; 32], 50);
-        apply_price_update(&mut account, &CREATOR, &price([7u8; 32], 75), true, 3000).unwrap();
+        apply_price_update(
+            &mut account,
+            &CREATOR,
+            &price([7u8; 32], 75),
+            true,
+            3000,
+            None,
+        )
+        .unwrap();
         assert_eq!(account.authority, CREATOR);
         assert_eq!(account.pyth_price.price.price, 75);
-        let err = apply_price_update(&mut account, &INTRUDER, &price([7u8; 32], 1), false, 4000)
+        let err = apply_price_update(
+            &mut account,
+            &INTRUDER,
+            &price([7u8; 32], 1),
+            false,
+            4000,
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err, ProgramError::IncorrectAuthority);
+    }
+
+    #[test]
+    fn feed_creation_stores_the_price_move_bound() {
+        let mut account = PythPriceAccount::zeroed();
+        apply_price_update(
+            &mut account,
+            &CREATOR,
+            &price([7u8; 32], 100),
+            true,
+            1000,
+            None,
+        )
+        .unwrap();
+        assert_eq!(account.max_price_move_bps, DEFAULT_MAX_PRICE_MOVE_BPS);
+        let mut account = PythPriceAccount::zeroed();
+        apply_price_update(
+            &mut account,
+            &CREATOR,
+            &price([7u8; 32], 100),
+            true,
+            1000,
+            Some(5000),
+        )
+        .unwrap();
+        assert_eq!(account.max_price_move_bps, 5000);
+        let err = apply_price_update(
+            &mut PythPriceAccount::zeroed(),
+            &CREATOR,
+            &price([7u8; 32], 100),
+            true,
+            1000,
+            Some(0),
+        )
+        .unwrap_err();
+        assert_eq!(err, ProgramError::InvalidInstructionData);
+    }
+
+    #[test]
+    fn price_move_within_bound_is_accepted() {
+        let mut account = PythPriceAccount::zeroed();
+        apply_price_update(
+            &mut account,
+            &CREATOR,
+            &price([7u8; 32], 100),
+            true,
+            1000,
+            Some(5000),
+        )
+        .unwrap();
+        apply_price_update(
+            &mut account,
+            &CREATOR,
+            &price([7u8; 32], 140),
+            false,
+            1010,
+            None,
+        )
+        .unwrap();
+        assert_eq!(account.pyth_price.price.price, 140);
+    }
+
+    #[test]
+    fn price_jump_beyond_bound_is_rejected() {
+        let mut account = PythPriceAccount::zeroed();
+        apply_price_update(
+            &mut account,
+            &CREATOR,
+            &price([7u8; 32], 100),
+            true,
+            1000,
+            Some(5000),
+        )
+        .unwrap();
+        // the stored bound applies whether or not the update repeats it
+        for (jump, max_price_move_bps) in [(151, None), (49, Some(5000))] {
+            let err = apply_price_update(
+                &mut account,
+                &CREATOR,
+                &price([7u8; 32], jump),
+                false,
+                1010,
+                max_price_move_bps,
+            )
             .unwrap_err();
+            assert_eq!(err, ProgramError::Custom(PRICE_MOVE_TOO_LARGE));
+        }
+        // the rejected updates must not have touched the stored price
+        assert_eq!(account.pyth_price.price.price, 100);
+        assert_eq!(account.pyth_price.price.publish_time, 1000);
+    }
+
+    #[test]
+    fn price_move_bound_scales_with_elapsed_time() {
+        let mut account = PythPriceAccount::zeroed();
+        apply_price_update(
+            &mut account,
+            &CREATOR,
+            &price([7u8; 32], 100),
+            true,
+            1000,
+            Some(5000),
+        )
+        .unwrap();
+        // two windows elapsed allow twice the bound
+        apply_price_update(
+            &mut account,
+            &CREATOR,
+            &price([7u8; 32], 199),
+            false,
+            1000 + PRICE_MOVE_WINDOW_SECONDS,
+            None,
+        )
+        .unwrap();
+        let err = apply_price_update(
+            &mut account,
+            &CREATOR,
+            &price([7u8; 32], 400),
+            false,
+            1010 + PRICE_MOVE_WINDOW_SECONDS,
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err, ProgramError::Custom(PRICE_MOVE_TOO_LARGE));
+        apply_price_update(
+            &mut account,
+            &CREATOR,
+            &price([7u8; 32], 400),
+            false,
+            1000 + 4 * PRICE_MOVE_WINDOW_SECONDS,
+            None,
+        )
+        .unwrap();
+        assert_eq!(account.pyth_price.price.price, 400);
+    }
+
+    #[test]
+    fn update_cannot_change_the_price_move_bound() {
+        let mut account = PythPriceAccount::zeroed();
+        apply_price_update(
+            &mut account,
+            &CREATOR,
+            &price([7u8; 32], 100),
+            true,
+            1000,
+            Some(5000),
+        )
+        .unwrap();
+        let err = apply_price_update(
+            &mut account,
+            &CREATOR,
+            &price([7u8; 32], 400),
+            false,
+            1010,
+            Some(NO_PRICE_MOVE_BOUND),
+        )
+        .unwrap_err();
+        assert_eq!(err, ProgramError::Custom(PRICE_MOVE_BOUND_MISMATCH));
+        assert_eq!(account.max_price_move_bps, 5000);
+        assert_eq!(account.pyth_price.price.price, 100);
+    }
+
+    #[test]
+    fn no_price_move_bound_lets_any_jump_through() {
+        let mut account = PythPriceAccount::zeroed();
+        apply_price_update(
+            &mut account,
+            &CREATOR,
+            &price([7u8; 32], 100),
+            true,
+            1000,
+            Some(NO_PRICE_MOVE_BOUND),
+        )
+        .unwrap();
+        apply_price_update(
+            &mut account,
+            &CREATOR,
+            &price([7u8; 32], 1),
+            false,
+            1001,
+            None,
+        )
+        .unwrap();
+        assert_eq!(account.pyth_price.price.price, 1);
+    }
+
+    /// Feeds grown from the layout without a stored bound keep their authority
+    /// and get their bound from the next push.
+    #[test]
+    fn grown_feed_gets_its_bound_from_the_next_push() {
+        let mut account = PythPriceAccount::zeroed();
+        account.pyth_price = price([7u8; 32], 100);
+        account.pyth_price.price.publish_time = 1000;
+        account.authority = CREATOR;
+        let err = apply_price_update(
+            &mut account,
+            &INTRUDER,
+            &price([7u8; 32], 100),
+            false,
+            1010,
+            Some(5000),
+        )
+        .unwrap_err();
         assert_eq!(err, ProgramError::IncorrectAuthority);
+        assert_eq!(account.max_price_move_bps, 0);
+        // the bound is enforced from the push that sets it
+        let err = apply_price_update(
+            &mut account,
+            &CREATOR,
+            &price([7u8; 32], 400),
+            false,
+            1010,
+            Some(5000),
+        )
+        .unwrap_err();
+        assert_eq!(err, ProgramError::Custom(PRICE_MOVE_TOO_LARGE));
+        assert_eq!(account.max_price_move_bps, 0);
+        apply_price_update(
+            &mut account,
+            &CREATOR,
+            &price([7u8; 32], 101),
+            false,
+            1010,
+            Some(5000),
+        )
+        .unwrap();
+        assert_eq!(account.authority, CREATOR);
+        assert_eq!(account.max_price_move_bps, 5000);
+    }
+
+    #[test]
+    fn price_move_compares_across_exponents() {
+        let mut account = PythPriceAccount::zeroed();
+        apply_price_update(
+            &mut account,
+            &CREATOR,
+            &price([7u8; 32], 100),
+            true,
+            1000,
+            Some(1000),
+        )
+        .unwrap();
+        let mut rescaled = price([7u8; 32], 10_500);
+        rescaled.price.expo = -2;
+        apply_price_update(&mut account, &CREATOR, &rescaled, false, 1010, None).unwrap();
+        let mut jump = price([7u8; 32], 2);
+        jump.price.expo = 2;
+        let err = apply_price_update(&mut account, &CREATOR, &jump, false, 1020, None).unwrap_err();
+        assert_eq!(err, ProgramError::Custom(PRICE_MOVE_TOO_LARGE));
+    }
+
+    #[test]
+    fn trailing_bytes_carry_the_max_price_move() {
+        let pyth = price([7u8; 32], 100);
+        let mut data = bytemuck::bytes_of(&pyth).to_vec();
+        assert_eq!(parse_instruction_data(&data).unwrap().1, None);
+        data.extend_from_slice(&5000u16.to_le_bytes());
+        assert_eq!(parse_instruction_data(&data).unwrap().1, Some(5000));
+        data.push(0);
+        assert_eq!(
+            parse_instruction_data(&data).unwrap_err(),
+            ProgramError::InvalidInstructionData
+        );
     }
 }
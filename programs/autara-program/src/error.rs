@@ -56,6 +56,7 @@ pub enum LendingAccountValidationError {
     InvalidMarketVault,
     InvalidMintForTokenAccount,
     InvalidProtocolAuthority,
+    InvalidCuratorMarketCount,
 }
 
 pub const ACCOUNT_VALIDATION_ERROR_OFFSET: u32 = 6000;
@@ -10,6 +10,7 @@ use autara_program_lib::accounts::{
 
 use crate::{
     error::{LendingAccountValidationError, LendingProgramResult},
+    ixs::next_market_oracle_accounts,
     state::AutaraAccount,
 };
 
@@ -22,8 +23,8 @@ pub struct BorrowDepositAplAccounts<'a, 'b> {
     pub authority_collateral_ata: PackedOwnedAccount<'a, 'b, BoxedTokenAccount>,
     pub market_collateral_vault: PackedOwnedAccount<'a, 'b, BoxedTokenAccount>,
     pub apl_token_program: Program<'a, 'b, AplTokenProgram>,
-    pub supply_oracle: &'b AccountInfo<'a>,
-    pub collateral_oracle: &'b AccountInfo<'a>,
+    pub supply_oracle: Option<&'b AccountInfo<'a>>,
+    pub collateral_oracle: Option<&'b AccountInfo<'a>>,
 }
 
 impl<'a, 'b> BorrowDepositAplAccounts<'a, 'b> {
@@ -33,7 +34,7 @@ impl<'a, 'b> BorrowDepositAplAccounts<'a, 'b> {
     where
         'a: 'b,
     {
-        let this = Self {
+        let mut this = Self {
             market: next_account_info(accounts)?.try_into()?,
             borrow_position: next_account_info(accounts)?.try_into()?,
             authority: next_account_info(accounts)?.try_into()?,
@@ -42,15 +43,18 @@ impl<'a, 'b> BorrowDepositAplAccounts<'a, 'b> {
             authority_collateral_ata: next_account_info(accounts)?.try_into()?,
             market_collateral_vault: next_account_info(accounts)?.try_into()?,
             apl_token_program: next_account_info(accounts)?.try_into()?,
-            supply_oracle: next_account_info(accounts)?,
-            collateral_oracle: next_account_info(accounts)?,
+            supply_oracle: None,
+            collateral_oracle: None,
         };
+        (this.supply_oracle, this.collateral_oracle) =
+            next_market_oracle_accounts(accounts, &this.market.load_ref())?;
         this.validate()?;
         Ok(this)
     }
 
     pub fn validate(&self) -> LendingProgramResult<()> {
         let borrow_position = self.borrow_position.load_ref();
+        borrow_position.check_not_reentrant()?;
         let market = self.market.load_ref();
         if borrow_position.authority() != self.authority.key {
             return Err(LendingAccountValidationError::InvalidAuthority.into());
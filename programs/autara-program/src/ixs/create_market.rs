@@ -1,6 +1,6 @@
 use apl_token::state::Mint;
 use arch_program::account::{next_account_info, AccountInfo};
-use autara_lib::state::global_config::GlobalConfig;
+use autara_lib::{ixs::CreateMarketInstruction, state::global_config::GlobalConfig};
 use autara_program_lib::accounts::{
     packed::PackedOwnedAccount,
     program::{Program, SystemProgram},
@@ -9,7 +9,7 @@ use autara_program_lib::accounts::{
     zero_copy::ZeroCopyOwnedAccount,
 };
 
-use crate::{error::LendingProgramResult, state::AutaraAccount};
+use crate::{error::LendingProgramResult, ixs::next_oracle_accounts, state::AutaraAccount};
 
 pub struct CreateMarketAccounts<'a, 'b> {
     pub curator: Signer<'a, 'b>,
@@ -23,16 +23,20 @@ pub struct CreateMarketAccounts<'a, 'b> {
     pub apl_token_program: Program<'a, 'b, AplTokenProgram>,
     pub associated_token_program: Program<'a, 'b, AplAssociatedTokenProgram>,
     pub system_program: Program<'a, 'b, SystemProgram>,
+    pub curator_market_count: &'b AccountInfo<'a>,
+    pub supply_oracle: Option<&'b AccountInfo<'a>>,
+    pub collateral_oracle: Option<&'b AccountInfo<'a>>,
 }
 
 impl<'a, 'b> CreateMarketAccounts<'a, 'b> {
     pub fn from_accounts(
         accounts: &mut impl Iterator<Item = &'b AccountInfo<'a>>,
+        data: &CreateMarketInstruction,
     ) -> LendingProgramResult<Self>
     where
         'a: 'b,
     {
-        let this = Self {
+        let mut this = Self {
             curator: next_account_info(accounts)?.try_into()?,
             payer: next_account_info(accounts)?.try_into()?,
             global_config: next_account_info(accounts)?.try_into()?,
@@ -44,7 +48,15 @@ impl<'a, 'b> CreateMarketAccounts<'a, 'b> {
             apl_token_program: next_account_info(accounts)?.try_into()?,
             associated_token_program: next_account_info(accounts)?.try_into()?,
             system_program: next_account_info(accounts)?.try_into()?,
+            curator_market_count: next_account_info(accounts)?,
+            supply_oracle: None,
+            collateral_oracle: None,
         };
+        (this.supply_oracle, this.collateral_oracle) = next_oracle_accounts(
+            accounts,
+            &data.supply_oracle_config,
+            &data.collateral_oracle_config,
+        )?;
         this.validate()?;
         Ok(this)
     }
@@ -56,6 +68,13 @@ impl<'a, 'b> CreateMarketAccounts<'a, 'b> {
                 crate::error::LendingAccountValidationError::InvalidProtocolAuthority.into(),
             );
         }
+        let (expected_curator_market_count, _) =
+            autara_lib::pda::find_curator_market_count_pda(&crate::id(), self.curator.key);
+        if *self.curator_market_count.key != expected_curator_market_count {
+            return Err(
+                crate::error::LendingAccountValidationError::InvalidCuratorMarketCount.into(),
+            );
+        }
         Ok(())
     }
 }
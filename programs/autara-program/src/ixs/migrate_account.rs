@@ -0,0 +1,37 @@
+use arch_program::account::{next_account_info, AccountInfo};
+use autara_lib::state::{
+    borrow_position::BorrowPosition, curator_market_count::CuratorMarketCount,
+    global_config::GlobalConfig, market::Market, supply_position::SupplyPosition,
+};
+use autara_program_lib::accounts::zero_copy::ZeroCopyOwnedAccountMut;
+
+use crate::{error::LendingProgramResult, state::AutaraAccount};
+
+/// Any Autara account, discriminated by its size
+pub enum MigrateAccountAccounts<'a, 'b> {
+    Market(ZeroCopyOwnedAccountMut<'a, 'b, AutaraAccount<Market>>),
+    SupplyPosition(ZeroCopyOwnedAccountMut<'a, 'b, AutaraAccount<SupplyPosition>>),
+    BorrowPosition(ZeroCopyOwnedAccountMut<'a, 'b, AutaraAccount<BorrowPosition>>),
+    GlobalConfig(ZeroCopyOwnedAccountMut<'a, 'b, AutaraAccount<GlobalConfig>>),
+    CuratorMarketCount(ZeroCopyOwnedAccountMut<'a, 'b, AutaraAccount<CuratorMarketCount>>),
+}
+
+impl<'a, 'b> MigrateAccountAccounts<'a, 'b> {
+    pub fn from_accounts(
+        accounts: &mut impl Iterator<Item = &'b AccountInfo<'a>>,
+    ) -> LendingProgramResult<Self>
+    where
+        'a: 'b,
+    {
+        let account = next_account_info(accounts)?;
+        let accounts = ZeroCopyOwnedAccountMut::try_from(account)
+            .map(Self::Market)
+            .or_else(|_| ZeroCopyOwnedAccountMut::try_from(account).map(Self::SupplyPosition))
+            .or_else(|_| ZeroCopyOwnedAccountMut::try_from(account).map(Self::BorrowPosition))
+            .or_else(|_| ZeroCopyOwnedAccountMut::try_from(account).map(Self::GlobalConfig))
+            .or_else(|_| {
+                ZeroCopyOwnedAccountMut::try_from(account).map(Self::CuratorMarketCount)
+            })?;
+        Ok(accounts)
+    }
+}
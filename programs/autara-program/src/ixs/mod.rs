@@ -8,11 +8,15 @@ pub mod deposit_apl_collateral;
 pub mod donate_supply;
 pub mod liquidate;
 pub mod log;
+pub mod migrate_account;
 pub mod redeem_curator_fees;
 pub mod redeem_protocol_fees;
 pub mod repay_apl;
+pub mod set_curator_market_count;
 pub mod socialize_loss;
 pub mod supply_apl;
+pub mod sweep_fee_dust;
+pub mod sync_market;
 pub mod update_config;
 pub mod update_global_config;
 pub mod withdraw_apl_collateral;
@@ -29,9 +33,13 @@ pub use deposit_apl_collateral::DepositAplCollateralAccounts;
 pub use donate_supply::DonateSupplyAccounts;
 pub use liquidate::LiquidateAccounts;
 pub use log::LogAccounts;
+pub use migrate_account::MigrateAccountAccounts;
 pub use repay_apl::RepayAplAccounts;
+pub use set_curator_market_count::SetCuratorMarketCountAccounts;
 pub use socialize_loss::SocializeLossAccounts;
 pub use supply_apl::SupplyAplAccounts;
+pub use sweep_fee_dust::SweepFeeDustAccounts;
+pub use sync_market::SyncMarketAccounts;
 pub use update_config::UpdateConfigAccounts;
 pub use update_global_config::UpdateGlobalConfigAccounts;
 pub use withdraw_apl_collateral::WithdrawAplCollateralAccounts;
@@ -40,3 +48,48 @@ pub use withdraw_supply::WithdrawSupplyAccounts;
 
 #[cfg(test)]
 pub mod test_utils;
+
+use arch_program::account::{next_account_info, AccountInfo};
+use autara_lib::{oracle::oracle_config::OracleConfig, state::market::Market};
+
+use crate::error::LendingProgramResult;
+
+/// Oracle accounts of an instruction, none when the oracle is constant
+pub type OracleAccounts<'a, 'b> = (Option<&'b AccountInfo<'a>>, Option<&'b AccountInfo<'a>>);
+
+/// Next supply and collateral oracle accounts, reading no account for a constant oracle
+/// since it has no feed
+pub fn next_oracle_accounts<'a, 'b>(
+    accounts: &mut impl Iterator<Item = &'b AccountInfo<'a>>,
+    supply_oracle_config: &OracleConfig,
+    collateral_oracle_config: &OracleConfig,
+) -> LendingProgramResult<OracleAccounts<'a, 'b>>
+where
+    'a: 'b,
+{
+    let mut next_oracle_account = |oracle_config: &OracleConfig| {
+        if oracle_config.is_constant() {
+            return Ok(None);
+        }
+        next_account_info(accounts).map(Some)
+    };
+    Ok((
+        next_oracle_account(supply_oracle_config)?,
+        next_oracle_account(collateral_oracle_config)?,
+    ))
+}
+
+/// Same as [next_oracle_accounts] for the oracles configured in `market`
+pub fn next_market_oracle_accounts<'a, 'b>(
+    accounts: &mut impl Iterator<Item = &'b AccountInfo<'a>>,
+    market: &Market,
+) -> LendingProgramResult<OracleAccounts<'a, 'b>>
+where
+    'a: 'b,
+{
+    next_oracle_accounts(
+        accounts,
+        market.supply_vault().oracle_config(),
+        market.collateral_vault().oracle_config(),
+    )
+}
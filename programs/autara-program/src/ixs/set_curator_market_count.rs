@@ -0,0 +1,58 @@
+use arch_program::account::{next_account_info, AccountInfo};
+use autara_lib::{ixs::SetCuratorMarketCountInstruction, state::global_config::GlobalConfig};
+use autara_program_lib::accounts::{
+    program::{Program, SystemProgram},
+    signer::Signer,
+    zero_copy::ZeroCopyOwnedAccount,
+};
+
+use crate::{
+    error::{LendingAccountValidationError, LendingProgramResult},
+    state::AutaraAccount,
+};
+
+pub struct SetCuratorMarketCountAccounts<'a, 'b> {
+    pub admin: Signer<'a, 'b>,
+    pub global_config: ZeroCopyOwnedAccount<'a, 'b, AutaraAccount<GlobalConfig>>,
+    pub curator_market_count: &'b AccountInfo<'a>,
+    pub system_program: Program<'a, 'b, SystemProgram>,
+}
+
+impl<'a, 'b> SetCuratorMarketCountAccounts<'a, 'b> {
+    pub fn from_accounts(
+        accounts: &mut impl Iterator<Item = &'b AccountInfo<'a>>,
+        data: &SetCuratorMarketCountInstruction,
+    ) -> LendingProgramResult<Self>
+    where
+        'a: 'b,
+    {
+        let this = Self {
+            admin: next_account_info(accounts)?.try_into()?,
+            global_config: next_account_info(accounts)?.try_into()?,
+            curator_market_count: next_account_info(accounts)?,
+            system_program: next_account_info(accounts)?.try_into()?,
+        };
+        this.validate(data)?;
+        Ok(this)
+    }
+
+    pub fn validate(&self, data: &SetCuratorMarketCountInstruction) -> LendingProgramResult<()> {
+        let (expected_global_config, _) = autara_lib::pda::find_global_config_pda(&crate::id());
+        if *self.global_config.key() != expected_global_config {
+            return Err(LendingAccountValidationError::InvalidProtocolAuthority.into());
+        }
+        if !self
+            .global_config
+            .load_ref()
+            .can_update_config(self.admin.key)
+        {
+            return Err(LendingAccountValidationError::InvalidAuthority.into());
+        }
+        let (expected_curator_market_count, _) =
+            autara_lib::pda::find_curator_market_count_pda(&crate::id(), &data.curator);
+        if *self.curator_market_count.key != expected_curator_market_count {
+            return Err(LendingAccountValidationError::InvalidCuratorMarketCount.into());
+        }
+        Ok(())
+    }
+}
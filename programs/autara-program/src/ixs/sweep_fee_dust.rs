@@ -0,0 +1,80 @@
+use arch_program::account::{next_account_info, AccountInfo};
+use autara_lib::state::{global_config::GlobalConfig, market::Market};
+use autara_program_lib::accounts::{
+    signer::Signer,
+    zero_copy::{ZeroCopyOwnedAccount, ZeroCopyOwnedAccountMut},
+};
+
+use crate::{
+    error::{LendingAccountValidationError, LendingProgramResult},
+    state::AutaraAccount,
+};
+
+pub struct SweepFeeDustAccounts<'a, 'b> {
+    pub admin: Signer<'a, 'b>,
+    pub global_config: ZeroCopyOwnedAccount<'a, 'b, AutaraAccount<GlobalConfig>>,
+    pub market: ZeroCopyOwnedAccountMut<'a, 'b, AutaraAccount<Market>>,
+}
+
+impl<'a, 'b> SweepFeeDustAccounts<'a, 'b> {
+    pub fn from_accounts(
+        accounts: &mut impl Iterator<Item = &'b AccountInfo<'a>>,
+    ) -> LendingProgramResult<Self>
+    where
+        'a: 'b,
+    {
+        let this = Self {
+            admin: next_account_info(accounts)?.try_into()?,
+            global_config: next_account_info(accounts)?.try_into()?,
+            market: next_account_info(accounts)?.try_into()?,
+        };
+        this.validate()?;
+        Ok(this)
+    }
+
+    pub fn validate(&self) -> LendingProgramResult<()> {
+        let (expected_global_config, _) = autara_lib::pda::find_global_config_pda(&crate::id());
+        if *self.global_config.key() != expected_global_config {
+            return Err(LendingAccountValidationError::InvalidProtocolAuthority.into());
+        }
+        if !self
+            .global_config
+            .load_ref()
+            .can_update_config(self.admin.key)
+        {
+            return Err(LendingAccountValidationError::InvalidAuthority.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::ixs::test_utils::AutaraAccounts;
+
+    #[test]
+    pub fn validate_correct_accounts() {
+        let account_set = AutaraAccounts::new();
+        let accounts = [
+            account_set.global_admin.clone(),
+            account_set.global_config.clone(),
+            account_set.market.clone(),
+        ];
+        SweepFeeDustAccounts::from_accounts(&mut accounts.iter()).unwrap();
+    }
+
+    #[test]
+    pub fn validate_fails_if_signer_is_not_admin() {
+        let account_set = AutaraAccounts::new();
+        let accounts = [
+            account_set.user.clone(),
+            account_set.global_config.clone(),
+            account_set.market.clone(),
+        ];
+        let Err(err) = SweepFeeDustAccounts::from_accounts(&mut accounts.iter()) else {
+            panic!("Expected an error, but got Ok");
+        };
+        assert_eq!(err, LendingAccountValidationError::InvalidAuthority);
+    }
+}
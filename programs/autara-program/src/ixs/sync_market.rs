@@ -0,0 +1,22 @@
+use arch_program::account::{next_account_info, AccountInfo};
+use autara_lib::state::market::Market;
+use autara_program_lib::accounts::zero_copy::ZeroCopyOwnedAccountMut;
+
+use crate::{error::LendingProgramResult, state::AutaraAccount};
+
+pub struct SyncMarketAccounts<'a, 'b> {
+    pub market: ZeroCopyOwnedAccountMut<'a, 'b, AutaraAccount<Market>>,
+}
+
+impl<'a, 'b> SyncMarketAccounts<'a, 'b> {
+    pub fn from_accounts(
+        accounts: &mut impl Iterator<Item = &'b AccountInfo<'a>>,
+    ) -> LendingProgramResult<Self>
+    where
+        'a: 'b,
+    {
+        Ok(Self {
+            market: next_account_info(accounts)?.try_into()?,
+        })
+    }
+}
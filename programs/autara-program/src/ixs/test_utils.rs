@@ -57,8 +57,9 @@ impl AutaraAccounts {
                 },
                 0.05.into(),
                 1000000000000,
-                0,
+                autara_lib::math::bps::Bps::ZERO,
                 &global_config_data,
+                0,
             )
             .unwrap();
         let collateral_mint_address = Pubkey::new_unique();
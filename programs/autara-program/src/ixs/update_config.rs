@@ -1,34 +1,50 @@
 use arch_program::account::{next_account_info, AccountInfo};
+use autara_lib::ixs::UpdateConfigInstruction;
 use autara_lib::state::global_config::GlobalConfig;
 use autara_lib::state::market::Market;
 use autara_program_lib::accounts::signer::Signer;
 use autara_program_lib::accounts::zero_copy::{ZeroCopyOwnedAccount, ZeroCopyOwnedAccountMut};
 
 use crate::error::{LendingAccountValidationError, LendingProgramResult};
+use crate::ixs::next_oracle_accounts;
 use crate::state::AutaraAccount;
 
 pub struct UpdateConfigAccounts<'a, 'b> {
     pub market: ZeroCopyOwnedAccountMut<'a, 'b, AutaraAccount<Market>>,
     pub global_config: ZeroCopyOwnedAccount<'a, 'b, AutaraAccount<GlobalConfig>>,
     pub curator: Signer<'a, 'b>,
-    pub updated_supply_oracle: &'b AccountInfo<'a>,
-    pub updated_collateral_oracle: &'b AccountInfo<'a>,
+    pub updated_supply_oracle: Option<&'b AccountInfo<'a>>,
+    pub updated_collateral_oracle: Option<&'b AccountInfo<'a>>,
 }
 
 impl<'a, 'b> UpdateConfigAccounts<'a, 'b> {
     pub fn from_accounts(
         accounts: &mut impl Iterator<Item = &'b AccountInfo<'a>>,
+        data: &UpdateConfigInstruction,
     ) -> LendingProgramResult<Self>
     where
         'a: 'b,
     {
-        let this = Self {
+        let mut this = Self {
             market: next_account_info(accounts)?.try_into()?,
             global_config: next_account_info(accounts)?.try_into()?,
             curator: next_account_info(accounts)?.try_into()?,
-            updated_supply_oracle: next_account_info(accounts)?,
-            updated_collateral_oracle: next_account_info(accounts)?,
+            updated_supply_oracle: None,
+            updated_collateral_oracle: None,
         };
+        {
+            // the oracles the market is left with once the update is applied
+            let market = this.market.load_ref();
+            (this.updated_supply_oracle, this.updated_collateral_oracle) = next_oracle_accounts(
+                accounts,
+                data.supply_oracle_config
+                    .as_ref()
+                    .unwrap_or(market.supply_vault().oracle_config()),
+                data.collateral_oracle_config
+                    .as_ref()
+                    .unwrap_or(market.collateral_vault().oracle_config()),
+            )?;
+        }
         this.validate()?;
         Ok(this)
     }
@@ -64,7 +80,11 @@ mod tests {
             account_set.oracle.clone(),
         ];
         let accounts_iter = accounts.iter();
-        UpdateConfigAccounts::from_accounts(&mut accounts_iter.into_iter()).unwrap();
+        UpdateConfigAccounts::from_accounts(
+            &mut accounts_iter.into_iter(),
+            &UpdateConfigInstruction::default(),
+        )
+        .unwrap();
     }
 
     #[test]
@@ -87,7 +107,10 @@ mod tests {
             account_set.oracle.clone(),
         ];
         let accounts_iter = accounts.iter();
-        let result = UpdateConfigAccounts::from_accounts(&mut accounts_iter.into_iter());
+        let result = UpdateConfigAccounts::from_accounts(
+            &mut accounts_iter.into_iter(),
+            &UpdateConfigInstruction::default(),
+        );
         let Err(err) = result else {
             panic!("Expected an error, but got Ok");
         };
@@ -106,7 +129,10 @@ mod tests {
             account_set.oracle.clone(),
         ];
         let accounts_iter = accounts.iter();
-        let result = UpdateConfigAccounts::from_accounts(&mut accounts_iter.into_iter());
+        let result = UpdateConfigAccounts::from_accounts(
+            &mut accounts_iter.into_iter(),
+            &UpdateConfigInstruction::default(),
+        );
         let Err(err) = result else {
             panic!("Expected an error, but got Ok");
         };
@@ -125,7 +151,10 @@ mod tests {
             account_set.oracle.clone(),
         ];
         let accounts_iter = accounts.iter();
-        let result = UpdateConfigAccounts::from_accounts(&mut accounts_iter.into_iter());
+        let result = UpdateConfigAccounts::from_accounts(
+            &mut accounts_iter.into_iter(),
+            &UpdateConfigInstruction::default(),
+        );
         let Err(err) = result else {
             panic!("Expected an error, but got Ok");
         };
@@ -144,7 +173,10 @@ mod tests {
             account_set_a.oracle.clone(),
         ];
         let accounts_iter = accounts.iter();
-        let result = UpdateConfigAccounts::from_accounts(&mut accounts_iter.into_iter());
+        let result = UpdateConfigAccounts::from_accounts(
+            &mut accounts_iter.into_iter(),
+            &UpdateConfigInstruction::default(),
+        );
         let Err(err) = result else {
             panic!("Expected an error, but got Ok");
         };
@@ -15,9 +15,12 @@ use crate::{
         create_supply_position::process_create_supply_position,
         deposit_apl_collateral::process_deposit_apl_collateral,
         donate_supply::process_donate_supply, liquidate::process_liquidate,
+        migrate_account::process_migrate_account,
         redeem_curator_fees::process_redeem_curator_fees,
         redeem_protocol_fees::process_redeem_protocol_fees, repay_apl::process_repay_apl,
+        set_curator_market_count::process_set_curator_market_count,
         socialize_loss::process_socialize_loss, supply_apl::process_supply_apl,
+        sweep_fee_dust::process_sweep_fee_dust, sync_market::process_sync_market,
         update_config::process_update_config, update_global_config::process_update_global_config,
         withdraw_apl_collateral::process_withdraw_apl_collateral,
         withdraw_repay_apl::process_withdraw_repay_apl, withdraw_supply::process_withdraw_supply,
@@ -28,6 +31,8 @@ pub mod error;
 pub mod ixs;
 pub mod processor;
 pub mod state;
+#[cfg(test)]
+pub mod test_runtime;
 pub mod utils;
 
 pub const fn id() -> Pubkey {
@@ -56,10 +61,11 @@ pub fn autara_process_instruction<'a>(
     let clock = utils::clock();
     let ix = <Box<AurataInstruction>>::deserialize(&mut &instruction_data[..])
         .map_err(|_| ProgramError::InvalidInstructionData)?;
+    ix.validate()?;
     match &*ix {
         AurataInstruction::CreateMarket(data) => {
             msg!("Processing CreateMarket instruction");
-            let create_market_accounts = CreateMarketAccounts::from_accounts(&mut accounts_iter)?;
+            let create_market_accounts = CreateMarketAccounts::from_accounts(&mut accounts_iter, data)?;
             process_create_market(&create_market_accounts, data, accounts, program_id, &clock)
         }
         AurataInstruction::CreateSupplyPosition(data) => {
@@ -125,7 +131,7 @@ pub fn autara_process_instruction<'a>(
         }
         AurataInstruction::UpdateConfig(data) => {
             msg!("Processing UpdateConfig instruction");
-            let update_config_accounts = UpdateConfigAccounts::from_accounts(&mut accounts_iter)?;
+            let update_config_accounts = UpdateConfigAccounts::from_accounts(&mut accounts_iter, data)?;
             process_update_config(&update_config_accounts, data, &clock)
         }
         AurataInstruction::WithdrawAplCollateral(data) => {
@@ -208,6 +214,32 @@ pub fn autara_process_instruction<'a>(
             let donate_supply_accounts = DonateSupplyAccounts::from_accounts(&mut accounts_iter)?;
             process_donate_supply(&donate_supply_accounts, data, accounts, program_id, &clock)
         }
+        AurataInstruction::SyncMarket => {
+            msg!("Processing SyncMarket instruction");
+            let sync_market_accounts = SyncMarketAccounts::from_accounts(&mut accounts_iter)?;
+            process_sync_market(&sync_market_accounts, &clock)
+        }
+        AurataInstruction::MigrateAccount => {
+            msg!("Processing MigrateAccount instruction");
+            let migrate_account_accounts = MigrateAccountAccounts::from_accounts(&mut accounts_iter)?;
+            process_migrate_account(&migrate_account_accounts)
+        }
+        AurataInstruction::SweepFeeDust => {
+            msg!("Processing SweepFeeDust instruction");
+            let sweep_fee_dust_accounts = SweepFeeDustAccounts::from_accounts(&mut accounts_iter)?;
+            process_sweep_fee_dust(&sweep_fee_dust_accounts, accounts, program_id, &clock)
+        }
+        AurataInstruction::SetCuratorMarketCount(data) => {
+            msg!("Processing SetCuratorMarketCount instruction");
+            let set_curator_market_count_accounts =
+                SetCuratorMarketCountAccounts::from_accounts(&mut accounts_iter, data)?;
+            process_set_curator_market_count(
+                &set_curator_market_count_accounts,
+                data,
+                accounts,
+                program_id,
+            )
+        }
         AurataInstruction::Log => {
             let _check_accounts = LogAccounts::from_accounts(&mut accounts_iter)?;
             Ok(())
@@ -1,12 +1,11 @@
-use arch_program::{
-    account::AccountInfo, clock::Clock, program::invoke_signed_unchecked, pubkey::Pubkey,
-};
+use arch_program::{account::AccountInfo, clock::Clock, pubkey::Pubkey};
 use autara_lib::{
     event::AutaraEvent,
     ixs::{log_ix, BorrowAplInstruction},
+    oracle::oracle_provider::AccountView,
 };
 
-use crate::{error::LendingProgramResult, ixs::BorrowAplAccounts};
+use crate::{error::LendingProgramResult, ixs::BorrowAplAccounts, utils::invoke_signed_unchecked};
 
 pub fn process_borrow_apl(
     borrow_apl_accounts: &BorrowAplAccounts,
@@ -17,9 +16,9 @@ pub fn process_borrow_apl(
 ) -> LendingProgramResult {
     let mut market_ref = borrow_apl_accounts.market.load_mut();
     let mut borrowing_position_ref = borrow_apl_accounts.borrow_position.load_mut();
-    let mut market_wrapper = market_ref.wrapper_mut(
-        borrow_apl_accounts.supply_oracle.try_into()?,
-        borrow_apl_accounts.collateral_oracle.try_into()?,
+    let mut market_wrapper = market_ref.borrow_wrapper_mut(
+        AccountView::try_from_optional(borrow_apl_accounts.supply_oracle)?,
+        AccountView::try_from_optional(borrow_apl_accounts.collateral_oracle)?,
         clock.unix_timestamp,
     )?;
     market_wrapper.sync_clock(clock.unix_timestamp)?;
@@ -55,3 +54,122 @@ pub fn process_borrow_apl(
     )?;
     Ok(())
 }
+
+#[cfg(test)]
+pub mod tests {
+    use arch_program::pubkey::Pubkey;
+    use autara_lib::{
+        error::LendingError,
+        ixs::{
+            borrow_apl_ix, create_borrow_position_ix, create_supply_position_ix,
+            deposit_apl_collateral_ix, repay_apl_ix, supply_apl_ix, update_config_ix,
+            UpdateConfigInstruction,
+        },
+        state::market::Market,
+    };
+
+    use crate::{
+        error::LendingProgramErrorKind,
+        test_runtime::{tests::create_btc_usdc_market, TestRuntime},
+    };
+
+    const USDC: u64 = 1_000_000;
+    const BTC: u64 = 100_000_000;
+
+    #[test]
+    fn borrow_requires_fresher_oracle_than_repay() {
+        let mut runtime = TestRuntime::new();
+        let program_id = crate::id();
+        let market = create_btc_usdc_market(&mut runtime);
+        let curator = *runtime.load::<Market>(&market.market).config().curator();
+        runtime
+            .process(&update_config_ix(
+                program_id,
+                market.market,
+                curator,
+                UpdateConfigInstruction {
+                    borrow_max_staleness_seconds: Some(10),
+                    ..Default::default()
+                },
+                market.supply_oracle,
+                market.collateral_oracle,
+            ))
+            .unwrap();
+
+        let lender = Pubkey::new_unique();
+        runtime.airdrop(lender, 1_000_000_000);
+        let lender_ata = runtime.create_token_account(lender, market.supply_mint, 10_000 * USDC);
+        let (supply_position, ix) =
+            create_supply_position_ix(program_id, market.market, lender, lender);
+        runtime.process(&ix).unwrap();
+        runtime
+            .process(&supply_apl_ix(
+                program_id,
+                market.market,
+                supply_position,
+                lender,
+                lender_ata,
+                market.supply_vault,
+                market.supply_oracle,
+                market.collateral_oracle,
+                10_000 * USDC,
+            ))
+            .unwrap();
+
+        let borrower = Pubkey::new_unique();
+        runtime.airdrop(borrower, 1_000_000_000);
+        let collateral_ata = runtime.create_token_account(borrower, market.collateral_mint, BTC);
+        let supply_ata = runtime.create_token_account(borrower, market.supply_mint, 0);
+        let (borrow_position, ix) =
+            create_borrow_position_ix(program_id, market.market, borrower, borrower);
+        runtime.process(&ix).unwrap();
+        runtime
+            .process(&deposit_apl_collateral_ix(
+                program_id,
+                market.market,
+                borrower,
+                borrow_position,
+                collateral_ata,
+                market.collateral_vault,
+                market.supply_oracle,
+                market.collateral_oracle,
+                BTC,
+            ))
+            .unwrap();
+        let borrow = borrow_apl_ix(
+            program_id,
+            market.market,
+            borrower,
+            borrow_position,
+            supply_ata,
+            market.supply_vault,
+            market.supply_oracle,
+            market.collateral_oracle,
+            1_000 * USDC,
+        );
+        runtime.process(&borrow).unwrap();
+
+        // the oracles are still within the general max age but too old to borrow
+        runtime.advance_time(20);
+        let err = runtime.process(&borrow).unwrap_err();
+        assert_eq!(
+            err.0.error,
+            LendingProgramErrorKind::LendingError(LendingError::OracleRateTooOld)
+        );
+        runtime
+            .process(&repay_apl_ix(
+                program_id,
+                market.market,
+                borrow_position,
+                borrower,
+                supply_ata,
+                market.supply_vault,
+                market.supply_oracle,
+                market.collateral_oracle,
+                500 * USDC,
+                false,
+            ))
+            .unwrap();
+        assert_eq!(runtime.token_balance(&supply_ata), 500 * USDC);
+    }
+}
@@ -1,12 +1,13 @@
-use arch_program::{
-    account::AccountInfo, clock::Clock, program::invoke_signed_unchecked, pubkey::Pubkey,
-};
+use arch_program::{account::AccountInfo, clock::Clock, pubkey::Pubkey};
 use autara_lib::{
     event::AutaraEvent,
     ixs::{log_ix, BorrowDepositAplInstruction},
+    oracle::oracle_provider::AccountView,
 };
 
-use crate::{error::LendingProgramResult, ixs::BorrowDepositAplAccounts};
+use crate::{
+    error::LendingProgramResult, ixs::BorrowDepositAplAccounts, utils::invoke_signed_unchecked,
+};
 
 pub fn process_borrow_deposit_apl(
     borrow_deposit_apl_accounts: &BorrowDepositAplAccounts,
@@ -16,10 +17,13 @@ pub fn process_borrow_deposit_apl(
     clock: &Clock,
 ) -> LendingProgramResult {
     let mut market_ref = borrow_deposit_apl_accounts.market.load_mut();
+    if let Some(ix) = &data.ix_callback {
+        market_ref.check_callback_program(&ix.program_id)?;
+    }
     let mut borrowing_position_ref = borrow_deposit_apl_accounts.borrow_position.load_mut();
-    let mut market_wrapper = market_ref.wrapper_mut(
-        borrow_deposit_apl_accounts.supply_oracle.try_into()?,
-        borrow_deposit_apl_accounts.collateral_oracle.try_into()?,
+    let mut market_wrapper = market_ref.borrow_wrapper_mut(
+        AccountView::try_from_optional(borrow_deposit_apl_accounts.supply_oracle)?,
+        AccountView::try_from_optional(borrow_deposit_apl_accounts.collateral_oracle)?,
         clock.unix_timestamp,
     )?;
     market_wrapper.sync_clock(clock.unix_timestamp)?;
@@ -62,7 +66,14 @@ pub fn process_borrow_deposit_apl(
     )?;
 
     if let Some(ix) = &data.ix_callback {
+        borrowing_position_ref.set_reentrancy_guard(true);
+        drop(market_ref);
+        drop(borrowing_position_ref);
         invoke_signed_unchecked(ix, accounts, &[])?;
+        borrow_deposit_apl_accounts
+            .borrow_position
+            .load_mut()
+            .set_reentrancy_guard(false);
     }
 
     invoke_signed_unchecked(
@@ -79,3 +90,219 @@ pub fn process_borrow_deposit_apl(
     )?;
     Ok(())
 }
+
+#[cfg(test)]
+pub mod tests {
+    use arch_program::{program_error::ProgramError, pubkey::Pubkey};
+    use autara_lib::{
+        error::LendingError,
+        ixs::{
+            borrow_apl_ix, borrow_deposit_apl_ix, create_borrow_position_ix,
+            create_supply_position_ix, deposit_apl_collateral_ix, supply_apl_ix, sync_market_ix,
+            update_config_ix, BorrowDepositAplInstruction, UpdateConfigInstruction,
+        },
+        state::{borrow_position::BorrowPosition, market::Market},
+    };
+
+    use crate::{
+        error::LendingProgramErrorKind,
+        test_runtime::{
+            tests::{create_btc_usdc_market, TestMarket},
+            TestRuntime,
+        },
+    };
+
+    const USDC: u64 = 1_000_000;
+    const BTC: u64 = 100_000_000;
+
+    /// Supplies the market and returns a borrower holding one BTC with its borrow position,
+    /// supply and collateral token accounts
+    pub fn setup_borrower(runtime: &mut TestRuntime, market: &TestMarket) -> [Pubkey; 4] {
+        let program_id = crate::id();
+        let lender = Pubkey::new_unique();
+        runtime.airdrop(lender, 1_000_000_000);
+        let lender_ata = runtime.create_token_account(lender, market.supply_mint, 10_000 * USDC);
+        let (supply_position, ix) =
+            create_supply_position_ix(program_id, market.market, lender, lender);
+        runtime.process(&ix).unwrap();
+        runtime
+            .process(&supply_apl_ix(
+                program_id,
+                market.market,
+                supply_position,
+                lender,
+                lender_ata,
+                market.supply_vault,
+                market.supply_oracle,
+                market.collateral_oracle,
+                10_000 * USDC,
+            ))
+            .unwrap();
+
+        let borrower = Pubkey::new_unique();
+        runtime.airdrop(borrower, 1_000_000_000);
+        let collateral_ata = runtime.create_token_account(borrower, market.collateral_mint, BTC);
+        let supply_ata = runtime.create_token_account(borrower, market.supply_mint, 0);
+        let (borrow_position, ix) =
+            create_borrow_position_ix(program_id, market.market, borrower, borrower);
+        runtime.process(&ix).unwrap();
+        [borrower, borrow_position, supply_ata, collateral_ata]
+    }
+
+    #[test]
+    fn callback_cannot_reenter_on_the_same_position() {
+        let mut runtime = TestRuntime::new();
+        let program_id = crate::id();
+        let market = create_btc_usdc_market(&mut runtime);
+        let [borrower, borrow_position, supply_ata, collateral_ata] =
+            setup_borrower(&mut runtime, &market);
+        let borrow_deposit = |ix_callback| {
+            borrow_deposit_apl_ix(
+                program_id,
+                market.market,
+                borrower,
+                borrow_position,
+                supply_ata,
+                market.supply_vault,
+                collateral_ata,
+                market.collateral_vault,
+                market.supply_oracle,
+                market.collateral_oracle,
+                BorrowDepositAplInstruction {
+                    deposit_amount: BTC / 2,
+                    borrow_amount: 1_000 * USDC,
+                    ix_callback: Some(ix_callback),
+                },
+            )
+        };
+        let borrow_on = |authority, position, authority_supply_ata| {
+            borrow_apl_ix(
+                program_id,
+                market.market,
+                authority,
+                position,
+                authority_supply_ata,
+                market.supply_vault,
+                market.supply_oracle,
+                market.collateral_oracle,
+                1_000 * USDC,
+            )
+        };
+
+        // the error of the nested instruction surfaces as the error code of the failed call
+        let err = runtime
+            .process(&borrow_deposit(borrow_on(
+                borrower,
+                borrow_position,
+                supply_ata,
+            )))
+            .unwrap_err();
+        let LendingProgramErrorKind::ProgramError(ProgramError::Custom(code)) = err.0.error else {
+            panic!("expected the callback to fail, got {:?}", err);
+        };
+        assert_eq!(
+            LendingProgramErrorKind::from_error_code(code),
+            LendingError::ReentrancyDetected
+        );
+        assert_eq!(runtime.token_balance(&supply_ata), 0);
+
+        // the callback can still use the program on other positions
+        let other_borrower = Pubkey::new_unique();
+        runtime.airdrop(other_borrower, 1_000_000_000);
+        let other_collateral_ata =
+            runtime.create_token_account(other_borrower, market.collateral_mint, BTC);
+        let other_supply_ata = runtime.create_token_account(other_borrower, market.supply_mint, 0);
+        let (other_position, ix) =
+            create_borrow_position_ix(program_id, market.market, other_borrower, other_borrower);
+        runtime.process(&ix).unwrap();
+        runtime
+            .process(&deposit_apl_collateral_ix(
+                program_id,
+                market.market,
+                other_borrower,
+                other_position,
+                other_collateral_ata,
+                market.collateral_vault,
+                market.supply_oracle,
+                market.collateral_oracle,
+                BTC,
+            ))
+            .unwrap();
+        runtime
+            .process(&borrow_deposit(borrow_on(
+                other_borrower,
+                other_position,
+                other_supply_ata,
+            )))
+            .unwrap();
+        assert_eq!(runtime.token_balance(&supply_ata), 1_000 * USDC);
+        assert_eq!(runtime.token_balance(&other_supply_ata), 1_000 * USDC);
+        assert!(!runtime
+            .load::<BorrowPosition>(&borrow_position)
+            .is_reentrancy_guarded());
+    }
+
+    #[test]
+    fn callback_program_must_be_in_the_market_allowlist() {
+        let mut runtime = TestRuntime::new();
+        let program_id = crate::id();
+        let market = create_btc_usdc_market(&mut runtime);
+        let [borrower, borrow_position, supply_ata, collateral_ata] =
+            setup_borrower(&mut runtime, &market);
+        let curator = *runtime.load::<Market>(&market.market).config().curator();
+        runtime
+            .process(&update_config_ix(
+                program_id,
+                market.market,
+                curator,
+                UpdateConfigInstruction {
+                    callback_program_allowlist: Some(vec![apl_token::id()]),
+                    ..Default::default()
+                },
+                market.supply_oracle,
+                market.collateral_oracle,
+            ))
+            .unwrap();
+        let recipient = Pubkey::new_unique();
+        let recipient_ata = runtime.create_token_account(recipient, market.supply_mint, 0);
+        let borrow_deposit = |ix_callback| {
+            borrow_deposit_apl_ix(
+                program_id,
+                market.market,
+                borrower,
+                borrow_position,
+                supply_ata,
+                market.supply_vault,
+                collateral_ata,
+                market.collateral_vault,
+                market.supply_oracle,
+                market.collateral_oracle,
+                BorrowDepositAplInstruction {
+                    deposit_amount: BTC / 2,
+                    borrow_amount: 1_000 * USDC,
+                    ix_callback: Some(ix_callback),
+                },
+            )
+        };
+
+        let sync_market = sync_market_ix(program_id, market.market);
+        let err = runtime.process(&borrow_deposit(sync_market)).unwrap_err();
+        assert_eq!(
+            err.0.error,
+            LendingProgramErrorKind::LendingError(LendingError::CallbackProgramNotAllowed)
+        );
+        assert_eq!(runtime.token_balance(&supply_ata), 0);
+
+        let transfer = apl_token::instruction::transfer(
+            &apl_token::id(),
+            &supply_ata,
+            &recipient_ata,
+            &borrower,
+            &[],
+            1_000 * USDC,
+        )
+        .unwrap();
+        runtime.process(&borrow_deposit(transfer)).unwrap();
+        assert_eq!(runtime.token_balance(&recipient_ata), 1_000 * USDC);
+    }
+}
@@ -1,15 +1,13 @@
-use arch_program::{
-    account::AccountInfo, program::invoke_signed_unchecked, pubkey::Pubkey, rent::minimum_rent,
-    system_instruction,
-};
+use arch_program::{account::AccountInfo, pubkey::Pubkey, rent::minimum_rent, system_instruction};
 use autara_lib::{
-    ixs::CreateGlobalConfigInstruction, pda::global_config_seed_with_bump,
+    error::LendingError, ixs::CreateGlobalConfigInstruction, pda::global_config_seed_with_bump,
     state::global_config::GlobalConfig,
 };
 use autara_program_lib::accounts::zero_copy::ZeroCopyOwnedAccountMut;
 
 use crate::{
-    error::LendingProgramResult, ixs::CreateGlobalConfigAccounts, state::AutaraUninitializedAccount,
+    error::LendingProgramResult, ixs::CreateGlobalConfigAccounts,
+    state::AutaraUninitializedAccount, utils::invoke_signed_unchecked,
 };
 
 pub fn process_create_global_config(
@@ -18,6 +16,10 @@ pub fn process_create_global_config(
     accounts: &[AccountInfo],
     program_id: &Pubkey,
 ) -> LendingProgramResult {
+    // the default pubkey is the system program id, fees sent there would be burned
+    if data.admin == Pubkey::default() || data.fee_receiver == Pubkey::default() {
+        return Err(LendingError::InvalidGlobalConfig.into());
+    }
     let bump = [data.bump];
     let seed = global_config_seed_with_bump(&bump);
     invoke_signed_unchecked(
@@ -43,3 +45,39 @@ pub fn process_create_global_config(
     );
     Ok(())
 }
+
+#[cfg(test)]
+pub mod tests {
+    use arch_program::pubkey::Pubkey;
+    use autara_lib::{
+        error::LendingError, ixs::create_global_config_ix, pda::find_global_config_pda,
+    };
+
+    use crate::{error::LendingProgramErrorKind, test_runtime::TestRuntime};
+
+    #[test]
+    fn create_global_config_rejects_default_fee_receiver() {
+        let mut runtime = TestRuntime::new();
+        let admin = Pubkey::new_unique();
+        runtime.airdrop(admin, 1_000_000_000);
+        let (global_config, ix) =
+            create_global_config_ix(crate::id(), admin, admin, Pubkey::default(), 0);
+        let err = runtime.process(&ix).unwrap_err();
+        assert_eq!(
+            err.0.error,
+            LendingProgramErrorKind::LendingError(LendingError::InvalidGlobalConfig)
+        );
+        assert!(!runtime.accounts.contains_key(&global_config));
+
+        let (_, ix) = create_global_config_ix(crate::id(), admin, Pubkey::default(), admin, 0);
+        let err = runtime.process(&ix).unwrap_err();
+        assert_eq!(
+            err.0.error,
+            LendingProgramErrorKind::LendingError(LendingError::InvalidGlobalConfig)
+        );
+
+        let (_, ix) = create_global_config_ix(crate::id(), admin, admin, admin, 0);
+        runtime.process(&ix).unwrap();
+        assert_eq!(global_config, find_global_config_pda(&crate::id()).0);
+    }
+}
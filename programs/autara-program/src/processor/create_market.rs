@@ -1,15 +1,24 @@
 use arch_program::{
-    account::AccountInfo, clock::Clock, program::invoke_signed_unchecked, pubkey::Pubkey,
-    rent::minimum_rent, system_instruction,
+    account::AccountInfo, clock::Clock, pubkey::Pubkey, rent::minimum_rent, system_instruction,
 };
 use autara_lib::{
-    ixs::CreateMarketInstruction, pda::market_seed_with_bump, state::market::Market,
+    error::LendingError,
+    ixs::CreateMarketInstruction,
+    math::bps::Bps,
+    oracle::oracle_provider::AccountView,
+    pda::{
+        curator_market_count_seed_with_bump, find_curator_market_count_pda, market_seed_with_bump,
+    },
+    state::{curator_market_count::CuratorMarketCount, market::Market},
     token::create_ata_ix,
 };
 use autara_program_lib::accounts::zero_copy::ZeroCopyOwnedAccountMut;
 
 use crate::{
-    error::LendingProgramResult, ixs::CreateMarketAccounts, state::AutaraUninitializedAccount,
+    error::LendingProgramResult,
+    ixs::CreateMarketAccounts,
+    state::{AutaraAccount, AutaraUninitializedAccount},
+    utils::invoke_signed_unchecked,
 };
 
 pub fn process_create_market(
@@ -19,6 +28,11 @@ pub fn process_create_market(
     program_id: &Pubkey,
     clock: &Clock,
 ) -> LendingProgramResult {
+    // both vaults are associated token accounts of the market, they would alias
+    if create_market_accounts.supply_mint.key() == create_market_accounts.collateral_mint.key() {
+        return Err(LendingError::SameSupplyAndCollateralMint.into());
+    }
+    record_curator_market(create_market_accounts, accounts, program_id)?;
     let bump = [data.market_bump];
     let index = [data.index];
     let seed = market_seed_with_bump(
@@ -75,9 +89,14 @@ pub fn process_create_market(
         &data.ltv_config,
         data.max_utilisation_rate,
         u64::MAX,
-        data.lending_market_fee_in_bps,
+        Bps::new(data.lending_market_fee_in_bps),
         &create_market_accounts.global_config.load_ref(),
+        clock.unix_timestamp,
     )?;
+    market_ref
+        .config_mut()
+        .set_borrow_origination_grace_seconds(data.borrow_origination_grace_seconds);
+    market_ref.config().validate()?;
     market_ref.initlize_supply_vault(
         *create_market_accounts.supply_mint.key(),
         create_market_accounts.supply_mint.decimals as u64,
@@ -92,5 +111,415 @@ pub fn process_create_market(
         *create_market_accounts.collateral_vault.key,
         data.collateral_oracle_config,
     )?;
+    market_ref.update_metadata_uri(data.metadata_uri)?;
+    // a market is only usable once both feeds have a price
+    if [
+        create_market_accounts.supply_oracle,
+        create_market_accounts.collateral_oracle,
+    ]
+    .into_iter()
+    .flatten()
+    .any(|oracle| oracle.data_is_empty())
+    {
+        return Err(LendingError::OracleNotInitialized.into());
+    }
+    let _ = market_ref.wrapper_mut(
+        AccountView::try_from_optional(create_market_accounts.supply_oracle)?,
+        AccountView::try_from_optional(create_market_accounts.collateral_oracle)?,
+        clock.unix_timestamp,
+    )?;
+    Ok(())
+}
+
+/// Count the new market against the curator limit of the global config, creating the
+/// counter account on the first market of the curator
+fn record_curator_market(
+    create_market_accounts: &CreateMarketAccounts,
+    accounts: &[AccountInfo],
+    program_id: &Pubkey,
+) -> LendingProgramResult {
+    create_curator_market_count_if_empty(
+        create_market_accounts.payer.key,
+        create_market_accounts.curator_market_count,
+        create_market_accounts.curator.key,
+        accounts,
+        program_id,
+    )?;
+    let count = ZeroCopyOwnedAccountMut::<AutaraAccount<CuratorMarketCount>>::try_from(
+        create_market_accounts.curator_market_count,
+    )?;
+    count.load_mut().record_market(
+        create_market_accounts
+            .global_config
+            .load_ref()
+            .max_markets_per_curator(),
+    )?;
+    Ok(())
+}
+
+/// Create and initialize the market count of `curator` if it does not exist yet.
+/// `curator_market_count` must be the curator market count PDA
+pub fn create_curator_market_count_if_empty(
+    payer: &Pubkey,
+    curator_market_count: &AccountInfo,
+    curator: &Pubkey,
+    accounts: &[AccountInfo],
+    program_id: &Pubkey,
+) -> LendingProgramResult {
+    if !curator_market_count.data_is_empty() {
+        return Ok(());
+    }
+    let (_, bump) = find_curator_market_count_pda(program_id, curator);
+    let bump = [bump];
+    let seed = curator_market_count_seed_with_bump(curator, &bump);
+    invoke_signed_unchecked(
+        &system_instruction::create_account(
+            payer,
+            curator_market_count.key,
+            minimum_rent(std::mem::size_of::<CuratorMarketCount>()),
+            std::mem::size_of::<CuratorMarketCount>() as u64,
+            program_id,
+        ),
+        accounts,
+        &[&seed],
+    )?;
+    let count =
+        ZeroCopyOwnedAccountMut::<AutaraUninitializedAccount<CuratorMarketCount>>::try_from(
+            curator_market_count,
+        )?;
+    count.load_mut().initialize(*curator);
     Ok(())
 }
+
+#[cfg(test)]
+pub mod tests {
+    use arch_program::pubkey::Pubkey;
+    use autara_lib::{
+        error::LendingError,
+        interest_rate::interest_rate_kind::InterestRateCurveKind,
+        ixs::{
+            create_global_config_ix, create_market_ix, update_config_ix, update_global_config_ix,
+            CreateMarketInstruction, UpdateConfigInstruction, UpdateGlobalConfigInstruction,
+        },
+        pda::find_curator_market_count_pda,
+        state::{
+            curator_market_count::CuratorMarketCount,
+            global_config::GlobalConfig,
+            market::Market,
+            market_config::{LtvConfig, MAX_BORROW_ORIGINATION_GRACE_SECONDS},
+            metadata_uri::{MetadataUri, MAX_METADATA_URI_LEN},
+        },
+    };
+    use borsh::BorshDeserialize;
+
+    use crate::{
+        error::LendingProgramErrorKind,
+        test_runtime::{tests::create_btc_usdc_market, TestRuntime},
+    };
+
+    #[test]
+    fn create_market_rejects_oracle_without_price() {
+        const USDC_FEED: [u8; 32] = [1; 32];
+        const BTC_FEED: [u8; 32] = [2; 32];
+        let mut runtime = TestRuntime::new();
+        let admin = Pubkey::new_unique();
+        let curator = Pubkey::new_unique();
+        runtime.airdrop(admin, 1_000_000_000);
+        runtime.airdrop(curator, 1_000_000_000);
+        let (_, ix) = create_global_config_ix(crate::id(), admin, admin, admin, 0);
+        runtime.process(&ix).unwrap();
+        let supply_mint = runtime.create_mint(6);
+        let collateral_mint = runtime.create_mint(8);
+        runtime.set_pyth_price(USDC_FEED, 100_000_000, 10_000, -8);
+        let (market, ix) = create_market_ix(
+            CreateMarketInstruction {
+                market_bump: 0,
+                index: 0,
+                ltv_config: LtvConfig {
+                    max_ltv: 0.8.into(),
+                    unhealthy_ltv: 0.9.into(),
+                    liquidation_bonus: 0.05.into(),
+                },
+                max_utilisation_rate: 0.9.into(),
+                supply_oracle_config: runtime.pyth_oracle_config(USDC_FEED),
+                collateral_oracle_config: runtime.pyth_oracle_config(BTC_FEED),
+                interest_rate: InterestRateCurveKind::new_adaptive(),
+                lending_market_fee_in_bps: 0,
+                metadata_uri: Default::default(),
+                borrow_origination_grace_seconds: 0,
+            },
+            supply_mint,
+            collateral_mint,
+            crate::id(),
+            curator,
+            curator,
+        );
+        let err = runtime.process(&ix).unwrap_err();
+        assert_eq!(
+            err.0.error,
+            LendingProgramErrorKind::LendingError(LendingError::OracleNotInitialized)
+        );
+        assert!(!runtime.accounts.contains_key(&market));
+
+        runtime.set_pyth_price(BTC_FEED, 100_000 * 100_000_000, 10 * 100_000_000, -8);
+        runtime.process(&ix).unwrap();
+        assert!(runtime.accounts.contains_key(&market));
+    }
+
+    #[test]
+    fn create_market_rejects_same_supply_and_collateral_mint() {
+        const USDC_FEED: [u8; 32] = [1; 32];
+        let mut runtime = TestRuntime::new();
+        let test_market = create_btc_usdc_market(&mut runtime);
+        let market = runtime.load::<Market>(&test_market.market);
+        assert_ne!(
+            market.supply_vault().vault(),
+            market.collateral_vault().vault()
+        );
+
+        let curator = *market.config().curator();
+        let mint = test_market.supply_mint;
+        let (market, ix) = create_market_ix(
+            CreateMarketInstruction {
+                market_bump: 0,
+                index: 1,
+                ltv_config: LtvConfig {
+                    max_ltv: 0.8.into(),
+                    unhealthy_ltv: 0.9.into(),
+                    liquidation_bonus: 0.05.into(),
+                },
+                max_utilisation_rate: 0.9.into(),
+                supply_oracle_config: runtime.pyth_oracle_config(USDC_FEED),
+                collateral_oracle_config: runtime.pyth_oracle_config(USDC_FEED),
+                interest_rate: InterestRateCurveKind::new_adaptive(),
+                lending_market_fee_in_bps: 0,
+                metadata_uri: Default::default(),
+                borrow_origination_grace_seconds: 0,
+            },
+            mint,
+            mint,
+            crate::id(),
+            curator,
+            curator,
+        );
+        // the supply and collateral vaults would be the same associated token account
+        assert_eq!(ix.accounts[5].pubkey, ix.accounts[7].pubkey);
+        let err = runtime.process(&ix).unwrap_err();
+        assert_eq!(
+            err.0.error,
+            LendingProgramErrorKind::LendingError(LendingError::SameSupplyAndCollateralMint)
+        );
+        assert!(!runtime.accounts.contains_key(&market));
+        assert!(!runtime.accounts.contains_key(&ix.accounts[5].pubkey));
+    }
+
+    #[test]
+    fn create_market_enforces_max_markets_per_curator() {
+        const USDC_FEED: [u8; 32] = [1; 32];
+        const BTC_FEED: [u8; 32] = [2; 32];
+        let mut runtime = TestRuntime::new();
+        let test_market = create_btc_usdc_market(&mut runtime);
+        let market = runtime.load::<Market>(&test_market.market);
+        let curator = *market.config().curator();
+        let (global_config, _) = autara_lib::pda::find_global_config_pda(&crate::id());
+        let admin = *runtime.load::<GlobalConfig>(&global_config).admin();
+        runtime
+            .process(&update_global_config_ix(
+                crate::id(),
+                admin,
+                UpdateGlobalConfigInstruction {
+                    max_markets_per_curator: Some(3),
+                    ..Default::default()
+                },
+            ))
+            .unwrap();
+        let create_market = |runtime: &mut TestRuntime, curator: Pubkey, index: u8| {
+            create_market_ix(
+                CreateMarketInstruction {
+                    market_bump: 0,
+                    index,
+                    ltv_config: LtvConfig {
+                        max_ltv: 0.8.into(),
+                        unhealthy_ltv: 0.9.into(),
+                        liquidation_bonus: 0.05.into(),
+                    },
+                    max_utilisation_rate: 0.9.into(),
+                    supply_oracle_config: runtime.pyth_oracle_config(USDC_FEED),
+                    collateral_oracle_config: runtime.pyth_oracle_config(BTC_FEED),
+                    interest_rate: InterestRateCurveKind::new_adaptive(),
+                    lending_market_fee_in_bps: 0,
+                    metadata_uri: Default::default(),
+                    borrow_origination_grace_seconds: 0,
+                },
+                test_market.supply_mint,
+                test_market.collateral_mint,
+                crate::id(),
+                curator,
+                curator,
+            )
+        };
+        let (curator_market_count, _) = find_curator_market_count_pda(&crate::id(), &curator);
+        for index in 1..3 {
+            let (market, ix) = create_market(&mut runtime, curator, index);
+            runtime.process(&ix).unwrap();
+            assert!(runtime.accounts.contains_key(&market));
+        }
+        let count = runtime.load::<CuratorMarketCount>(&curator_market_count);
+        assert_eq!(count.curator(), &curator);
+        assert_eq!(count.market_count(), 3);
+
+        let (market, ix) = create_market(&mut runtime, curator, 3);
+        let err = runtime.process(&ix).unwrap_err();
+        assert_eq!(
+            err.0.error,
+            LendingProgramErrorKind::LendingError(LendingError::CuratorMarketLimitReached)
+        );
+        assert!(!runtime.accounts.contains_key(&market));
+
+        // another curator has its own count
+        let other_curator = Pubkey::new_unique();
+        runtime.airdrop(other_curator, 1_000_000_000);
+        let (market, ix) = create_market(&mut runtime, other_curator, 0);
+        runtime.process(&ix).unwrap();
+        assert!(runtime.accounts.contains_key(&market));
+
+        // raising the limit lets the curator create more markets
+        runtime
+            .process(&update_global_config_ix(
+                crate::id(),
+                admin,
+                UpdateGlobalConfigInstruction {
+                    max_markets_per_curator: Some(4),
+                    ..Default::default()
+                },
+            ))
+            .unwrap();
+        let (market, ix) = create_market(&mut runtime, curator, 3);
+        runtime.process(&ix).unwrap();
+        assert!(runtime.accounts.contains_key(&market));
+    }
+
+    #[test]
+    fn metadata_uri_is_set_at_creation_and_updatable_by_the_curator() {
+        const USDC_FEED: [u8; 32] = [1; 32];
+        const BTC_FEED: [u8; 32] = [2; 32];
+        let mut runtime = TestRuntime::new();
+        let test_market = create_btc_usdc_market(&mut runtime);
+        let curator = *runtime
+            .load::<Market>(&test_market.market)
+            .config()
+            .curator();
+        assert!(runtime
+            .load::<Market>(&test_market.market)
+            .metadata_uri()
+            .is_empty());
+        let uri = "https://autara.finance/markets/btc-usdc.json";
+        let (market, ix) = create_market_ix(
+            CreateMarketInstruction {
+                market_bump: 0,
+                index: 1,
+                ltv_config: LtvConfig {
+                    max_ltv: 0.8.into(),
+                    unhealthy_ltv: 0.9.into(),
+                    liquidation_bonus: 0.05.into(),
+                },
+                max_utilisation_rate: 0.9.into(),
+                supply_oracle_config: runtime.pyth_oracle_config(USDC_FEED),
+                collateral_oracle_config: runtime.pyth_oracle_config(BTC_FEED),
+                interest_rate: InterestRateCurveKind::new_adaptive(),
+                lending_market_fee_in_bps: 0,
+                metadata_uri: MetadataUri::try_new(uri).unwrap(),
+                borrow_origination_grace_seconds: 0,
+            },
+            test_market.supply_mint,
+            test_market.collateral_mint,
+            crate::id(),
+            curator,
+            curator,
+        );
+        runtime.process(&ix).unwrap();
+        assert_eq!(runtime.load::<Market>(&market).metadata_uri().as_str(), uri);
+
+        let mut update = |metadata_uri: MetadataUri| {
+            runtime
+                .process(&update_config_ix(
+                    crate::id(),
+                    market,
+                    curator,
+                    UpdateConfigInstruction {
+                        metadata_uri: Some(metadata_uri),
+                        ..Default::default()
+                    },
+                    test_market.supply_oracle,
+                    test_market.collateral_oracle,
+                ))
+                .map(|_| ())
+                .map_err(|err| err.0.error)
+        };
+        let updated_uri = "ipfs://bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbz";
+        update(MetadataUri::try_new(updated_uri).unwrap()).unwrap();
+        // instruction data is not checked on deserialization
+        let invalid = MetadataUri::try_from_slice(&[0xff; MAX_METADATA_URI_LEN]).unwrap();
+        assert_eq!(
+            update(invalid).unwrap_err(),
+            LendingProgramErrorKind::LendingError(LendingError::InvalidInstructionArguments)
+        );
+        assert_eq!(
+            runtime.load::<Market>(&market).metadata_uri().as_str(),
+            updated_uri
+        );
+    }
+
+    #[test]
+    fn borrow_origination_grace_is_capped_at_creation() {
+        const USDC_FEED: [u8; 32] = [1; 32];
+        const BTC_FEED: [u8; 32] = [2; 32];
+        let mut runtime = TestRuntime::new();
+        let test_market = create_btc_usdc_market(&mut runtime);
+        let curator = *runtime
+            .load::<Market>(&test_market.market)
+            .config()
+            .curator();
+        let create = |runtime: &mut TestRuntime, borrow_origination_grace_seconds: u64| {
+            let (market, ix) = create_market_ix(
+                CreateMarketInstruction {
+                    market_bump: 0,
+                    index: 1,
+                    ltv_config: LtvConfig {
+                        max_ltv: 0.8.into(),
+                        unhealthy_ltv: 0.9.into(),
+                        liquidation_bonus: 0.05.into(),
+                    },
+                    max_utilisation_rate: 0.9.into(),
+                    supply_oracle_config: runtime.pyth_oracle_config(USDC_FEED),
+                    collateral_oracle_config: runtime.pyth_oracle_config(BTC_FEED),
+                    interest_rate: InterestRateCurveKind::new_adaptive(),
+                    lending_market_fee_in_bps: 0,
+                    metadata_uri: Default::default(),
+                    borrow_origination_grace_seconds,
+                },
+                test_market.supply_mint,
+                test_market.collateral_mint,
+                crate::id(),
+                curator,
+                curator,
+            );
+            runtime
+                .process(&ix)
+                .map(|_| market)
+                .map_err(|err| err.0.error)
+        };
+        assert_eq!(
+            create(&mut runtime, MAX_BORROW_ORIGINATION_GRACE_SECONDS + 1).unwrap_err(),
+            LendingProgramErrorKind::LendingError(LendingError::InvalidInstructionArguments)
+        );
+        let market = create(&mut runtime, MAX_BORROW_ORIGINATION_GRACE_SECONDS).unwrap();
+        assert_eq!(
+            runtime
+                .load::<Market>(&market)
+                .config()
+                .borrow_origination_grace_seconds(),
+            MAX_BORROW_ORIGINATION_GRACE_SECONDS
+        );
+    }
+}
@@ -1,7 +1,4 @@
-use arch_program::{
-    account::AccountInfo, program::invoke_signed_unchecked, pubkey::Pubkey, rent::minimum_rent,
-    system_instruction,
-};
+use arch_program::{account::AccountInfo, pubkey::Pubkey, rent::minimum_rent, system_instruction};
 use autara_lib::{
     ixs::supply::CreateSupplyPositionInstruction, pda::supply_position_seed_with_bump,
     state::supply_position::SupplyPosition,
@@ -10,7 +7,7 @@ use autara_program_lib::accounts::zero_copy::ZeroCopyOwnedAccountMut;
 
 use crate::{
     error::LendingProgramResult, ixs::CreateSupplyPositionAccounts,
-    state::AutaraUninitializedAccount,
+    state::AutaraUninitializedAccount, utils::invoke_signed_unchecked,
 };
 
 pub fn process_create_supply_position(
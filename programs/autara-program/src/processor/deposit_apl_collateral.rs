@@ -1,12 +1,13 @@
-use arch_program::{
-    account::AccountInfo, clock::Clock, program::invoke_signed_unchecked, pubkey::Pubkey,
-};
+use arch_program::{account::AccountInfo, clock::Clock, pubkey::Pubkey};
 use autara_lib::{
     event::AutaraEvent,
     ixs::{log_ix, DepositAplCollateralInstruction},
+    oracle::oracle_provider::AccountView,
 };
 
-use crate::{error::LendingProgramResult, ixs::DepositAplCollateralAccounts};
+use crate::{
+    error::LendingProgramResult, ixs::DepositAplCollateralAccounts, utils::invoke_signed_unchecked,
+};
 
 pub fn process_deposit_apl_collateral(
     deposit_apl_collateral_accounts: &DepositAplCollateralAccounts,
@@ -17,10 +18,8 @@ pub fn process_deposit_apl_collateral(
 ) -> LendingProgramResult {
     let mut market_ref = deposit_apl_collateral_accounts.market.load_mut();
     let mut market_wrapper = market_ref.wrapper_mut(
-        deposit_apl_collateral_accounts.supply_oracle.try_into()?,
-        deposit_apl_collateral_accounts
-            .collateral_oracle
-            .try_into()?,
+        AccountView::try_from_optional(deposit_apl_collateral_accounts.supply_oracle)?,
+        AccountView::try_from_optional(deposit_apl_collateral_accounts.collateral_oracle)?,
         clock.unix_timestamp,
     )?;
 
@@ -1,12 +1,12 @@
-use arch_program::{
-    account::AccountInfo, clock::Clock, program::invoke_signed_unchecked, pubkey::Pubkey,
-};
+use arch_program::{account::AccountInfo, clock::Clock, pubkey::Pubkey};
 use autara_lib::{
     event::{AutaraEvent, DonateSupplyEvent},
     ixs::{log_ix, DonateSupplyInstruction},
 };
 
-use crate::{error::LendingProgramResult, ixs::DonateSupplyAccounts};
+use crate::{
+    error::LendingProgramResult, ixs::DonateSupplyAccounts, utils::invoke_signed_unchecked,
+};
 
 pub fn process_donate_supply(
     donate_supply_accounts: &DonateSupplyAccounts,
@@ -47,3 +47,91 @@ pub fn process_donate_supply(
     )?;
     Ok(())
 }
+
+#[cfg(test)]
+pub mod tests {
+    use arch_program::pubkey::Pubkey;
+    use autara_lib::{
+        error::LendingError,
+        ixs::{
+            create_supply_position_ix, donate_supply_ix, supply_apl_ix, update_config_ix,
+            UpdateConfigInstruction,
+        },
+        state::market::Market,
+    };
+
+    use crate::{
+        error::LendingProgramErrorKind,
+        test_runtime::{tests::create_btc_usdc_market, TestRuntime},
+    };
+
+    const USDC: u64 = 1_000_000;
+
+    #[test]
+    fn donation_rejected_when_disabled() {
+        let mut runtime = TestRuntime::new();
+        let program_id = crate::id();
+        let market = create_btc_usdc_market(&mut runtime);
+        let curator = *runtime.load::<Market>(&market.market).config().curator();
+
+        let lender = Pubkey::new_unique();
+        runtime.airdrop(lender, 1_000_000_000);
+        let lender_ata = runtime.create_token_account(lender, market.supply_mint, 10_000 * USDC);
+        let (supply_position, ix) =
+            create_supply_position_ix(program_id, market.market, lender, lender);
+        runtime.process(&ix).unwrap();
+        runtime
+            .process(&supply_apl_ix(
+                program_id,
+                market.market,
+                supply_position,
+                lender,
+                lender_ata,
+                market.supply_vault,
+                market.supply_oracle,
+                market.collateral_oracle,
+                1_000 * USDC,
+            ))
+            .unwrap();
+        let donate = donate_supply_ix(
+            program_id,
+            market.market,
+            lender,
+            lender_ata,
+            market.supply_vault,
+            100 * USDC,
+        );
+        runtime.process(&donate).unwrap();
+
+        let set_allow_donations = |allow_donations| {
+            update_config_ix(
+                program_id,
+                market.market,
+                curator,
+                UpdateConfigInstruction {
+                    allow_donations: Some(allow_donations),
+                    ..Default::default()
+                },
+                market.supply_oracle,
+                market.collateral_oracle,
+            )
+        };
+        runtime.process(&set_allow_donations(false)).unwrap();
+        assert!(!runtime
+            .load::<Market>(&market.market)
+            .config()
+            .allow_donations());
+        let err = runtime.process(&donate).unwrap_err();
+        assert_eq!(
+            err.0.error,
+            LendingProgramErrorKind::LendingError(LendingError::DonationsDisabled)
+        );
+        assert_eq!(
+            runtime.token_balance(&lender_ata),
+            10_000 * USDC - 1_100 * USDC
+        );
+
+        runtime.process(&set_allow_donations(true)).unwrap();
+        runtime.process(&donate).unwrap();
+    }
+}
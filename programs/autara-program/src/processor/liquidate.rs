@@ -1,13 +1,12 @@
-use arch_program::{
-    account::AccountInfo, clock::Clock, program::invoke_signed_unchecked, pubkey::Pubkey,
-};
+use arch_program::{account::AccountInfo, clock::Clock, pubkey::Pubkey};
 use autara_lib::{
     error::LendingError,
     event::{AutaraEvent, LiquidateEvent},
     ixs::{log_ix, LiquidateInstruction},
+    oracle::oracle_provider::AccountView,
 };
 
-use crate::{error::LendingProgramResult, ixs::LiquidateAccounts};
+use crate::{error::LendingProgramResult, ixs::LiquidateAccounts, utils::invoke_signed_unchecked};
 
 pub fn process_liquidate(
     liquidate_accounts: &LiquidateAccounts,
@@ -19,8 +18,8 @@ pub fn process_liquidate(
     let mut market_ref = liquidate_accounts.market.load_mut();
     let mut borrow_position_ref = liquidate_accounts.borrow_position.load_mut();
     let mut market_wrapper = market_ref.wrapper_mut(
-        liquidate_accounts.supply_oracle.try_into()?,
-        liquidate_accounts.collateral_oracle.try_into()?,
+        AccountView::try_from_optional(liquidate_accounts.supply_oracle)?,
+        AccountView::try_from_optional(liquidate_accounts.collateral_oracle)?,
         clock.unix_timestamp,
     )?;
 
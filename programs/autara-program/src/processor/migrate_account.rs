@@ -0,0 +1,84 @@
+use arch_program::msg;
+use autara_lib::state::version::VersionedAccount;
+
+use crate::{error::LendingProgramResult, ixs::MigrateAccountAccounts};
+
+pub fn process_migrate_account(
+    migrate_account_accounts: &MigrateAccountAccounts,
+) -> LendingProgramResult {
+    let migrated = match migrate_account_accounts {
+        MigrateAccountAccounts::Market(market) => market.load_mut().migrate()?,
+        MigrateAccountAccounts::SupplyPosition(position) => position.load_mut().migrate()?,
+        MigrateAccountAccounts::BorrowPosition(position) => position.load_mut().migrate()?,
+        MigrateAccountAccounts::GlobalConfig(global_config) => {
+            global_config.load_mut().migrate()?
+        }
+        MigrateAccountAccounts::CuratorMarketCount(count) => count.load_mut().migrate()?,
+    };
+    if !migrated {
+        msg!("Account is already at the current version");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use autara_lib::{
+        error::LendingError,
+        ixs::migrate_account_ix,
+        state::{
+            market::Market,
+            version::{VersionedAccount, CURRENT_ACCOUNT_VERSION},
+        },
+    };
+
+    use crate::{
+        error::LendingProgramErrorKind,
+        test_runtime::{tests::create_btc_usdc_market, TestRuntime},
+    };
+
+    fn set_market_version(
+        runtime: &mut TestRuntime,
+        market: &arch_program::pubkey::Pubkey,
+        version: u8,
+    ) {
+        let mut data = runtime.load::<Market>(market);
+        data.set_version(version);
+        runtime.accounts.get_mut(market).unwrap().data = bytemuck::bytes_of(&data).to_vec();
+    }
+
+    #[test]
+    fn migrate_account_upgrades_legacy_market_once() {
+        let mut runtime = TestRuntime::new();
+        let market = create_btc_usdc_market(&mut runtime).market;
+        let current = runtime.accounts[&market].data.clone();
+        set_market_version(&mut runtime, &market, 0);
+        assert_eq!(runtime.load::<Market>(&market).version(), 0);
+
+        let ix = migrate_account_ix(crate::id(), market);
+        runtime.process(&ix).unwrap();
+        assert_eq!(
+            runtime.load::<Market>(&market).version(),
+            CURRENT_ACCOUNT_VERSION
+        );
+        assert_eq!(runtime.accounts[&market].data, current);
+
+        // Migrating an account already at the current version is a no-op
+        runtime.process(&ix).unwrap();
+        assert_eq!(runtime.accounts[&market].data, current);
+    }
+
+    #[test]
+    fn migrate_account_rejects_unknown_versions() {
+        let mut runtime = TestRuntime::new();
+        let market = create_btc_usdc_market(&mut runtime).market;
+        set_market_version(&mut runtime, &market, CURRENT_ACCOUNT_VERSION + 1);
+        let err = runtime
+            .process(&migrate_account_ix(crate::id(), market))
+            .unwrap_err();
+        assert_eq!(
+            err.0.error,
+            LendingProgramErrorKind::LendingError(LendingError::UnsupportedAccountVersion)
+        );
+    }
+}
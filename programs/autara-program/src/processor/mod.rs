@@ -7,11 +7,15 @@ pub mod create_supply_position;
 pub mod deposit_apl_collateral;
 pub mod donate_supply;
 pub mod liquidate;
+pub mod migrate_account;
 pub mod redeem_curator_fees;
 pub mod redeem_protocol_fees;
 pub mod repay_apl;
+pub mod set_curator_market_count;
 pub mod socialize_loss;
 pub mod supply_apl;
+pub mod sweep_fee_dust;
+pub mod sync_market;
 pub mod update_config;
 pub mod update_global_config;
 pub mod withdraw_apl_collateral;
@@ -1,12 +1,13 @@
-use arch_program::{
-    account::AccountInfo, clock::Clock, program::invoke_signed_unchecked, pubkey::Pubkey,
-};
+use arch_program::{account::AccountInfo, clock::Clock, pubkey::Pubkey};
 use autara_lib::{
     event::{AutaraEvent, ReedeemFeeEvent},
     ixs::log_ix,
 };
 
-use crate::{error::LendingProgramResult, ixs::redeem_curator_fees::RedeemCuratorFeesAccounts};
+use crate::{
+    error::LendingProgramResult, ixs::redeem_curator_fees::RedeemCuratorFeesAccounts,
+    utils::invoke_signed_unchecked,
+};
 
 pub fn process_redeem_curator_fees(
     accounts: &RedeemCuratorFeesAccounts,
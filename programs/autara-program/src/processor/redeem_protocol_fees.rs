@@ -1,12 +1,13 @@
-use arch_program::{
-    account::AccountInfo, clock::Clock, program::invoke_signed_unchecked, pubkey::Pubkey,
-};
+use arch_program::{account::AccountInfo, clock::Clock, pubkey::Pubkey};
 use autara_lib::{
     event::{AutaraEvent, ReedeemFeeEvent},
     ixs::log_ix,
 };
 
-use crate::{error::LendingProgramResult, ixs::redeem_protocol_fees::RedeemProtocolFeesAccounts};
+use crate::{
+    error::LendingProgramResult, ixs::redeem_protocol_fees::RedeemProtocolFeesAccounts,
+    utils::invoke_signed_unchecked,
+};
 
 pub fn process_redeem_protocol_fees(
     accounts: &RedeemProtocolFeesAccounts,
@@ -22,7 +23,7 @@ pub fn process_redeem_protocol_fees(
         &log_ix(
             program_id,
             accounts.market.key(),
-            AutaraEvent::ReedeemCuratorFees(ReedeemFeeEvent {
+            AutaraEvent::ReedeemProtocolFees(ReedeemFeeEvent {
                 market: *accounts.market.key(),
                 fee_receiver: accounts.receiver_supply_ata.owner,
                 fee_amount: to_withdraw,
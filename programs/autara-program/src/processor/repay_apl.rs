@@ -1,12 +1,11 @@
-use arch_program::{
-    account::AccountInfo, clock::Clock, program::invoke_signed_unchecked, pubkey::Pubkey,
-};
+use arch_program::{account::AccountInfo, clock::Clock, pubkey::Pubkey};
 use autara_lib::{
     event::AutaraEvent,
     ixs::{log_ix, RepayAplInstruction},
+    oracle::oracle_provider::AccountView,
 };
 
-use crate::{error::LendingProgramResult, ixs::RepayAplAccounts};
+use crate::{error::LendingProgramResult, ixs::RepayAplAccounts, utils::invoke_signed_unchecked};
 
 pub fn process_repay_apl(
     repay_apl_accounts: &RepayAplAccounts,
@@ -18,8 +17,8 @@ pub fn process_repay_apl(
     let mut market_ref = repay_apl_accounts.market.load_mut();
     let mut borrowing_position_ref = repay_apl_accounts.borrow_position.load_mut();
     let mut market_wrapper = market_ref.wrapper_mut(
-        repay_apl_accounts.supply_oracle.try_into()?,
-        repay_apl_accounts.collateral_oracle.try_into()?,
+        AccountView::try_from_optional(repay_apl_accounts.supply_oracle)?,
+        AccountView::try_from_optional(repay_apl_accounts.collateral_oracle)?,
         clock.unix_timestamp,
     )?;
     market_wrapper.sync_clock(clock.unix_timestamp)?;
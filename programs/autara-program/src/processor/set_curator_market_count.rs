@@ -0,0 +1,159 @@
+use arch_program::{account::AccountInfo, pubkey::Pubkey};
+use autara_lib::{
+    ixs::SetCuratorMarketCountInstruction, state::curator_market_count::CuratorMarketCount,
+};
+use autara_program_lib::accounts::zero_copy::ZeroCopyOwnedAccountMut;
+
+use crate::{
+    error::LendingProgramResult, ixs::SetCuratorMarketCountAccounts,
+    processor::create_market::create_curator_market_count_if_empty, state::AutaraAccount,
+};
+
+pub fn process_set_curator_market_count(
+    set_curator_market_count_accounts: &SetCuratorMarketCountAccounts,
+    data: &SetCuratorMarketCountInstruction,
+    accounts: &[AccountInfo],
+    program_id: &Pubkey,
+) -> LendingProgramResult {
+    create_curator_market_count_if_empty(
+        set_curator_market_count_accounts.admin.key,
+        set_curator_market_count_accounts.curator_market_count,
+        &data.curator,
+        accounts,
+        program_id,
+    )?;
+    let count = ZeroCopyOwnedAccountMut::<AutaraAccount<CuratorMarketCount>>::try_from(
+        set_curator_market_count_accounts.curator_market_count,
+    )?;
+    count.load_mut().set_market_count(data.market_count);
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use arch_program::pubkey::Pubkey;
+    use autara_lib::{
+        error::LendingError,
+        interest_rate::interest_rate_kind::InterestRateCurveKind,
+        ixs::{
+            create_market_ix, set_curator_market_count_ix, update_global_config_ix,
+            CreateMarketInstruction, UpdateGlobalConfigInstruction,
+        },
+        pda::{find_curator_market_count_pda, find_global_config_pda},
+        state::{
+            curator_market_count::CuratorMarketCount, global_config::GlobalConfig, market::Market,
+            market_config::LtvConfig,
+        },
+    };
+
+    use crate::{
+        error::{LendingAccountValidationError, LendingProgramErrorKind},
+        test_runtime::{tests::create_btc_usdc_market, TestRuntime},
+    };
+
+    #[test]
+    fn backfilled_count_applies_to_the_market_limit() {
+        const USDC_FEED: [u8; 32] = [1; 32];
+        const BTC_FEED: [u8; 32] = [2; 32];
+        let mut runtime = TestRuntime::new();
+        let test_market = create_btc_usdc_market(&mut runtime);
+        let (global_config, _) = find_global_config_pda(&crate::id());
+        let admin = *runtime.load::<GlobalConfig>(&global_config).admin();
+        let curator = Pubkey::new_unique();
+        runtime.airdrop(curator, 1_000_000_000);
+        let (curator_market_count, _) = find_curator_market_count_pda(&crate::id(), &curator);
+
+        // a curator whose markets predate the count has no count account yet
+        assert!(!runtime.accounts.contains_key(&curator_market_count));
+        let backfill = set_curator_market_count_ix(crate::id(), admin, curator, 2);
+        let mut not_admin_backfill = backfill.clone();
+        not_admin_backfill.accounts[0].pubkey = curator;
+        let err = runtime.process(&not_admin_backfill).unwrap_err();
+        assert_eq!(
+            err.0.error,
+            LendingProgramErrorKind::LendingAccountValidationError(
+                LendingAccountValidationError::InvalidAuthority
+            )
+        );
+        runtime.process(&backfill).unwrap();
+        let count = runtime.load::<CuratorMarketCount>(&curator_market_count);
+        assert_eq!(count.curator(), &curator);
+        assert_eq!(count.market_count(), 2);
+
+        runtime
+            .process(&update_global_config_ix(
+                crate::id(),
+                admin,
+                UpdateGlobalConfigInstruction {
+                    max_markets_per_curator: Some(3),
+                    ..Default::default()
+                },
+            ))
+            .unwrap();
+        let create_market = |runtime: &mut TestRuntime, index: u8| {
+            create_market_ix(
+                CreateMarketInstruction {
+                    market_bump: 0,
+                    index,
+                    ltv_config: LtvConfig {
+                        max_ltv: 0.8.into(),
+                        unhealthy_ltv: 0.9.into(),
+                        liquidation_bonus: 0.05.into(),
+                    },
+                    max_utilisation_rate: 0.9.into(),
+                    supply_oracle_config: runtime.pyth_oracle_config(USDC_FEED),
+                    collateral_oracle_config: runtime.pyth_oracle_config(BTC_FEED),
+                    interest_rate: InterestRateCurveKind::new_adaptive(),
+                    lending_market_fee_in_bps: 0,
+                    metadata_uri: Default::default(),
+                    borrow_origination_grace_seconds: 0,
+                },
+                test_market.supply_mint,
+                test_market.collateral_mint,
+                crate::id(),
+                curator,
+                curator,
+            )
+        };
+        let (market, ix) = create_market(&mut runtime, 0);
+        runtime.process(&ix).unwrap();
+        assert!(runtime.accounts.contains_key(&market));
+        let (market, ix) = create_market(&mut runtime, 1);
+        let err = runtime.process(&ix).unwrap_err();
+        assert_eq!(
+            err.0.error,
+            LendingProgramErrorKind::LendingError(LendingError::CuratorMarketLimitReached)
+        );
+        assert!(!runtime.accounts.contains_key(&market));
+
+        // the admin can correct an existing count
+        runtime
+            .process(&set_curator_market_count_ix(crate::id(), admin, curator, 1))
+            .unwrap();
+        runtime.process(&ix).unwrap();
+        assert_eq!(
+            runtime
+                .load::<CuratorMarketCount>(&curator_market_count)
+                .market_count(),
+            2
+        );
+        assert_eq!(runtime.load::<Market>(&market).config().curator(), &curator);
+    }
+
+    #[test]
+    fn set_curator_market_count_rejects_another_curator_count() {
+        let mut runtime = TestRuntime::new();
+        create_btc_usdc_market(&mut runtime);
+        let (global_config, _) = find_global_config_pda(&crate::id());
+        let admin = *runtime.load::<GlobalConfig>(&global_config).admin();
+        let mut ix = set_curator_market_count_ix(crate::id(), admin, Pubkey::new_unique(), 1);
+        ix.accounts[2].pubkey = find_curator_market_count_pda(&crate::id(), &admin).0;
+        let err = runtime.process(&ix).unwrap_err();
+        assert_eq!(
+            err.0.error,
+            LendingProgramErrorKind::LendingAccountValidationError(
+                LendingAccountValidationError::InvalidCuratorMarketCount
+            )
+        );
+    }
+}
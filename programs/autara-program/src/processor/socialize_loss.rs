@@ -1,12 +1,13 @@
-use arch_program::{
-    account::AccountInfo, clock::Clock, program::invoke_signed_unchecked, pubkey::Pubkey,
-};
+use arch_program::{account::AccountInfo, clock::Clock, pubkey::Pubkey};
 use autara_lib::{
     event::{AutaraEvent, SocializeLossEvent},
     ixs::{log_ix, SocializeLossInstruction},
+    oracle::oracle_provider::AccountView,
 };
 
-use crate::{error::LendingProgramResult, ixs::SocializeLossAccounts};
+use crate::{
+    error::LendingProgramResult, ixs::SocializeLossAccounts, utils::invoke_signed_unchecked,
+};
 
 pub fn process_socialize_loss(
     socialize_loss_accounts: &SocializeLossAccounts,
@@ -18,8 +19,8 @@ pub fn process_socialize_loss(
     let mut market_ref = socialize_loss_accounts.market.load_mut();
     let mut borrow_position_ref = socialize_loss_accounts.borrow_position.load_mut();
     let mut market_wrapper = market_ref.wrapper_mut(
-        socialize_loss_accounts.supply_oracle.try_into()?,
-        socialize_loss_accounts.collateral_oracle.try_into()?,
+        AccountView::try_from_optional(socialize_loss_accounts.supply_oracle)?,
+        AccountView::try_from_optional(socialize_loss_accounts.collateral_oracle)?,
         clock.unix_timestamp,
     )?;
 
@@ -1,12 +1,11 @@
-use arch_program::{
-    account::AccountInfo, clock::Clock, program::invoke_signed_unchecked, pubkey::Pubkey,
-};
+use arch_program::{account::AccountInfo, clock::Clock, pubkey::Pubkey};
 use autara_lib::{
     event::AutaraEvent,
     ixs::{log_ix, SupplyAplInstruction},
+    oracle::oracle_provider::AccountView,
 };
 
-use crate::{error::LendingProgramResult, ixs::SupplyAplAccounts};
+use crate::{error::LendingProgramResult, ixs::SupplyAplAccounts, utils::invoke_signed_unchecked};
 
 pub fn process_supply_apl(
     create_supply_position_accounts: &SupplyAplAccounts,
@@ -18,10 +17,8 @@ pub fn process_supply_apl(
     let mut market_ref = create_supply_position_accounts.market.load_mut();
     let mut supply_position_ref = create_supply_position_accounts.supply_position.load_mut();
     let mut market_wrapper = market_ref.wrapper_mut(
-        create_supply_position_accounts.supply_oracle.try_into()?,
-        create_supply_position_accounts
-            .collateral_oracle
-            .try_into()?,
+        AccountView::try_from_optional(create_supply_position_accounts.supply_oracle)?,
+        AccountView::try_from_optional(create_supply_position_accounts.collateral_oracle)?,
         clock.unix_timestamp,
     )?;
     market_wrapper.sync_clock(clock.unix_timestamp)?;
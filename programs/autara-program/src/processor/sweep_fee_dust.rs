@@ -0,0 +1,167 @@
+use arch_program::{account::AccountInfo, clock::Clock, pubkey::Pubkey};
+use autara_lib::{
+    event::{AutaraEvent, SweepFeeDustEvent},
+    ixs::log_ix,
+};
+
+use crate::{
+    error::LendingProgramResult, ixs::SweepFeeDustAccounts, utils::invoke_signed_unchecked,
+};
+
+pub fn process_sweep_fee_dust(
+    accounts: &SweepFeeDustAccounts,
+    account_infos: &[AccountInfo],
+    program_id: &Pubkey,
+    clock: &Clock,
+) -> LendingProgramResult {
+    let mut market_ref = accounts.market.load_mut();
+    market_ref.sync_clock(clock.unix_timestamp)?;
+    let swept_shares = market_ref.sweep_fee_dust()?;
+    let seed = market_ref.seed();
+    invoke_signed_unchecked(
+        &log_ix(
+            program_id,
+            accounts.market.key(),
+            AutaraEvent::SweepFeeDust(SweepFeeDustEvent {
+                market: *accounts.market.key(),
+                admin: *accounts.admin.key,
+                mint: *market_ref.supply_vault().mint(),
+                swept_shares,
+                supply_vault_summary: market_ref.supply_vault().get_summary()?,
+            }),
+        ),
+        account_infos,
+        &[&seed],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use arch_program::pubkey::Pubkey;
+    use autara_lib::{
+        event::{AutaraEvent, SweepFeeDustEvent},
+        ixs::{
+            borrow_deposit_apl_ix, sweep_fee_dust_ix, sync_market_ix, update_config_ix,
+            BorrowDepositAplInstruction, UpdateConfigInstruction,
+        },
+        math::ufixed_point::UFixedPoint,
+        pda::find_global_config_pda,
+        state::{global_config::GlobalConfig, market::Market},
+    };
+
+    use crate::{
+        error::{LendingAccountValidationError, LendingProgramErrorKind},
+        processor::borrow_deposit_apl::tests::setup_borrower,
+        test_runtime::{tests::create_btc_usdc_market, TestRuntime},
+    };
+
+    const USDC: u64 = 1_000_000;
+    const BTC: u64 = 100_000_000;
+
+    #[test]
+    fn only_admin_can_sweep_fee_dust() {
+        let mut runtime = TestRuntime::new();
+        let market = create_btc_usdc_market(&mut runtime).market;
+        let (global_config, _) = find_global_config_pda(&crate::id());
+        let admin = *runtime.load::<GlobalConfig>(&global_config).admin();
+
+        let err = runtime
+            .process(&sweep_fee_dust_ix(
+                crate::id(),
+                market,
+                Pubkey::new_unique(),
+            ))
+            .unwrap_err();
+        assert_eq!(
+            err.0.error,
+            LendingProgramErrorKind::LendingAccountValidationError(
+                LendingAccountValidationError::InvalidAuthority
+            )
+        );
+
+        runtime.advance_time(3600);
+        runtime
+            .process(&sweep_fee_dust_ix(crate::id(), market, admin))
+            .unwrap();
+        assert_eq!(
+            runtime
+                .load::<Market>(&market)
+                .supply_vault()
+                .last_update_unix_timestamp(),
+            runtime.unix_timestamp
+        );
+    }
+
+    #[test]
+    fn sweep_fee_dust_clears_sub_atom_pending_fees() {
+        let mut runtime = TestRuntime::new();
+        let program_id = crate::id();
+        let market = create_btc_usdc_market(&mut runtime);
+        let (global_config, _) = find_global_config_pda(&program_id);
+        let admin = *runtime.load::<GlobalConfig>(&global_config).admin();
+        let curator = *runtime.load::<Market>(&market.market).config().curator();
+        runtime
+            .process(&update_config_ix(
+                program_id,
+                market.market,
+                curator,
+                UpdateConfigInstruction {
+                    lending_market_fee_in_bps: Some(1000),
+                    ..Default::default()
+                },
+                market.supply_oracle,
+                market.collateral_oracle,
+            ))
+            .unwrap();
+        let [borrower, borrow_position, supply_ata, collateral_ata] =
+            setup_borrower(&mut runtime, &market);
+        runtime
+            .process(&borrow_deposit_apl_ix(
+                program_id,
+                market.market,
+                borrower,
+                borrow_position,
+                supply_ata,
+                market.supply_vault,
+                collateral_ata,
+                market.collateral_vault,
+                market.supply_oracle,
+                market.collateral_oracle,
+                BorrowDepositAplInstruction {
+                    deposit_amount: BTC / 2,
+                    borrow_amount: 100 * USDC,
+                    ix_callback: None,
+                },
+            ))
+            .unwrap();
+        // a second of interest on 100 USDC leaves a curator fee below one atom
+        runtime.advance_time(1);
+        runtime
+            .process(&sync_market_ix(program_id, market.market))
+            .unwrap();
+
+        let before = *runtime.load::<Market>(&market.market).supply_vault();
+        let dust = before.pending_curator_fee_shares();
+        assert!(!dust.is_zero());
+        assert_eq!(before.get_summary().unwrap().pending_curator_fee_atoms, 0);
+        assert_eq!(before.pending_protocol_fee_shares(), UFixedPoint::zero());
+
+        runtime
+            .process(&sweep_fee_dust_ix(program_id, market.market, admin))
+            .unwrap();
+        let after = *runtime.load::<Market>(&market.market).supply_vault();
+        assert_eq!(after.pending_curator_fee_shares(), UFixedPoint::zero());
+        assert_eq!(after.pending_protocol_fee_shares(), UFixedPoint::zero());
+        assert_eq!(
+            runtime.events().last(),
+            Some(&AutaraEvent::SweepFeeDust(SweepFeeDustEvent {
+                market: market.market,
+                admin,
+                mint: market.supply_mint,
+                swept_shares: dust,
+                supply_vault_summary: after.get_summary().unwrap(),
+            }))
+        );
+    }
+}
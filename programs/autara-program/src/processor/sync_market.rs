@@ -0,0 +1,50 @@
+use arch_program::clock::Clock;
+
+use crate::{error::LendingProgramResult, ixs::SyncMarketAccounts};
+
+pub fn process_sync_market(
+    sync_market_accounts: &SyncMarketAccounts,
+    clock: &Clock,
+) -> LendingProgramResult {
+    let mut market_ref = sync_market_accounts.market.load_mut();
+    market_ref.sync_clock(clock.unix_timestamp)?;
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use autara_lib::{ixs::sync_market_ix, state::market::Market};
+
+    use crate::test_runtime::{tests::create_btc_usdc_market, TestRuntime};
+
+    #[test]
+    fn sync_market_advances_last_update() {
+        let mut runtime = TestRuntime::new();
+        let market = create_btc_usdc_market(&mut runtime).market;
+        let created_at = runtime.unix_timestamp;
+        let last_update = |runtime: &TestRuntime| {
+            runtime
+                .load::<Market>(&market)
+                .supply_vault()
+                .last_update_unix_timestamp()
+        };
+        assert_eq!(last_update(&runtime), created_at);
+
+        runtime.advance_time(3600);
+        let market_before = runtime.load::<Market>(&market);
+        runtime
+            .process(&sync_market_ix(crate::id(), market))
+            .unwrap();
+        assert_eq!(last_update(&runtime), created_at + 3600);
+        let market_after = runtime.load::<Market>(&market);
+        assert_eq!(
+            market_before.config().curator(),
+            market_after.config().curator()
+        );
+        assert_eq!(
+            market_before.supply_vault().total_supply().unwrap(),
+            market_after.supply_vault().total_supply().unwrap()
+        );
+        assert!(runtime.events().is_empty());
+    }
+}
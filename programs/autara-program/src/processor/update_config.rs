@@ -1,5 +1,8 @@
 use arch_program::clock::Clock;
-use autara_lib::ixs::UpdateConfigInstruction;
+use autara_lib::{
+    error::LendingError, ixs::UpdateConfigInstruction, math::bps::Bps,
+    oracle::oracle_provider::AccountView,
+};
 
 use crate::{error::LendingProgramResult, ixs::UpdateConfigAccounts};
 
@@ -8,37 +11,259 @@ pub fn process_update_config(
     data: &UpdateConfigInstruction,
     clock: &Clock,
 ) -> LendingProgramResult {
+    if data.max_supply_ramp_seconds.is_some() && data.max_supply_atoms.is_none() {
+        // a ramp only applies to a new max supply
+        return Err(LendingError::InvalidInstructionArguments.into());
+    }
     let mut market_ref = accounts.market.load_mut();
+    // interest up to now accrues with the config it was earned under
+    market_ref.sync_clock(clock.unix_timestamp)?;
+    let max_supply_atoms_before = market_ref.max_supply_atoms(clock.unix_timestamp);
+    // fields are all applied before the market is validated once as a whole, so that
+    // changes which are only consistent together can be made in one update
     if let Some(supply_oracle_config) = &data.supply_oracle_config {
         market_ref.set_supply_oracle_config(*supply_oracle_config);
     }
     if let Some(collateral_oracle_config) = &data.collateral_oracle_config {
         market_ref.set_collateral_oracle_config(*collateral_oracle_config);
     }
+    let config = market_ref.config_mut();
     if let Some(ltv_config) = &data.ltv_config {
-        market_ref.config_mut().update_ltv(ltv_config)?;
+        config.update_ltv(ltv_config)?;
+    }
+    if let Some(borrow_cap_ltv) = &data.borrow_cap_ltv {
+        config.update_borrow_cap_ltv(*borrow_cap_ltv)?;
+    }
+    if let Some(warning_ltv) = &data.warning_ltv {
+        config.update_warning_ltv(*warning_ltv)?;
     }
     if let Some(max_utilisation_rate) = &data.max_utilisation_rate {
-        market_ref
-            .config_mut()
-            .update_max_utilisation_rate(*max_utilisation_rate)?;
+        config.update_max_utilisation_rate(*max_utilisation_rate)?;
     }
     if let Some(max_supply_atoms) = &data.max_supply_atoms {
-        market_ref
-            .config_mut()
-            .update_max_supply_atoms(*max_supply_atoms);
+        config.update_max_supply_atoms(*max_supply_atoms)?;
+    }
+    if let Some(min_liquidity_buffer_atoms) = &data.min_liquidity_buffer_atoms {
+        config.update_min_liquidity_buffer_atoms(*min_liquidity_buffer_atoms);
+    }
+    if let Some(min_collateral_atoms) = &data.min_collateral_after_liquidation_atoms {
+        config.update_min_collateral_after_liquidation_atoms(*min_collateral_atoms);
+    }
+    if let Some(seconds) = &data.borrow_max_staleness_seconds {
+        config.update_borrow_max_staleness_seconds(*seconds);
+    }
+    if let Some(min_supply_atoms) = &data.min_supply_for_borrow_atoms {
+        config.update_min_supply_for_borrow_atoms(*min_supply_atoms);
+    }
+    if let Some(allow_donations) = &data.allow_donations {
+        config.update_allow_donations(*allow_donations);
     }
     if let Some(fee) = &data.lending_market_fee_in_bps {
-        market_ref.config_mut().set_lending_market_fee(*fee)?;
+        config.set_lending_market_fee(Bps::new(*fee))?;
+    }
+    config.sync_global_config(&accounts.global_config.load_ref());
+    if let Some(max_ltv) = &data.max_ltv_at_max_utilisation {
+        market_ref.update_max_ltv_at_max_utilisation(*max_ltv)?;
+    }
+    if let Some(allowlist) = &data.callback_program_allowlist {
+        market_ref.update_callback_program_allowlist(allowlist)?;
+    }
+    if let Some(metadata_uri) = &data.metadata_uri {
+        market_ref.update_metadata_uri(*metadata_uri)?;
+    }
+    if data.max_supply_atoms.is_some() {
+        market_ref.ramp_max_supply(
+            max_supply_atoms_before,
+            data.max_supply_ramp_seconds.unwrap_or_default(),
+            clock.unix_timestamp,
+        );
     }
-    market_ref
-        .config_mut()
-        .sync_global_config(&accounts.global_config.load_ref());
+    market_ref.validate()?;
     // check oracles are valid
     let _ = market_ref.wrapper_mut(
-        accounts.updated_supply_oracle.try_into()?,
-        accounts.updated_collateral_oracle.try_into()?,
+        AccountView::try_from_optional(accounts.updated_supply_oracle)?,
+        AccountView::try_from_optional(accounts.updated_collateral_oracle)?,
         clock.unix_timestamp,
     )?;
     Ok(())
 }
+
+#[cfg(test)]
+pub mod tests {
+    use autara_lib::{
+        error::LendingError,
+        ixs::{update_config_ix, UpdateConfigInstruction},
+        math::ifixed_point::IFixedPoint,
+        oracle::oracle_config::OracleConfig,
+        state::{market::Market, market_config::LtvConfig},
+    };
+
+    use crate::{
+        error::LendingProgramErrorKind,
+        test_runtime::{
+            tests::{create_btc_usdc_market, TestMarket},
+            TestRuntime,
+        },
+    };
+
+    fn update_config(
+        runtime: &mut TestRuntime,
+        test_market: &TestMarket,
+        update: UpdateConfigInstruction,
+    ) -> Result<(), LendingProgramErrorKind> {
+        let curator = *runtime
+            .load::<Market>(&test_market.market)
+            .config()
+            .curator();
+        runtime
+            .process(&update_config_ix(
+                crate::id(),
+                test_market.market,
+                curator,
+                update,
+                test_market.supply_oracle,
+                test_market.collateral_oracle,
+            ))
+            .map(|_| ())
+            .map_err(|err| err.0.error)
+    }
+
+    fn ltv_config(max_ltv: IFixedPoint) -> LtvConfig {
+        LtvConfig {
+            max_ltv,
+            unhealthy_ltv: 0.9.into(),
+            liquidation_bonus: 0.05.into(),
+        }
+    }
+
+    #[test]
+    fn update_config_validates_fields_together() {
+        let mut runtime = TestRuntime::new();
+        let test_market = create_btc_usdc_market(&mut runtime);
+        let config = |runtime: &TestRuntime| *runtime.load::<Market>(&test_market.market).config();
+        update_config(
+            &mut runtime,
+            &test_market,
+            UpdateConfigInstruction {
+                borrow_cap_ltv: Some(IFixedPoint::lit("0.7")),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        // raising the cap above the current max ltv is only valid with the new max ltv
+        let raise_cap = UpdateConfigInstruction {
+            borrow_cap_ltv: Some(IFixedPoint::lit("0.85")),
+            ..Default::default()
+        };
+        assert_eq!(
+            update_config(&mut runtime, &test_market, raise_cap.clone()).unwrap_err(),
+            LendingProgramErrorKind::LendingError(LendingError::InvalidLtvConfig)
+        );
+        assert_eq!(config(&runtime).borrow_cap_ltv(), IFixedPoint::lit("0.7"));
+        update_config(
+            &mut runtime,
+            &test_market,
+            UpdateConfigInstruction {
+                ltv_config: Some(ltv_config(IFixedPoint::lit("0.87"))),
+                ..raise_cap
+            },
+        )
+        .unwrap();
+        assert_eq!(config(&runtime).borrow_cap_ltv(), IFixedPoint::lit("0.85"));
+        // lowering the max ltv below the current cap is only valid with the new cap
+        let lower_max_ltv = UpdateConfigInstruction {
+            ltv_config: Some(ltv_config(IFixedPoint::lit("0.6"))),
+            ..Default::default()
+        };
+        assert_eq!(
+            update_config(&mut runtime, &test_market, lower_max_ltv.clone()).unwrap_err(),
+            LendingProgramErrorKind::LendingError(LendingError::InvalidLtvConfig)
+        );
+        assert_eq!(
+            config(&runtime).ltv_config().max_ltv,
+            IFixedPoint::lit("0.87")
+        );
+        update_config(
+            &mut runtime,
+            &test_market,
+            UpdateConfigInstruction {
+                borrow_cap_ltv: Some(IFixedPoint::lit("0.5")),
+                ..lower_max_ltv
+            },
+        )
+        .unwrap();
+        let config = config(&runtime);
+        assert_eq!(config.ltv_config().max_ltv, IFixedPoint::lit("0.6"));
+        assert_eq!(config.borrow_cap_ltv(), IFixedPoint::lit("0.5"));
+    }
+
+    #[test]
+    fn update_config_syncs_and_validates_the_whole_market() {
+        let mut runtime = TestRuntime::new();
+        let test_market = create_btc_usdc_market(&mut runtime);
+        runtime.advance_time(10);
+        update_config(
+            &mut runtime,
+            &test_market,
+            UpdateConfigInstruction {
+                max_utilisation_rate: Some(IFixedPoint::lit("0.8")),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            runtime
+                .load::<Market>(&test_market.market)
+                .supply_vault()
+                .last_update_unix_timestamp(),
+            runtime.unix_timestamp
+        );
+        assert_eq!(
+            update_config(
+                &mut runtime,
+                &test_market,
+                UpdateConfigInstruction {
+                    max_supply_ramp_seconds: Some(3600),
+                    ..Default::default()
+                },
+            )
+            .unwrap_err(),
+            LendingProgramErrorKind::LendingError(LendingError::InvalidInstructionArguments)
+        );
+        assert_eq!(
+            update_config(
+                &mut runtime,
+                &test_market,
+                UpdateConfigInstruction {
+                    collateral_oracle_config: Some(OracleConfig::new_constant(IFixedPoint::zero())),
+                    ..Default::default()
+                },
+            )
+            .unwrap_err(),
+            LendingProgramErrorKind::LendingError(LendingError::InvalidOracleConfig)
+        );
+        // raising the max ltv at max utilisation above the max ltv is only valid with it
+        let raise_max_ltv_at_max_utilisation = UpdateConfigInstruction {
+            max_ltv_at_max_utilisation: Some(IFixedPoint::lit("0.85")),
+            ..Default::default()
+        };
+        assert_eq!(
+            update_config(
+                &mut runtime,
+                &test_market,
+                raise_max_ltv_at_max_utilisation.clone()
+            )
+            .unwrap_err(),
+            LendingProgramErrorKind::LendingError(LendingError::InvalidLtvConfig)
+        );
+        update_config(
+            &mut runtime,
+            &test_market,
+            UpdateConfigInstruction {
+                ltv_config: Some(ltv_config(IFixedPoint::lit("0.87"))),
+                ..raise_max_ltv_at_max_utilisation
+            },
+        )
+        .unwrap();
+    }
+}
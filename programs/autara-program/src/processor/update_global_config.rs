@@ -1,4 +1,4 @@
-use autara_lib::{error::LendingError, ixs::UpdateGlobalConfigInstruction};
+use autara_lib::{error::LendingError, ixs::UpdateGlobalConfigInstruction, math::bps::Bps};
 
 use crate::{
     error::{LendingAccountValidationError, LendingProgramResult},
@@ -21,14 +21,88 @@ pub fn process_update_global_config(
         return Err(LendingAccountValidationError::InvalidProtocolAuthority.into());
     }
     if let Some(protocol_fee_share_in_bps) = instruction.protocol_fee_share_in_bps {
-        global_config.update_protocol_fee_share_in_bps(protocol_fee_share_in_bps)?;
+        global_config.update_protocol_fee_share_in_bps(Bps::new(protocol_fee_share_in_bps))?;
     }
     if let Some(fee_receiver) = instruction.fee_receiver {
         global_config.set_fee_receiver(fee_receiver);
     }
+    if let Some(max_markets_per_curator) = instruction.max_markets_per_curator {
+        global_config.set_max_markets_per_curator(max_markets_per_curator);
+    }
+    if instruction.cancel_nomination {
+        global_config.cancel_nomination();
+    }
     if let Some(nominated_admin) = instruction.nominated_admin {
         global_config.set_nominated_admin(nominated_admin);
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+pub mod tests {
+    use arch_program::pubkey::Pubkey;
+    use autara_lib::{
+        ixs::{create_global_config_ix, update_global_config_ix},
+        state::global_config::GlobalConfig,
+    };
+
+    use super::*;
+    use crate::{error::LendingProgramErrorKind, test_runtime::TestRuntime};
+
+    #[test]
+    fn cancelled_nomination_cannot_be_accepted() {
+        let mut runtime = TestRuntime::new();
+        let admin = Pubkey::new_unique();
+        let nominated_admin = Pubkey::new_unique();
+        runtime.airdrop(admin, 1_000_000_000);
+        let (global_config, ix) = create_global_config_ix(crate::id(), admin, admin, admin, 0);
+        runtime.process(&ix).unwrap();
+        runtime
+            .process(&update_global_config_ix(
+                crate::id(),
+                admin,
+                UpdateGlobalConfigInstruction {
+                    nominated_admin: Some(nominated_admin),
+                    ..Default::default()
+                },
+            ))
+            .unwrap();
+        let config = runtime.load::<GlobalConfig>(&global_config);
+        assert_eq!(config.nominated_admin(), Some(&nominated_admin));
+
+        let cancel = update_global_config_ix(
+            crate::id(),
+            admin,
+            UpdateGlobalConfigInstruction {
+                cancel_nomination: true,
+                ..Default::default()
+            },
+        );
+        // only the admin can cancel
+        let mut not_admin_cancel = cancel.clone();
+        not_admin_cancel.accounts[0].pubkey = nominated_admin;
+        assert!(runtime.process(&not_admin_cancel).is_err());
+        runtime.process(&cancel).unwrap();
+        let config = runtime.load::<GlobalConfig>(&global_config);
+        assert_eq!(config.nominated_admin(), None);
+
+        let err = runtime
+            .process(&update_global_config_ix(
+                crate::id(),
+                nominated_admin,
+                UpdateGlobalConfigInstruction {
+                    accept_nomination: true,
+                    ..Default::default()
+                },
+            ))
+            .unwrap_err();
+        assert_eq!(
+            err.0.error,
+            LendingProgramErrorKind::LendingAccountValidationError(
+                LendingAccountValidationError::InvalidProtocolAuthority
+            )
+        );
+        assert_eq!(runtime.load::<GlobalConfig>(&global_config).admin(), &admin);
+    }
+}
@@ -1,12 +1,13 @@
-use arch_program::{
-    account::AccountInfo, clock::Clock, program::invoke_signed_unchecked, pubkey::Pubkey,
-};
+use arch_program::{account::AccountInfo, clock::Clock, pubkey::Pubkey};
 use autara_lib::{
     event::AutaraEvent,
     ixs::{log_ix, WithdrawAplCollateralInstruction},
+    oracle::oracle_provider::AccountView,
 };
 
-use crate::{error::LendingProgramResult, ixs::WithdrawAplCollateralAccounts};
+use crate::{
+    error::LendingProgramResult, ixs::WithdrawAplCollateralAccounts, utils::invoke_signed_unchecked,
+};
 
 pub fn process_withdraw_apl_collateral(
     withdraw_apl_collateral_accounts: &WithdrawAplCollateralAccounts,
@@ -17,10 +18,8 @@ pub fn process_withdraw_apl_collateral(
 ) -> LendingProgramResult {
     let mut market_ref = withdraw_apl_collateral_accounts.market.load_mut();
     let mut market_wrapper = market_ref.wrapper_mut(
-        withdraw_apl_collateral_accounts.supply_oracle.try_into()?,
-        withdraw_apl_collateral_accounts
-            .collateral_oracle
-            .try_into()?,
+        AccountView::try_from_optional(withdraw_apl_collateral_accounts.supply_oracle)?,
+        AccountView::try_from_optional(withdraw_apl_collateral_accounts.collateral_oracle)?,
         clock.unix_timestamp,
     )?;
     market_wrapper.sync_clock(clock.unix_timestamp)?;
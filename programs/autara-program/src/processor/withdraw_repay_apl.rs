@@ -1,12 +1,13 @@
-use arch_program::{
-    account::AccountInfo, clock::Clock, program::invoke_signed_unchecked, pubkey::Pubkey,
-};
+use arch_program::{account::AccountInfo, clock::Clock, pubkey::Pubkey};
 use autara_lib::{
     event::AutaraEvent,
     ixs::{log_ix, WithdrawRepayAplInstruction},
+    oracle::oracle_provider::AccountView,
 };
 
-use crate::{error::LendingProgramResult, ixs::WithdrawRepayAplAccounts};
+use crate::{
+    error::LendingProgramResult, ixs::WithdrawRepayAplAccounts, utils::invoke_signed_unchecked,
+};
 
 pub fn process_withdraw_repay_apl(
     withdraw_repay_apl_accounts: &WithdrawRepayAplAccounts,
@@ -16,10 +17,13 @@ pub fn process_withdraw_repay_apl(
     clock: &Clock,
 ) -> LendingProgramResult {
     let mut market_ref = withdraw_repay_apl_accounts.market.load_mut();
+    if let Some(ix) = &data.ix_callback {
+        market_ref.check_callback_program(&ix.program_id)?;
+    }
     let mut borrowing_position_ref = withdraw_repay_apl_accounts.borrow_position.load_mut();
     let mut market_wrapper = market_ref.wrapper_mut(
-        withdraw_repay_apl_accounts.supply_oracle.try_into()?,
-        withdraw_repay_apl_accounts.collateral_oracle.try_into()?,
+        AccountView::try_from_optional(withdraw_repay_apl_accounts.supply_oracle)?,
+        AccountView::try_from_optional(withdraw_repay_apl_accounts.collateral_oracle)?,
         clock.unix_timestamp,
     )?;
     market_wrapper.sync_clock(clock.unix_timestamp)?;
@@ -71,7 +75,14 @@ pub fn process_withdraw_repay_apl(
     )?;
 
     if let Some(ix) = &data.ix_callback {
+        borrowing_position_ref.set_reentrancy_guard(true);
+        drop(market_ref);
+        drop(borrowing_position_ref);
         invoke_signed_unchecked(ix, accounts, &[])?;
+        withdraw_repay_apl_accounts
+            .borrow_position
+            .load_mut()
+            .set_reentrancy_guard(false);
     }
 
     invoke_signed_unchecked(
@@ -89,3 +100,118 @@ pub fn process_withdraw_repay_apl(
 
     Ok(())
 }
+
+#[cfg(test)]
+pub mod tests {
+    use arch_program::pubkey::Pubkey;
+    use autara_lib::{
+        error::LendingError,
+        ixs::{
+            borrow_apl_ix, deposit_apl_collateral_ix, sync_market_ix, update_config_ix,
+            withdraw_repay_apl_ix, UpdateConfigInstruction, WithdrawRepayAplInstruction,
+        },
+        state::market::Market,
+    };
+
+    use crate::{
+        error::LendingProgramErrorKind,
+        processor::borrow_deposit_apl::tests::setup_borrower,
+        test_runtime::{tests::create_btc_usdc_market, TestRuntime},
+    };
+
+    const USDC: u64 = 1_000_000;
+    const BTC: u64 = 100_000_000;
+
+    #[test]
+    fn callback_program_must_be_in_the_market_allowlist() {
+        let mut runtime = TestRuntime::new();
+        let program_id = crate::id();
+        let market = create_btc_usdc_market(&mut runtime);
+        let [borrower, borrow_position, supply_ata, collateral_ata] =
+            setup_borrower(&mut runtime, &market);
+        runtime
+            .process(&deposit_apl_collateral_ix(
+                program_id,
+                market.market,
+                borrower,
+                borrow_position,
+                collateral_ata,
+                market.collateral_vault,
+                market.supply_oracle,
+                market.collateral_oracle,
+                BTC,
+            ))
+            .unwrap();
+        runtime
+            .process(&borrow_apl_ix(
+                program_id,
+                market.market,
+                borrower,
+                borrow_position,
+                supply_ata,
+                market.supply_vault,
+                market.supply_oracle,
+                market.collateral_oracle,
+                1_000 * USDC,
+            ))
+            .unwrap();
+        let curator = *runtime.load::<Market>(&market.market).config().curator();
+        runtime
+            .process(&update_config_ix(
+                program_id,
+                market.market,
+                curator,
+                UpdateConfigInstruction {
+                    callback_program_allowlist: Some(vec![apl_token::id()]),
+                    ..Default::default()
+                },
+                market.supply_oracle,
+                market.collateral_oracle,
+            ))
+            .unwrap();
+        let recipient = Pubkey::new_unique();
+        let recipient_ata = runtime.create_token_account(recipient, market.collateral_mint, 0);
+        let withdraw_repay = |ix_callback| {
+            withdraw_repay_apl_ix(
+                program_id,
+                market.market,
+                borrower,
+                borrow_position,
+                supply_ata,
+                market.supply_vault,
+                collateral_ata,
+                market.collateral_vault,
+                market.supply_oracle,
+                market.collateral_oracle,
+                WithdrawRepayAplInstruction {
+                    repay_amount: 0,
+                    withdraw_amount: 0,
+                    repay_all: true,
+                    withdraw_all: true,
+                    ix_callback: Some(ix_callback),
+                },
+            )
+        };
+
+        let sync_market = sync_market_ix(program_id, market.market);
+        let err = runtime.process(&withdraw_repay(sync_market)).unwrap_err();
+        assert_eq!(
+            err.0.error,
+            LendingProgramErrorKind::LendingError(LendingError::CallbackProgramNotAllowed)
+        );
+        assert_eq!(runtime.token_balance(&collateral_ata), 0);
+
+        let transfer = apl_token::instruction::transfer(
+            &apl_token::id(),
+            &collateral_ata,
+            &recipient_ata,
+            &borrower,
+            &[],
+            BTC,
+        )
+        .unwrap();
+        runtime.process(&withdraw_repay(transfer)).unwrap();
+        assert_eq!(runtime.token_balance(&recipient_ata), BTC);
+        assert_eq!(runtime.token_balance(&supply_ata), 0);
+    }
+}
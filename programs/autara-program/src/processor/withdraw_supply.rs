@@ -1,12 +1,13 @@
-use arch_program::{
-    account::AccountInfo, clock::Clock, program::invoke_signed_unchecked, pubkey::Pubkey,
-};
+use arch_program::{account::AccountInfo, clock::Clock, pubkey::Pubkey};
 use autara_lib::{
     event::AutaraEvent,
     ixs::{log_ix, WithdrawSupplyInstruction},
+    oracle::oracle_provider::AccountView,
 };
 
-use crate::{error::LendingProgramResult, ixs::WithdrawSupplyAccounts};
+use crate::{
+    error::LendingProgramResult, ixs::WithdrawSupplyAccounts, utils::invoke_signed_unchecked,
+};
 
 pub fn process_withdraw_supply(
     withdraw_supply_accounts: &WithdrawSupplyAccounts,
@@ -18,8 +19,8 @@ pub fn process_withdraw_supply(
     let mut market_ref = withdraw_supply_accounts.market.load_mut();
     let mut supply_position_ref = withdraw_supply_accounts.supply_position.load_mut();
     let mut market_wrapper = market_ref.wrapper_mut(
-        withdraw_supply_accounts.supply_oracle.try_into()?,
-        withdraw_supply_accounts.collateral_oracle.try_into()?,
+        AccountView::try_from_optional(withdraw_supply_accounts.supply_oracle)?,
+        AccountView::try_from_optional(withdraw_supply_accounts.collateral_oracle)?,
         clock.unix_timestamp,
     )?;
     market_wrapper.sync_clock(clock.unix_timestamp)?;
@@ -60,3 +61,69 @@ pub fn process_withdraw_supply(
     )?;
     Ok(())
 }
+
+#[cfg(test)]
+pub mod tests {
+    use arch_program::pubkey::Pubkey;
+    use autara_lib::{
+        error::LendingError,
+        ixs::{repay_apl_ix, withdraw_supply_ix},
+    };
+
+    use crate::{
+        error::LendingProgramErrorKind,
+        test_runtime::{tests::create_btc_usdc_market, TestRuntime},
+    };
+
+    #[test]
+    fn all_flag_with_nonzero_amount_is_rejected() {
+        let mut runtime = TestRuntime::new();
+        let market = create_btc_usdc_market(&mut runtime);
+        let authority = Pubkey::new_unique();
+        let position = Pubkey::new_unique();
+        let ata = Pubkey::new_unique();
+        let withdraw = |amount, withdraw_all| {
+            withdraw_supply_ix(
+                crate::id(),
+                market.market,
+                position,
+                authority,
+                ata,
+                market.supply_vault,
+                market.supply_oracle,
+                market.collateral_oracle,
+                amount,
+                withdraw_all,
+            )
+        };
+        let err = runtime.process(&withdraw(5, true)).unwrap_err();
+        assert_eq!(
+            err.0.error,
+            LendingProgramErrorKind::LendingError(LendingError::InvalidInstructionArguments)
+        );
+        let err = runtime
+            .process(&repay_apl_ix(
+                crate::id(),
+                market.market,
+                position,
+                authority,
+                ata,
+                market.supply_vault,
+                market.supply_oracle,
+                market.collateral_oracle,
+                5,
+                true,
+            ))
+            .unwrap_err();
+        assert_eq!(
+            err.0.error,
+            LendingProgramErrorKind::LendingError(LendingError::InvalidInstructionArguments)
+        );
+        // Consistent arguments get past validation and fail on the missing position instead
+        let err = runtime.process(&withdraw(0, true)).unwrap_err();
+        assert_ne!(
+            err.0.error,
+            LendingProgramErrorKind::LendingError(LendingError::InvalidInstructionArguments)
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use arch_program::pubkey::Pubkey;
 use autara_lib::state::{
-    borrow_position::BorrowPosition, global_config::GlobalConfig, market::Market,
-    supply_position::SupplyPosition,
+    borrow_position::BorrowPosition, curator_market_count::CuratorMarketCount,
+    global_config::GlobalConfig, market::Market, supply_position::SupplyPosition,
 };
 use autara_program_lib::accounts::{
     program::ProgramAccount, zero_copy::ZeroCopyInitialized, OwnedAccount,
@@ -45,6 +45,12 @@ impl ZeroCopyInitialized for AutaraAccount<GlobalConfig> {
     }
 }
 
+impl ZeroCopyInitialized for AutaraAccount<CuratorMarketCount> {
+    fn is_initialized(&self) -> bool {
+        self.0.curator() != &ZEROED_PUBKEY
+    }
+}
+
 impl<T> Deref for AutaraAccount<T> {
     type Target = T;
 
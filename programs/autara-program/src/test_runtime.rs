@@ -0,0 +1,846 @@
+//! In-memory runtime used to run instructions end to end through
+//! [`crate::autara_process_instruction`] in tests.
+//!
+//! Accounts live in a map keyed by pubkey and are only written back when the
+//! instruction succeeds. CPIs issued by the processors are routed to small
+//! mocks of the system, APL token and associated token programs, and the
+//! events logged through the program itself are collected. Other instructions
+//! of the program, e.g. from user callbacks, are processed in place.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+};
+
+use apl_token::{instruction::TokenInstruction, state::Mint};
+use arch_program::{
+    account::AccountInfo, clock::Clock, entrypoint::ProgramResult, instruction::Instruction,
+    program_error::ProgramError, program_pack::Pack, pubkey::Pubkey, utxo::UtxoMeta,
+};
+use autara_lib::{
+    event::AutaraEvent,
+    oracle::{
+        oracle_config::OracleConfig,
+        pyth::{Metadata, PriceData, PythPrice, PythPriceAccount},
+    },
+    token::get_associated_token_address,
+};
+use autara_program_lib::accounts::{
+    program::ProgramAccount,
+    token::{AplAssociatedTokenProgram, TokenAccount},
+};
+use bytemuck::Pod;
+
+use crate::error::LendingProgramResult;
+
+thread_local! {
+    static UNIX_TIMESTAMP: Cell<i64> = const { Cell::new(0) };
+    static EVENTS: RefCell<Vec<AutaraEvent>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Test replacement of [`crate::utils::clock`], reading the timestamp of the
+/// runtime currently processing an instruction.
+pub fn clock() -> Clock {
+    let unix_timestamp = UNIX_TIMESTAMP.with(Cell::get);
+    if unix_timestamp == 0 {
+        panic!()
+    }
+    Clock {
+        unix_timestamp,
+        ..Default::default()
+    }
+}
+
+/// Test replacement of `arch_program::program::invoke_signed_unchecked`.
+/// Signer seeds are not checked.
+pub fn invoke_signed_unchecked(
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+    _signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let account = |index: usize| find_account(instruction, account_infos, index);
+    if instruction.program_id == Pubkey::system_program() {
+        mock_create_account(&instruction.data, account(0)?, account(1)?)
+    } else if instruction.program_id == apl_token::id() {
+        mock_token_transfer(&instruction.data, account(0)?, account(1)?, account(2)?)
+    } else if AplAssociatedTokenProgram::is_valid_key(&instruction.program_id) {
+        mock_create_associated_token_account(account(1)?, account(2)?, account(3)?)
+    } else if instruction.program_id == crate::id() {
+        if let Some(event) = AutaraEvent::from_bytes(&instruction.data) {
+            EVENTS.with(|events| events.borrow_mut().push(event));
+            return Ok(());
+        }
+        let account_infos = (0..instruction.accounts.len())
+            .map(|index| account(index).cloned())
+            .collect::<Result<Vec<_>, _>>()?;
+        crate::autara_process_instruction(
+            &crate::id(),
+            Box::leak(account_infos.into_boxed_slice()),
+            &instruction.data,
+        )
+        .map_err(Into::into)
+    } else {
+        Err(ProgramError::IncorrectProgramId)
+    }
+}
+
+fn find_account<'a, 'b>(
+    instruction: &Instruction,
+    account_infos: &'b [AccountInfo<'a>],
+    index: usize,
+) -> Result<&'b AccountInfo<'a>, ProgramError> {
+    let key = instruction
+        .accounts
+        .get(index)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?
+        .pubkey;
+    account_infos
+        .iter()
+        .find(|info| *info.key == key)
+        .ok_or(ProgramError::NotEnoughAccountKeys)
+}
+
+fn mock_create_account(data: &[u8], from: &AccountInfo, to: &AccountInfo) -> ProgramResult {
+    // bincode encoded `SystemInstruction::CreateAccount { lamports, space, owner }`
+    if data.len() != 52 || data[..4] != [0; 4] {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let lamports = u64::from_le_bytes(data[4..12].try_into().unwrap());
+    let space = u64::from_le_bytes(data[12..20].try_into().unwrap()) as usize;
+    let owner = Pubkey::from_slice(&data[20..52]);
+    if !to.data.borrow().is_empty() || *to.owner != Pubkey::system_program() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    {
+        let mut from_lamports = from.lamports.borrow_mut();
+        **from_lamports = from_lamports
+            .checked_sub(lamports)
+            .ok_or(ProgramError::InsufficientFunds)?;
+    }
+    **to.lamports.borrow_mut() += lamports;
+    *to.data.borrow_mut() = Box::leak(vec![0; space].into_boxed_slice());
+    to.assign(&owner);
+    Ok(())
+}
+
+fn mock_token_transfer(
+    data: &[u8],
+    source: &AccountInfo,
+    destination: &AccountInfo,
+    authority: &AccountInfo,
+) -> ProgramResult {
+    let TokenInstruction::Transfer { amount } = TokenInstruction::unpack(data)? else {
+        return Err(ProgramError::InvalidInstructionData);
+    };
+    if *source.owner != apl_token::id() || *destination.owner != apl_token::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut source_account = TokenAccount::unpack(&source.data.borrow())?;
+    let mut destination_account = TokenAccount::unpack(&destination.data.borrow())?;
+    if source_account.owner != *authority.key {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if source_account.mint != destination_account.mint {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if source.key == destination.key {
+        return Ok(());
+    }
+    source_account.amount = source_account
+        .amount
+        .checked_sub(amount)
+        .ok_or(ProgramError::InsufficientFunds)?;
+    destination_account.amount = destination_account
+        .amount
+        .checked_add(amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    TokenAccount::pack(source_account, &mut source.data.borrow_mut())?;
+    TokenAccount::pack(destination_account, &mut destination.data.borrow_mut())?;
+    Ok(())
+}
+
+fn mock_create_associated_token_account(
+    associated_token_account: &AccountInfo,
+    wallet: &AccountInfo,
+    mint: &AccountInfo,
+) -> ProgramResult {
+    if *associated_token_account.key != get_associated_token_address(wallet.key, mint.key) {
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if !associated_token_account.data.borrow().is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    *associated_token_account.data.borrow_mut() =
+        Box::leak(token_account_data(*wallet.key, *mint.key, 0)?.into_boxed_slice());
+    associated_token_account.assign(&apl_token::id());
+    Ok(())
+}
+
+fn token_account_data(owner: Pubkey, mint: Pubkey, amount: u64) -> Result<Vec<u8>, ProgramError> {
+    let mut data = vec![0; TokenAccount::LEN];
+    TokenAccount::pack(
+        TokenAccount {
+            mint,
+            owner,
+            amount,
+            state: apl_token::state::AccountState::Initialized,
+            ..Default::default()
+        },
+        &mut data,
+    )?;
+    Ok(data)
+}
+
+#[derive(Clone, Debug)]
+pub struct TestAccount {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub executable: bool,
+}
+
+impl Default for TestAccount {
+    fn default() -> Self {
+        Self {
+            lamports: 0,
+            data: Vec::new(),
+            owner: Pubkey::system_program(),
+            executable: false,
+        }
+    }
+}
+
+pub struct TestRuntime {
+    pub accounts: HashMap<Pubkey, TestAccount>,
+    pub unix_timestamp: i64,
+    pub pyth_program_id: Pubkey,
+    events: Vec<AutaraEvent>,
+}
+
+impl Default for TestRuntime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestRuntime {
+    pub fn new() -> Self {
+        Self {
+            accounts: HashMap::new(),
+            unix_timestamp: 1_700_000_000,
+            pyth_program_id: Pubkey::new_unique(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Runs `instruction` against the stored accounts. Accounts missing from
+    /// the map are empty system accounts. Changes are only kept on success.
+    pub fn process(&mut self, instruction: &Instruction) -> LendingProgramResult {
+        let mut keys: Vec<Pubkey> = Vec::new();
+        for meta in &instruction.accounts {
+            if !keys.contains(&meta.pubkey) {
+                keys.push(meta.pubkey);
+            }
+        }
+        let unique_infos: Vec<AccountInfo<'static>> = keys
+            .iter()
+            .map(|key| {
+                let account = self.accounts.get(key).cloned().unwrap_or_default();
+                let metas = instruction.accounts.iter().filter(|m| m.pubkey == *key);
+                AccountInfo::new(
+                    Box::leak(Box::new(*key)),
+                    Box::leak(Box::new(account.lamports)),
+                    Box::leak(account.data.into_boxed_slice()),
+                    Box::leak(Box::new(account.owner)),
+                    Box::leak(Box::new(UtxoMeta::default())),
+                    metas.clone().any(|m| m.is_signer),
+                    metas.clone().any(|m| m.is_writable),
+                    account.executable,
+                )
+            })
+            .collect();
+        let account_infos: &'static [AccountInfo<'static>] = Box::leak(
+            instruction
+                .accounts
+                .iter()
+                .map(|meta| {
+                    unique_infos[keys.iter().position(|k| *k == meta.pubkey).unwrap()].clone()
+                })
+                .collect::<Vec<_>>()
+                .into_boxed_slice(),
+        );
+        UNIX_TIMESTAMP.with(|timestamp| timestamp.set(self.unix_timestamp));
+        EVENTS.with(|events| events.borrow_mut().clear());
+        let result =
+            crate::autara_process_instruction(&crate::id(), account_infos, &instruction.data);
+        let events = EVENTS.with(|events| events.take());
+        result?;
+        for info in &unique_infos {
+            self.accounts.insert(
+                *info.key,
+                TestAccount {
+                    lamports: **info.lamports.borrow(),
+                    data: info.data.borrow().to_vec(),
+                    owner: *info.owner,
+                    executable: info.is_executable,
+                },
+            );
+        }
+        self.events.extend(events);
+        Ok(())
+    }
+
+    pub fn advance_time(&mut self, seconds: i64) {
+        self.unix_timestamp += seconds;
+    }
+
+    pub fn events(&self) -> &[AutaraEvent] {
+        &self.events
+    }
+
+    pub fn airdrop(&mut self, key: Pubkey, lamports: u64) {
+        self.accounts.entry(key).or_default().lamports += lamports;
+    }
+
+    pub fn create_mint(&mut self, decimals: u8) -> Pubkey {
+        let key = Pubkey::new_unique();
+        let mut data = vec![0; Mint::LEN];
+        Mint::pack(
+            Mint {
+                decimals,
+                is_initialized: true,
+                ..Default::default()
+            },
+            &mut data,
+        )
+        .unwrap();
+        self.accounts.insert(
+            key,
+            TestAccount {
+                data,
+                owner: apl_token::id(),
+                ..Default::default()
+            },
+        );
+        key
+    }
+
+    /// Creates the associated token account of `owner` for `mint`.
+    pub fn create_token_account(&mut self, owner: Pubkey, mint: Pubkey, amount: u64) -> Pubkey {
+        let key = get_associated_token_address(&owner, &mint);
+        self.accounts.insert(
+            key,
+            TestAccount {
+                data: token_account_data(owner, mint, amount).unwrap(),
+                owner: apl_token::id(),
+                ..Default::default()
+            },
+        );
+        key
+    }
+
+    pub fn token_balance(&self, key: &Pubkey) -> u64 {
+        TokenAccount::unpack(&self.accounts[key].data)
+            .unwrap()
+            .amount
+    }
+
+    pub fn pyth_oracle_config(&self, feed_id: [u8; 32]) -> OracleConfig {
+        OracleConfig::new_pyth(feed_id, self.pyth_program_id)
+    }
+
+    /// Publishes a price for `feed_id` at the current timestamp and returns
+    /// the feed account.
+    pub fn set_pyth_price(
+        &mut self,
+        feed_id: [u8; 32],
+        price: u64,
+        conf: u64,
+        expo: i64,
+    ) -> Pubkey {
+        let key = self
+            .pyth_oracle_config(feed_id)
+            .oracle_provider()
+            .oracle_provider_ref()
+            .oracle_feed_pubkey()
+            .unwrap();
+        let price = PriceData {
+            price,
+            conf,
+            expo,
+            publish_time: self.unix_timestamp,
+        };
+        let account = PythPriceAccount {
+            pyth_price: PythPrice {
+                id: feed_id,
+                price,
+                ema_price: price,
+                metadata: Metadata {
+                    slot: 0,
+                    proof_available_time: self.unix_timestamp,
+                    prev_publish_time: self.unix_timestamp,
+                },
+            },
+            authority: Pubkey::new_unique(),
+            max_price_move_bps: 0,
+            _padding: [0; 6],
+        };
+        self.accounts.insert(
+            key,
+            TestAccount {
+                data: bytemuck::bytes_of(&account).to_vec(),
+                owner: self.pyth_program_id,
+                ..Default::default()
+            },
+        );
+        key
+    }
+
+    pub fn load<T: Pod>(&self, key: &Pubkey) -> T {
+        bytemuck::pod_read_unaligned(&self.accounts[key].data)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use autara_lib::{
+        interest_rate::interest_rate_kind::InterestRateCurveKind,
+        ixs::{
+            borrow_apl_ix, create_borrow_position_ix, create_global_config_ix, create_market_ix,
+            create_supply_position_ix, deposit_apl_collateral_ix, liquidate_ix, supply_apl_ix,
+            sync_market_ix, CreateMarketInstruction,
+        },
+        state::{borrow_position::BorrowPosition, market::Market, market_config::LtvConfig},
+    };
+
+    use super::*;
+    use autara_lib::{ixs::AurataInstructionTag, math::ifixed_point::IFixedPoint};
+
+    const USDC: u64 = 1_000_000;
+    const BTC: u64 = 100_000_000;
+    const USDC_FEED: [u8; 32] = [1; 32];
+    const BTC_FEED: [u8; 32] = [2; 32];
+
+    pub struct TestMarket {
+        pub market: Pubkey,
+        pub supply_mint: Pubkey,
+        pub collateral_mint: Pubkey,
+        pub supply_vault: Pubkey,
+        pub collateral_vault: Pubkey,
+        pub supply_oracle: Option<Pubkey>,
+        pub collateral_oracle: Option<Pubkey>,
+    }
+
+    /// Creates the global config and a BTC/USDC market priced at 100_000 USDC per BTC.
+    pub fn create_btc_usdc_market(runtime: &mut TestRuntime) -> TestMarket {
+        create_btc_usdc_market_with_interest_rate(runtime, InterestRateCurveKind::new_adaptive())
+    }
+
+    /// See [create_btc_usdc_market]
+    pub fn create_btc_usdc_market_with_interest_rate(
+        runtime: &mut TestRuntime,
+        interest_rate: InterestRateCurveKind,
+    ) -> TestMarket {
+        let program_id = crate::id();
+        let admin = Pubkey::new_unique();
+        let curator = Pubkey::new_unique();
+        runtime.airdrop(admin, 1_000_000_000);
+        runtime.airdrop(curator, 1_000_000_000);
+
+        let (_, ix) = create_global_config_ix(program_id, admin, admin, admin, 0);
+        runtime.process(&ix).unwrap();
+
+        let supply_mint = runtime.create_mint(6);
+        let collateral_mint = runtime.create_mint(8);
+        let supply_oracle = runtime.set_pyth_price(USDC_FEED, 100_000_000, 10_000, -8);
+        let collateral_oracle =
+            runtime.set_pyth_price(BTC_FEED, 100_000 * 100_000_000, 10 * 100_000_000, -8);
+        let (market, ix) = create_market_ix(
+            CreateMarketInstruction {
+                market_bump: 0,
+                index: 0,
+                ltv_config: LtvConfig {
+                    max_ltv: 0.8.into(),
+                    unhealthy_ltv: 0.9.into(),
+                    liquidation_bonus: 0.05.into(),
+                },
+                max_utilisation_rate: 0.9.into(),
+                supply_oracle_config: runtime.pyth_oracle_config(USDC_FEED),
+                collateral_oracle_config: runtime.pyth_oracle_config(BTC_FEED),
+                interest_rate,
+                lending_market_fee_in_bps: 0,
+                metadata_uri: Default::default(),
+                borrow_origination_grace_seconds: 0,
+            },
+            supply_mint,
+            collateral_mint,
+            program_id,
+            curator,
+            curator,
+        );
+        runtime.process(&ix).unwrap();
+        TestMarket {
+            market,
+            supply_mint,
+            collateral_mint,
+            supply_vault: get_associated_token_address(&market, &supply_mint),
+            collateral_vault: get_associated_token_address(&market, &collateral_mint),
+            supply_oracle: Some(supply_oracle),
+            collateral_oracle: Some(collateral_oracle),
+        }
+    }
+
+    #[test]
+    fn create_market_records_creation_timestamp() {
+        let mut runtime = TestRuntime::new();
+        runtime.advance_time(42);
+        let test_market = create_btc_usdc_market(&mut runtime);
+        let market = runtime.load::<Market>(&test_market.market);
+        assert_eq!(
+            market.config().created_at_unix_timestamp(),
+            runtime.unix_timestamp
+        );
+        assert_eq!(market.config().created_at_unix_timestamp(), 1_700_000_042);
+    }
+
+    #[test]
+    fn stable_market_with_constant_supply_oracle() {
+        const USDT_FEED: [u8; 32] = [3; 32];
+        const USDT: u64 = 1_000_000;
+        let mut runtime = TestRuntime::new();
+        let program_id = crate::id();
+        let admin = Pubkey::new_unique();
+        let curator = Pubkey::new_unique();
+        let lender = Pubkey::new_unique();
+        let borrower = Pubkey::new_unique();
+        runtime.airdrop(admin, 1_000_000_000);
+        runtime.airdrop(curator, 1_000_000_000);
+        runtime.airdrop(lender, 1_000_000_000);
+        runtime.airdrop(borrower, 1_000_000_000);
+        let (_, ix) = create_global_config_ix(program_id, admin, admin, admin, 0);
+        runtime.process(&ix).unwrap();
+
+        let supply_mint = runtime.create_mint(6);
+        let collateral_mint = runtime.create_mint(6);
+        let collateral_oracle = Some(runtime.set_pyth_price(USDT_FEED, 100_000_000, 10_000, -8));
+        let (market, ix) = create_market_ix(
+            CreateMarketInstruction {
+                market_bump: 0,
+                index: 0,
+                ltv_config: LtvConfig {
+                    max_ltv: 0.8.into(),
+                    unhealthy_ltv: 0.9.into(),
+                    liquidation_bonus: 0.05.into(),
+                },
+                max_utilisation_rate: 0.9.into(),
+                supply_oracle_config: OracleConfig::new_constant(IFixedPoint::one()),
+                collateral_oracle_config: runtime.pyth_oracle_config(USDT_FEED),
+                interest_rate: InterestRateCurveKind::new_adaptive(),
+                lending_market_fee_in_bps: 0,
+                metadata_uri: Default::default(),
+                borrow_origination_grace_seconds: 0,
+            },
+            supply_mint,
+            collateral_mint,
+            program_id,
+            curator,
+            curator,
+        );
+        // the constant side has no feed account to pass
+        let supply_oracle = None;
+        assert_eq!(
+            ix.accounts.len(),
+            AurataInstructionTag::CreateMarket.expected_accounts().len()
+        );
+        assert_eq!(Some(ix.accounts[12].pubkey), collateral_oracle);
+        runtime.process(&ix).unwrap();
+        let supply_vault = get_associated_token_address(&market, &supply_mint);
+        let collateral_vault = get_associated_token_address(&market, &collateral_mint);
+
+        let lender_ata = runtime.create_token_account(lender, supply_mint, 1_000 * USDC);
+        let (supply_position, ix) = create_supply_position_ix(program_id, market, lender, lender);
+        runtime.process(&ix).unwrap();
+        runtime
+            .process(&supply_apl_ix(
+                program_id,
+                market,
+                supply_position,
+                lender,
+                lender_ata,
+                supply_vault,
+                supply_oracle,
+                collateral_oracle,
+                1_000 * USDC,
+            ))
+            .unwrap();
+
+        let borrower_collateral_ata =
+            runtime.create_token_account(borrower, collateral_mint, 100 * USDT);
+        let borrower_supply_ata = runtime.create_token_account(borrower, supply_mint, 0);
+        let (borrow_position, ix) =
+            create_borrow_position_ix(program_id, market, borrower, borrower);
+        runtime.process(&ix).unwrap();
+        runtime
+            .process(&deposit_apl_collateral_ix(
+                program_id,
+                market,
+                borrower,
+                borrow_position,
+                borrower_collateral_ata,
+                collateral_vault,
+                supply_oracle,
+                collateral_oracle,
+                100 * USDT,
+            ))
+            .unwrap();
+        let borrow_ix = |amount| {
+            borrow_apl_ix(
+                program_id,
+                market,
+                borrower,
+                borrow_position,
+                borrower_supply_ata,
+                supply_vault,
+                supply_oracle,
+                collateral_oracle,
+                amount,
+            )
+        };
+        assert!(runtime.process(&borrow_ix(81 * USDC)).is_err());
+        runtime.process(&borrow_ix(70 * USDC)).unwrap();
+
+        // the constant side never goes stale, only the collateral feed needs a fresh price
+        runtime.advance_time(24 * 3600);
+        runtime.set_pyth_price(USDT_FEED, 100_000_000, 10_000, -8);
+        runtime.process(&borrow_ix(5 * USDC)).unwrap();
+        assert_eq!(runtime.token_balance(&borrower_supply_ata), 75 * USDC);
+    }
+
+    #[test]
+    fn create_market_supply_borrow_and_liquidate() {
+        let mut runtime = TestRuntime::new();
+        let program_id = crate::id();
+        let lender = Pubkey::new_unique();
+        let borrower = Pubkey::new_unique();
+        let liquidator = Pubkey::new_unique();
+        runtime.airdrop(lender, 1_000_000_000);
+        runtime.airdrop(borrower, 1_000_000_000);
+        let TestMarket {
+            market,
+            supply_mint,
+            collateral_mint,
+            supply_vault,
+            collateral_vault,
+            supply_oracle,
+            collateral_oracle,
+        } = create_btc_usdc_market(&mut runtime);
+        assert_eq!(runtime.token_balance(&supply_vault), 0);
+        assert_eq!(runtime.token_balance(&collateral_vault), 0);
+
+        let lender_ata = runtime.create_token_account(lender, supply_mint, 100_000 * USDC);
+        let (supply_position, ix) = create_supply_position_ix(program_id, market, lender, lender);
+        runtime.process(&ix).unwrap();
+        runtime
+            .process(&supply_apl_ix(
+                program_id,
+                market,
+                supply_position,
+                lender,
+                lender_ata,
+                supply_vault,
+                supply_oracle,
+                collateral_oracle,
+                100_000 * USDC,
+            ))
+            .unwrap();
+        assert_eq!(runtime.token_balance(&lender_ata), 0);
+        assert_eq!(runtime.token_balance(&supply_vault), 100_000 * USDC);
+
+        let borrower_collateral_ata = runtime.create_token_account(borrower, collateral_mint, BTC);
+        let borrower_supply_ata = runtime.create_token_account(borrower, supply_mint, 0);
+        let (borrow_position, ix) =
+            create_borrow_position_ix(program_id, market, borrower, borrower);
+        runtime.process(&ix).unwrap();
+        runtime
+            .process(&deposit_apl_collateral_ix(
+                program_id,
+                market,
+                borrower,
+                borrow_position,
+                borrower_collateral_ata,
+                collateral_vault,
+                supply_oracle,
+                collateral_oracle,
+                BTC,
+            ))
+            .unwrap();
+        let borrow_ix = |amount| {
+            borrow_apl_ix(
+                program_id,
+                market,
+                borrower,
+                borrow_position,
+                borrower_supply_ata,
+                supply_vault,
+                supply_oracle,
+                collateral_oracle,
+                amount,
+            )
+        };
+        assert!(runtime.process(&borrow_ix(81_000 * USDC)).is_err());
+        runtime.process(&borrow_ix(75_000 * USDC)).unwrap();
+        assert_eq!(runtime.token_balance(&borrower_supply_ata), 75_000 * USDC);
+        assert_eq!(runtime.token_balance(&supply_vault), 25_000 * USDC);
+
+        let liquidator_supply_ata =
+            runtime.create_token_account(liquidator, supply_mint, 100_000 * USDC);
+        let liquidator_collateral_ata =
+            runtime.create_token_account(liquidator, collateral_mint, 0);
+        let liquidate = liquidate_ix(
+            program_id,
+            market,
+            borrow_position,
+            liquidator,
+            liquidator_supply_ata,
+            liquidator_collateral_ata,
+            supply_vault,
+            collateral_vault,
+            supply_oracle,
+            collateral_oracle,
+            u64::MAX,
+            0,
+            None,
+        );
+        assert!(runtime.process(&liquidate).is_err());
+
+        runtime.advance_time(3600);
+        runtime.set_pyth_price(USDC_FEED, 100_000_000, 10_000, -8);
+        runtime.set_pyth_price(BTC_FEED, 80_000 * 100_000_000, 10 * 100_000_000, -8);
+        let borrowed_before = runtime
+            .load::<Market>(&market)
+            .supply_vault()
+            .total_borrow()
+            .unwrap();
+        runtime.process(&liquidate).unwrap();
+
+        let repaid = 100_000 * USDC - runtime.token_balance(&liquidator_supply_ata);
+        let seized = runtime.token_balance(&liquidator_collateral_ata);
+        assert!(repaid > 0);
+        assert!(seized > 0);
+        assert_eq!(runtime.token_balance(&supply_vault), 25_000 * USDC + repaid);
+        assert_eq!(runtime.token_balance(&collateral_vault), BTC - seized);
+        let position = runtime.load::<BorrowPosition>(&borrow_position);
+        assert_eq!(position.collateral_deposited_atoms(), BTC - seized);
+        let borrowed_after = runtime
+            .load::<Market>(&market)
+            .supply_vault()
+            .total_borrow()
+            .unwrap();
+        assert!(borrowed_after < borrowed_before);
+
+        assert_eq!(runtime.events().len(), 4);
+        assert!(matches!(runtime.events()[0], AutaraEvent::Supply(_)));
+        assert!(matches!(
+            runtime.events()[1],
+            AutaraEvent::DepositCollateral(_)
+        ));
+        assert!(matches!(runtime.events()[2], AutaraEvent::Borrow(_)));
+        assert!(matches!(runtime.events()[3], AutaraEvent::Liquidate(_)));
+    }
+
+    #[test]
+    fn borrow_accrues_a_year_of_interest() {
+        let mut runtime = TestRuntime::new();
+        let program_id = crate::id();
+        let lender = Pubkey::new_unique();
+        let borrower = Pubkey::new_unique();
+        runtime.airdrop(lender, 1_000_000_000);
+        runtime.airdrop(borrower, 1_000_000_000);
+        let TestMarket {
+            market,
+            supply_mint,
+            collateral_mint,
+            supply_vault,
+            collateral_vault,
+            supply_oracle,
+            collateral_oracle,
+        } = create_btc_usdc_market_with_interest_rate(
+            &mut runtime,
+            InterestRateCurveKind::new_fixed_apr(0.05),
+        );
+
+        let lender_ata = runtime.create_token_account(lender, supply_mint, 100_000 * USDC);
+        let (supply_position, ix) = create_supply_position_ix(program_id, market, lender, lender);
+        runtime.process(&ix).unwrap();
+        runtime
+            .process(&supply_apl_ix(
+                program_id,
+                market,
+                supply_position,
+                lender,
+                lender_ata,
+                supply_vault,
+                supply_oracle,
+                collateral_oracle,
+                100_000 * USDC,
+            ))
+            .unwrap();
+
+        let borrower_collateral_ata = runtime.create_token_account(borrower, collateral_mint, BTC);
+        let borrower_supply_ata = runtime.create_token_account(borrower, supply_mint, 0);
+        let (borrow_position, ix) =
+            create_borrow_position_ix(program_id, market, borrower, borrower);
+        runtime.process(&ix).unwrap();
+        runtime
+            .process(&deposit_apl_collateral_ix(
+                program_id,
+                market,
+                borrower,
+                borrow_position,
+                borrower_collateral_ata,
+                collateral_vault,
+                supply_oracle,
+                collateral_oracle,
+                BTC,
+            ))
+            .unwrap();
+        runtime
+            .process(&borrow_apl_ix(
+                program_id,
+                market,
+                borrower,
+                borrow_position,
+                borrower_supply_ata,
+                supply_vault,
+                supply_oracle,
+                collateral_oracle,
+                10_000 * USDC,
+            ))
+            .unwrap();
+        let borrowed_atoms = |runtime: &TestRuntime| {
+            let shares = runtime
+                .load::<BorrowPosition>(&borrow_position)
+                .borrowed_shares();
+            runtime
+                .load::<Market>(&market)
+                .supply_vault()
+                .borrow_shares_to_atoms(shares)
+                .unwrap()
+        };
+        let before = borrowed_atoms(&runtime);
+        assert!((10_000 * USDC..=10_000 * USDC + 1).contains(&before));
+
+        runtime.advance_time(365 * 24 * 3600);
+        runtime.set_pyth_price(USDC_FEED, 100_000_000, 10_000, -8);
+        runtime.set_pyth_price(BTC_FEED, 100_000 * 100_000_000, 10 * 100_000_000, -8);
+        runtime
+            .process(&sync_market_ix(program_id, market))
+            .unwrap();
+        // interest compounds every second, which is continuous compounding over a year
+        autara_lib::assert_eq_float!(
+            borrowed_atoms(&runtime) as f64 / before as f64,
+            0.05f64.exp(),
+            0.0001
+        );
+    }
+}
@@ -1,5 +1,12 @@
+#[cfg(not(test))]
 use arch_program::clock::Clock;
 
+#[cfg(test)]
+pub use crate::test_runtime::{clock, invoke_signed_unchecked};
+#[cfg(not(test))]
+pub use arch_program::program::invoke_signed_unchecked;
+
+#[cfg(not(test))]
 pub fn clock() -> Clock {
     let mut clock = Clock::default();
     unsafe { arch_program::syscalls::arch_get_clock(&mut clock) };